@@ -1,6 +1,6 @@
 use criterion::{criterion_group, criterion_main, Criterion};
 use ip_network_table_deps_treebitmap::IpLookupTable;
-use ipnet::Ipv4Net;
+use ipnet::{Ipv4Net, Ipv6Net};
 use prefix_trie::*;
 use rand::prelude::*;
 use std::collections::HashSet;
@@ -271,11 +271,66 @@ pub fn sparse_lookup(c: &mut Criterion) {
     group.finish();
 }
 
+/// Random IPv6 prefixes between /16 and /64 within `2000::/4`, and random host addresses to look
+/// up.
+fn generate_random_ipv6() -> (Vec<(u128, u8)>, Vec<u128>) {
+    let mut rng = thread_rng();
+    let prefix = |rng: &mut ThreadRng| (0x2u128 << 124) | (rng.gen::<u128>() >> 4);
+    let prefixes = (0..ITERS)
+        .map(|_| (prefix(&mut rng), rng.gen_range(16..=64)))
+        .collect();
+    let lookups = (0..ITERS).map(|_| prefix(&mut rng)).collect();
+    (prefixes, lookups)
+}
+
+/// Compare the lookups in IPv6 maps with the specialized 128-bit operations (`Ipv6Net`) to the
+/// generic implementation of `Prefix` (`Bits<u128>`).
+pub fn ipv6_lookup(c: &mut Criterion) {
+    let (prefixes, lookups) = generate_random_ipv6();
+
+    let fast: PrefixMap<Ipv6Net, u32> = prefixes
+        .iter()
+        .zip(0..)
+        .map(|(&(addr, len), i)| (Ipv6Net::new(addr.into(), len).unwrap().trunc(), i))
+        .collect();
+    let generic: PrefixMap<Bits<u128>, u32> = prefixes
+        .iter()
+        .zip(0..)
+        .map(|(&(addr, len), i)| (Bits::new(addr, len), i))
+        .collect();
+    let fast_lookups: Vec<Ipv6Net> = lookups
+        .iter()
+        .map(|&addr| Ipv6Net::new(addr.into(), 128).unwrap())
+        .collect();
+    let generic_lookups: Vec<Bits<u128>> =
+        lookups.iter().map(|&addr| Bits::new(addr, 128)).collect();
+
+    let mut group = c.benchmark_group("ipv6 lookups");
+
+    group.bench_function("PrefixMap<Ipv6Net>", |b| {
+        b.iter(|| {
+            for p in &fast_lookups {
+                criterion::black_box(fast.get_lpm(p).map(|(_, x)| *x));
+            }
+        })
+    });
+    group.bench_function("PrefixMap<Bits<u128>>", |b| {
+        b.iter(|| {
+            for p in &generic_lookups {
+                criterion::black_box(generic.get_lpm(p).map(|(_, x)| *x));
+            }
+        })
+    });
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     dense_lookup,
     dense_mods,
     sparse_lookup,
-    sparse_mods
+    sparse_mods,
+    ipv6_lookup
 );
 criterion_main!(benches);
@@ -0,0 +1,103 @@
+//! Derive macro for the `Prefix` trait of [prefix-trie](https://docs.rs/prefix-trie). Do not use
+//! this crate directly. Instead, enable the feature `derive` of `prefix-trie`, and use
+//! `prefix_trie::Prefix`.
+
+#![deny(missing_docs)]
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Fields, Index, Member};
+
+/// Derive `Prefix` for a newtype wrapper around a type that already implements `Prefix`. The
+/// struct must have exactly one field (either named or unnamed). All methods are forwarded to that
+/// field.
+#[proc_macro_derive(Prefix)]
+pub fn derive_prefix(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(mut input: DeriveInput) -> syn::Result<TokenStream2> {
+    let data = match &input.data {
+        Data::Struct(data) => data,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "`Prefix` can only be derived for structs with a single field",
+            ))
+        }
+    };
+    let field = match &data.fields {
+        Fields::Named(fields) if fields.named.len() == 1 => &fields.named[0],
+        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => &fields.unnamed[0],
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &data.fields,
+                "`Prefix` can only be derived for structs with a single field",
+            ))
+        }
+    };
+    let member = match &field.ident {
+        Some(ident) => Member::Named(ident.clone()),
+        None => Member::Unnamed(Index::from(0)),
+    };
+    let inner = field.ty.clone();
+    let name = &input.ident;
+
+    input
+        .generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote!(#inner: ::prefix_trie::Prefix));
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::prefix_trie::Prefix for #name #ty_generics #where_clause {
+            type R = <#inner as ::prefix_trie::Prefix>::R;
+
+            fn repr(&self) -> Self::R {
+                ::prefix_trie::Prefix::repr(&self.#member)
+            }
+
+            fn prefix_len(&self) -> u8 {
+                ::prefix_trie::Prefix::prefix_len(&self.#member)
+            }
+
+            fn from_repr_len(repr: Self::R, len: u8) -> Self {
+                Self { #member: <#inner as ::prefix_trie::Prefix>::from_repr_len(repr, len) }
+            }
+
+            fn mask(&self) -> Self::R {
+                ::prefix_trie::Prefix::mask(&self.#member)
+            }
+
+            fn zero() -> Self {
+                Self { #member: <#inner as ::prefix_trie::Prefix>::zero() }
+            }
+
+            fn longest_common_prefix(&self, other: &Self) -> Self {
+                Self {
+                    #member: ::prefix_trie::Prefix::longest_common_prefix(
+                        &self.#member,
+                        &other.#member,
+                    ),
+                }
+            }
+
+            fn contains(&self, other: &Self) -> bool {
+                ::prefix_trie::Prefix::contains(&self.#member, &other.#member)
+            }
+
+            fn is_bit_set(&self, bit: u8) -> bool {
+                ::prefix_trie::Prefix::is_bit_set(&self.#member, bit)
+            }
+
+            fn eq(&self, other: &Self) -> bool {
+                ::prefix_trie::Prefix::eq(&self.#member, &other.#member)
+            }
+        }
+    })
+}
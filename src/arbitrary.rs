@@ -0,0 +1,90 @@
+//! Support for the `arbitrary` crate, e.g., to construct tries from fuzzer input in `cargo-fuzz`
+//! harnesses.
+//!
+//! [`PrefixMap`] and [`PrefixSet`] implement [`Arbitrary`] for any prefix type, as well as the
+//! prefix types of this crate ([`Bits`], [`RdPrefix`], and [`Strict`]). Since `Arbitrary` cannot
+//! be implemented for prefix types of other crates (e.g., `ipnet::Ipv4Net`) or for tuples, use
+//! [`prefix`] to generate them.
+//!
+//! ```
+//! use arbitrary::{Arbitrary, Unstructured};
+//! use prefix_trie::{Prefix, PrefixMap};
+//!
+//! let data = [1, 0x0a, 0, 0, 0, 8, 42, 1, 0x0a, 1, 0, 0, 16, 7, 0];
+//! let mut u = Unstructured::new(&data);
+//! let map = PrefixMap::<(u32, u8), u8>::arbitrary(&mut u).unwrap();
+//! assert!(map.iter().all(|(p, _)| p.1 <= 32 && p.0 == Prefix::mask(p)));
+//! ```
+
+use ::arbitrary::{Arbitrary, Result, Unstructured};
+use num_traits::{NumCast, PrimInt, Zero};
+
+use crate::prefix::mask_from_prefix_len;
+use crate::*;
+
+/// Generate a prefix with random (masked) bits and a random prefix length of at most `max_len`.
+fn prefix_with_max_len<P: Prefix>(u: &mut Unstructured<'_>, max_len: u8) -> Result<P> {
+    let len = u.int_in_range(0..=max_len)?;
+    let mut bytes = [0u8; 16];
+    u.fill_buffer(&mut bytes[..std::mem::size_of::<P::R>()])?;
+    let repr: P::R = <P::R as NumCast>::from(u128::from_le_bytes(bytes)).unwrap();
+    Ok(P::from_repr_len(repr & mask_from_prefix_len(len), len))
+}
+
+/// Generate a prefix of any type with random (masked) bits and a random prefix length.
+///
+/// ```
+/// use arbitrary::Unstructured;
+///
+/// let mut u = Unstructured::new(&[24, 0x00, 0x02, 0x00, 0xc0]);
+/// let p: (u32, u8) = prefix_trie::arbitrary::prefix(&mut u).unwrap();
+/// assert_eq!(p, (0xc0000200, 24));
+/// ```
+pub fn prefix<P: Prefix>(u: &mut Unstructured<'_>) -> Result<P> {
+    prefix_with_max_len(u, P::R::zero().count_zeros() as u8)
+}
+
+impl<'a, P: Prefix, T: Arbitrary<'a>> Arbitrary<'a> for PrefixMap<P, T> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut map = PrefixMap::new();
+        while u.arbitrary::<bool>()? {
+            let p = prefix(u)?;
+            map.insert(p, T::arbitrary(u)?);
+        }
+        Ok(map)
+    }
+}
+
+impl<'a, P: Prefix> Arbitrary<'a> for PrefixSet<P> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(PrefixSet(PrefixMap::<P, ()>::arbitrary(u)?))
+    }
+}
+
+impl<'a, I> Arbitrary<'a> for Bits<I>
+where
+    Bits<I>: Prefix,
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        prefix(u)
+    }
+}
+
+impl<'a, P: Prefix> Arbitrary<'a> for RdPrefix<P> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(RdPrefix::new(u.arbitrary()?, prefix::<P>(u)?))
+    }
+}
+
+impl<'a, P: Prefix> Arbitrary<'a> for Strict<P> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        prefix(u)
+    }
+}
+
+#[cfg(feature = "macaddr")]
+impl<'a> Arbitrary<'a> for MacPrefix {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        prefix_with_max_len(u, 48)
+    }
+}
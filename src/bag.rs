@@ -0,0 +1,265 @@
+//! A multiset of prefixes that counts how often each prefix was inserted.
+//!
+//! A [`PrefixBag`] is useful for reference counting, e.g., when the same route is originated by
+//! multiple sources, and must only be withdrawn once all sources have withdrawn it.
+
+use crate::{trieview::UnionItem, AsView, Prefix, PrefixMap};
+
+/// A multiset of prefixes, organized in a tree. Each prefix is stored with its count, which is
+/// always at least one. Removing a prefix decrements its count, and removes the prefix once the
+/// count reaches zero.
+///
+/// ```
+/// # use prefix_trie::bag::PrefixBag;
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut bag: PrefixBag<ipnet::Ipv4Net> = PrefixBag::new();
+/// let p = "10.0.0.0/8".parse()?;
+/// assert_eq!(bag.insert(p), 1);
+/// assert_eq!(bag.insert(p), 2);
+/// assert_eq!(bag.remove(&p), Some(1));
+/// assert!(bag.contains(&p));
+/// assert_eq!(bag.remove(&p), Some(0));
+/// assert!(!bag.contains(&p));
+/// assert_eq!(bag.remove(&p), None);
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+#[derive(Clone)]
+pub struct PrefixBag<P> {
+    map: PrefixMap<P, usize>,
+    total: usize,
+}
+
+impl<P: Prefix> Default for PrefixBag<P> {
+    fn default() -> Self {
+        Self {
+            map: PrefixMap::new(),
+            total: 0,
+        }
+    }
+}
+
+impl<P: std::fmt::Debug> std::fmt::Debug for PrefixBag<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.map.fmt(f)
+    }
+}
+
+impl<P> PartialEq for PrefixBag<P>
+where
+    P: Prefix + PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.map == other.map
+    }
+}
+
+impl<P> Eq for PrefixBag<P> where P: Prefix + Eq {}
+
+impl<P: Prefix> PrefixBag<P> {
+    /// Create a new, empty bag.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of distinct prefixes in the bag.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns the sum of the counts of all prefixes in the bag.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Returns `true` if the bag contains no prefix.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Get the underlying map from prefixes to their counts, e.g., to create views or to perform
+    /// set operations.
+    pub fn as_map(&self) -> &PrefixMap<P, usize> {
+        &self.map
+    }
+
+    /// Get the count of a prefix (without using longest prefix match). Returns 0 if the prefix is
+    /// not present.
+    pub fn count(&self, prefix: &P) -> usize {
+        self.map.get(prefix).copied().unwrap_or(0)
+    }
+
+    /// Check whether the prefix is present in the bag (without using longest prefix match).
+    pub fn contains(&self, prefix: &P) -> bool {
+        self.map.contains_key(prefix)
+    }
+
+    /// Get the longest prefix in the bag that contains `prefix`, together with its count.
+    pub fn get_lpm<'a>(&'a self, prefix: &P) -> Option<(&'a P, usize)> {
+        self.map.get_lpm(prefix).map(|(p, c)| (p, *c))
+    }
+
+    /// Insert the prefix once, and return its new count.
+    pub fn insert(&mut self, prefix: P) -> usize {
+        self.insert_many(prefix, 1)
+    }
+
+    /// Insert the prefix `n` times, and return its new count. If `n` is zero, the bag is not
+    /// modified.
+    pub fn insert_many(&mut self, prefix: P, n: usize) -> usize {
+        if n == 0 {
+            return self.count(&prefix);
+        }
+        self.total += n;
+        let count = self.map.entry(prefix).or_insert(0);
+        *count += n;
+        *count
+    }
+
+    /// Remove the prefix once, and return its remaining count. The prefix is removed from the bag
+    /// once its count reaches zero. Returns `None` if the prefix was not present.
+    pub fn remove(&mut self, prefix: &P) -> Option<usize> {
+        let count = self.map.get_mut(prefix)?;
+        *count -= 1;
+        self.total -= 1;
+        let count = *count;
+        if count == 0 {
+            self.map.remove(prefix);
+        }
+        Some(count)
+    }
+
+    /// Remove the prefix entirely, regardless of its count, and return the count it had. Returns
+    /// `None` if the prefix was not present.
+    pub fn remove_all(&mut self, prefix: &P) -> Option<usize> {
+        let count = self.map.remove(prefix)?;
+        self.total -= count;
+        Some(count)
+    }
+
+    /// Remove all prefixes.
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.total = 0;
+    }
+
+    /// Iterate over all distinct prefixes and their counts in lexicographic order.
+    pub fn iter(&self) -> impl Iterator<Item = (&P, usize)> {
+        self.map.iter().map(|(p, c)| (p, *c))
+    }
+
+    /// Create a bag in which each prefix has the larger of its counts in `a` and `b`. The result
+    /// is built in a single simultaneous traversal over both bags, which is `O(n)`.
+    pub fn union(a: &Self, b: &Self) -> Self
+    where
+        P: Clone,
+    {
+        Self::combine(a, b, |x, y| x.max(y))
+    }
+
+    /// Create a bag in which each prefix has the sum of its counts in `a` and `b`. The result is
+    /// built in a single simultaneous traversal over both bags, which is `O(n)`.
+    ///
+    /// ```
+    /// # use prefix_trie::bag::PrefixBag;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let p1: ipnet::Ipv4Net = "10.0.0.0/8".parse()?;
+    /// let p2: ipnet::Ipv4Net = "10.1.0.0/16".parse()?;
+    /// let a: PrefixBag<_> = [p1, p1, p2].into_iter().collect();
+    /// let b: PrefixBag<_> = [p1, p2, p2, p2].into_iter().collect();
+    /// let count = |bag: &PrefixBag<_>| (bag.count(&p1), bag.count(&p2));
+    /// assert_eq!(count(&PrefixBag::sum(&a, &b)), (3, 4));
+    /// assert_eq!(count(&PrefixBag::union(&a, &b)), (2, 3));
+    /// assert_eq!(count(&PrefixBag::intersection(&a, &b)), (1, 1));
+    /// assert_eq!(count(&PrefixBag::difference(&a, &b)), (1, 0));
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn sum(a: &Self, b: &Self) -> Self
+    where
+        P: Clone,
+    {
+        Self::combine(a, b, |x, y| x + y)
+    }
+
+    /// Create a bag in which each prefix has the smaller of its counts in `a` and `b`. Prefixes
+    /// that are not present in both bags are omitted.
+    pub fn intersection(a: &Self, b: &Self) -> Self
+    where
+        P: Clone,
+    {
+        trace_span!("intersection", a = a.len(), b = b.len());
+        let map: PrefixMap<P, usize> = a
+            .map
+            .view()
+            .intersection(&b.map)
+            .map(|(p, x, y)| (p.clone(), *x.min(y)))
+            .collect();
+        Self::from_map(map)
+    }
+
+    /// Create a bag in which each prefix has its count in `a` minus its count in `b`. Prefixes
+    /// whose count drops to zero are omitted.
+    pub fn difference(a: &Self, b: &Self) -> Self
+    where
+        P: Clone,
+    {
+        Self::combine(a, b, |x, y| x.saturating_sub(y))
+    }
+
+    /// Combine the counts of each prefix in `a` or `b` (where missing prefixes have a count of
+    /// zero), and omit prefixes whose combined count is zero.
+    fn combine<F>(a: &Self, b: &Self, f: F) -> Self
+    where
+        P: Clone,
+        F: Fn(usize, usize) -> usize,
+    {
+        trace_span!("combine", a = a.len(), b = b.len());
+        let map: PrefixMap<P, usize> = a
+            .map
+            .view()
+            .union(&b.map)
+            .filter_map(|item| {
+                // the other side of `Left` and `Right` is only a longest prefix match.
+                let (x, y) = match item {
+                    UnionItem::Left { left, .. } => (*left, 0),
+                    UnionItem::Right { right, .. } => (0, *right),
+                    UnionItem::Both { left, right, .. } => (*left, *right),
+                };
+                let count = f(x, y);
+                (count > 0).then(|| (item.prefix().clone(), count))
+            })
+            .collect();
+        Self::from_map(map)
+    }
+
+    /// Create a bag from a map of counts, all of which are non-zero.
+    fn from_map(map: PrefixMap<P, usize>) -> Self {
+        let total = map.iter().map(|(_, c)| c).sum();
+        Self { map, total }
+    }
+}
+
+impl<P: Prefix> FromIterator<P> for PrefixBag<P> {
+    fn from_iter<I: IntoIterator<Item = P>>(iter: I) -> Self {
+        let mut bag = Self::new();
+        for p in iter {
+            bag.insert(p);
+        }
+        bag
+    }
+}
+
+impl<P: Prefix> Extend<P> for PrefixBag<P> {
+    fn extend<I: IntoIterator<Item = P>>(&mut self, iter: I) {
+        for p in iter {
+            self.insert(p);
+        }
+    }
+}
@@ -0,0 +1,269 @@
+//! Longest-suffix matching of domain names.
+//!
+//! A [`DomainMap`] stores values associated with domain names, and answers queries of the form
+//! "what is the longest stored domain that is a suffix of this name?" (e.g., `ads.example.com`
+//! matches an entry for `example.com`). Internally, each name is reversed into its sequence of
+//! labels (`com`, `example`, `ads`), and each label is replaced by a 16-bit identifier that is
+//! unique for its depth. These identifiers are concatenated into a [`Bits<u128>`] prefix, such that
+//! the longest-prefix match of the trie corresponds to the longest matching domain suffix.
+//!
+//! As a consequence, a name can have at most [`MAX_DEPTH`] labels, and each depth can have at
+//! most 65535 distinct labels. Labels are compared case-insensitively, and a trailing dot is
+//! ignored.
+
+use std::collections::HashMap;
+
+use crate::{Bits, PrefixMap};
+
+/// Maximum number of labels of a domain name stored in a [`DomainMap`].
+pub const MAX_DEPTH: usize = 8;
+
+/// Number of bits used to encode a single label.
+const LABEL_BITS: usize = 16;
+
+/// Errors when inserting a domain name into a [`DomainMap`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DomainError {
+    /// The name has more than [`MAX_DEPTH`] labels.
+    TooDeep(usize),
+    /// The name contains an empty label, e.g., `example..com`.
+    EmptyLabel,
+    /// There are already 65535 distinct labels at the given depth.
+    TooManyLabels(usize),
+}
+
+impl std::fmt::Display for DomainError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DomainError::TooDeep(n) => {
+                write!(
+                    f,
+                    "Domain has {n} labels, but at most {MAX_DEPTH} are supported"
+                )
+            }
+            DomainError::EmptyLabel => write!(f, "Domain contains an empty label"),
+            DomainError::TooManyLabels(d) => write!(f, "Too many distinct labels at depth {d}"),
+        }
+    }
+}
+
+impl std::error::Error for DomainError {}
+
+/// A map from domain names to values that supports longest-suffix matching. See the
+/// [module documentation](self) for how names are encoded.
+///
+/// ```
+/// # use prefix_trie::domain::*;
+/// # fn main() -> Result<(), DomainError> {
+/// let mut blocklist = DomainMap::new();
+/// blocklist.insert("example.com", "ads")?;
+/// blocklist.insert("tracker.example.com", "tracking")?;
+/// assert_eq!(blocklist.get_suffix("ads.example.com"), Some(("example.com", &"ads")));
+/// assert_eq!(blocklist.get_suffix("a.tracker.Example.com."), Some(("tracker.example.com", &"tracking")));
+/// assert_eq!(blocklist.get_suffix("example.org"), None);
+/// assert_eq!(blocklist.get_suffix("com"), None);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct DomainMap<T> {
+    map: PrefixMap<Bits<u128>, (String, T)>,
+    labels: Vec<HashMap<String, u16>>,
+}
+
+impl<T> Default for DomainMap<T> {
+    fn default() -> Self {
+        Self {
+            map: PrefixMap::new(),
+            labels: vec![HashMap::new(); MAX_DEPTH],
+        }
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for DomainMap<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<T> DomainMap<T> {
+    /// Create an empty domain map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of elements stored in `self`.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the map contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Insert a value for the given domain name. If the name was already present, then the old
+    /// value is replaced and returned. The empty name (or `.`) matches every domain.
+    ///
+    /// ```
+    /// # use prefix_trie::domain::*;
+    /// # fn main() -> Result<(), DomainError> {
+    /// let mut dm = DomainMap::new();
+    /// assert_eq!(dm.insert("example.com", 1)?, None);
+    /// assert_eq!(dm.insert("EXAMPLE.com.", 2)?, Some(1));
+    /// assert_eq!(dm.insert("a.b.c.d.e.f.g.h.i", 3), Err(DomainError::TooDeep(9)));
+    /// assert_eq!(dm.insert("example..com", 3), Err(DomainError::EmptyLabel));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn insert(&mut self, name: &str, value: T) -> Result<Option<T>, DomainError> {
+        let name = normalize(name);
+        let key = self.intern(&name)?;
+        Ok(self.map.insert(key, (name, value)).map(|(_, old)| old))
+    }
+
+    /// Get the value of a domain name by matching exactly.
+    ///
+    /// ```
+    /// # use prefix_trie::domain::*;
+    /// # fn main() -> Result<(), DomainError> {
+    /// let mut dm = DomainMap::new();
+    /// dm.insert("example.com", 1)?;
+    /// assert_eq!(dm.get("example.com"), Some(&1));
+    /// assert_eq!(dm.get("www.example.com"), None);
+    /// assert_eq!(dm.get("com"), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get(&self, name: &str) -> Option<&T> {
+        let (key, exact) = self.lookup(&normalize(name))?;
+        if exact {
+            self.map.get(&key).map(|(_, t)| t)
+        } else {
+            None
+        }
+    }
+
+    /// Get a mutable reference to the value of a domain name by matching exactly.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut T> {
+        let (key, exact) = self.lookup(&normalize(name))?;
+        if exact {
+            self.map.get_mut(&key).map(|(_, t)| t)
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if the map contains a value for exactly that domain name.
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Get the value of the longest stored domain that is a suffix of `name` (label-wise), along
+    /// with the stored (normalized) name.
+    ///
+    /// ```
+    /// # use prefix_trie::domain::*;
+    /// # fn main() -> Result<(), DomainError> {
+    /// let mut dm = DomainMap::new();
+    /// dm.insert("example.com", 1)?;
+    /// dm.insert("", 0)?;
+    /// assert_eq!(dm.get_suffix("www.example.com"), Some(("example.com", &1)));
+    /// assert_eq!(dm.get_suffix("example.com"), Some(("example.com", &1)));
+    /// assert_eq!(dm.get_suffix("myexample.com"), Some(("", &0)));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_suffix(&self, name: &str) -> Option<(&str, &T)> {
+        let (key, _) = self.lookup(&normalize(name))?;
+        self.map.get_lpm(&key).map(|(_, (n, t))| (n.as_str(), t))
+    }
+
+    /// Remove the value of a domain name (matching exactly), and return it. Labels are not
+    /// forgotten, i.e., they still count towards the limit of distinct labels at each depth.
+    ///
+    /// ```
+    /// # use prefix_trie::domain::*;
+    /// # fn main() -> Result<(), DomainError> {
+    /// let mut dm = DomainMap::new();
+    /// dm.insert("example.com", 1)?;
+    /// assert_eq!(dm.remove("www.example.com"), None);
+    /// assert_eq!(dm.remove("example.com"), Some(1));
+    /// assert!(dm.is_empty());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn remove(&mut self, name: &str) -> Option<T> {
+        let (key, exact) = self.lookup(&normalize(name))?;
+        if exact {
+            self.map.remove(&key).map(|(_, t)| t)
+        } else {
+            None
+        }
+    }
+
+    /// Iterate over all (normalized) domain names and their values. The order of the elements
+    /// is unspecified, but parent domains are always yielded before their subdomains.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &T)> {
+        self.map.iter().map(|(_, (n, t))| (n.as_str(), t))
+    }
+
+    /// Encode a normalized name, assigning new identifiers to new labels.
+    fn intern(&mut self, name: &str) -> Result<Bits<u128>, DomainError> {
+        let labels = split(name)?;
+        let mut bits = 0u128;
+        for (depth, label) in labels.iter().enumerate() {
+            let table = &mut self.labels[depth];
+            let id = match table.get(*label) {
+                Some(id) => *id,
+                None => {
+                    let id = u16::try_from(table.len() + 1)
+                        .map_err(|_| DomainError::TooManyLabels(depth))?;
+                    table.insert(label.to_string(), id);
+                    id
+                }
+            };
+            bits |= (id as u128) << (128 - LABEL_BITS * (depth + 1));
+        }
+        Ok(Bits::new(bits, (LABEL_BITS * labels.len()) as u8))
+    }
+
+    /// Encode a normalized name without assigning new identifiers. If some label is unknown (or if
+    /// the name is too deep), the name is truncated just before that label, and the returned flag
+    /// is `false`. Returns `None` if the name contains an empty label.
+    fn lookup(&self, name: &str) -> Option<(Bits<u128>, bool)> {
+        let labels = match split(name) {
+            Ok(labels) => labels,
+            Err(DomainError::TooDeep(_)) => name.rsplit('.').collect(),
+            Err(_) => return None,
+        };
+        let mut bits = 0u128;
+        for (depth, label) in labels.iter().enumerate() {
+            match self.labels.get(depth).and_then(|t| t.get(*label)) {
+                Some(id) => bits |= (*id as u128) << (128 - LABEL_BITS * (depth + 1)),
+                None => return Some((Bits::new(bits, (LABEL_BITS * depth) as u8), false)),
+            }
+        }
+        Some((Bits::new(bits, (LABEL_BITS * labels.len()) as u8), true))
+    }
+}
+
+/// Lower-case the name and remove the trailing dot.
+fn normalize(name: &str) -> String {
+    name.strip_suffix('.').unwrap_or(name).to_ascii_lowercase()
+}
+
+/// Split a normalized name into its labels, starting with the top-level domain.
+fn split(name: &str) -> Result<Vec<&str>, DomainError> {
+    if name.is_empty() {
+        return Ok(Vec::new());
+    }
+    let labels: Vec<&str> = name.rsplit('.').collect();
+    if labels.iter().any(|l| l.is_empty()) {
+        return Err(DomainError::EmptyLabel);
+    }
+    if labels.len() > MAX_DEPTH {
+        return Err(DomainError::TooDeep(labels.len()));
+    }
+    Ok(labels)
+}
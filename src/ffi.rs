@@ -0,0 +1,439 @@
+//! C interface for prefix maps of IPv4 and IPv6 prefixes with `u64` values.
+//!
+//! Maps are exposed as opaque handles ([`PtMapV4`] and [`PtMapV6`]), which are created by
+//! `pt_map_v4_new` (or `pt_map_v6_new`) and must be released by `pt_map_v4_free` (or
+//! `pt_map_v6_free`). The values are plain `u64`, so C code typically stores an index into its own
+//! table (or a pointer cast to an integer). IPv4 addresses are passed as `uint32_t` in host byte
+//! order (i.e., `10.0.0.1` is `0x0a000001`), and IPv6 addresses as pointers to 16 bytes in network
+//! byte order. Host bits of inserted prefixes are cleared.
+//!
+//! All functions report their outcome as a [`PtStatus`], and write results into out-pointers,
+//! which may be null if the caller is not interested in that result. All items only use types
+//! that `cbindgen` can translate, so a C header can be generated from this module. To link the
+//! functions into a C program, build a `staticlib` or `cdylib` crate that depends on
+//! `prefix-trie` with the `ffi` feature.
+//!
+//! ```
+//! # use prefix_trie::ffi::*;
+//! unsafe {
+//!     let map = pt_map_v4_new();
+//!     assert_eq!(pt_map_v4_insert(map, 0x0a000000, 8, 1), PtStatus::Ok);
+//!     assert_eq!(pt_map_v4_insert(map, 0x0a010000, 16, 2), PtStatus::Ok);
+//!     assert_eq!(pt_map_v4_insert(map, 0x0a010000, 33, 3), PtStatus::InvalidPrefix);
+//!
+//!     let (mut addr, mut len, mut value) = (0, 0, 0);
+//!     let status = pt_map_v4_lookup_lpm(map, 0x0a020304, 32, &mut addr, &mut len, &mut value);
+//!     assert_eq!(status, PtStatus::Ok);
+//!     assert_eq!((addr, len, value), (0x0a000000, 8, 1));
+//!
+//!     let status = pt_map_v4_remove(map, 0x0a000000, 8, std::ptr::null_mut());
+//!     assert_eq!(status, PtStatus::Ok);
+//!     let status = pt_map_v4_lookup_lpm(map, 0x0a020304, 32, &mut addr, &mut len, &mut value);
+//!     assert_eq!(status, PtStatus::NotFound);
+//!     assert_eq!(pt_map_v4_len(map), 1);
+//!     pt_map_v4_free(map);
+//! }
+//! ```
+
+use std::ffi::c_void;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use ipnet::{Ipv4Net, Ipv6Net};
+
+use crate::{Prefix, PrefixMap};
+
+/// Outcome of a function of the C interface.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PtStatus {
+    /// The operation succeeded.
+    Ok = 0,
+    /// The map contains no matching prefix.
+    NotFound = 1,
+    /// The prefix length exceeds the number of bits of the address.
+    InvalidPrefix = 2,
+    /// The map (or a required argument) is a null pointer.
+    NullPointer = 3,
+}
+
+/// Opaque handle of a map from IPv4 prefixes to `u64`.
+pub struct PtMapV4(PrefixMap<Ipv4Net, u64>);
+
+/// Opaque handle of a map from IPv6 prefixes to `u64`.
+pub struct PtMapV6(PrefixMap<Ipv6Net, u64>);
+
+/// Callback of [`pt_map_v4_iterate`], called with each prefix (address and length), its value,
+/// and the user pointer. Return `false` to stop the iteration.
+pub type PtIterV4 = Option<unsafe extern "C" fn(u32, u8, u64, *mut c_void) -> bool>;
+
+/// Callback of [`pt_map_v6_iterate`], called with each prefix (a pointer to the 16 bytes of the
+/// address, which is only valid during the call, and the length), its value, and the user
+/// pointer. Return `false` to stop the iteration.
+pub type PtIterV6 = Option<unsafe extern "C" fn(*const u8, u8, u64, *mut c_void) -> bool>;
+
+fn v4(addr: u32, len: u8) -> Option<Ipv4Net> {
+    Ipv4Net::new(Ipv4Addr::from(addr), len)
+        .ok()
+        .map(|p| p.trunc())
+}
+
+/// Safety: `addr` must be null or point to 16 readable bytes.
+unsafe fn v6(addr: *const u8, len: u8) -> Result<Ipv6Net, PtStatus> {
+    if addr.is_null() {
+        return Err(PtStatus::NullPointer);
+    }
+    let octets = *addr.cast::<[u8; 16]>();
+    Ipv6Net::new(Ipv6Addr::from(octets), len)
+        .map(|p| p.trunc())
+        .map_err(|_| PtStatus::InvalidPrefix)
+}
+
+/// Write `value` to `out`, unless `out` is null.
+///
+/// Safety: `out` must be null or valid for writes.
+unsafe fn write<T>(out: *mut T, value: T) {
+    if let Some(out) = out.as_mut() {
+        *out = value;
+    }
+}
+
+fn insert<P: Prefix>(
+    map: Option<&mut PrefixMap<P, u64>>,
+    prefix: Option<P>,
+    value: u64,
+) -> PtStatus {
+    let Some(map) = map else {
+        return PtStatus::NullPointer;
+    };
+    let Some(prefix) = prefix else {
+        return PtStatus::InvalidPrefix;
+    };
+    map.insert(prefix, value);
+    PtStatus::Ok
+}
+
+/// Safety: `value` must be null or valid for writes.
+unsafe fn remove<P: Prefix>(
+    map: Option<&mut PrefixMap<P, u64>>,
+    prefix: Option<P>,
+    value: *mut u64,
+) -> PtStatus {
+    let Some(map) = map else {
+        return PtStatus::NullPointer;
+    };
+    let Some(prefix) = prefix else {
+        return PtStatus::InvalidPrefix;
+    };
+    match map.remove(&prefix) {
+        Some(v) => {
+            write(value, v);
+            PtStatus::Ok
+        }
+        None => PtStatus::NotFound,
+    }
+}
+
+/// Safety: `value` must be null or valid for writes.
+unsafe fn get<P: Prefix>(
+    map: Option<&PrefixMap<P, u64>>,
+    prefix: Option<P>,
+    value: *mut u64,
+) -> PtStatus {
+    let Some(map) = map else {
+        return PtStatus::NullPointer;
+    };
+    let Some(prefix) = prefix else {
+        return PtStatus::InvalidPrefix;
+    };
+    match map.get(&prefix) {
+        Some(v) => {
+            write(value, *v);
+            PtStatus::Ok
+        }
+        None => PtStatus::NotFound,
+    }
+}
+
+/// Find the longest prefix match of `prefix` and pass it to `f`.
+fn lookup_lpm<P: Prefix, F: FnOnce(&P, u64)>(
+    map: Option<&PrefixMap<P, u64>>,
+    prefix: Option<P>,
+    f: F,
+) -> PtStatus {
+    let Some(map) = map else {
+        return PtStatus::NullPointer;
+    };
+    let Some(prefix) = prefix else {
+        return PtStatus::InvalidPrefix;
+    };
+    match map.get_lpm(&prefix) {
+        Some((p, v)) => {
+            f(p, *v);
+            PtStatus::Ok
+        }
+        None => PtStatus::NotFound,
+    }
+}
+
+/// Create a new, empty map of IPv4 prefixes. The map must be released with [`pt_map_v4_free`].
+#[no_mangle]
+pub extern "C" fn pt_map_v4_new() -> *mut PtMapV4 {
+    Box::into_raw(Box::new(PtMapV4(PrefixMap::new())))
+}
+
+/// Release a map created by [`pt_map_v4_new`]. Passing null does nothing.
+///
+/// # Safety
+///
+/// `map` must be null or a handle returned by [`pt_map_v4_new`] that was not yet released. The
+/// handle must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn pt_map_v4_free(map: *mut PtMapV4) {
+    if !map.is_null() {
+        drop(Box::from_raw(map));
+    }
+}
+
+/// Get the number of prefixes in the map (or zero if `map` is null).
+///
+/// # Safety
+///
+/// `map` must be null or a valid handle returned by [`pt_map_v4_new`].
+#[no_mangle]
+pub unsafe extern "C" fn pt_map_v4_len(map: *const PtMapV4) -> usize {
+    map.as_ref().map(|m| m.0.len()).unwrap_or(0)
+}
+
+/// Insert the prefix `addr/len` with `value`, replacing any previous value of that prefix.
+///
+/// # Safety
+///
+/// `map` must be null or a valid handle returned by [`pt_map_v4_new`].
+#[no_mangle]
+pub unsafe extern "C" fn pt_map_v4_insert(
+    map: *mut PtMapV4,
+    addr: u32,
+    len: u8,
+    value: u64,
+) -> PtStatus {
+    insert(map.as_mut().map(|m| &mut m.0), v4(addr, len), value)
+}
+
+/// Remove the prefix `addr/len`, and write its value to `value` (unless it is null).
+///
+/// # Safety
+///
+/// `map` must be null or a valid handle returned by [`pt_map_v4_new`], and `value` must be null
+/// or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn pt_map_v4_remove(
+    map: *mut PtMapV4,
+    addr: u32,
+    len: u8,
+    value: *mut u64,
+) -> PtStatus {
+    remove(map.as_mut().map(|m| &mut m.0), v4(addr, len), value)
+}
+
+/// Get the value of the prefix `addr/len` (using an exact match), and write it to `value`
+/// (unless it is null).
+///
+/// # Safety
+///
+/// `map` must be null or a valid handle returned by [`pt_map_v4_new`], and `value` must be null
+/// or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn pt_map_v4_get(
+    map: *const PtMapV4,
+    addr: u32,
+    len: u8,
+    value: *mut u64,
+) -> PtStatus {
+    get(map.as_ref().map(|m| &m.0), v4(addr, len), value)
+}
+
+/// Find the longest prefix in the map that contains `addr/len`, and write its address, length,
+/// and value to `out_addr`, `out_len`, and `value` (each unless it is null).
+///
+/// # Safety
+///
+/// `map` must be null or a valid handle returned by [`pt_map_v4_new`], and all out-pointers must
+/// be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn pt_map_v4_lookup_lpm(
+    map: *const PtMapV4,
+    addr: u32,
+    len: u8,
+    out_addr: *mut u32,
+    out_len: *mut u8,
+    value: *mut u64,
+) -> PtStatus {
+    lookup_lpm(map.as_ref().map(|m| &m.0), v4(addr, len), |p, v| {
+        write(out_addr, p.addr().into());
+        write(out_len, p.prefix_len());
+        write(value, v);
+    })
+}
+
+/// Call `callback` for each prefix in the map in lexicographic order, until it returns `false`.
+/// The map must not be modified by the callback.
+///
+/// # Safety
+///
+/// `map` must be null or a valid handle returned by [`pt_map_v4_new`], and `callback` must be
+/// safe to call with `user`.
+#[no_mangle]
+pub unsafe extern "C" fn pt_map_v4_iterate(
+    map: *const PtMapV4,
+    callback: PtIterV4,
+    user: *mut c_void,
+) -> PtStatus {
+    let (Some(map), Some(callback)) = (map.as_ref(), callback) else {
+        return PtStatus::NullPointer;
+    };
+    for (p, v) in map.0.iter() {
+        if !callback(p.addr().into(), p.prefix_len(), *v, user) {
+            break;
+        }
+    }
+    PtStatus::Ok
+}
+
+/// Create a new, empty map of IPv6 prefixes. The map must be released with [`pt_map_v6_free`].
+#[no_mangle]
+pub extern "C" fn pt_map_v6_new() -> *mut PtMapV6 {
+    Box::into_raw(Box::new(PtMapV6(PrefixMap::new())))
+}
+
+/// Release a map created by [`pt_map_v6_new`]. Passing null does nothing.
+///
+/// # Safety
+///
+/// `map` must be null or a handle returned by [`pt_map_v6_new`] that was not yet released. The
+/// handle must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn pt_map_v6_free(map: *mut PtMapV6) {
+    if !map.is_null() {
+        drop(Box::from_raw(map));
+    }
+}
+
+/// Get the number of prefixes in the map (or zero if `map` is null).
+///
+/// # Safety
+///
+/// `map` must be null or a valid handle returned by [`pt_map_v6_new`].
+#[no_mangle]
+pub unsafe extern "C" fn pt_map_v6_len(map: *const PtMapV6) -> usize {
+    map.as_ref().map(|m| m.0.len()).unwrap_or(0)
+}
+
+/// Insert the prefix `addr/len` with `value`, replacing any previous value of that prefix.
+///
+/// # Safety
+///
+/// `map` must be null or a valid handle returned by [`pt_map_v6_new`], and `addr` must point to
+/// 16 readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn pt_map_v6_insert(
+    map: *mut PtMapV6,
+    addr: *const u8,
+    len: u8,
+    value: u64,
+) -> PtStatus {
+    match v6(addr, len) {
+        Ok(prefix) => insert(map.as_mut().map(|m| &mut m.0), Some(prefix), value),
+        Err(e) => e,
+    }
+}
+
+/// Remove the prefix `addr/len`, and write its value to `value` (unless it is null).
+///
+/// # Safety
+///
+/// `map` must be null or a valid handle returned by [`pt_map_v6_new`], `addr` must point to 16
+/// readable bytes, and `value` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn pt_map_v6_remove(
+    map: *mut PtMapV6,
+    addr: *const u8,
+    len: u8,
+    value: *mut u64,
+) -> PtStatus {
+    match v6(addr, len) {
+        Ok(prefix) => remove(map.as_mut().map(|m| &mut m.0), Some(prefix), value),
+        Err(e) => e,
+    }
+}
+
+/// Get the value of the prefix `addr/len` (using an exact match), and write it to `value`
+/// (unless it is null).
+///
+/// # Safety
+///
+/// `map` must be null or a valid handle returned by [`pt_map_v6_new`], `addr` must point to 16
+/// readable bytes, and `value` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn pt_map_v6_get(
+    map: *const PtMapV6,
+    addr: *const u8,
+    len: u8,
+    value: *mut u64,
+) -> PtStatus {
+    match v6(addr, len) {
+        Ok(prefix) => get(map.as_ref().map(|m| &m.0), Some(prefix), value),
+        Err(e) => e,
+    }
+}
+
+/// Find the longest prefix in the map that contains `addr/len`, and write its address (16 bytes),
+/// length, and value to `out_addr`, `out_len`, and `value` (each unless it is null).
+///
+/// # Safety
+///
+/// `map` must be null or a valid handle returned by [`pt_map_v6_new`], `addr` must point to 16
+/// readable bytes, `out_addr` must be null or valid for writing 16 bytes, and all other
+/// out-pointers must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn pt_map_v6_lookup_lpm(
+    map: *const PtMapV6,
+    addr: *const u8,
+    len: u8,
+    out_addr: *mut u8,
+    out_len: *mut u8,
+    value: *mut u64,
+) -> PtStatus {
+    match v6(addr, len) {
+        Ok(prefix) => lookup_lpm(map.as_ref().map(|m| &m.0), Some(prefix), |p, v| {
+            write(out_addr.cast::<[u8; 16]>(), p.addr().octets());
+            write(out_len, p.prefix_len());
+            write(value, v);
+        }),
+        Err(e) => e,
+    }
+}
+
+/// Call `callback` for each prefix in the map in lexicographic order, until it returns `false`.
+/// The map must not be modified by the callback.
+///
+/// # Safety
+///
+/// `map` must be null or a valid handle returned by [`pt_map_v6_new`], and `callback` must be
+/// safe to call with `user`.
+#[no_mangle]
+pub unsafe extern "C" fn pt_map_v6_iterate(
+    map: *const PtMapV6,
+    callback: PtIterV6,
+    user: *mut c_void,
+) -> PtStatus {
+    let (Some(map), Some(callback)) = (map.as_ref(), callback) else {
+        return PtStatus::NullPointer;
+    };
+    for (p, v) in map.0.iter() {
+        let octets = p.addr().octets();
+        if !callback(octets.as_ptr(), p.prefix_len(), *v, user) {
+            break;
+        }
+    }
+    PtStatus::Ok
+}
@@ -17,7 +17,7 @@ impl<P: Debug, T: Debug> Debug for DebugPrefixMap<'_, P, T> {
         let map = self.0;
         let idx = self.1;
         let node = &map.table[idx];
-        match (node.value.as_ref(), node.left, node.right) {
+        match (map.table.value(idx), node.left(), node.right()) {
             (None, None, None) => node.prefix.fmt(f),
             (None, None, Some(child)) | (None, Some(child), None) => f
                 .debug_map()
@@ -45,3 +45,41 @@ impl<P: Debug> Debug for PrefixSet<P> {
         DebugPrefixMap(&self.0, 0).fmt(f)
     }
 }
+
+/// Format the set as a list of prefixes in CIDR notation, separated by `", "`, or one prefix per
+/// line with the alternate flag (`{:#}`). The list is aggregated: overlapping and adjacent
+/// prefixes are merged (see [`PrefixSet::ranges`]), and each merged range is printed as the
+/// minimal list of prefixes that covers it exactly (see [`crate::range::AddrRange::cidrs`]). The
+/// host part of the stored prefixes is not printed.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut set: PrefixSet<ipnet::Ipv4Net> = PrefixSet::new();
+/// set.insert("10.0.0.0/24".parse()?);
+/// set.insert("10.0.1.0/24".parse()?);
+/// set.insert("10.0.1.128/25".parse()?);
+/// set.insert("10.0.2.0/24".parse()?);
+/// set.insert("192.168.0.0/16".parse()?);
+/// assert_eq!(set.to_string(), "10.0.0.0/23, 10.0.2.0/24, 192.168.0.0/16");
+/// assert_eq!(format!("{set:#}"), "10.0.0.0/23\n10.0.2.0/24\n192.168.0.0/16");
+/// assert_eq!(PrefixSet::<ipnet::Ipv4Net>::new().to_string(), "");
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+impl<P: Prefix + std::fmt::Display> std::fmt::Display for PrefixSet<P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let sep = if f.alternate() { "\n" } else { ", " };
+        let cidrs = self.ranges().flat_map(|range| range.cidrs::<P>());
+        for (i, p) in cidrs.enumerate() {
+            if i > 0 {
+                f.write_str(sep)?;
+            }
+            write!(f, "{p}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,54 @@
+use std::collections::BTreeMap;
+
+use super::*;
+use crate::bag::PrefixBag;
+
+qc!(bag_counts, _bag_counts);
+fn _bag_counts(list: Vec<Operation<TestPrefix, u8>>) -> bool {
+    let mut bag = PrefixBag::new();
+    let mut model: BTreeMap<TestPrefix, usize> = BTreeMap::new();
+    for op in list {
+        let ok = match op {
+            Operation::Add(p, n) => {
+                let n = (n % 3) as usize;
+                if n > 0 {
+                    *model.entry(p).or_default() += n;
+                }
+                bag.insert_many(p, n) == model.get(&p).copied().unwrap_or(0)
+            }
+            Operation::Remove(p) => {
+                let want = model.get_mut(&p).map(|c| {
+                    *c -= 1;
+                    *c
+                });
+                model.retain(|_, c| *c > 0);
+                bag.remove(&p) == want
+            }
+        };
+        if !ok || bag.total() != model.values().sum::<usize>() {
+            return false;
+        }
+    }
+    bag.iter().eq(model.iter().map(|(p, c)| (p, *c)))
+}
+
+qc!(bag_ops, _bag_ops);
+fn _bag_ops((a, b): (Vec<TestPrefix>, Vec<TestPrefix>)) -> bool {
+    let count = |list: &[TestPrefix], p: &TestPrefix| list.iter().filter(|x| *x == p).count();
+    let bag_a: PrefixBag<TestPrefix> = a.iter().copied().collect();
+    let bag_b: PrefixBag<TestPrefix> = b.iter().copied().collect();
+    let sum = PrefixBag::sum(&bag_a, &bag_b);
+    let union = PrefixBag::union(&bag_a, &bag_b);
+    let intersection = PrefixBag::intersection(&bag_a, &bag_b);
+    let difference = PrefixBag::difference(&bag_a, &bag_b);
+    let no_zero = |bag: &PrefixBag<TestPrefix>| bag.iter().all(|(_, c)| c > 0);
+    a.iter().chain(b.iter()).all(|p| {
+        let (x, y) = (count(&a, p), count(&b, p));
+        sum.count(p) == x + y
+            && union.count(p) == x.max(y)
+            && intersection.count(p) == x.min(y)
+            && difference.count(p) == x.saturating_sub(y)
+    }) && sum.total() == a.len() + b.len()
+        && sum.len() == union.len()
+        && [sum, union, intersection, difference].iter().all(no_zero)
+}
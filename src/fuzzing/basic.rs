@@ -1,7 +1,7 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use super::*;
-use itertools::Itertools;
+use itertools::{EitherOrBoth, Itertools};
 
 qc!(new, _new);
 fn _new(list: Vec<(TestPrefix, i32)>) -> bool {
@@ -17,6 +17,34 @@ fn _new(list: Vec<(TestPrefix, i32)>) -> bool {
     pmap.into_iter().eq(hmap.into_iter().sorted())
 }
 
+qc!(from_iter, _from_iter);
+fn _from_iter(list: Vec<(TestPrefix, i32)>) -> bool {
+    let mut inserted = PrefixMap::new();
+    for (p, t) in list.iter() {
+        inserted.insert(*p, *t);
+    }
+    let collected: PrefixMap<TestPrefix, i32> = list.into_iter().collect();
+    let sorted: PrefixMap<TestPrefix, i32> = inserted.iter().map(|(p, t)| (*p, *t)).collect();
+    collected == inserted
+        && sorted == inserted
+        && collected.len() == inserted.len()
+        && sorted.len() == inserted.len()
+        && collected.iter().eq(inserted.iter())
+        && sorted.iter().eq(inserted.iter())
+}
+
+qc!(std_maps, _std_maps);
+fn _std_maps(map: PrefixMap<TestPrefix, i32>) -> bool {
+    let btree: BTreeMap<TestPrefix, i32> = map.clone().into();
+    let hash: HashMap<TestPrefix, i32> = map.clone().into();
+    btree.len() == map.len()
+        && hash.len() == map.len()
+        && PrefixMap::from(btree.clone()) == map
+        && PrefixMap::from(hash.clone()) == map
+        && PrefixMap::try_from_iter(hash).as_ref() == Ok(&map)
+        && btree.iter().eq(map.iter())
+}
+
 qc!(new_mods, _new_mods);
 fn _new_mods(list: Vec<Operation<TestPrefix, i32>>) -> bool {
     let mut pmap = PrefixMap::new();
@@ -61,6 +89,194 @@ fn _new_mods_entry(list: Vec<Operation<TestPrefix, i32>>) -> bool {
     pmap.into_iter().eq(hmap.into_iter().sorted())
 }
 
+qc!(unallocated, _unallocated);
+fn _unallocated((list, lookups): (Vec<(TestPrefix, i32)>, Vec<TestPrefix>)) -> bool {
+    let mut pmap: PrefixMap<TestPrefix, i32> = PrefixMap::new();
+    let mut ok = true;
+    for round in 0..2 {
+        // reading (or mutably iterating over) an empty map must not allocate the root.
+        ok &= lookups.iter().all(|p| {
+            pmap.get(p).is_none()
+                && pmap.get_lpm(p).is_none()
+                && pmap.cover(p).next().is_none()
+                && pmap.children(*p).next().is_none()
+                && pmap.view_at(*p).map_or(true, |v| v.iter().next().is_none())
+        });
+        ok &= pmap.iter().next().is_none()
+            && pmap.iter_mut().next().is_none()
+            && pmap.view().iter().next().is_none()
+            && pmap.table.len() == 0;
+        pmap.compact();
+        ok &= pmap.table.len() == 0 && pmap.check_invariants().is_ok();
+        for (p, t) in list.iter() {
+            pmap.insert(*p, t.wrapping_add(round));
+        }
+        ok &= pmap.check_invariants().is_ok()
+            && pmap.iter().count() == list.iter().map(|(p, _)| p).unique().count();
+        pmap.clear();
+    }
+    ok
+}
+
+qc!(prefix_len_bounds, _prefix_len_bounds);
+fn _prefix_len_bounds(list: Vec<Operation<TestPrefix, i32>>) -> bool {
+    let mut pmap = PrefixMap::new();
+    for (i, op) in list.into_iter().enumerate() {
+        match op {
+            Operation::Add(p, t) => {
+                if i % 2 == 0 {
+                    pmap.insert(p, t);
+                } else {
+                    pmap.entry(p).or_insert(t);
+                }
+            }
+            Operation::Remove(p) => match i % 3 {
+                0 => {
+                    pmap.remove(&p);
+                }
+                1 => {
+                    pmap.remove_keep_tree(&p);
+                }
+                _ => {
+                    if let Some(mut view) = pmap.view_mut_at(p) {
+                        view.remove();
+                    }
+                }
+            },
+        }
+        let lens = || pmap.iter().map(|(p, _)| p.prefix_len());
+        if pmap.min_prefix_len() != lens().min() || pmap.max_prefix_len() != lens().max() {
+            return false;
+        }
+    }
+    pmap.retain(|p, _| p.prefix_len() % 2 == 0);
+    let lens = || pmap.iter().map(|(p, _)| p.prefix_len());
+    pmap.min_prefix_len() == lens().min()
+        && pmap.max_prefix_len() == lens().max()
+        && pmap.check_invariants().is_ok()
+}
+
+qc!(invariants, _invariants);
+fn _invariants(list: Vec<Operation<TestPrefix, i32>>) -> bool {
+    let mut pmap = PrefixMap::new();
+    let mut hmap = HashMap::new();
+
+    for (i, op) in list.into_iter().enumerate() {
+        match op {
+            Operation::Add(p, t) => {
+                match i % 3 {
+                    0 => {
+                        pmap.insert(p, t);
+                    }
+                    1 => {
+                        pmap.entry(p).insert(t);
+                    }
+                    _ => {
+                        let set = match pmap.view_mut_at(p) {
+                            Some(mut view) if view.prefix() == &p => view.set(t).is_ok(),
+                            _ => false,
+                        };
+                        if !set {
+                            pmap.insert(p, t);
+                        }
+                    }
+                }
+                hmap.insert(p, t);
+            }
+            Operation::Remove(p) => {
+                match i % 4 {
+                    0 => {
+                        pmap.remove(&p);
+                    }
+                    1 => {
+                        pmap.remove_keep_tree(&p);
+                    }
+                    2 => {
+                        if let map::Entry::Occupied(mut e) = pmap.entry(p) {
+                            e.remove();
+                        }
+                    }
+                    _ => {
+                        if let Some(mut view) = pmap.view_mut_at(p) {
+                            if view.prefix() == &p {
+                                view.remove();
+                            }
+                        }
+                    }
+                }
+                hmap.remove(&p);
+            }
+        }
+        if pmap.check_invariants().is_err() || pmap.len() != hmap.len() {
+            return false;
+        }
+    }
+
+    pmap.compact();
+    pmap.check_invariants().is_ok() && pmap.into_iter().eq(hmap.into_iter().sorted())
+}
+
+qc!(node_ids, _node_ids);
+fn _node_ids(list: Vec<Operation<TestPrefix, i32>>) -> bool {
+    let mut pmap = PrefixMap::new();
+    let mut hmap: HashMap<TestPrefix, (i32, map::NodeId)> = HashMap::new();
+    let mut stale = Vec::new();
+
+    for (i, op) in list.into_iter().enumerate() {
+        match op {
+            Operation::Add(p, t) => {
+                let (id, _) = pmap.insert_with_id(p, t);
+                if let Some((_, old)) = hmap.insert(p, (t, id)) {
+                    if old != id {
+                        return false;
+                    }
+                }
+            }
+            Operation::Remove(p) => {
+                if i % 2 == 0 {
+                    pmap.remove(&p);
+                } else {
+                    pmap.remove_keep_tree(&p);
+                }
+                stale.extend(hmap.remove(&p).map(|(_, id)| id));
+            }
+        }
+        if i % 16 == 15 {
+            pmap.compact();
+            stale.extend(hmap.values().map(|(_, id)| *id));
+            for (p, (_, id)) in hmap.iter_mut() {
+                *id = pmap.node_id(p).unwrap();
+            }
+        }
+        if stale.iter().any(|id| pmap.get_by_id(*id).is_some()) {
+            return false;
+        }
+        if !hmap
+            .iter()
+            .all(|(p, (t, id))| pmap.get_by_id(*id) == Some((p, t)))
+        {
+            return false;
+        }
+    }
+    true
+}
+
+qc!(lpm_traced, _lpm_traced);
+fn _lpm_traced((map, prefix): (PrefixMap<TestPrefix, i32>, TestPrefix)) -> bool {
+    let trace = map.get_lpm_traced(&prefix);
+    // all visited nodes contain the prefix, and the last candidate is the result.
+    trace.result == map.get_lpm(&prefix)
+        && trace.steps.iter().all(|s| s.prefix.contains(&prefix))
+        && trace.steps.iter().rfind(|s| s.has_value).map(|s| s.prefix)
+            == trace.result.map(|(p, _)| p)
+        && trace
+            .steps
+            .iter()
+            .rev()
+            .skip(1)
+            .all(|s| matches!(s.action, map::LpmAction::Enter { .. }))
+}
+
 qc!(equality, _equality);
 fn _equality(list: Vec<Operation<TestPrefix, i32>>) -> bool {
     let mut map = PrefixMap::default();
@@ -82,6 +298,738 @@ fn _equality(list: Vec<Operation<TestPrefix, i32>>) -> bool {
     map == clone && map.len() == clone.len() && map.is_empty() == clone.is_empty()
 }
 
+qc!(clone_from, _clone_from);
+fn _clone_from((a, b): (Vec<(TestPrefix, i32)>, Vec<(TestPrefix, i32)>)) -> bool {
+    let source: PrefixMap<TestPrefix, i32> = a.into_iter().collect();
+    let mut dest: PrefixMap<TestPrefix, i32> = b.into_iter().collect();
+    dest.clone_from(&source);
+    dest == source && dest.len() == source.len() && dest.iter().eq(source.iter())
+}
+
+qc!(compact, _compact);
+fn _compact(
+    (list, rest): (
+        Vec<Operation<TestPrefix, i32>>,
+        Vec<Operation<TestPrefix, i32>>,
+    ),
+) -> bool {
+    let mut map = PrefixMap::default();
+    let apply = |map: &mut PrefixMap<_, _>, list: Vec<Operation<TestPrefix, i32>>| {
+        for op in list {
+            match op {
+                Operation::Add(p, t) => {
+                    map.insert(p, t);
+                }
+                Operation::Remove(p) => {
+                    map.remove_keep_tree(&p);
+                }
+            }
+        }
+    };
+    apply(&mut map, list);
+    let mut compacted = map.clone();
+    compacted.compact();
+    if map != compacted || !map.iter().eq(compacted.iter()) {
+        return false;
+    }
+    // the compacted map must continue to work
+    apply(&mut map, rest.clone());
+    apply(&mut compacted, rest);
+    map == compacted && map.len() == compacted.len() && map.iter().eq(compacted.iter())
+}
+
+qc!(optimize, _optimize);
+fn _optimize(
+    (list, rest, queries): (
+        Vec<Operation<TestPrefix, i32>>,
+        Vec<Operation<TestPrefix, i32>>,
+        Vec<TestPrefix>,
+    ),
+) -> bool {
+    let mut map = PrefixMap::default();
+    let apply = |map: &mut PrefixMap<_, _>, list: Vec<Operation<TestPrefix, i32>>| {
+        for op in list {
+            match op {
+                Operation::Add(p, t) => {
+                    map.insert(p, t);
+                }
+                Operation::Remove(p) => {
+                    map.remove(&p);
+                }
+            }
+        }
+    };
+    apply(&mut map, list);
+    let mut optimized = map.clone();
+    optimized.optimize();
+    if map != optimized
+        || !map.iter().eq(optimized.iter())
+        || !queries
+            .iter()
+            .all(|q| map.get_lpm(q) == optimized.get_lpm(q))
+    {
+        return false;
+    }
+    // the optimized map must continue to work
+    apply(&mut map, rest.clone());
+    apply(&mut optimized, rest);
+    map == optimized && map.len() == optimized.len() && map.iter().eq(optimized.iter())
+}
+
+qc!(write_read, _write_read);
+fn _write_read(map: PrefixMap<TestPrefix, i32>) -> bool {
+    let mut buf = Vec::new();
+    map.write_to(&mut buf).unwrap();
+    let loaded = PrefixMap::<TestPrefix, i32>::read_from(buf.as_slice()).unwrap();
+    let mut corrupted = buf.clone();
+    corrupted[20] ^= 1;
+    loaded == map
+        && loaded.len() == map.len()
+        && PrefixMap::<TestPrefix, i32>::read_from(corrupted.as_slice()).is_err()
+        && PrefixMap::<TestPrefix, i32>::read_from(&buf[..buf.len() - 1]).is_err()
+        && PrefixMap::<TestPrefix, u8>::read_from(buf.as_slice()).is_err()
+}
+
+qc!(concurrent, _concurrent);
+fn _concurrent(batches: Vec<Vec<Operation<TestPrefix, i32>>>) -> bool {
+    let mut map = PrefixMap::default();
+    let concurrent = crate::map::ConcurrentPrefixMap::new();
+    let mut snapshots = Vec::new();
+    for batch in batches {
+        let before = concurrent.load();
+        if *before != map {
+            return false;
+        }
+        snapshots.push((before, map.clone()));
+        let ok = concurrent.update(|pm| {
+            batch.into_iter().all(|op| match op {
+                Operation::Add(p, t) => map.insert(p, t) == pm.insert(p, t),
+                Operation::Remove(p) => map.remove(&p) == pm.remove(&p),
+            })
+        });
+        if !ok {
+            return false;
+        }
+        // drop some snapshots, such that their allocation is reused.
+        snapshots.retain(|(s, _)| s.len() % 2 == 0);
+    }
+    snapshots.iter().all(|(s, m)| **s == *m)
+        && *concurrent.load() == map
+        && concurrent.into_inner() == map
+}
+
+#[cfg(feature = "metrics")]
+qc!(metrics, _metrics);
+#[cfg(feature = "metrics")]
+fn _metrics((list, lookups): (Vec<Operation<TestPrefix, i32>>, Vec<TestPrefix>)) -> bool {
+    let mut map = PrefixMap::new();
+    let (mut removes, mut hits) = (0, 0);
+    for op in list.iter() {
+        match op {
+            Operation::Add(p, t) => {
+                map.insert(*p, *t);
+            }
+            Operation::Remove(p) => removes += map.remove(p).is_some() as usize,
+        }
+    }
+    for p in lookups.iter() {
+        hits += map.get_lpm(p).is_some() as usize;
+    }
+    let metrics = map.metrics();
+    metrics.inserts
+        == list.len()
+            - list
+                .iter()
+                .filter(|op| matches!(op, Operation::Remove(_)))
+                .count()
+        && metrics.removes == removes
+        && metrics.lookups == lookups.len()
+        && metrics.lpm_hits == hits
+        && metrics.lpm_misses == lookups.len() - hits
+        && (lookups.is_empty() || metrics.max_depth >= 1)
+}
+
+#[cfg(feature = "epoch")]
+qc!(epoch, _epoch);
+#[cfg(feature = "epoch")]
+fn _epoch(batches: Vec<Vec<Operation<TestPrefix, i32>>>) -> bool {
+    let mut map = PrefixMap::default();
+    let epoch = crate::map::EpochPrefixMap::new();
+    for batch in batches {
+        let before = epoch.pin();
+        let old = map.clone();
+        let ok = epoch.update(|pm| {
+            batch.into_iter().all(|op| match op {
+                Operation::Add(p, t) => map.insert(p, t) == pm.insert(p, t),
+                Operation::Remove(p) => map.remove(&p) == pm.remove(&p),
+            })
+        });
+        // the pinned snapshot is not affected by the update.
+        if !ok || *before != old || *epoch.pin() != map {
+            return false;
+        }
+    }
+    *epoch.pin() == map && epoch.into_inner() == map
+}
+
+qc!(sharded, _sharded);
+fn _sharded((bits, list, queries): (u8, Vec<Operation<TestPrefix, i32>>, Vec<TestPrefix>)) -> bool {
+    let mut map = PrefixMap::default();
+    let sharded = crate::map::ShardedPrefixMap::new(bits % 9);
+    for op in list {
+        let ok = match op {
+            Operation::Add(p, t) if t % 2 == 0 => map.insert(p, t) == sharded.insert(p, t),
+            Operation::Add(p, t) => {
+                // flipping the first bit moves `p` into another shard, unless it is too short.
+                let q = TestPrefix(p.0 ^ (1 << 31), p.1);
+                sharded.with_shard_mut(&p, |s| {
+                    (s.belongs(&q) || s.insert(q, t) == Err(t))
+                        && s.insert(p, t) == Ok(map.insert(p, t))
+                })
+            }
+            Operation::Remove(p) => map.remove(&p) == sharded.remove(&p),
+        };
+        if !ok {
+            return false;
+        }
+    }
+    sharded.len() == map.len()
+        && sharded.is_empty() == map.is_empty()
+        && queries.iter().all(|q| {
+            sharded.get(q) == map.get(q).copied()
+                && sharded.contains_key(q) == map.contains_key(q)
+                && sharded.get_lpm(q) == map.get_lpm(q).map(|(p, t)| (*p, *t))
+        })
+        && sharded.to_map() == map
+        && sharded.into_map() == map
+}
+
+qc!(sync, _sync);
+fn _sync((map, locks): (PrefixMap<TestPrefix, i32>, Vec<TestPrefix>)) -> bool {
+    let mut expected = map.clone();
+    let sync = crate::map::SyncPrefixMap::new(map);
+    let mut guards: Vec<crate::map::SubtreeGuard<'_, TestPrefix, i32>> = Vec::new();
+    for p in locks {
+        let overlap = guards
+            .iter()
+            .any(|g| g.prefix().contains(&p) || p.contains(g.prefix()));
+        match sync.try_lock(p) {
+            Some(g) if !overlap => guards.push(g),
+            None if overlap => {}
+            _ => return false,
+        }
+    }
+    for g in guards.iter_mut() {
+        if let Some(mut view) = g.view_mut() {
+            view.iter_mut().for_each(|(_, t)| *t = t.wrapping_add(1));
+        }
+    }
+    let locked = guards.iter().map(|g| *g.prefix()).collect::<Vec<_>>();
+    drop(guards);
+    for (p, t) in expected.iter_mut() {
+        if locked.iter().any(|l| l.contains(p)) {
+            *t = t.wrapping_add(1);
+        }
+    }
+    sync.into_inner() == expected
+}
+
+qc!(journal, _journal);
+fn _journal(
+    (init, batches): (
+        PrefixMap<TestPrefix, i32>,
+        Vec<Vec<Operation<TestPrefix, i32>>>,
+    ),
+) -> bool {
+    let mut replica = init.clone();
+    let mut primary = crate::map::JournaledPrefixMap::from(init);
+    for (i, batch) in batches.into_iter().enumerate() {
+        for op in batch {
+            match op {
+                Operation::Add(p, t) if t % 3 == 0 => {
+                    primary.modify(&p, |x| *x = t);
+                }
+                Operation::Add(p, t) => {
+                    primary.insert(p, t);
+                }
+                Operation::Remove(p) => {
+                    primary.remove(&p);
+                }
+            }
+        }
+        if i % 5 == 4 {
+            primary.clear();
+        }
+        let ops = primary.drain_journal();
+        let mut buf = Vec::new();
+        crate::map::JournalOp::write_all(&ops, &mut buf).unwrap();
+        if crate::map::JournalOp::read_all(buf.as_slice()).unwrap() != ops {
+            return false;
+        }
+        replica.apply_journal(ops);
+        if &replica != primary.as_map() || !primary.journal().is_empty() {
+            return false;
+        }
+    }
+    true
+}
+
+/// Observer that mirrors the observed map.
+#[derive(Default)]
+struct Mirror(PrefixMap<TestPrefix, i32>, bool);
+
+impl crate::map::Observer<TestPrefix, i32> for Mirror {
+    fn on_insert(&mut self, prefix: &TestPrefix, value: &i32) {
+        self.1 &= self.0.insert(*prefix, *value).is_none();
+    }
+    fn on_overwrite(&mut self, prefix: &TestPrefix, old: &i32, new: &i32) {
+        self.1 &= self.0.insert(*prefix, *new) == Some(*old);
+    }
+    fn on_remove(&mut self, prefix: &TestPrefix, old: &i32) {
+        self.1 &= self.0.remove(prefix) == Some(*old);
+    }
+}
+
+qc!(default_route, _default_route);
+fn _default_route((list, lookups): (Vec<Operation<TestPrefix, i32>>, Vec<TestPrefix>)) -> bool {
+    let mut map = PrefixMap::new();
+    for op in list {
+        match op {
+            Operation::Add(p, t) if p.prefix_len() == 0 => {
+                map.set_default(t);
+            }
+            Operation::Add(p, t) => {
+                map.insert(p, t);
+            }
+            Operation::Remove(p) => {
+                map.remove(&p);
+            }
+        }
+    }
+    let root = TestPrefix::zero();
+    map.get_default() == map.get(&root)
+        && map.has_default() == map.contains_key(&root)
+        && lookups
+            .iter()
+            .all(|p| map.get_lpm(p).is_some() || !map.has_default())
+}
+
+qc!(observer, _observer);
+fn _observer(list: Vec<Operation<TestPrefix, i32>>) -> bool {
+    let mut map = PrefixMap::default();
+    let mut observed = crate::map::ObservedPrefixMap::new(Mirror(PrefixMap::new(), true));
+    for op in list {
+        let ok = match op {
+            Operation::Add(p, t) => map.insert(p, t) == observed.insert(p, t),
+            Operation::Remove(p) => map.remove(&p) == observed.remove(&p),
+        };
+        if !ok || observed.observer().0 != map {
+            return false;
+        }
+    }
+    observed.retain(|_, t| t % 2 == 0);
+    map.retain(|_, t| t % 2 == 0);
+    let ok = observed.observer().0 == map;
+    observed.clear();
+    ok && observed.observer().0.is_empty() && observed.observer().1
+}
+
+#[cfg(feature = "futures")]
+qc!(subscribe, _subscribe);
+#[cfg(feature = "futures")]
+fn _subscribe(list: Vec<Operation<TestPrefix, i32>>) -> bool {
+    use crate::map::Change;
+    let mut map = crate::map::SubscribedPrefixMap::default();
+    let mut changes = map.subscribe();
+    drop(map.subscribe());
+    let mut mirror = PrefixMap::new();
+    for op in list {
+        match op {
+            Operation::Add(p, t) => {
+                map.insert(p, t);
+            }
+            Operation::Remove(p) => {
+                map.remove(&p);
+            }
+        }
+    }
+    let ok = map.observer().len() == 1;
+    while let Some(change) = changes.try_recv() {
+        let ok = match change {
+            Change::Insert { prefix, value } => mirror.insert(prefix, value).is_none(),
+            Change::Update { prefix, old, new } => mirror.insert(prefix, new) == Some(old),
+            Change::Remove { prefix, old } => mirror.remove(&prefix) == Some(old),
+        };
+        if !ok {
+            return false;
+        }
+    }
+    ok && &mirror == map.as_map()
+}
+
+qc!(expiring, _expiring);
+fn _expiring(list: Vec<(Operation<TestPrefix, i32>, u8)>) -> bool {
+    let mut map = crate::map::ExpiringPrefixMap::new();
+    let mut model: HashMap<TestPrefix, (i32, u8)> = HashMap::new();
+    for (op, deadline) in list {
+        let ok = match op {
+            Operation::Add(p, _) if deadline % 3 == 0 => {
+                map.refresh(&p, deadline) == model.get_mut(&p).map(|x| x.1 = deadline).is_some()
+            }
+            Operation::Add(p, t) => {
+                map.insert(p, t, deadline) == model.insert(p, (t, deadline)).map(|x| x.0)
+            }
+            Operation::Remove(p) if deadline % 2 == 0 => {
+                map.remove(&p) == model.remove(&p).map(|x| x.0)
+            }
+            Operation::Remove(_) => {
+                let mut expired = map.expire_before(&deadline);
+                let mut want: Vec<(TestPrefix, i32)> = model
+                    .iter()
+                    .filter(|(_, (_, d))| *d <= deadline)
+                    .map(|(p, (t, _))| (*p, *t))
+                    .collect();
+                model.retain(|_, (_, d)| *d > deadline);
+                expired.sort();
+                want.sort();
+                expired == want
+            }
+        };
+        let want_next = model.values().map(|(_, d)| d).min();
+        if !ok || map.len() != model.len() || map.next_deadline() != want_next {
+            return false;
+        }
+    }
+    model
+        .iter()
+        .all(|(p, (t, d))| map.get(p) == Some(t) && map.deadline(p) == Some(d))
+}
+
+qc!(priority, _priority);
+fn _priority(list: Vec<(Operation<TestPrefix, i32>, u8)>) -> bool {
+    let mut map = crate::map::PriorityPrefixMap::new();
+    let mut model: HashMap<(TestPrefix, u8), (u32, i32)> = HashMap::new();
+    for (op, source) in list {
+        let source = source % 4;
+        match op {
+            Operation::Add(p, t) => {
+                let distance = (t % 3).unsigned_abs();
+                if map.insert(p, source, distance, t)
+                    != model.insert((p, source), (distance, t)).map(|x| x.1)
+                {
+                    return false;
+                }
+            }
+            Operation::Remove(p) if p.1 % 4 == 0 => {
+                let want = model.len();
+                model.retain(|(_, s), _| *s != source);
+                if map.withdraw_source(&source) != want - model.len() {
+                    return false;
+                }
+            }
+            Operation::Remove(p) => {
+                if map.remove(&p, &source) != model.remove(&(p, source)).map(|x| x.1) {
+                    return false;
+                }
+            }
+        }
+    }
+    let mut want: BTreeMap<TestPrefix, (u32, u8, i32)> = BTreeMap::new();
+    for ((p, s), (d, t)) in model {
+        let best = want.entry(p).or_insert((d, s, t));
+        if (d, s) < (best.0, best.1) {
+            *best = (d, s, t);
+        }
+    }
+    map.iter().eq(want.iter().map(|(p, (_, _, t))| (p, t)))
+}
+
+#[cfg(feature = "arbitrary")]
+qc!(arbitrary, _arbitrary);
+#[cfg(feature = "arbitrary")]
+fn _arbitrary(data: Vec<u8>) -> bool {
+    use ::arbitrary::{Arbitrary, Unstructured};
+    let mut u = Unstructured::new(&data);
+    let Ok(map) = PrefixMap::<(u16, u8), u8>::arbitrary(&mut u) else {
+        return false;
+    };
+    let Ok(set) = PrefixSet::<Bits<u8>>::arbitrary(&mut u) else {
+        return false;
+    };
+    map.iter().all(|(p, _)| p.1 <= 16 && p.0 == Prefix::mask(p))
+        && set.iter().all(|p| p.prefix_len() <= 8)
+        && map.clone().into_iter().collect::<PrefixMap<_, _>>() == map
+}
+
+qc!(interned, _interned);
+fn _interned((list, queries): (Vec<Operation<TestPrefix, u8>>, Vec<TestPrefix>)) -> bool {
+    let mut map = PrefixMap::default();
+    let mut interned = crate::map::InternedPrefixMap::new();
+    for op in list {
+        let ok = match op {
+            Operation::Add(p, t) => map.insert(p, t % 4) == interned.insert(p, t % 4),
+            Operation::Remove(p) => map.remove(&p) == interned.remove(&p),
+        };
+        if !ok {
+            return false;
+        }
+    }
+    interned.len() == map.len()
+        && interned.iter().eq(map.iter())
+        && interned.num_values() == map.values().collect::<std::collections::HashSet<_>>().len()
+        && queries.iter().all(|q| {
+            interned.get(q) == map.get(q)
+                && interned.contains_key(q) == map.contains_key(q)
+                && interned.get_lpm(q) == map.get_lpm(q)
+        })
+        && PrefixMap::from(interned) == map
+}
+
+#[derive(Clone)]
+struct Sum(u64);
+
+impl crate::map::Aggregate<u8> for Sum {
+    fn from_value(value: &u8) -> Self {
+        Sum(*value as u64)
+    }
+
+    fn combine(&mut self, other: &Self) {
+        self.0 += other.0;
+    }
+}
+
+qc!(aggregate, _aggregate);
+fn _aggregate((list, queries): (Vec<Operation<TestPrefix, u8>>, Vec<TestPrefix>)) -> bool {
+    let mut map = PrefixMap::default();
+    let mut agg: crate::map::AggregateMap<_, _, Sum> = crate::map::AggregateMap::new();
+    for op in list {
+        let ok = match op {
+            Operation::Add(p, t) => map.insert(p, t) == agg.insert(p, t),
+            Operation::Remove(p) => map.remove(&p) == agg.remove(&p),
+        };
+        if !ok {
+            return false;
+        }
+    }
+    agg.len() == map.len()
+        && agg.iter().eq(map.iter())
+        && queries.iter().chain(map.keys()).all(|q| {
+            let covered = map
+                .iter()
+                .filter(|(p, _)| q.contains(p))
+                .collect::<Vec<_>>();
+            let expected =
+                (!covered.is_empty()).then(|| covered.iter().map(|(_, t)| **t as u64).sum::<u64>());
+            agg.aggregate(q).map(|s| s.0) == expected
+        })
+}
+
+qc!(aggregate_select_weighted, _aggregate_select_weighted);
+fn _aggregate_select_weighted(list: Vec<(TestPrefix, u8)>) -> bool {
+    let agg: crate::map::AggregateMap<_, _, Sum> = list.into_iter().collect();
+    // each entry occupies as many positions as its weight, in lexicographic order.
+    let positions = agg
+        .iter()
+        .flat_map(|(p, t)| std::iter::repeat((p, t)).take(*t as usize));
+    positions
+        .enumerate()
+        .all(|(x, want)| agg.select_weighted(x as u64, |s| s.0) == Some(want))
+}
+
+#[cfg(feature = "rand")]
+qc!(sample_weighted, _sample_weighted);
+#[cfg(feature = "rand")]
+fn _sample_weighted((map, seed): (PrefixMap<TestPrefix, u8>, u64)) -> bool {
+    use rand::SeedableRng;
+    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+    let agg: crate::map::AggregateMap<_, _, Sum> = map.iter().map(|(p, t)| (*p, *t % 4)).collect();
+    let weighted = |_: &TestPrefix, t: &u8| (*t % 4) as u64;
+    let total: u64 = map.iter().map(|(p, t)| weighted(p, t)).sum();
+    (0..8).all(|_| {
+        let a = map.sample_weighted(&mut rng, weighted);
+        let b = agg.sample_weighted(&mut rng, |s| s.0);
+        match (a, b) {
+            (None, None) => total == 0,
+            (Some((p, t)), Some((q, _))) => weighted(p, t) > 0 && agg.get(q).unwrap() > &0,
+            _ => false,
+        }
+    })
+}
+
+qc!(indexed, _indexed);
+fn _indexed((list, queries): (Vec<Operation<TestPrefix, i32>>, Vec<TestPrefix>)) -> bool {
+    let mut map = PrefixMap::default();
+    let mut indexed = crate::map::IndexedPrefixMap::new();
+    for op in list {
+        let ok = match op {
+            Operation::Add(p, t) => map.insert(p, t) == indexed.insert(p, t),
+            Operation::Remove(p) => map.remove(&p) == indexed.remove(&p),
+        };
+        if !ok {
+            return false;
+        }
+    }
+    indexed.len() == map.len()
+        && indexed.iter().eq(map.iter())
+        && queries.iter().chain(map.keys()).all(|q| {
+            indexed.get_key_value(q) == map.get_key_value(q)
+                && indexed.contains_key(q) == map.contains_key(q)
+                && indexed.get_lpm(q) == map.get_lpm(q)
+        })
+}
+
+qc!(flat, _flat);
+fn _flat((list, other): (Vec<Operation<TestPrefix, i32>>, Vec<(TestPrefix, i32)>)) -> bool {
+    let mut map = PrefixMap::default();
+    for op in list {
+        match op {
+            Operation::Add(p, t) => {
+                map.insert(p, t);
+            }
+            Operation::Remove(p) => {
+                map.remove_keep_tree(&p);
+            }
+        }
+    }
+    let flat = map.to_flat();
+    let mut compacted = map.clone();
+    compacted.compact();
+    let rebuilt = PrefixMap::from_flat(flat.clone()).unwrap();
+    // the topology must be preserved exactly.
+    if rebuilt.to_flat() != flat || compacted.to_flat() != flat || rebuilt != map {
+        return false;
+    }
+    // the map must continue to work
+    let mut rebuilt = rebuilt;
+    for (p, t) in other {
+        if map.insert(p, t) != rebuilt.insert(p, t) {
+            return false;
+        }
+    }
+    rebuilt == map && rebuilt.len() == map.len()
+}
+
+qc!(flat_invalid, _flat_invalid);
+fn _flat_invalid((map, idx, parent): (PrefixMap<TestPrefix, i32>, usize, usize)) -> bool {
+    let mut flat = map.to_flat();
+    let n = flat.nodes.len();
+    if n < 2 {
+        return true;
+    }
+    let (idx, parent) = (1 + idx % (n - 1), parent % n);
+    if flat.nodes[idx].parent == Some(parent) {
+        return true;
+    }
+    flat.nodes[idx].parent = Some(parent);
+    PrefixMap::from_flat(flat).is_err()
+}
+
+qc!(freeze, _freeze);
+fn _freeze((list, queries): (Vec<Operation<TestPrefix, i32>>, Vec<TestPrefix>)) -> bool {
+    let mut map = PrefixMap::default();
+    for op in list {
+        match op {
+            Operation::Add(p, t) => {
+                map.insert(p, t);
+            }
+            Operation::Remove(p) => {
+                map.remove_keep_tree(&p);
+            }
+        }
+    }
+    let frozen = map.clone().freeze();
+    let queries = queries
+        .into_iter()
+        .chain(map.keys().copied())
+        .collect::<Vec<_>>();
+    frozen.len() == map.len()
+        && frozen.iter().eq(map.iter())
+        && frozen.values().eq(map.values())
+        && queries.iter().all(|q| {
+            frozen.get(q) == map.get(q)
+                && frozen.contains_key(q) == map.contains_key(q)
+                && frozen.get_lpm(q) == map.get_lpm(q)
+        })
+        && frozen.thaw() == map
+}
+
+qc!(lpm_batch, _lpm_batch);
+fn _lpm_batch((list, queries): (Vec<(TestPrefix, i32)>, Vec<TestPrefix>)) -> bool {
+    let map: PrefixMap<TestPrefix, i32> = list.into_iter().collect();
+    let expected = queries.iter().map(|q| map.get_lpm(q)).collect::<Vec<_>>();
+    map.get_lpm_batch(&queries) == expected
+}
+
+qc!(frozen_bytes, _frozen_bytes);
+fn _frozen_bytes((list, queries): (Vec<Operation<TestPrefix, i32>>, Vec<TestPrefix>)) -> bool {
+    let mut map = PrefixMap::default();
+    for op in list {
+        match op {
+            Operation::Add(p, t) => {
+                map.insert(p, t);
+            }
+            Operation::Remove(p) => {
+                map.remove_keep_tree(&p);
+            }
+        }
+    }
+    let frozen = map.clone().freeze();
+    let bytes = frozen.to_bytes();
+    let view = crate::map::FrozenBytes::<TestPrefix, i32>::new(&bytes).unwrap();
+    let queries = queries
+        .into_iter()
+        .chain(map.keys().copied())
+        .collect::<Vec<_>>();
+    let mut corrupted = bytes.clone();
+    *corrupted.last_mut().unwrap() ^= 1;
+    view.len() == map.len()
+        && view.iter().eq(map.iter().map(|(p, t)| (*p, *t)))
+        && queries.iter().all(|q| {
+            view.get(q) == map.get(q).copied()
+                && view.contains_key(q) == map.contains_key(q)
+                && view.get_lpm(q) == map.get_lpm(q).map(|(p, t)| (*p, *t))
+        })
+        && view.to_frozen().thaw() == map
+        && crate::map::FrozenBytes::<TestPrefix, i32>::new(&corrupted).is_err()
+        && crate::map::FrozenBytes::<TestPrefix, u8>::new(&bytes).is_err()
+}
+
+qc!(frozen_bytes_corrupted, _frozen_bytes_corrupted);
+fn _frozen_bytes_corrupted(
+    (list, pos, flip): (Vec<Operation<TestPrefix, i32>>, usize, u8),
+) -> bool {
+    let mut map = PrefixMap::default();
+    for op in list {
+        match op {
+            Operation::Add(p, t) => {
+                map.insert(p, t);
+            }
+            Operation::Remove(p) => {
+                map.remove_keep_tree(&p);
+            }
+        }
+    }
+    let mut bytes = map.freeze().to_bytes();
+    // corrupt a single byte of the body, and fix the checksum.
+    let body_pos = 40 + pos % (bytes.len() - 40);
+    bytes[body_pos] ^= flip;
+    let checksum = crate::map::checksum(&bytes[40..]);
+    bytes[32..40].copy_from_slice(&checksum.to_le_bytes());
+    let Ok(view) = crate::map::FrozenBytes::<TestPrefix, i32>::new(&bytes) else {
+        return true;
+    };
+    // if the buffer is accepted, it must describe a valid tree.
+    let mut thawed = view.to_frozen().thaw();
+    let keys = thawed.keys().copied().collect::<Vec<_>>();
+    keys.len() == view.len()
+        && thawed.len() == view.len()
+        && keys.windows(2).all(|w| w[0] < w[1])
+        && thawed.iter_mut().count() == view.len()
+        && view
+            .iter()
+            .all(|(p, t)| thawed.get(&p) == Some(&t) && view.get(&p) == Some(t))
+}
+
 qc!(equality_keep_tree, _equality_keep_tree);
 fn _equality_keep_tree(list: Vec<Operation<TestPrefix, i32>>) -> bool {
     let mut map = PrefixMap::default();
@@ -124,6 +1072,31 @@ fn _equality_set(list: Vec<Operation<TestPrefix, ()>>) -> bool {
     set == clone && set.len() == clone.len() && set.is_empty() == clone.is_empty()
 }
 
+qc!(ordering, _ordering);
+fn _ordering((a, b): (PrefixMap<TestPrefix, i32>, PrefixMap<TestPrefix, i32>)) -> bool {
+    // also compare `a` with its own prefix, which must be smaller unless they are equal.
+    let c: PrefixMap<_, _> = a.iter().take(a.len() / 2).map(|(p, t)| (*p, *t)).collect();
+    check_ordering(&a, &b) && check_ordering(&a, &c) && check_ordering(&c, &a)
+}
+
+fn check_ordering(x: &PrefixMap<TestPrefix, i32>, y: &PrefixMap<TestPrefix, i32>) -> bool {
+    let want = x
+        .iter()
+        .collect::<Vec<_>>()
+        .cmp(&y.iter().collect::<Vec<_>>());
+    let set_x: PrefixSet<_> = x.keys().copied().collect();
+    let set_y: PrefixSet<_> = y.keys().copied().collect();
+    let want_set = set_x
+        .iter()
+        .collect::<Vec<_>>()
+        .cmp(&set_y.iter().collect::<Vec<_>>());
+    x.cmp(y) == want
+        && x.partial_cmp(y) == Some(want)
+        && (x == y) == want.is_eq()
+        && set_x.cmp(&set_y) == want_set
+        && (set_x == set_y) == want_set.is_eq()
+}
+
 qc!(remove_children, _remove_children);
 fn _remove_children((mut map, root): (PrefixMap<TestPrefix, i32>, TestPrefix)) -> bool {
     let want = select(&map, |p, _| !root.contains(p));
@@ -131,6 +1104,83 @@ fn _remove_children((mut map, root): (PrefixMap<TestPrefix, i32>, TestPrefix)) -
     map.len() == want.len() && map.into_iter().eq(want)
 }
 
+qc!(remove_cover, _remove_cover);
+fn _remove_cover((mut map, prefix): (PrefixMap<TestPrefix, i32>, TestPrefix)) -> bool {
+    let want_removed: Vec<(TestPrefix, i32)> = map.cover(&prefix).map(|(p, t)| (*p, *t)).collect();
+    let want = select(&map, |p, _| !p.contains(&prefix));
+    let removed = map.remove_cover(&prefix);
+    removed == want_removed
+        && map.check_invariants().is_ok()
+        && map.len() == want.len()
+        && map.into_iter().eq(want)
+}
+
+qc!(remove_lpm, _remove_lpm);
+fn _remove_lpm((mut map, prefix): (PrefixMap<TestPrefix, i32>, TestPrefix)) -> bool {
+    let want_removed = map.get_lpm(&prefix).map(|(p, t)| (*p, *t));
+    let want = select(&map, |p, _| Some(*p) != want_removed.map(|(p, _)| p));
+    let removed = map.remove_lpm(&prefix);
+    removed == want_removed
+        && map.check_invariants().is_ok()
+        && map.len() == want.len()
+        && map.into_iter().eq(want)
+}
+
+qc!(cleanup_tree, _cleanup_tree);
+fn _cleanup_tree((list, extra): (Vec<Operation<TestPrefix, i32>>, Vec<(TestPrefix, i32)>)) -> bool {
+    let mut map = PrefixMap::new();
+    for op in list {
+        match op {
+            Operation::Add(p, t) => {
+                map.insert(p, t);
+            }
+            Operation::Remove(p) => {
+                map.remove_keep_tree(&p);
+            }
+        }
+    }
+    let mut want = map.clone();
+    map.cleanup_tree();
+    let table = &map.table;
+    let mut stack = vec![0];
+    let mut minimal = true;
+    while let Some(idx) = stack.pop().filter(|_| table.len() > 0) {
+        let children = [table[idx].left(), table[idx].right()];
+        let n = children.iter().flatten().count();
+        minimal &= idx == 0 || table.value(idx).is_some() || n == 2;
+        stack.extend(children.into_iter().flatten());
+    }
+    // removed nodes must be reused correctly
+    for (p, t) in extra {
+        map.insert(p, t);
+        want.insert(p, t);
+    }
+    minimal && map.check_invariants().is_ok() && map == want
+}
+
+qc!(covered_by, _covered_by);
+fn _covered_by((map, set): (PrefixMap<TestPrefix, i32>, PrefixSet<TestPrefix>)) -> bool {
+    let is_covered = |p: &TestPrefix| set.iter().any(|s| s.contains(p));
+    let mut retained = map.clone();
+    retained.retain_covered_by(&set);
+    let mut removed = map.clone();
+    removed.remove_covered_by(&set);
+    let want_retained = select(&map, |p, _| is_covered(p));
+    let want_removed = select(&map, |p, _| !is_covered(p));
+    retained.check_invariants().is_ok()
+        && removed.check_invariants().is_ok()
+        && retained.len() == want_retained.len()
+        && removed.len() == want_removed.len()
+        && retained.into_iter().eq(want_retained)
+        && removed.into_iter().eq(want_removed)
+}
+
+qc!(lpm_join, _lpm_join);
+fn _lpm_join((a, b): (PrefixMap<TestPrefix, i32>, PrefixMap<TestPrefix, u8>)) -> bool {
+    a.lpm_join(&b)
+        .eq(a.iter().map(|(p, t)| (p, t, b.get_lpm(p))))
+}
+
 qc!(retain, _retain);
 fn _retain((mut map, root): (PrefixMap<TestPrefix, i32>, TestPrefix)) -> bool {
     let want = select(&map, |p, _| !(root.contains(p) && p.1 >= root.1 + 2));
@@ -138,6 +1188,53 @@ fn _retain((mut map, root): (PrefixMap<TestPrefix, i32>, TestPrefix)) -> bool {
     map.into_iter().eq(want)
 }
 
+qc!(summarize, _summarize);
+fn _summarize(map: PrefixMap<TestPrefix, i32>) -> bool {
+    let map: PrefixMap<TestPrefix, i32> = map.into_iter().map(|(p, t)| (p, t % 3)).collect();
+    let mut summary = map.clone();
+    summary.summarize(|a, b| a == b);
+    // all keys have at most 9 bits, so it suffices to check all prefixes of length 9.
+    let equivalent = TestPrefix(0, 0)
+        .subnets(9)
+        .all(|p| map.get_lpm(&p).map(|(_, t)| t) == summary.get_lpm(&p).map(|(_, t)| t));
+    let minimal = summary.iter().all(|(p, t)| {
+        let shadowed = p
+            .supernet()
+            .and_then(|s| summary.get_lpm(&s))
+            .map(|(_, x)| x == t)
+            .unwrap_or(false);
+        let mergeable = p.sibling().and_then(|s| summary.get(&s)) == Some(t);
+        !shadowed && !mergeable
+    });
+    equivalent && minimal && summary.len() <= map.len()
+}
+
+qc!(disjoint, _disjoint);
+fn _disjoint(map: PrefixMap<TestPrefix, i32>) -> bool {
+    let disjoint = map.disjoint();
+    // all keys have at most 9 bits, so it suffices to check all prefixes of length 9.
+    let equivalent = TestPrefix(0, 0)
+        .subnets(9)
+        .all(|p| map.get_lpm(&p).map(|(_, t)| t) == disjoint.get_lpm(&p).map(|(_, t)| t));
+    let no_overlap = disjoint
+        .iter()
+        .all(|(p, _)| disjoint.cover(p).count() == 1 && disjoint.children(*p).count() == 1);
+    let leaves_kept = map
+        .iter()
+        .filter(|(p, _)| map.children(**p).count() == 1)
+        .all(|(p, t)| disjoint.get(p) == Some(t));
+    equivalent && no_overlap && leaves_kept
+}
+
+qc!(lookup_by, _lookup_by);
+fn _lookup_by((mut map, prefix): (PrefixMap<TestPrefix, i32>, TestPrefix)) -> bool {
+    let q = (prefix.0, prefix.1);
+    map.get_by(&q) == map.get(&prefix)
+        && map.get_lpm_by(&q) == map.get_lpm(&prefix)
+        && map.contains_key_by(&q) == map.contains_key(&prefix)
+        && map.clone().remove_by(&q) == map.remove(&prefix)
+}
+
 qc!(view_at, _view_at);
 fn _view_at((map, root): (PrefixMap<TestPrefix, i32>, TestPrefix)) -> bool {
     let value = map.get(&root).cloned();
@@ -195,3 +1292,449 @@ fn _view_mut_split((mut map, root): (PrefixMap<TestPrefix, i32>, TestPrefix)) ->
         .unwrap_or((false, false))
         == (left_prefix_has_nodes, right_prefix_has_nodes)
 }
+
+#[cfg(feature = "rayon")]
+qc!(par_collect, _par_collect);
+#[cfg(feature = "rayon")]
+fn _par_collect(list: Vec<(TestPrefix, i32)>) -> bool {
+    use ::rayon::prelude::*;
+    let want = list.iter().copied().collect::<PrefixMap<_, _>>();
+    let got = list.par_iter().copied().collect::<PrefixMap<_, _>>();
+    want == got && want.len() == got.len()
+}
+
+#[cfg(feature = "rayon")]
+qc!(par_fold, _par_fold);
+#[cfg(feature = "rayon")]
+fn _par_fold(map: PrefixMap<TestPrefix, i32>) -> bool {
+    let want = map.iter().map(|(p, t)| (*p, *t)).collect::<Vec<_>>();
+    let got = map.par_fold(
+        Vec::new,
+        |mut acc, p, t| {
+            acc.push((*p, *t));
+            acc
+        },
+        |mut a, b| {
+            a.extend(b);
+            a
+        },
+    );
+    want == got
+}
+
+qc!(utilization, _utilization);
+fn _utilization((map, parent, extra): (PrefixMap<TestPrefix, i32>, TestPrefix, u8)) -> bool {
+    use crate::map::Coverage;
+    let len = parent.1 + extra % 4;
+    let usage = map.utilization(&parent, len);
+    // all keys have at most 9 bits, so it suffices to check all prefixes of length 9 (or longer).
+    let want: Vec<(TestPrefix, Coverage)> = parent
+        .subnets(len)
+        .map(|s| {
+            let covered: Vec<bool> = s
+                .subnets(len.max(9))
+                .map(|h| map.get_lpm(&h).is_some())
+                .collect();
+            let coverage = if covered.iter().all(|c| *c) {
+                Coverage::Full
+            } else if covered.iter().any(|c| *c) {
+                Coverage::Partial
+            } else {
+                Coverage::Free
+            };
+            (s, coverage)
+        })
+        .collect();
+    let count = |c| want.iter().filter(|(_, x)| *x == c).count();
+    usage.subnets == want
+        && usage.full == count(Coverage::Full)
+        && usage.partial == count(Coverage::Partial)
+        && usage.free == count(Coverage::Free)
+}
+
+qc!(next_free, _next_free);
+fn _next_free((map, addr): (PrefixMap<TestPrefix, i32>, u32)) -> bool {
+    // all keys have at most 9 bits, so coverage only changes at boundaries of `/9` blocks.
+    let block = |a: u32| TestPrefix(a & !((1 << 23) - 1), 9);
+    let covered = |a: u32| map.get_lpm(&block(a)).is_some();
+    let blocks = || (0..512u32).map(|i| i << 23);
+    let want_next = if covered(addr) {
+        blocks().find(|b| *b > addr && !covered(*b))
+    } else {
+        Some(addr)
+    };
+    let want_prev = if covered(addr) {
+        blocks()
+            .rev()
+            .find(|b| *b < addr && !covered(*b))
+            .map(|b| b + ((1 << 23) - 1))
+    } else {
+        Some(addr)
+    };
+    let want_block = (!covered(addr)).then(|| {
+        (0..=32)
+            .map(|len| TestPrefix(addr & !(u32::MAX.checked_shr(len as u32).unwrap_or(0)), len))
+            .find(|p| map.children(*p).next().is_none())
+            .unwrap()
+    });
+    map.next_free(addr) == want_next
+        && map.prev_free(addr) == want_prev
+        && map.free_block(addr) == want_block
+}
+
+qc!(get_many_mut, _get_many_mut);
+fn _get_many_mut(
+    (mut map, a, b, c): (
+        PrefixMap<TestPrefix, i32>,
+        TestPrefix,
+        TestPrefix,
+        TestPrefix,
+    ),
+) -> bool {
+    let keys = [a, b, c];
+    let distinct = a != b && b != c && a != c;
+    let want: Option<Vec<i32>> = keys
+        .iter()
+        .map(|k| map.get(k).copied())
+        .collect::<Option<_>>()
+        .filter(|_| distinct);
+    let got = map.get_many_mut([&a, &b, &c]).map(|values| {
+        values
+            .into_iter()
+            .map(|v| {
+                let old = *v;
+                *v = old.wrapping_add(1);
+                old
+            })
+            .collect::<Vec<_>>()
+    });
+    let updated = match &want {
+        Some(values) => keys
+            .iter()
+            .zip(values)
+            .all(|(k, v)| map.get(k) == Some(&v.wrapping_add(1))),
+        None => true,
+    };
+    got == want && updated
+}
+
+qc!(merkle, _merkle);
+fn _merkle(
+    (a, b): (
+        Vec<Operation<TestPrefix, u8>>,
+        Vec<Operation<TestPrefix, u8>>,
+    ),
+) -> bool {
+    use crate::map::MerkleMap;
+    let build = |list: Vec<Operation<TestPrefix, u8>>| {
+        let mut map: MerkleMap<TestPrefix, u8> = MerkleMap::new();
+        for op in list {
+            match op {
+                Operation::Add(p, t) => map.insert(p, t),
+                Operation::Remove(p) => map.remove(&p),
+            };
+        }
+        map
+    };
+    let a = build(a);
+    let b = build(b);
+    // fingerprints only depend on the contents.
+    let rebuilt: MerkleMap<TestPrefix, u8> = a
+        .iter()
+        .map(|(p, t)| (*p, *t))
+        .collect_vec()
+        .into_iter()
+        .rev()
+        .collect();
+    let want: Vec<TestPrefix> = a
+        .iter()
+        .merge_join_by(b.iter(), |(x, _), (y, _)| x.cmp(y))
+        .filter_map(|item| match item {
+            EitherOrBoth::Both((p, x), (_, y)) => (x != y).then_some(*p),
+            EitherOrBoth::Left((p, _)) | EitherOrBoth::Right((p, _)) => Some(*p),
+        })
+        .collect();
+    rebuilt.root_fingerprint() == a.root_fingerprint()
+        && (a.root_fingerprint() == b.root_fingerprint()) == want.is_empty()
+        && a.differences(&b) == want
+}
+
+qc!(reverse_indexed, _reverse_indexed);
+fn _reverse_indexed(list: Vec<Operation<TestPrefix, u8>>) -> bool {
+    let mut map = crate::map::ReverseIndexedMap::with_key(|t: &u8| t % 4);
+    let mut model: BTreeMap<TestPrefix, u8> = BTreeMap::new();
+    for op in list {
+        let ok = match op {
+            Operation::Add(_, t) if t % 8 == 7 => {
+                let want: Vec<(TestPrefix, u8)> = model
+                    .iter()
+                    .filter(|(_, x)| *x % 4 == t % 4)
+                    .map(|(p, x)| (*p, *x))
+                    .collect();
+                model.retain(|_, x| *x % 4 != t % 4);
+                map.remove_with(&(t % 4)) == want
+            }
+            Operation::Add(p, t) => map.insert(p, t) == model.insert(p, t),
+            Operation::Remove(p) => map.remove(&p) == model.remove(&p),
+        };
+        if !ok || map.len() != model.len() {
+            return false;
+        }
+    }
+    (0..4).all(|k| {
+        let want: Vec<TestPrefix> = model
+            .iter()
+            .filter(|(_, x)| *x % 4 == k)
+            .map(|(p, _)| *p)
+            .collect();
+        let got: Vec<TestPrefix> = map
+            .prefixes_with(&k)
+            .map(|s| s.iter().copied().collect())
+            .unwrap_or_default();
+        got == want
+    }) && map.iter().map(|(p, t)| (*p, *t)).eq(model)
+}
+
+qc!(cached_lpm, _cached_lpm);
+fn _cached_lpm(list: Vec<(Operation<TestPrefix, u8>, TestPrefix)>) -> bool {
+    let mut map = crate::map::CachedPrefixMap::new(4);
+    let mut model = PrefixMap::new();
+    let mut queries = Vec::new();
+    for (op, query) in list {
+        match op {
+            Operation::Add(p, t) => {
+                map.insert(p, t);
+                model.insert(p, t);
+            }
+            Operation::Remove(p) => {
+                map.remove(&p);
+                model.remove(&p);
+            }
+        }
+        // repeat recent queries, such that some of them are answered from the cache.
+        queries.push(query);
+        if !queries
+            .iter()
+            .rev()
+            .take(6)
+            .all(|q| map.get_lpm(q) == model.get_lpm(q))
+        {
+            return false;
+        }
+    }
+    map.len() == model.len()
+}
+
+qc!(rooted, _rooted);
+fn _rooted((universe, list): (TestPrefix, Vec<(TestPrefix, i32)>)) -> bool {
+    use crate::map::Coverage;
+    let mut map = PrefixMap::rooted_at(universe);
+    for (p, t) in list {
+        let inserted = map.insert(p, t).is_ok();
+        if inserted != universe.contains(&p) || map.get(&p).is_some() != inserted {
+            return false;
+        }
+    }
+    let complement = map.complement();
+    // all prefixes of length 9 within the universe are either covered by an entry, or by
+    // exactly one prefix of the complement.
+    let probes = universe.subnets(9);
+    let partition = probes.into_iter().all(|q| {
+        let free = complement.iter().filter(|c| c.contains(&q)).count();
+        (map.get_lpm(&q).is_some() as usize) + free == 1
+    });
+    let maximal = complement.iter().all(|c| {
+        universe.contains(c)
+            && (c == &universe || {
+                let parent = TestPrefix::from_repr_len(c.0, c.1 - 1);
+                map.as_map().children(parent).next().is_some()
+            })
+    });
+    let coverage = match map.coverage() {
+        Coverage::Full => complement.is_empty(),
+        Coverage::Free => complement == vec![universe],
+        Coverage::Partial => !complement.is_empty() && complement != vec![universe],
+    };
+    partition && maximal && coverage
+}
+
+qc!(total, _total);
+fn _total((list, queries): (Vec<Operation<TestPrefix, i32>>, Vec<TestPrefix>)) -> bool {
+    let mut map = crate::map::TotalPrefixMap::new(-1);
+    let mut model = PrefixMap::new();
+    model.set_default(-1);
+    for op in list {
+        let ok = match op {
+            Operation::Add(p, t) => map.insert(p, t) == model.insert(p, t),
+            Operation::Remove(p) if p.1 == 0 => map.remove(&p).is_none(),
+            Operation::Remove(p) => map.remove(&p) == model.remove(&p),
+        };
+        if !ok || map.len() + 1 != model.len() {
+            return false;
+        }
+    }
+    queries
+        .iter()
+        .all(|q| Some(map.get_lpm(q)) == model.get_lpm(q))
+        && map.iter().eq(model.iter())
+}
+
+qc!(bulk_insert, _bulk_insert);
+fn _bulk_insert((map, list): (PrefixMap<TestPrefix, i32>, Vec<(TestPrefix, i32, bool)>)) -> bool {
+    let mut want = map.clone();
+    let mut map = map;
+    let mut bulk = map.bulk();
+    for (p, t, commit) in list {
+        bulk.insert(p, t);
+        want.insert(p, t);
+        if commit {
+            bulk.commit();
+        }
+    }
+    // the remaining elements are committed when the buffer is dropped.
+    drop(bulk);
+    map.check_invariants().is_ok() && map.len() == want.len() && map == want
+}
+
+qc!(try_reserve, _try_reserve);
+fn _try_reserve((map, list): (PrefixMap<TestPrefix, i32>, Vec<(TestPrefix, i32)>)) -> bool {
+    let mut want = map.clone();
+    for (p, t) in list.iter() {
+        want.insert(*p, *t);
+    }
+    let mut map = map;
+    let before = map.clone();
+    // a failed reservation does not modify the map.
+    if map.try_reserve(usize::MAX).is_ok() || map != before {
+        return false;
+    }
+    if map.try_reserve(list.len()).is_err() {
+        return false;
+    }
+    let mut bulk = map.bulk();
+    bulk.extend(list);
+    if bulk.try_commit().is_err() || bulk.staged() != 0 {
+        return false;
+    }
+    drop(bulk);
+    map.check_invariants().is_ok() && map == want
+}
+
+#[cfg(feature = "ffi")]
+qc!(ffi, _ffi);
+#[cfg(feature = "ffi")]
+fn _ffi((list, lookups): (Vec<Operation<TestPrefix, u64>>, Vec<TestPrefix>)) -> bool {
+    use crate::ffi::*;
+    use std::ffi::c_void;
+
+    unsafe extern "C" fn collect_v4(addr: u32, len: u8, value: u64, user: *mut c_void) -> bool {
+        (*user.cast::<Vec<(TestPrefix, u64)>>()).push((TestPrefix(addr, len), value));
+        true
+    }
+    unsafe extern "C" fn collect_v6(
+        addr: *const u8,
+        len: u8,
+        value: u64,
+        user: *mut c_void,
+    ) -> bool {
+        let addr = (u128::from_be_bytes(*addr.cast::<[u8; 16]>()) >> 96) as u32;
+        (*user.cast::<Vec<(TestPrefix, u64)>>()).push((TestPrefix(addr, len), value));
+        true
+    }
+    // the IPv6 map stores each prefix in the first 32 bits.
+    let v6 = |p: &TestPrefix| ((p.0 as u128) << 96).to_be_bytes();
+
+    let mut pmap = PrefixMap::new();
+    let map4 = pt_map_v4_new();
+    let map6 = pt_map_v6_new();
+    let mut ok = true;
+    unsafe {
+        for op in list {
+            match op {
+                Operation::Add(p, t) => {
+                    pmap.insert(p, t);
+                    ok &= pt_map_v4_insert(map4, p.0, p.1, t) == PtStatus::Ok;
+                    ok &= pt_map_v6_insert(map6, v6(&p).as_ptr(), p.1, t) == PtStatus::Ok;
+                }
+                Operation::Remove(p) => {
+                    let want = pmap.remove(&p);
+                    let (mut v4_value, mut v6_value) = (0, 0);
+                    let v4_status = pt_map_v4_remove(map4, p.0, p.1, &mut v4_value);
+                    let v6_status = pt_map_v6_remove(map6, v6(&p).as_ptr(), p.1, &mut v6_value);
+                    ok &= match want {
+                        Some(t) => {
+                            (v4_status, v4_value, v6_status, v6_value)
+                                == (PtStatus::Ok, t, PtStatus::Ok, t)
+                        }
+                        None => v4_status == PtStatus::NotFound && v6_status == PtStatus::NotFound,
+                    };
+                }
+            }
+        }
+
+        for p in lookups {
+            let want = pmap.get_lpm(&p).map(|(p, t)| (*p, *t));
+            let (mut addr, mut len, mut value) = (0, 0, 0);
+            let status = pt_map_v4_lookup_lpm(map4, p.0, p.1, &mut addr, &mut len, &mut value);
+            ok &= match want {
+                Some(want) => status == PtStatus::Ok && (TestPrefix(addr, len), value) == want,
+                None => status == PtStatus::NotFound,
+            };
+            let mut addr = [0; 16];
+            let status = pt_map_v6_lookup_lpm(
+                map6,
+                v6(&p).as_ptr(),
+                p.1,
+                addr.as_mut_ptr(),
+                &mut len,
+                &mut value,
+            );
+            ok &= match want {
+                Some(want) => {
+                    status == PtStatus::Ok && (addr, len, value) == (v6(&want.0), want.0 .1, want.1)
+                }
+                None => status == PtStatus::NotFound,
+            };
+        }
+
+        let want: Vec<_> = pmap.into_iter().collect();
+        let mut got4: Vec<(TestPrefix, u64)> = Vec::new();
+        let mut got6: Vec<(TestPrefix, u64)> = Vec::new();
+        pt_map_v4_iterate(map4, Some(collect_v4), (&mut got4 as *mut Vec<_>).cast());
+        pt_map_v6_iterate(map6, Some(collect_v6), (&mut got6 as *mut Vec<_>).cast());
+        ok &= pt_map_v4_len(map4) == want.len() && got4 == want && got6 == want;
+
+        pt_map_v4_free(map4);
+        pt_map_v6_free(map6);
+    }
+    ok
+}
+
+qc!(static_map, _static_map);
+fn _static_map((map, lookups): (PrefixMap<TestPrefix, i32>, Vec<TestPrefix>)) -> bool {
+    let frozen = map.clone().freeze();
+    let nodes = frozen.static_nodes();
+    let values: Vec<i32> = frozen.values().copied().collect();
+    let sm: crate::map::StaticPrefixMap<'_, TestPrefix, i32> =
+        crate::map::StaticPrefixMap::new(&nodes, &values);
+    sm.len() == map.len()
+        && sm.iter().eq(map.iter().map(|(p, t)| (*p, t)))
+        && lookups.iter().all(|p| {
+            sm.get(p) == map.get(p) && sm.get_lpm(p) == map.get_lpm(p).map(|(p, t)| (*p, t))
+        })
+}
+
+qc!(owned_set_ops, _owned_set_ops);
+fn _owned_set_ops((a, b): (PrefixMap<TestPrefix, i32>, PrefixMap<TestPrefix, i32>)) -> bool {
+    let intersection = a.intersection_map(&b, |_, x, y| (*x, *y));
+    let difference = a.difference_map(&b);
+    let want_intersection: Vec<_> = a
+        .iter()
+        .filter_map(|(p, x)| b.get(p).map(|y| (*p, (*x, *y))))
+        .collect();
+    let want_difference = select(&a, |p, _| !b.contains_key(p));
+    intersection.check_invariants().is_ok()
+        && difference.check_invariants().is_ok()
+        && intersection.into_iter().eq(want_intersection)
+        && difference.into_iter().eq(want_difference)
+}
@@ -0,0 +1,50 @@
+use super::*;
+use crate::domain::DomainMap;
+
+/// Build a domain name from a list of label indices, using only few distinct labels.
+fn name(labels: &[u8]) -> String {
+    const LABELS: [&str; 4] = ["a", "b", "Ex", "com"];
+    labels
+        .iter()
+        .take(MAX_LABELS)
+        .map(|l| LABELS[*l as usize % LABELS.len()])
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+const MAX_LABELS: usize = 5;
+
+/// Naive suffix matching by comparing the lower-case names.
+fn is_suffix(name: &str, suffix: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    let suffix = suffix.to_ascii_lowercase();
+    suffix.is_empty() || name == suffix || name.ends_with(&format!(".{suffix}"))
+}
+
+qc!(domain_suffix, _domain_suffix);
+fn _domain_suffix((entries, queries): (Vec<(Vec<u8>, i32)>, Vec<Vec<u8>>)) -> bool {
+    let mut map = DomainMap::new();
+    let mut want: Vec<(String, i32)> = Vec::new();
+    for (labels, value) in entries {
+        let n = name(&labels);
+        map.insert(&n, value).unwrap();
+        want.retain(|(x, _)| !x.eq_ignore_ascii_case(&n));
+        want.push((n.to_ascii_lowercase(), value));
+    }
+    if map.len() != want.len() {
+        return false;
+    }
+    queries.iter().all(|q| {
+        let q = name(q);
+        let exp = want
+            .iter()
+            .filter(|(n, _)| is_suffix(&q, n))
+            .max_by_key(|(n, _)| n.len())
+            .map(|(n, v)| (n.as_str(), v));
+        let exact = want
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(&q))
+            .map(|(_, v)| v);
+        map.get_suffix(&q) == exp && map.get(&q) == exact
+    })
+}
@@ -0,0 +1,79 @@
+use super::*;
+use crate::ipam::{Fit, Ipam, Usage};
+
+/// Check that the free and used blocks of each pool are disjoint and exactly cover the pool, and
+/// that no two free siblings exist.
+fn consistent(ipam: &Ipam<TestPrefix>) -> bool {
+    let size = |p: &TestPrefix| 1u64 << (32 - p.1);
+    let mut blocks: Vec<TestPrefix> = ipam
+        .free_blocks()
+        .copied()
+        .chain(ipam.used().map(|(p, _)| *p))
+        .collect();
+    blocks.sort_by_key(|p| p.0);
+    let disjoint = blocks
+        .windows(2)
+        .all(|w| w[0].0 as u64 + size(&w[0]) <= w[1].0 as u64);
+    let covered = ipam.pools().all(|pool| {
+        blocks
+            .iter()
+            .filter(|b| pool.contains(b))
+            .map(size)
+            .sum::<u64>()
+            == size(pool)
+    });
+    let in_pool = blocks
+        .iter()
+        .all(|b| ipam.pools().any(|pool| pool.contains(b)));
+    let free: PrefixSet<TestPrefix> = ipam.free_blocks().copied().collect();
+    let coalesced = free.iter().all(|b| {
+        ipam.pools().any(|pool| Prefix::eq(pool, b))
+            || !b.sibling().map(|s| free.contains(&s)).unwrap_or(false)
+    });
+    disjoint && covered && in_pool && coalesced
+}
+
+qc!(ipam_ops, _ipam_ops);
+fn _ipam_ops((pools, ops): (Vec<TestPrefix>, Vec<(u8, u8, TestPrefix)>)) -> bool {
+    let mut ipam = Ipam::new();
+    for pool in pools {
+        let _ = ipam.add_pool(pool);
+    }
+    let mut allocated = Vec::new();
+    for (op, len, prefix) in ops {
+        let len = len % 33;
+        match op % 4 {
+            0 | 1 => {
+                let fit = if op % 4 == 0 { Fit::First } else { Fit::Best };
+                let free_before: Vec<TestPrefix> = ipam.free_blocks().copied().collect();
+                match ipam.allocate(len, fit) {
+                    Some(p) => {
+                        if p.1 != len || !free_before.iter().any(|b| b.contains(&p)) {
+                            return false;
+                        }
+                        allocated.push(p);
+                    }
+                    None => {
+                        if free_before.iter().any(|b| b.1 <= len) {
+                            return false;
+                        }
+                    }
+                }
+            }
+            2 => {
+                let free = ipam.free_blocks().any(|b| b.contains(&prefix));
+                if ipam.reserve(prefix).is_ok() != free {
+                    return false;
+                }
+            }
+            _ => {
+                if let Some(p) = allocated.pop() {
+                    if ipam.free(&p) != Some(Usage::Allocated) {
+                        return false;
+                    }
+                }
+            }
+        }
+    }
+    consistent(&ipam)
+}
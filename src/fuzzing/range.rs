@@ -0,0 +1,169 @@
+use super::*;
+use crate::range::{AddrRange, RangeMap};
+
+qc!(range_cidrs, _range_cidrs);
+fn _range_cidrs((a, b): (u8, u8)) -> bool {
+    let range = AddrRange::new(a.min(b), a.max(b)).unwrap();
+    let cidrs: Vec<(u8, u8)> = range.cidrs().collect();
+    // the prefixes must be consecutive, and exactly cover the range
+    let parts: Vec<AddrRange<u8>> = cidrs.iter().map(AddrRange::from_prefix).collect();
+    let covers = parts.first().map(|r| r.start()) == Some(range.start())
+        && parts.last().map(|r| r.end()) == Some(range.end())
+        && parts
+            .windows(2)
+            .all(|w| w[0].end() as u32 + 1 == w[1].start() as u32);
+    // the decomposition is minimal iff no two siblings are present
+    let minimal = cidrs.iter().all(|p| {
+        p.sibling()
+            .map(|s| !cidrs.iter().any(|q| Prefix::eq(q, &s)))
+            .unwrap_or(true)
+    });
+    covers && minimal
+}
+
+qc!(set_ranges, _set_ranges);
+fn _set_ranges(list: Vec<(u8, u8)>) -> bool {
+    let set: PrefixSet<(u8, u8)> = list
+        .into_iter()
+        .map(|(a, l)| <(u8, u8)>::from_repr_len(a, l % 9))
+        .collect();
+    // compute the maximal runs of covered addresses
+    let covered: Vec<bool> = (0..=255u8)
+        .map(|a| set.get_lpm(&(a, 8)).is_some())
+        .collect();
+    let mut want = Vec::new();
+    for (a, c) in covered.iter().enumerate() {
+        match (c, want.last_mut()) {
+            (true, Some((_, end))) if *end + 1 == a => *end = a,
+            (true, _) => want.push((a, a)),
+            (false, _) => {}
+        }
+    }
+    let got: Vec<(usize, usize)> = set
+        .ranges()
+        .map(|r| (r.start() as usize, r.end() as usize))
+        .collect();
+    got == want
+}
+
+qc!(range_map, _range_map);
+fn _range_map((bounds, queries): (Vec<(u32, u32)>, Vec<u32>)) -> bool {
+    // create non-overlapping ranges from the sorted bounds
+    let mut bounds: Vec<u32> = bounds.into_iter().flat_map(|(a, b)| [a, b]).collect();
+    bounds.truncate(20);
+    bounds.sort();
+    bounds.dedup();
+    let ranges: Vec<AddrRange<u32>> = bounds
+        .chunks_exact(2)
+        .map(|c| AddrRange::new(c[0], c[1]).unwrap())
+        .collect();
+    let map: RangeMap<TestPrefix, usize> = ranges.iter().copied().zip(0..).collect();
+    if map.len() != ranges.len() {
+        return false;
+    }
+    let addrs = queries
+        .into_iter()
+        .chain(ranges.iter().flat_map(|r| [r.start(), r.end()]))
+        .chain(
+            ranges
+                .iter()
+                .flat_map(|r| [r.start().wrapping_sub(1), r.end().wrapping_add(1)]),
+        );
+    addrs.into_iter().all(|addr| {
+        let want = ranges
+            .iter()
+            .enumerate()
+            .find(|(_, r)| r.contains(addr))
+            .map(|(i, r)| (r, i));
+        map.get_addr(addr).map(|(r, i)| (r, *i)) == want
+    })
+}
+
+qc!(range_map_ops, _range_map_ops);
+fn _range_map_ops(ops: Vec<Operation<(u8, u8), i32>>) -> bool {
+    // a naive model that stores the ranges in insertion order. Ranges are confined to the first
+    // 32 addresses, such that they overlap often.
+    let mut model: Vec<(AddrRange<u8>, i32)> = Vec::new();
+    let mut map: RangeMap<(u8, u8), i32> = RangeMap::new();
+    for op in ops {
+        match op {
+            Operation::Add((a, b), t) => {
+                let range = AddrRange::new((a % 32).min(b % 32), (a % 32).max(b % 32)).unwrap();
+                let want = model.iter().position(|(r, _)| *r == range);
+                let want = want.map(|i| model.remove(i).1);
+                model.push((range, t));
+                if map.insert(range, t) != want {
+                    return false;
+                }
+            }
+            Operation::Remove((i, _)) if !model.is_empty() => {
+                let (range, t) = model.remove(i as usize % model.len());
+                if map.remove(&range) != Some(t) {
+                    return false;
+                }
+            }
+            Operation::Remove(_) => {}
+        }
+    }
+    // each address is mapped to the range with the longest covering prefix, and among those, to
+    // the one inserted last.
+    let lookups = (0..=u8::MAX).all(|addr| {
+        let want = model
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (r, t))| {
+                let p: (u8, u8) = r.cidrs().find(|p: &(u8, u8)| p.contains(&(addr, 8)))?;
+                Some(((p.1, i), (r, t)))
+            })
+            .max_by_key(|(key, _)| *key)
+            .map(|(_, x)| x);
+        map.get_addr(addr) == want
+    });
+    let overlapping = (0..32u8).all(|a| {
+        let query = AddrRange::new(a, a + 3).unwrap();
+        let mut want: Vec<(&AddrRange<u8>, &i32)> = model
+            .iter()
+            .filter(|(r, _)| r.overlaps(&query))
+            .map(|(r, t)| (r, t))
+            .collect();
+        want.sort();
+        map.overlapping(&query).eq(want)
+    });
+    lookups && overlapping && map.len() == model.len()
+}
+
+qc!(range_overlapping, _range_overlapping);
+fn _range_overlapping((bounds, queries): (Vec<(u32, u32)>, Vec<(u32, u32)>)) -> bool {
+    let mut bounds: Vec<u32> = bounds.into_iter().flat_map(|(a, b)| [a, b]).collect();
+    bounds.truncate(20);
+    bounds.sort();
+    bounds.dedup();
+    let ranges: Vec<AddrRange<u32>> = bounds
+        .chunks_exact(2)
+        .map(|c| AddrRange::new(c[0], c[1]).unwrap())
+        .collect();
+    let map: RangeMap<TestPrefix, usize> = ranges.iter().copied().zip(0..).collect();
+    let queries = queries
+        .into_iter()
+        .map(|(a, b)| AddrRange::new(a.min(b), a.max(b)).unwrap())
+        .chain(ranges.iter().copied());
+    queries.into_iter().all(|query| {
+        let want: Vec<(&AddrRange<u32>, usize)> = ranges
+            .iter()
+            .zip(0..)
+            .filter(|(r, _)| r.overlaps(&query))
+            .collect();
+        let got: Vec<(&AddrRange<u32>, usize)> =
+            map.overlapping(&query).map(|(r, i)| (r, *i)).collect();
+        got == want
+    })
+}
+
+qc!(map_overlapping, _map_overlapping);
+fn _map_overlapping((map, (a, b)): (PrefixMap<TestPrefix, i32>, (u32, u32))) -> bool {
+    let range = AddrRange::new(a.min(b), a.max(b)).unwrap();
+    let want = map
+        .iter()
+        .filter(|(p, _)| AddrRange::from_prefix(*p).overlaps(&range));
+    map.overlapping(&range).eq(want)
+}
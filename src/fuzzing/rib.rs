@@ -0,0 +1,60 @@
+use std::collections::BTreeMap;
+
+use super::*;
+use crate::rib::{Path, Rib};
+
+qc!(rib_fib, _rib_fib);
+fn _rib_fib(ops: Vec<(TestPrefix, u8, u8, Option<u8>)>) -> bool {
+    let mut rib: Rib<TestPrefix, u8> = Rib::new();
+    let mut model: BTreeMap<TestPrefix, BTreeMap<u8, (u32, u32)>> = BTreeMap::new();
+    for (p, hop, metric, weight) in ops {
+        let hop = hop % 4;
+        match weight {
+            Some(weight) => {
+                let path = Path::new(hop, (metric % 3) as u32).with_weight(weight as u32);
+                rib.add_path(p, path);
+                model
+                    .entry(p)
+                    .or_default()
+                    .insert(hop, (path.metric, path.weight));
+            }
+            None => {
+                let removed = rib.remove_path(&p, &hop).map(|p| (p.metric, p.weight));
+                let want = model.get_mut(&p).and_then(|paths| paths.remove(&hop));
+                if removed != want {
+                    return false;
+                }
+                model.retain(|_, paths| !paths.is_empty());
+            }
+        }
+    }
+    let want: Vec<(TestPrefix, Vec<(u8, u32)>)> = model
+        .into_iter()
+        .map(|(p, paths)| {
+            let min = paths.values().map(|(m, _)| *m).min().unwrap();
+            let hops = paths
+                .into_iter()
+                .filter(|(_, (m, _))| *m == min)
+                .map(|(h, (_, w))| (h, w))
+                .collect();
+            (p, hops)
+        })
+        .collect();
+    rib.fib().into_iter().eq(want)
+}
+
+qc!(rib_fib_recursive, _rib_fib_recursive);
+fn _rib_fib_recursive(routes: Vec<(TestPrefix, u8, u8)>) -> bool {
+    let rib: Rib<TestPrefix, u8> = routes
+        .into_iter()
+        .map(|(p, hop, metric)| (p, Path::new(hop % 8, (metric % 2) as u32)))
+        .collect();
+    // even next-hops are directly connected, odd ones are resolved recursively.
+    let recursive = |hop: &u8| (hop % 2 == 1).then_some(TestPrefix((*hop as u32) << 24, 8));
+    let fib = rib.fib_recursive(recursive);
+    fib.iter().all(|(p, hops)| {
+        !hops.is_empty()
+            && hops.iter().all(|(h, _)| h % 2 == 0)
+            && rib.resolve_lpm(p, recursive).as_ref() == Some(hops)
+    })
+}
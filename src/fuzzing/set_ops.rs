@@ -214,3 +214,20 @@ impl<P: Copy, T: Copy> MyCopy for Option<(&P, &T)> {
         self.map(|(p, t)| (*p, *t))
     }
 }
+
+qc!(owned_set_ops, _owned_set_ops);
+fn _owned_set_ops((a, b): (Vec<TestPrefix>, Vec<TestPrefix>)) -> bool {
+    use std::collections::BTreeSet;
+    let set_a: PrefixSet<TestPrefix> = a.iter().copied().collect();
+    let set_b: PrefixSet<TestPrefix> = b.iter().copied().collect();
+    let a: BTreeSet<TestPrefix> = a.into_iter().collect();
+    let b: BTreeSet<TestPrefix> = b.into_iter().collect();
+    let union = PrefixSet::union(&set_a, &set_b);
+    let intersection = PrefixSet::intersection(&set_a, &set_b);
+    let difference = PrefixSet::difference(&set_a, &set_b);
+    union.iter().copied().eq(a.union(&b).copied())
+        && intersection.iter().copied().eq(a.intersection(&b).copied())
+        && difference.iter().copied().eq(a.difference(&b).copied())
+        && union == a.union(&b).copied().collect()
+        && union.len() == a.union(&b).count()
+}
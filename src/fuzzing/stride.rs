@@ -0,0 +1,40 @@
+use super::*;
+use crate::stride::StrideMap;
+
+qc!(stride_map, _stride_map);
+fn _stride_map((map, addrs): (PrefixMap<TestPrefix, i32>, Vec<u32>)) -> bool {
+    let fib = StrideMap::from(&map);
+    // also query the addresses of all prefixes, and their neighbors
+    let addrs = addrs
+        .into_iter()
+        .chain(
+            map.keys()
+                .flat_map(|p| [p.0, p.0.wrapping_sub(1), p.0.wrapping_add(1)]),
+        )
+        .collect::<Vec<_>>();
+    fib.len() == map.len()
+        && fib.iter().eq(map.iter())
+        && addrs
+            .into_iter()
+            .all(|a| fib.get_lpm_addr(a) == map.get_lpm(&TestPrefix(a, 32)))
+        && PrefixMap::from(fib) == map
+}
+
+qc!(stride_map_long, _stride_map_long);
+fn _stride_map_long((list, addrs): (Vec<(u32, u8, i32)>, Vec<u32>)) -> bool {
+    let map: PrefixMap<(u32, u8), i32> = list
+        .into_iter()
+        .map(|(x, len, t)| (<(u32, u8)>::from_repr_len(x, len % 33), t))
+        .collect();
+    let fib = StrideMap::from(&map);
+    let addrs = addrs
+        .into_iter()
+        .chain(
+            map.keys()
+                .flat_map(|p| [p.0, p.0.wrapping_sub(1), p.0.wrapping_add(1)]),
+        )
+        .collect::<Vec<_>>();
+    addrs
+        .into_iter()
+        .all(|a| fib.get_lpm_addr(a) == map.get_lpm(&(a, 32)))
+}
@@ -53,6 +53,30 @@ fn _children_values_trieview((map, start): (PrefixMap<TestPrefix, i32>, TestPref
     }
 }
 
+qc!(fold_under, _fold_under);
+fn _fold_under((map, start, skip_len): (PrefixMap<TestPrefix, i32>, TestPrefix, u8)) -> bool {
+    use crate::trieview::FoldStep;
+    let skip_len = skip_len % 10;
+    // an element is visited unless one of its visited supernets below `start` was skipped.
+    let want: Vec<TestPrefix> = map
+        .children(start)
+        .map(|(p, _)| *p)
+        .filter(|p| {
+            !map.children(start)
+                .any(|(q, _)| q.1 >= skip_len && q.contains(p) && q != p)
+        })
+        .collect();
+    let got = map.fold_under(start, Vec::new(), |mut acc, p, _| {
+        acc.push(*p);
+        if p.1 >= skip_len {
+            FoldStep::Skip(acc)
+        } else {
+            FoldStep::Continue(acc)
+        }
+    });
+    got == want
+}
+
 qc!(simultaneous_iter_mut, _simultaneous_iter_mut);
 fn _simultaneous_iter_mut(mut map: PrefixMap<TestPrefix, i32>) -> bool {
     // go to the first split
@@ -108,3 +132,13 @@ fn _simultaneous_iter_mut(mut map: PrefixMap<TestPrefix, i32>) -> bool {
 
     want == got
 }
+
+qc!(iter_by_distance, _iter_by_distance);
+fn _iter_by_distance((map, target): (PrefixMap<TestPrefix, i32>, TestPrefix)) -> bool {
+    let want: Vec<(&TestPrefix, &i32)> = map
+        .iter()
+        .sorted_by_key(|(p, _)| (p.0 ^ target.0, p.1))
+        .collect();
+    map.iter_by_distance(&target).eq(want.iter().copied())
+        && map.closest(&target) == want.first().copied()
+}
@@ -0,0 +1,68 @@
+use super::*;
+use crate::wildcard::{Wildcard, WildcardMap};
+
+/// Create a wildcard with a contiguous prefix of length `len`, and some additional bits that must
+/// match.
+fn wildcard((value, len, extra): (u32, u8, u32)) -> Wildcard<u32> {
+    let mask = Prefix::mask(&(u32::MAX, len % 33)) | (extra & extra.rotate_left(7));
+    Wildcard::new(value, mask)
+}
+
+qc!(wildcard_matches, _wildcard_matches);
+fn _wildcard_matches((entries, queries): (Vec<((u32, u8, u32), i32)>, Vec<u32>)) -> bool {
+    let mut map: WildcardMap<TestPrefix, i32> = WildcardMap::new();
+    let mut want: Vec<(Wildcard<u32>, i32)> = Vec::new();
+    for (w, t) in entries {
+        let w = wildcard(w);
+        map.insert(w, t);
+        match want.iter_mut().find(|(x, _)| *x == w) {
+            Some((_, x)) => *x = t,
+            None => want.push((w, t)),
+        }
+    }
+    if map.len() != want.len() || want.iter().any(|(w, t)| map.get(w) != Some(t)) {
+        return false;
+    }
+    // also query addresses that match the stored wildcards
+    let addrs = queries.iter().copied().chain(
+        want.iter()
+            .zip(&queries)
+            .map(|((w, _), q)| w.value() | (q & !w.mask())),
+    );
+    addrs.into_iter().all(|addr| {
+        let mut exp: Vec<_> = want.iter().filter(|(w, _)| w.matches(addr)).collect();
+        let mut got: Vec<_> = map.matches(addr).map(|(w, t)| (*w, *t)).collect();
+        exp.sort();
+        got.sort();
+        exp.into_iter().copied().eq(got)
+    })
+}
+
+qc!(wildcard_remove, _wildcard_remove);
+fn _wildcard_remove(entries: Vec<((u32, u8, u32), bool)>) -> bool {
+    let mut map: WildcardMap<TestPrefix, ()> = WildcardMap::new();
+    let mut want: Vec<Wildcard<u32>> = Vec::new();
+    for (w, insert) in entries {
+        let mut w = wildcard(w);
+        if !insert && !want.is_empty() && w.value() % 2 == 0 {
+            // remove an element that is actually present
+            w = want[w.mask() as usize % want.len()];
+        }
+        if insert {
+            map.insert(w, ());
+            if !want.contains(&w) {
+                want.push(w);
+            }
+        } else {
+            let removed = map.remove(&w).is_some();
+            if removed != want.contains(&w) {
+                return false;
+            }
+            want.retain(|x| *x != w);
+        }
+    }
+    let mut got: Vec<_> = map.iter().map(|(w, _)| *w).collect();
+    got.sort();
+    want.sort();
+    map.len() == want.len() && got == want
+}
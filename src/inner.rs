@@ -2,83 +2,263 @@
 
 use std::{
     cell::UnsafeCell,
+    num::NonZeroU32,
     ops::{Index, IndexMut},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Once,
+    },
 };
 
 use crate::{to_right, Prefix};
 
+/// A node of the tree, storing its prefix and its children. The value of a node is stored
+/// separately in the [`Table`].
 #[derive(Clone)]
-pub(crate) struct Node<P, T> {
+pub(crate) struct Node<P> {
     pub(crate) prefix: P,
-    pub(crate) value: Option<T>,
-    pub(crate) left: Option<usize>,
-    pub(crate) right: Option<usize>,
+    left: Option<ChildIdx>,
+    right: Option<ChildIdx>,
 }
 
-impl<P, T> Node<P, T> {
-    /// get the tuple of prefix and value.
-    pub(crate) fn prefix_value(&self) -> Option<(&P, &T)> {
-        self.value.as_ref().map(|v| (&self.prefix, v))
+/// Index of a child node in the table. The root (at index 0) is never the child of any node, so
+/// the index is stored as a `NonZeroU32`. This makes `Option<ChildIdx>` only 4 bytes large, which
+/// reduces the size of a node significantly on 64-bit targets. As a consequence, a table can hold
+/// at most `u32::MAX` nodes.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct ChildIdx(NonZeroU32);
+
+impl ChildIdx {
+    #[inline(always)]
+    fn new(idx: usize) -> Self {
+        u32::try_from(idx)
+            .ok()
+            .and_then(NonZeroU32::new)
+            .map(Self)
+            .expect("too many nodes in the table")
     }
 
-    /// get the tuple of prefix and value.
-    pub(crate) fn prefix_value_mut(&mut self) -> Option<(&P, &mut T)> {
-        self.value.as_mut().map(|v| (&self.prefix, v))
+    #[inline(always)]
+    fn get(self) -> usize {
+        self.0.get() as usize
+    }
+}
+
+impl<P> Node<P> {
+    /// Create a new node without any children.
+    pub(crate) fn new(prefix: P) -> Self {
+        Self {
+            prefix,
+            left: None,
+            right: None,
+        }
+    }
+
+    /// Get the index of the left child.
+    #[inline(always)]
+    pub(crate) fn left(&self) -> Option<usize> {
+        self.left.map(ChildIdx::get)
+    }
+
+    /// Get the index of the right child.
+    #[inline(always)]
+    pub(crate) fn right(&self) -> Option<usize> {
+        self.right.map(ChildIdx::get)
+    }
+
+    /// Set the left child, and return the index of the old one.
+    #[inline(always)]
+    pub(crate) fn set_left(&mut self, child: Option<usize>) -> Option<usize> {
+        std::mem::replace(&mut self.left, child.map(ChildIdx::new)).map(ChildIdx::get)
+    }
+
+    /// Set the right child, and return the index of the old one.
+    #[inline(always)]
+    pub(crate) fn set_right(&mut self, child: Option<usize>) -> Option<usize> {
+        std::mem::replace(&mut self.right, child.map(ChildIdx::new)).map(ChildIdx::get)
+    }
+}
+
+/// The number of nodes that hold a value, both in total and for each prefix length. All counters
+/// are atomic (see `Table`).
+pub(crate) struct ValueCounts {
+    total: AtomicUsize,
+    /// The number of values of each prefix length. It always has an entry for the prefix length
+    /// of every node in the table (see `Table::push`), such that a `TrieViewMut` can set the value
+    /// of any node without growing it.
+    per_len: Vec<AtomicUsize>,
+}
+
+impl ValueCounts {
+    const fn new() -> Self {
+        Self {
+            total: AtomicUsize::new(0),
+            per_len: Vec::new(),
+        }
+    }
+
+    /// Make sure there is a counter for the prefix length `len`.
+    #[inline(always)]
+    fn reserve(&mut self, len: u8) {
+        if self.per_len.len() <= len as usize {
+            self.per_len
+                .resize_with(len as usize + 1, || AtomicUsize::new(0));
+        }
+    }
+
+    /// Count a new value of a node with prefix length `len`.
+    #[inline(always)]
+    pub(crate) fn add(&mut self, len: u8) {
+        self.reserve(len);
+        *self.total.get_mut() += 1;
+        *self.per_len[len as usize].get_mut() += 1;
+    }
+
+    /// Count a removed value of a node with prefix length `len`.
+    #[inline(always)]
+    pub(crate) fn sub(&mut self, len: u8) {
+        *self.total.get_mut() -= 1;
+        *self.per_len[len as usize].get_mut() -= 1;
+    }
+
+    /// Reset all counters to zero.
+    fn clear(&mut self) {
+        *self.total.get_mut() = 0;
+        self.per_len.iter_mut().for_each(|c| *c.get_mut() = 0);
+    }
+
+    /// The number of values of prefix length `len`.
+    #[cfg(any(test, feature = "debug-invariants"))]
+    pub(crate) fn of_len(&self, len: u8) -> usize {
+        self.per_len
+            .get(len as usize)
+            .map_or(0, |c| c.load(Ordering::Relaxed))
+    }
+
+    /// Iterate over all prefix lengths that have at least one value, in increasing order.
+    pub(crate) fn lens(&self) -> impl DoubleEndedIterator<Item = u8> + '_ {
+        self.per_len
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.load(Ordering::Relaxed) > 0)
+            .map(|(len, _)| len as u8)
+    }
+}
+
+impl Clone for ValueCounts {
+    fn clone(&self) -> Self {
+        let load = |c: &AtomicUsize| AtomicUsize::new(c.load(Ordering::Relaxed));
+        Self {
+            total: load(&self.total),
+            per_len: self.per_len.iter().map(load).collect(),
+        }
     }
 }
 
 /// A table to the prefix-trie that offers interior mutability.
 ///
+/// The table is stored as two parallel arrays: `nodes` stores the prefix and the children of each
+/// node (indexed by `Table[idx]`), while `values` stores the value of the node at the same index.
+/// Traversing the tree only ever touches `nodes`, such that the values do not pollute the cache on
+/// the lookup path.
+///
+/// For each node, the table stores a generation that is incremented whenever the node obtains a
+/// new value (i.e., its value changes from `None` to `Some`), and an epoch for the entire table
+/// that is incremented whenever nodes change their index (i.e., when compacting or clearing the
+/// table). Together, they identify an element (see `NodeId`).
+///
+/// The table also counts the nodes that hold a value, both in total and per prefix length. The
+/// counters are atomic, such that a `TrieViewMut` (which only owns a shared reference to the table)
+/// can keep them up-to-date when removing or setting values. All accesses use relaxed ordering, as
+/// the counters do not synchronize any other memory.
+///
+/// The root is allocated lazily, such that creating a table is a `const` operation that does not
+/// allocate. Until the root is allocated, all vectors are empty, and reading node `0` yields an
+/// empty root that is stored inline in the table (created on first use). All functions that modify
+/// the table through a mutable reference first allocate the root.
+///
 /// # Safety
 /// Owning a mutable reference to the Table implies that you can safely get a mutable reference to
 /// the inner data. If, however, you own an immutable reference, then you must guarantee that there
-/// is no other reference to the Table that potentially accesses the same node mutably. This interior
-/// mutability is only ever provided in `get_mut`.
-pub(crate) struct Table<P, T>(UnsafeCell<Vec<Node<P, T>>>);
+/// is no other reference to the Table that potentially accesses the same value mutably. This
+/// interior mutability is only ever provided in `get_mut`, and only for values. The nodes
+/// themselves can only be modified through a mutable reference to the table, except for the
+/// inline root of an unallocated table, which is initialized once on first use.
+pub(crate) struct Table<P, T> {
+    nodes: Vec<Node<P>>,
+    values: UnsafeCell<Vec<Option<T>>>,
+    generations: UnsafeCell<Vec<u32>>,
+    epoch: u32,
+    counts: ValueCounts,
+    /// The root that is read while the table is not yet allocated. It is written exactly once
+    /// (guarded by `empty_root_init`), and never modified afterwards.
+    empty_root: UnsafeCell<Option<Node<P>>>,
+    empty_root_init: Once,
+    /// Creates the prefix of the root (`P::zero`), such that the root can be allocated without
+    /// requiring `P: Prefix`.
+    zero: fn() -> P,
+}
 
 // Safety:
-// - Sending a PrefixMap over thread boundary is fine. No-one besides us can have the raw pointer,
-//   otherwise, the map would be borrowed.
-// - Sending a reference of PrefixMap over thread boundaries (i.e., TrieView is Send) is safe,
-//   because we ensure that the existence of a TrieView on a sub-tree implies the absence of a
-//   TrieViewMut that overlaps with that sub-tree.
-// - Sending a mutable reference of PrefixMap over thread boundaries (i.e., TrieView is Send) is
-//   safe, because we ensure that the existence of a TrieViewMut on a sub-tree implies the absence
-//   of any other TrieView or TrieViewMut that overlaps with that sub-tree.
-// The same argument holds for Sync.
+// - Sending a Table over thread boundaries is safe, because the table owns all nodes and values.
+//   The `UnsafeCell` is never aliased by a raw pointer that outlives a borrow of the table.
+// - Sharing a Table between threads (i.e., `&Table` is `Send`) is safe as long as only immutable
+//   references to nodes and values are created through a shared reference. This is the case for
+//   `PrefixMap`, `TrieView`, and all read-only iterators. The only node that is written through a
+//   shared reference is `empty_root`, which is initialized at most once (guarded by
+//   `empty_root_init`) by whichever thread first reads the root of an unallocated table. Hence, a
+//   `P` may be created on one thread and dropped by the owner on another, so `Sync` requires
+//   `P: Send + Sync` (just like `OnceLock<P>`).
+// - `TrieViewMut` also stores a `&Table`, but obtains mutable references to values using
+//   `get_mut`. It is constructed only from a mutable borrow of the map, and we ensure that the
+//   existence of a `TrieViewMut` on a sub-tree implies the absence of any other `TrieView` or
+//   `TrieViewMut` that overlaps with that sub-tree. Hence, no value is ever accessed by two threads
+//   at the same time. However, a `TrieViewMut` sent to another thread can move values out of the
+//   table (e.g., using `std::mem::replace`), so `Sync` additionally requires `T: Send` (just like
+//   `Mutex<T>`).
 unsafe impl<P: Send, T: Send> Send for Table<P, T> {}
-unsafe impl<P: Sync, T: Sync> Sync for Table<P, T> {}
-
-impl<P, T> AsRef<Vec<Node<P, T>>> for Table<P, T> {
-    fn as_ref(&self) -> &Vec<Node<P, T>> {
-        // Safety: We own an immutable reference to the table.
-        unsafe { self.0.get().as_ref().unwrap() }
-    }
-}
-
-impl<P, T> AsMut<Vec<Node<P, T>>> for Table<P, T> {
-    fn as_mut(&mut self) -> &mut Vec<Node<P, T>> {
-        self.0.get_mut()
-    }
-}
+unsafe impl<P: Send + Sync, T: Send + Sync> Sync for Table<P, T> {}
 
 impl<P, T> Index<usize> for Table<P, T> {
-    type Output = Node<P, T>;
+    type Output = Node<P>;
 
+    #[inline(always)]
     fn index(&self, index: usize) -> &Self::Output {
-        &self.as_ref()[index]
+        match self.nodes.get(index) {
+            Some(node) => node,
+            None => self.empty_root(index),
+        }
     }
 }
 
 impl<P, T> IndexMut<usize> for Table<P, T> {
+    #[inline(always)]
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
-        &mut self.as_mut()[index]
+        self.alloc_root();
+        &mut self.nodes[index]
     }
 }
 
 impl<P: Clone, T: Clone> Clone for Table<P, T> {
     fn clone(&self) -> Self {
-        Self(UnsafeCell::new(self.as_ref().clone()))
+        Self {
+            nodes: self.nodes.clone(),
+            values: UnsafeCell::new(self.values().clone()),
+            generations: UnsafeCell::new(self.generations().clone()),
+            epoch: self.epoch,
+            counts: self.counts.clone(),
+            empty_root: UnsafeCell::new(None),
+            empty_root_init: Once::new(),
+            zero: self.zero,
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.nodes.clone_from(&source.nodes);
+        self.values.get_mut().clone_from(source.values());
+        self.generations.get_mut().clone_from(source.generations());
+        self.epoch = source.epoch;
+        self.counts.clone_from(&source.counts);
     }
 }
 
@@ -87,12 +267,7 @@ where
     P: Prefix,
 {
     fn default() -> Self {
-        Self(UnsafeCell::new(vec![Node {
-            prefix: P::zero(),
-            value: None,
-            left: None,
-            right: None,
-        }]))
+        Self::new()
     }
 }
 
@@ -127,39 +302,436 @@ pub(crate) enum DirectionForInsert<P> {
 }
 
 impl<P, T> Table<P, T> {
-    pub(crate) fn into_inner(self) -> Vec<Node<P, T>> {
-        self.0.into_inner()
+    /// The number of nodes in the table (including unused ones, and zero if the root is not yet
+    /// allocated).
+    pub(crate) fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Allocate the root if the table is still empty.
+    #[inline(always)]
+    pub(crate) fn alloc_root(&mut self) {
+        if self.nodes.is_empty() {
+            self.counts.reserve(0);
+            self.nodes.push(Node::new((self.zero)()));
+            self.values.get_mut().push(None);
+            self.generations.get_mut().push(0);
+        }
+    }
+
+    /// Panic if `idx` is out of bounds, unless it refers to the root of an unallocated table.
+    #[cold]
+    fn check_unallocated(&self, idx: usize) {
+        let len = self.nodes.len();
+        if idx != 0 || len != 0 {
+            panic!("index out of bounds: the len is {len} but the index is {idx}");
+        }
+    }
+
+    /// Get the root of a table that is not yet allocated, or panic if `idx` is out of bounds.
+    #[cold]
+    fn empty_root(&self, idx: usize) -> &Node<P> {
+        self.check_unallocated(idx);
+        // Safety: `empty_root` is only written within `call_once`, which happens exactly once and
+        // before any reference to it is created. `call_once` synchronizes with the write, such
+        // that all threads observe the initialized root.
+        self.empty_root_init.call_once(|| unsafe {
+            *self.empty_root.get() = Some(Node::new((self.zero)()));
+        });
+        unsafe { self.empty_root.get().as_ref().unwrap().as_ref().unwrap() }
+    }
+
+    #[inline(always)]
+    fn values(&self) -> &Vec<Option<T>> {
+        // Safety: We own an immutable reference to the table.
+        unsafe { self.values.get().as_ref().unwrap() }
+    }
+
+    #[inline(always)]
+    fn generations(&self) -> &Vec<u32> {
+        // Safety: We own an immutable reference to the table.
+        unsafe { self.generations.get().as_ref().unwrap() }
+    }
+
+    /// Get the generation of a node, which is incremented whenever the node obtains a new value.
+    #[inline(always)]
+    pub(crate) fn generation(&self, idx: usize) -> u32 {
+        match self.generations().get(idx) {
+            Some(generation) => *generation,
+            None => {
+                self.check_unallocated(idx);
+                0
+            }
+        }
+    }
+
+    /// Get the epoch of the table, which is incremented whenever nodes change their index.
+    #[inline(always)]
+    pub(crate) fn epoch(&self) -> u32 {
+        self.epoch
+    }
+
+    /// Increment the generation of a node. Call this whenever the value of a node changes from
+    /// `None` to `Some`.
+    #[inline(always)]
+    pub(crate) fn bump_generation(&mut self, idx: usize) {
+        self.alloc_root();
+        let generation = &mut self.generations.get_mut()[idx];
+        *generation = generation.wrapping_add(1);
+    }
+
+    /// Increment the generation of a node through a shared reference (see `bump_generation`).
+    ///
+    /// *Safety*: The same conditions as for `get_mut` apply: no other reference to that node may
+    /// exist while calling this function.
+    #[inline(always)]
+    pub(crate) unsafe fn bump_generation_shared(&self, idx: usize) {
+        let generations = unsafe { self.generations.get().as_ref().unwrap() };
+        assert!(idx < generations.len(), "index out of bounds");
+        unsafe {
+            let ptr = (generations.as_ptr() as *mut u32).add(idx);
+            *ptr = (*ptr).wrapping_add(1);
+        }
+    }
+
+    /// The number of nodes that hold a value.
+    #[inline(always)]
+    pub(crate) fn count(&self) -> usize {
+        self.counts.total.load(Ordering::Relaxed)
+    }
+
+    /// Get the number of nodes that hold a value (in total and per prefix length).
+    #[inline(always)]
+    pub(crate) fn counts(&self) -> &ValueCounts {
+        &self.counts
+    }
+
+    /// Get a mutable reference to the number of nodes that hold a value.
+    #[inline(always)]
+    pub(crate) fn counts_mut(&mut self) -> &mut ValueCounts {
+        &mut self.counts
+    }
+
+    /// Update the number of nodes that hold a value after the value of a node with prefix length
+    /// `len` was replaced through a shared reference (see `get_mut`), where `was_some` and
+    /// `is_some` tell whether the node held a value before and after the modification.
+    #[inline(always)]
+    pub(crate) fn update_count(&self, len: u8, was_some: bool, is_some: bool) {
+        let per_len = &self.counts.per_len[len as usize];
+        match (was_some, is_some) {
+            (false, true) => {
+                self.counts.total.fetch_add(1, Ordering::Relaxed);
+                per_len.fetch_add(1, Ordering::Relaxed);
+            }
+            (true, false) => {
+                self.counts.total.fetch_sub(1, Ordering::Relaxed);
+                per_len.fetch_sub(1, Ordering::Relaxed);
+            }
+            _ => {}
+        }
     }
 
+    /// Get the value of a node.
+    #[inline(always)]
+    pub(crate) fn value(&self, idx: usize) -> Option<&T> {
+        match self.values().get(idx) {
+            Some(value) => value.as_ref(),
+            None => {
+                self.check_unallocated(idx);
+                None
+            }
+        }
+    }
+
+    /// Get a mutable reference to the value of a node.
+    #[inline(always)]
+    pub(crate) fn value_mut(&mut self, idx: usize) -> &mut Option<T> {
+        self.alloc_root();
+        &mut self.values.get_mut()[idx]
+    }
+
+    /// get the tuple of prefix and value.
+    #[inline(always)]
+    pub(crate) fn prefix_value(&self, idx: usize) -> Option<(&P, &T)> {
+        self.value(idx).map(|v| (&self.nodes[idx].prefix, v))
+    }
+
+    /// get the tuple of prefix and value.
+    #[inline(always)]
+    pub(crate) fn prefix_value_mut(&mut self, idx: usize) -> Option<(&P, &mut T)> {
+        if self.nodes.is_empty() {
+            self.check_unallocated(idx);
+            return None;
+        }
+        let prefix = &self.nodes[idx].prefix;
+        self.values.get_mut()[idx].as_mut().map(|v| (prefix, v))
+    }
+
+    /// Get mutable references to both the node and its value.
+    #[inline(always)]
+    pub(crate) fn node_value_mut(&mut self, idx: usize) -> (&mut Node<P>, &mut Option<T>) {
+        self.alloc_root();
+        (&mut self.nodes[idx], &mut self.values.get_mut()[idx])
+    }
+
+    /// Get mutable references to the node, its value, and the number of nodes that hold a value.
+    #[inline(always)]
+    pub(crate) fn node_value_count_mut(
+        &mut self,
+        idx: usize,
+    ) -> (&mut Node<P>, &mut Option<T>, &mut ValueCounts) {
+        self.alloc_root();
+        (
+            &mut self.nodes[idx],
+            &mut self.values.get_mut()[idx],
+            &mut self.counts,
+        )
+    }
+
+    /// Get the prefix and a mutable reference to the value of a node.
+    ///
     /// *Safety*: You must ensure for the lifetime of 'a, that you will never construct a second
-    /// reference to that node (neither mutable nor immutable).
+    /// reference to the value of that node (neither mutable nor immutable).
     #[allow(clippy::mut_from_ref)]
-    pub(crate) unsafe fn get_mut(&self, idx: usize) -> &mut Node<P, T> {
+    pub(crate) unsafe fn get_mut(&self, idx: usize) -> (&P, &mut Option<T>) {
         // old implementation that caused issues with Miri:
-        // unsafe { &mut self.0.get().as_mut().unwrap()[idx] }
+        // unsafe { &mut self.values.get().as_mut().unwrap()[idx] }
 
         // new implementation based on manually offsetting the pointers:
-        unsafe {
+        let value = unsafe {
             // do the bounds check
-            let len = self.0.get().as_ref().unwrap().len();
+            let len = self.values.get().as_ref().unwrap().len();
             if idx >= len {
                 panic!("index out of bounds: the len is {len} but the index is {idx}");
             }
-            let ptr_to_slice = self.0.get().as_ref().unwrap().as_ptr();
+            let ptr_to_slice = self.values.get().as_ref().unwrap().as_ptr();
             let ptr_to_elem = ptr_to_slice.add(idx);
-            (ptr_to_elem as *mut Node<P, T>).as_mut().unwrap()
-        }
+            (ptr_to_elem as *mut Option<T>).as_mut().unwrap()
+        };
+        (&self.nodes[idx].prefix, value)
     }
 }
 
 impl<P: Prefix, T> Table<P, T> {
+    /// Create an empty table without allocating.
+    pub(crate) const fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            values: UnsafeCell::new(Vec::new()),
+            generations: UnsafeCell::new(Vec::new()),
+            epoch: 0,
+            counts: ValueCounts::new(),
+            empty_root: UnsafeCell::new(None),
+            empty_root_init: Once::new(),
+            zero: P::zero,
+        }
+    }
+
+    /// Push a new node without children, and return its index.
+    pub(crate) fn push(&mut self, prefix: P, value: Option<T>) -> usize {
+        self.alloc_root();
+        self.counts.reserve(prefix.prefix_len());
+        let idx = self.nodes.len();
+        #[cfg(feature = "tracing")]
+        if idx == self.nodes.capacity() {
+            tracing::debug!(nodes = idx, "growing the table");
+        }
+        self.nodes.push(Node::new(prefix));
+        self.values.get_mut().push(value);
+        self.generations.get_mut().push(0);
+        idx
+    }
+
+    /// Create a table with space for `capacity` nodes (excluding the root).
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        let mut table = Self::new();
+        table.nodes.reserve(capacity + 1);
+        table.values.get_mut().reserve(capacity + 1);
+        table.generations.get_mut().reserve(capacity + 1);
+        table
+    }
+
+    /// Try to reserve space for `additional` more nodes (and the root, if it is not yet
+    /// allocated). On error, the table is left unchanged, apart from the capacity of vectors that
+    /// could be grown before the failure.
+    pub(crate) fn try_reserve(
+        &mut self,
+        additional: usize,
+    ) -> Result<(), std::collections::TryReserveError> {
+        let additional = if self.nodes.is_empty() {
+            additional.saturating_add(1)
+        } else {
+            additional
+        };
+        self.nodes.try_reserve(additional)?;
+        self.values.get_mut().try_reserve(additional)?;
+        self.generations.get_mut().try_reserve(additional)
+    }
+
+    /// Remove all nodes (including the root), but keep the allocations.
+    pub(crate) fn clear(&mut self) {
+        self.nodes.clear();
+        self.values.get_mut().clear();
+        self.generations.get_mut().clear();
+        self.epoch = self.epoch.wrapping_add(1);
+        self.counts.clear();
+    }
+
+    /// Rebuild the table densely, such that it only contains the nodes reachable from the root,
+    /// stored in the order of a depth-first traversal.
+    pub(crate) fn compact(&mut self) {
+        let mut order = Vec::new();
+        let mut stack = vec![0];
+        while let Some(idx) = stack.pop() {
+            order.push(idx);
+            // push the right child first, such that the left one is visited first.
+            stack.extend(self[idx].right());
+            stack.extend(self[idx].left());
+        }
+        self.reorder(order);
+    }
+
+    /// Rebuild the table densely, such that it only contains the nodes reachable from the root,
+    /// stored in van Emde Boas order. The tree is recursively split at half of its height into a
+    /// top tree and several bottom trees, each of which is stored contiguously. Hence, a path from
+    /// the root to any node touches only few cache lines, independent of the cache line size.
+    pub(crate) fn optimize(&mut self) {
+        let mut height = 0;
+        let mut stack = vec![(0, 1)];
+        while let Some((idx, depth)) = stack.pop() {
+            height = height.max(depth);
+            stack.extend(self[idx].left().map(|c| (c, depth + 1)));
+            stack.extend(self[idx].right().map(|c| (c, depth + 1)));
+        }
+        let mut order = Vec::new();
+        self.veb_order(0, height, &mut order);
+        self.reorder(order);
+    }
+
+    /// Append the nodes of the subtree rooted at `root` up to (excluding) `height` levels below in
+    /// van Emde Boas order to `order`. Returns the nodes that are exactly `height` levels below
+    /// `root`, i.e., the roots of the remaining subtrees.
+    fn veb_order(&self, root: usize, height: usize, order: &mut Vec<usize>) -> Vec<usize> {
+        if height <= 1 {
+            order.push(root);
+            return self[root]
+                .left()
+                .into_iter()
+                .chain(self[root].right())
+                .collect();
+        }
+        let top = height / 2;
+        let mut frontier = Vec::new();
+        for child in self.veb_order(root, top, order) {
+            frontier.extend(self.veb_order(child, height - top, order));
+        }
+        frontier
+    }
+
+    /// Rebuild the table such that the node at `order[i]` is moved to index `i`. All nodes not
+    /// contained in `order` are dropped. `order` must start with the root, and must contain all
+    /// children of each node it contains.
+    fn reorder(&mut self, order: Vec<usize>) {
+        if self.nodes.is_empty() {
+            // the root is not allocated, so there is nothing to reorder.
+            return;
+        }
+        let old_values = self.values.get_mut();
+        let mut new_idx = vec![0; self.nodes.len()];
+        for (new, old) in order.iter().enumerate() {
+            new_idx[*old] = new;
+        }
+        let mut nodes = Vec::with_capacity(order.len());
+        let mut values = Vec::with_capacity(order.len());
+        for old in order {
+            let node = &mut self.nodes[old];
+            let mut new = Node::new(std::mem::replace(&mut node.prefix, P::zero()));
+            new.set_left(node.left().map(|c| new_idx[c]));
+            new.set_right(node.right().map(|c| new_idx[c]));
+            nodes.push(new);
+            values.push(old_values[old].take());
+        }
+        self.nodes = nodes;
+        *self.values.get_mut() = values;
+        *self.generations.get_mut() = vec![0; self.nodes.len()];
+        self.epoch = self.epoch.wrapping_add(1);
+    }
+
+    /// Check the structural invariants of the tree: the root has a prefix length of 0, and each
+    /// child is a strictly longer sub-prefix of its parent, is stored on the correct side, and is
+    /// reachable only once. This function only looks at the nodes (not the values), such that it
+    /// can be called while a `TrieViewMut` borrows values mutably. Returns for each node whether
+    /// it is reachable from the root.
+    #[cfg(any(test, feature = "debug-invariants"))]
+    pub(crate) fn check_structure(&self) -> Result<Vec<bool>, String> {
+        let len = self.nodes.len();
+        if len == 0 {
+            return Ok(Vec::new());
+        }
+        let root = &self[0].prefix;
+        if root.prefix_len() != 0 {
+            return Err(format!(
+                "the root has prefix {root:?} instead of a prefix length of 0"
+            ));
+        }
+        let mut reachable = vec![false; len];
+        reachable[0] = true;
+        let mut stack = vec![0];
+        while let Some(idx) = stack.pop() {
+            let p = &self[idx].prefix;
+            for (child, right) in [(self[idx].left(), false), (self[idx].right(), true)] {
+                let Some(child) = child else { continue };
+                let side = if right { "right" } else { "left" };
+                if child >= len {
+                    return Err(format!(
+                        "node {idx} ({p:?}) has the {side} child {child}, but the table only has \
+                         {len} nodes"
+                    ));
+                }
+                let child_p = &self[child].prefix;
+                if reachable[child] {
+                    return Err(format!(
+                        "node {child} ({child_p:?}) is reachable twice, the second time as the \
+                         {side} child of node {idx} ({p:?})"
+                    ));
+                }
+                if child_p.prefix_len() <= p.prefix_len() || !p.contains(child_p) {
+                    return Err(format!(
+                        "node {child} ({child_p:?}) is not a sub-prefix of its parent, node {idx} \
+                         ({p:?})"
+                    ));
+                }
+                if to_right(p, child_p) != right {
+                    return Err(format!(
+                        "node {child} ({child_p:?}) is stored as the {side} child of node {idx} \
+                         ({p:?})"
+                    ));
+                }
+                reachable[child] = true;
+                stack.push(child);
+            }
+        }
+        Ok(reachable)
+    }
+
+    /// Panic if the structure of the tree is invalid (see `check_structure`) after the operation
+    /// `op`. This only does something if the `debug-invariants` feature is enabled.
+    #[inline(always)]
+    pub(crate) fn debug_check_structure(&self, op: &str) {
+        #[cfg(feature = "debug-invariants")]
+        if let Err(e) = self.check_structure() {
+            panic!("prefix-trie invariant violated after `{op}`: {e}");
+        }
+        let _ = op;
+    }
+
     /// Get the child of a node, either to the left or the right
     #[inline(always)]
     pub(crate) fn get_child(&self, idx: usize, right: bool) -> Option<usize> {
         if right {
-            self[idx].right
+            self[idx].right()
         } else {
-            self[idx].left
+            self[idx].left()
         }
     }
 
@@ -167,9 +739,9 @@ impl<P: Prefix, T> Table<P, T> {
     #[inline(always)]
     pub(crate) fn set_child(&mut self, idx: usize, child: usize, right: bool) -> Option<usize> {
         if right {
-            self[idx].right.replace(child)
+            self[idx].set_right(Some(child))
         } else {
-            self[idx].left.replace(child)
+            self[idx].set_left(Some(child))
         }
     }
 
@@ -177,9 +749,9 @@ impl<P: Prefix, T> Table<P, T> {
     #[inline(always)]
     pub(crate) fn clear_child(&mut self, idx: usize, right: bool) -> Option<usize> {
         if right {
-            self[idx].right.take()
+            self[idx].set_right(None)
         } else {
-            self[idx].left.take()
+            self[idx].set_left(None)
         }
     }
 
@@ -200,6 +772,28 @@ impl<P: Prefix, T> Table<P, T> {
         }
     }
 
+    /// Get the directions from some node `idx` to get to `prefix`, where `prefix` may be of a
+    /// different type `Q` with the same representation. Comparisons are done purely on
+    /// [`Prefix::mask`] and [`Prefix::prefix_len`].
+    #[inline(always)]
+    pub(crate) fn get_direction_by<Q>(&self, cur: usize, prefix: &Q) -> Direction
+    where
+        Q: Prefix<R = P::R>,
+    {
+        let cur_p = &self[cur].prefix;
+        if cur_p.prefix_len() == prefix.prefix_len() && cur_p.mask() == prefix.mask() {
+            Direction::Reached
+        } else {
+            let right = prefix.is_bit_set(cur_p.prefix_len());
+            match self.get_child(cur, right) {
+                Some(child) if contains_by(&self[child].prefix, prefix) => {
+                    Direction::Enter { next: child, right }
+                }
+                _ => Direction::Missing,
+            }
+        }
+    }
+
     /// Get the directions from some node `idx` to get to `prefix`.
     #[inline(always)]
     pub(crate) fn get_direction_for_insert(&self, cur: usize, prefix: &P) -> DirectionForInsert<P> {
@@ -232,3 +826,14 @@ impl<P: Prefix, T> Table<P, T> {
         }
     }
 }
+
+/// Check if `p` contains `q`, where both may be of a different type with the same representation.
+#[inline(always)]
+fn contains_by<P, Q>(p: &P, q: &Q) -> bool
+where
+    P: Prefix,
+    Q: Prefix<R = P::R>,
+{
+    p.prefix_len() <= q.prefix_len()
+        && q.repr() & crate::prefix::mask_from_prefix_len(p.prefix_len()) == p.mask()
+}
@@ -0,0 +1,453 @@
+//! Dual-stack maps and sets, keyed by `ipnet::IpNet`.
+//!
+//! A single trie cannot hold both IPv4 and IPv6 prefixes, as they do not share a common
+//! representation. Instead, [`IpPrefixMap`] and [`IpPrefixSet`] hold one trie for each address
+//! family, and dispatch each operation based on the family of the key. Iteration always yields all
+//! IPv4 prefixes before all IPv6 prefixes.
+//!
+//! Note that `ipnet::IpNet` does not implement [`crate::Prefix`] itself. Doing so would require
+//! encoding both families into a single `u128`, such that IPv4 and IPv6 prefixes occupy disjoint
+//! parts of the key space. However, IPv6 prefixes already use the entire 128-bit key space, so
+//! any such encoding would make some IPv4 prefixes indistinguishable from some IPv6 prefixes
+//! (e.g., IPv4-mapped IPv6 addresses). Keeping one trie per family avoids this ambiguity.
+
+use std::net::IpAddr;
+
+use ipnet::{IpNet, Ipv4Net, Ipv6Net};
+
+use crate::{trieview::UnionItem, AsView, PrefixMap, PrefixSet};
+
+/// Get the exact values of both sides of a union item.
+fn union_values<'a, P, L, R>(
+    item: UnionItem<'a, P, L, R>,
+) -> (&'a P, Option<&'a L>, Option<&'a R>) {
+    match item {
+        UnionItem::Left { prefix, left, .. } => (prefix, Some(left), None),
+        UnionItem::Right { prefix, right, .. } => (prefix, None, Some(right)),
+        UnionItem::Both {
+            prefix,
+            left,
+            right,
+        } => (prefix, Some(left), Some(right)),
+    }
+}
+
+/// Convert an address into a host prefix.
+fn host(addr: &IpAddr) -> IpNet {
+    match addr {
+        IpAddr::V4(a) => IpNet::V4(Ipv4Net::from(*a)),
+        IpAddr::V6(a) => IpNet::V6(Ipv6Net::from(*a)),
+    }
+}
+
+/// Dual-stack prefix map, consisting of one [`PrefixMap`] for IPv4 and one for IPv6 prefixes.
+///
+/// ```
+/// # use prefix_trie::ip::*;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut pm: IpPrefixMap<_> = IpPrefixMap::new();
+/// pm.insert("10.0.0.0/8".parse()?, 1);
+/// pm.insert("2001:db8::/32".parse()?, 2);
+/// assert_eq!(pm.len(), 2);
+/// assert_eq!(pm.get_lpm_addr(&"10.1.1.1".parse()?), Some(("10.0.0.0/8".parse()?, &1)));
+/// assert_eq!(pm.get_lpm_addr(&"2001:db8::1".parse()?), Some(("2001:db8::/32".parse()?, &2)));
+/// assert_eq!(pm.get_lpm_addr(&"::ffff:10.1.1.1".parse()?), None);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct IpPrefixMap<T> {
+    v4: PrefixMap<Ipv4Net, T>,
+    v6: PrefixMap<Ipv6Net, T>,
+}
+
+impl<T> Default for IpPrefixMap<T> {
+    fn default() -> Self {
+        Self {
+            v4: PrefixMap::new(),
+            v6: PrefixMap::new(),
+        }
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for IpPrefixMap<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<T> IpPrefixMap<T> {
+    /// Create an empty prefix map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the map of all IPv4 prefixes.
+    pub fn v4(&self) -> &PrefixMap<Ipv4Net, T> {
+        &self.v4
+    }
+
+    /// Get the map of all IPv6 prefixes.
+    pub fn v6(&self) -> &PrefixMap<Ipv6Net, T> {
+        &self.v6
+    }
+
+    /// Get a mutable reference to the map of all IPv4 prefixes.
+    pub fn v4_mut(&mut self) -> &mut PrefixMap<Ipv4Net, T> {
+        &mut self.v4
+    }
+
+    /// Get a mutable reference to the map of all IPv6 prefixes.
+    pub fn v6_mut(&mut self) -> &mut PrefixMap<Ipv6Net, T> {
+        &mut self.v6
+    }
+
+    /// Returns the number of elements stored in `self` (of both address families).
+    pub fn len(&self) -> usize {
+        self.v4.len() + self.v6.len()
+    }
+
+    /// Returns `true` if the map contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.v4.is_empty() && self.v6.is_empty()
+    }
+
+    /// Get the value of an element by matching exactly on the prefix.
+    pub fn get(&self, prefix: &IpNet) -> Option<&T> {
+        match prefix {
+            IpNet::V4(p) => self.v4.get(p),
+            IpNet::V6(p) => self.v6.get(p),
+        }
+    }
+
+    /// Get a mutable reference to the value of an element by matching exactly on the prefix.
+    pub fn get_mut(&mut self, prefix: &IpNet) -> Option<&mut T> {
+        match prefix {
+            IpNet::V4(p) => self.v4.get_mut(p),
+            IpNet::V6(p) => self.v6.get_mut(p),
+        }
+    }
+
+    /// Check if a key is present in the datastructure.
+    pub fn contains_key(&self, prefix: &IpNet) -> bool {
+        self.get(prefix).is_some()
+    }
+
+    /// Get the value of an element by using longest prefix matching. Only prefixes of the same
+    /// address family are considered.
+    ///
+    /// ```
+    /// # use prefix_trie::ip::*;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: IpPrefixMap<_> = IpPrefixMap::new();
+    /// pm.insert("0.0.0.0/0".parse()?, 1);
+    /// pm.insert("2001:db8::/32".parse()?, 2);
+    /// assert_eq!(pm.get_lpm(&"10.0.0.0/8".parse()?), Some(("0.0.0.0/0".parse()?, &1)));
+    /// assert_eq!(pm.get_lpm(&"2001:db8:1::/48".parse()?), Some(("2001:db8::/32".parse()?, &2)));
+    /// assert_eq!(pm.get_lpm(&"2001:db9::/32".parse()?), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_lpm(&self, prefix: &IpNet) -> Option<(IpNet, &T)> {
+        match prefix {
+            IpNet::V4(p) => self.v4.get_lpm(p).map(|(p, t)| (IpNet::V4(*p), t)),
+            IpNet::V6(p) => self.v6.get_lpm(p).map(|(p, t)| (IpNet::V6(*p), t)),
+        }
+    }
+
+    /// Get a mutable reference to the value of an element by using longest prefix matching.
+    pub fn get_lpm_mut(&mut self, prefix: &IpNet) -> Option<(IpNet, &mut T)> {
+        match prefix {
+            IpNet::V4(p) => self.v4.get_lpm_mut(p).map(|(p, t)| (IpNet::V4(*p), t)),
+            IpNet::V6(p) => self.v6.get_lpm_mut(p).map(|(p, t)| (IpNet::V6(*p), t)),
+        }
+    }
+
+    /// Get the value of the longest prefix that contains the address `addr`.
+    pub fn get_lpm_addr(&self, addr: &IpAddr) -> Option<(IpNet, &T)> {
+        self.get_lpm(&host(addr))
+    }
+
+    /// Insert a new item into the map. If the key was already present, then the old value is
+    /// replaced and returned.
+    pub fn insert(&mut self, prefix: IpNet, value: T) -> Option<T> {
+        match prefix {
+            IpNet::V4(p) => self.v4.insert(p, value),
+            IpNet::V6(p) => self.v6.insert(p, value),
+        }
+    }
+
+    /// Remove a prefix from the map, and return its value.
+    pub fn remove(&mut self, prefix: &IpNet) -> Option<T> {
+        match prefix {
+            IpNet::V4(p) => self.v4.remove(p),
+            IpNet::V6(p) => self.v6.remove(p),
+        }
+    }
+
+    /// Remove all elements of both address families.
+    pub fn clear(&mut self) {
+        self.v4.clear();
+        self.v6.clear();
+    }
+
+    /// Iterate over all elements, yielding all IPv4 prefixes (in lexicographic order) before all
+    /// IPv6 prefixes (in lexicographic order).
+    pub fn iter(&self) -> impl Iterator<Item = (IpNet, &T)> {
+        let v4 = self.v4.iter().map(|(p, t)| (IpNet::V4(*p), t));
+        let v6 = self.v6.iter().map(|(p, t)| (IpNet::V6(*p), t));
+        v4.chain(v6)
+    }
+
+    /// Iterate over all elements, yielding mutable references to the values.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (IpNet, &mut T)> {
+        let v4 = self.v4.iter_mut().map(|(p, t)| (IpNet::V4(*p), t));
+        let v6 = self.v6.iter_mut().map(|(p, t)| (IpNet::V6(*p), t));
+        v4.chain(v6)
+    }
+
+    /// Iterate over the union of both maps. Each prefix that is present in `self` or `other`
+    /// is yielded once, together with its value in `self` and its value in `other` (if present).
+    ///
+    /// ```
+    /// # use prefix_trie::ip::*;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let a: IpPrefixMap<_> = [("10.0.0.0/8".parse()?, 1), ("2001:db8::/32".parse()?, 2)]
+    ///     .into_iter()
+    ///     .collect();
+    /// let b: IpPrefixMap<_> = [("10.0.0.0/8".parse()?, 'a'), ("::/0".parse()?, 'b')]
+    ///     .into_iter()
+    ///     .collect();
+    /// assert_eq!(
+    ///     a.union(&b).collect::<Vec<_>>(),
+    ///     vec![
+    ///         ("10.0.0.0/8".parse()?, Some(&1), Some(&'a')),
+    ///         ("::/0".parse()?, None, Some(&'b')),
+    ///         ("2001:db8::/32".parse()?, Some(&2), None),
+    ///     ]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn union<'a, R>(
+        &'a self,
+        other: &'a IpPrefixMap<R>,
+    ) -> impl Iterator<Item = (IpNet, Option<&'a T>, Option<&'a R>)> + 'a {
+        let v4 = self.v4.view().union(&other.v4).map(|x| {
+            let (p, l, r) = union_values(x);
+            (IpNet::V4(*p), l, r)
+        });
+        let v6 = self.v6.view().union(&other.v6).map(|x| {
+            let (p, l, r) = union_values(x);
+            (IpNet::V6(*p), l, r)
+        });
+        v4.chain(v6)
+    }
+
+    /// Iterate over all prefixes that are present in both `self` and `other`.
+    pub fn intersection<'a, R>(
+        &'a self,
+        other: &'a IpPrefixMap<R>,
+    ) -> impl Iterator<Item = (IpNet, &'a T, &'a R)> + 'a {
+        let v4 = (self.v4.view().intersection(&other.v4)).map(|(p, l, r)| (IpNet::V4(*p), l, r));
+        let v6 = (self.v6.view().intersection(&other.v6)).map(|(p, l, r)| (IpNet::V6(*p), l, r));
+        v4.chain(v6)
+    }
+
+    /// Iterate over all prefixes that are present in `self` but not in `other`.
+    ///
+    /// ```
+    /// # use prefix_trie::ip::*;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let a: IpPrefixMap<_> = [("10.0.0.0/8".parse()?, 1), ("2001:db8::/32".parse()?, 2)]
+    ///     .into_iter()
+    ///     .collect();
+    /// let b: IpPrefixMap<_> = [("10.0.0.0/8".parse()?, 3)].into_iter().collect();
+    /// assert_eq!(
+    ///     a.difference(&b).collect::<Vec<_>>(),
+    ///     vec![("2001:db8::/32".parse()?, &2)]
+    /// );
+    /// assert_eq!(
+    ///     a.intersection(&b).collect::<Vec<_>>(),
+    ///     vec![("10.0.0.0/8".parse()?, &1, &3)]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn difference<'a, R>(
+        &'a self,
+        other: &'a IpPrefixMap<R>,
+    ) -> impl Iterator<Item = (IpNet, &'a T)> + 'a {
+        let v4 = (self.v4.view().difference(&other.v4)).map(|x| (IpNet::V4(*x.prefix), x.value));
+        let v6 = (self.v6.view().difference(&other.v6)).map(|x| (IpNet::V6(*x.prefix), x.value));
+        v4.chain(v6)
+    }
+}
+
+impl<T> FromIterator<(IpNet, T)> for IpPrefixMap<T> {
+    fn from_iter<I: IntoIterator<Item = (IpNet, T)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<T> Extend<(IpNet, T)> for IpPrefixMap<T> {
+    fn extend<I: IntoIterator<Item = (IpNet, T)>>(&mut self, iter: I) {
+        for (p, t) in iter {
+            self.insert(p, t);
+        }
+    }
+}
+
+/// Dual-stack prefix set, consisting of one [`PrefixSet`] for IPv4 and one for IPv6 prefixes.
+///
+/// ```
+/// # use prefix_trie::ip::*;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let a: IpPrefixSet = ["10.0.0.0/8".parse()?, "2001:db8::/32".parse()?].into_iter().collect();
+/// let b: IpPrefixSet = ["10.0.0.0/8".parse()?, "::/0".parse()?].into_iter().collect();
+/// assert!(a.contains(&"2001:db8::/32".parse()?));
+/// assert_eq!(a.get_lpm_addr(&"2001:db8::1".parse()?), Some("2001:db8::/32".parse()?));
+/// assert_eq!(a.union(&b).count(), 3);
+/// assert_eq!(a.intersection(&b).collect::<Vec<_>>(), vec!["10.0.0.0/8".parse()?]);
+/// assert_eq!(a.difference(&b).collect::<Vec<_>>(), vec!["2001:db8::/32".parse()?]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct IpPrefixSet {
+    v4: PrefixSet<Ipv4Net>,
+    v6: PrefixSet<Ipv6Net>,
+}
+
+impl std::fmt::Debug for IpPrefixSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl IpPrefixSet {
+    /// Create an empty prefix set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the set of all IPv4 prefixes.
+    pub fn v4(&self) -> &PrefixSet<Ipv4Net> {
+        &self.v4
+    }
+
+    /// Get the set of all IPv6 prefixes.
+    pub fn v6(&self) -> &PrefixSet<Ipv6Net> {
+        &self.v6
+    }
+
+    /// Get a mutable reference to the set of all IPv4 prefixes.
+    pub fn v4_mut(&mut self) -> &mut PrefixSet<Ipv4Net> {
+        &mut self.v4
+    }
+
+    /// Get a mutable reference to the set of all IPv6 prefixes.
+    pub fn v6_mut(&mut self) -> &mut PrefixSet<Ipv6Net> {
+        &mut self.v6
+    }
+
+    /// Returns the number of elements stored in `self` (of both address families).
+    pub fn len(&self) -> usize {
+        self.v4.len() + self.v6.len()
+    }
+
+    /// Returns `true` if the set contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.v4.is_empty() && self.v6.is_empty()
+    }
+
+    /// Check wether some prefix is present in the set, without using longest prefix match.
+    pub fn contains(&self, prefix: &IpNet) -> bool {
+        match prefix {
+            IpNet::V4(p) => self.v4.contains(p),
+            IpNet::V6(p) => self.v6.contains(p),
+        }
+    }
+
+    /// Get the longest prefix in the set that contains the given prefix.
+    pub fn get_lpm(&self, prefix: &IpNet) -> Option<IpNet> {
+        match prefix {
+            IpNet::V4(p) => self.v4.get_lpm(p).map(|p| IpNet::V4(*p)),
+            IpNet::V6(p) => self.v6.get_lpm(p).map(|p| IpNet::V6(*p)),
+        }
+    }
+
+    /// Get the longest prefix in the set that contains the address `addr`.
+    pub fn get_lpm_addr(&self, addr: &IpAddr) -> Option<IpNet> {
+        self.get_lpm(&host(addr))
+    }
+
+    /// Adds a value to the set. Returns whether the value was newly inserted.
+    pub fn insert(&mut self, prefix: IpNet) -> bool {
+        match prefix {
+            IpNet::V4(p) => self.v4.insert(p),
+            IpNet::V6(p) => self.v6.insert(p),
+        }
+    }
+
+    /// Removes a value from the set. Returns whether the value was present in the set.
+    pub fn remove(&mut self, prefix: &IpNet) -> bool {
+        match prefix {
+            IpNet::V4(p) => self.v4.remove(p),
+            IpNet::V6(p) => self.v6.remove(p),
+        }
+    }
+
+    /// Remove all elements of both address families.
+    pub fn clear(&mut self) {
+        self.v4.clear();
+        self.v6.clear();
+    }
+
+    /// Iterate over all prefixes, yielding all IPv4 prefixes (in lexicographic order) before all
+    /// IPv6 prefixes (in lexicographic order).
+    pub fn iter(&self) -> impl Iterator<Item = IpNet> + '_ {
+        let v4 = self.v4.iter().map(|p| IpNet::V4(*p));
+        let v6 = self.v6.iter().map(|p| IpNet::V6(*p));
+        v4.chain(v6)
+    }
+
+    /// Iterate over all prefixes that are present in `self` or `other`.
+    pub fn union<'a>(&'a self, other: &'a IpPrefixSet) -> impl Iterator<Item = IpNet> + 'a {
+        let v4 = (self.v4.view().union(&other.v4)).map(|x| IpNet::V4(*x.prefix()));
+        let v6 = (self.v6.view().union(&other.v6)).map(|x| IpNet::V6(*x.prefix()));
+        v4.chain(v6)
+    }
+
+    /// Iterate over all prefixes that are present in both `self` and `other`.
+    pub fn intersection<'a>(&'a self, other: &'a IpPrefixSet) -> impl Iterator<Item = IpNet> + 'a {
+        let v4 = (self.v4.view().intersection(&other.v4)).map(|(p, _, _)| IpNet::V4(*p));
+        let v6 = (self.v6.view().intersection(&other.v6)).map(|(p, _, _)| IpNet::V6(*p));
+        v4.chain(v6)
+    }
+
+    /// Iterate over all prefixes that are present in `self` but not in `other`.
+    pub fn difference<'a>(&'a self, other: &'a IpPrefixSet) -> impl Iterator<Item = IpNet> + 'a {
+        let v4 = (self.v4.view().difference(&other.v4)).map(|x| IpNet::V4(*x.prefix));
+        let v6 = (self.v6.view().difference(&other.v6)).map(|x| IpNet::V6(*x.prefix));
+        v4.chain(v6)
+    }
+}
+
+impl FromIterator<IpNet> for IpPrefixSet {
+    fn from_iter<I: IntoIterator<Item = IpNet>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<IpNet> for IpPrefixSet {
+    fn extend<I: IntoIterator<Item = IpNet>>(&mut self, iter: I) {
+        for p in iter {
+            self.insert(p);
+        }
+    }
+}
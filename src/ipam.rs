@@ -0,0 +1,336 @@
+//! IP address management: allocating prefixes from a set of parent blocks.
+//!
+//! An [`Ipam`] manages a set of disjoint *pools* (the parent blocks), and hands out prefixes of a
+//! requested length from them. The free space is stored as a set of disjoint, maximal blocks (like
+//! a buddy allocator): allocating a prefix splits the chosen free block, and freeing a prefix
+//! merges it with its sibling as long as that sibling is free as well. Specific prefixes can be
+//! reserved, such that they are never handed out.
+
+use num_traits::{PrimInt, Zero};
+
+use crate::{prefix::mask_from_prefix_len, Prefix, PrefixMap, PrefixSet};
+
+/// Strategy to select the free block from which a prefix is allocated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Fit {
+    /// Allocate from the free block with the lowest address that is large enough.
+    #[default]
+    First,
+    /// Allocate from the smallest free block that is large enough (preferring the lowest address
+    /// among blocks of equal size). This keeps large blocks intact for later allocations.
+    Best,
+}
+
+/// How a prefix is used in an [`Ipam`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Usage {
+    /// The prefix was handed out by [`Ipam::allocate`] or [`Ipam::allocate_in`].
+    Allocated,
+    /// The prefix was reserved using [`Ipam::reserve`].
+    Reserved,
+}
+
+/// Error when modifying the pools or reservations of an [`Ipam`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IpamError {
+    /// The pool overlaps with an existing pool.
+    Overlap,
+    /// The prefix is not contained in any pool.
+    NotInPool,
+    /// The prefix is not entirely free, i.e., it overlaps with an allocated or reserved prefix.
+    Unavailable,
+    /// The pool still contains allocated or reserved prefixes.
+    InUse,
+}
+
+impl std::fmt::Display for IpamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IpamError::Overlap => write!(f, "The pool overlaps with an existing pool"),
+            IpamError::NotInPool => write!(f, "The prefix is not contained in any pool"),
+            IpamError::Unavailable => write!(f, "The prefix is not entirely free"),
+            IpamError::InUse => write!(f, "The pool still contains used prefixes"),
+        }
+    }
+}
+
+impl std::error::Error for IpamError {}
+
+/// Utilization of a pool, counted in number of addresses. Counts saturate at `u128::MAX`, which
+/// only happens for the `/0` pool of a 128-bit prefix type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Utilization {
+    /// Total number of addresses in the pool.
+    pub total: u128,
+    /// Number of allocated addresses.
+    pub allocated: u128,
+    /// Number of reserved addresses.
+    pub reserved: u128,
+}
+
+impl Utilization {
+    /// Number of addresses that are neither allocated nor reserved. Like all counts, this
+    /// saturates, i.e., it is a lower bound if `total` saturated at `u128::MAX`.
+    ///
+    /// ```
+    /// # use prefix_trie::ipam::*;
+    /// let mut ipam: Ipam<(u128, u8)> = Ipam::new();
+    /// ipam.add_pool((0, 0)).unwrap();
+    /// assert_eq!(ipam.allocate(1, Fit::First), Some((0, 1)));
+    /// let util = ipam.utilization(&(0, 0)).unwrap();
+    /// assert_eq!(util.total, u128::MAX);
+    /// assert_eq!(util.free(), (1 << 127) - 1);
+    ///
+    /// // the pool is full, but `total` is one address short.
+    /// ipam.reserve((1 << 127, 1)).unwrap();
+    /// assert_eq!(ipam.utilization(&(0, 0)).unwrap().free(), 0);
+    /// ```
+    pub fn free(&self) -> u128 {
+        self.total
+            .saturating_sub(self.allocated)
+            .saturating_sub(self.reserved)
+    }
+
+    /// Fraction of addresses that are allocated or reserved, between `0.0` and `1.0`.
+    pub fn ratio(&self) -> f64 {
+        self.allocated.saturating_add(self.reserved) as f64 / self.total as f64
+    }
+}
+
+/// Allocator of prefixes from a set of disjoint pools.
+///
+/// ```
+/// # use prefix_trie::ipam::*;
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut ipam: Ipam<ipnet::Ipv4Net> = Ipam::new();
+/// ipam.add_pool("10.0.0.0/22".parse()?)?;
+/// ipam.reserve("10.0.0.0/24".parse()?)?;
+///
+/// let a = ipam.allocate(24, Fit::First).unwrap();
+/// let b = ipam.allocate(25, Fit::Best).unwrap();
+/// assert_eq!(a, "10.0.1.0/24".parse()?);
+/// assert_eq!(b, "10.0.2.0/25".parse()?);
+///
+/// let util = ipam.utilization(&"10.0.0.0/22".parse()?).unwrap();
+/// assert_eq!((util.allocated, util.reserved, util.free()), (384, 256, 384));
+///
+/// // freeing both halves of a block merges them again.
+/// assert_eq!(ipam.free(&b), Some(Usage::Allocated));
+/// assert_eq!(ipam.allocate(23, Fit::First), Some("10.0.2.0/23".parse()?));
+/// assert_eq!(ipam.allocate(24, Fit::First), None);
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+#[derive(Clone)]
+pub struct Ipam<P> {
+    pools: PrefixSet<P>,
+    used: PrefixMap<P, Usage>,
+    free: PrefixSet<P>,
+}
+
+impl<P: Prefix> Default for Ipam<P> {
+    fn default() -> Self {
+        Self {
+            pools: PrefixSet::new(),
+            used: PrefixMap::new(),
+            free: PrefixSet::new(),
+        }
+    }
+}
+
+impl<P: std::fmt::Debug> std::fmt::Debug for Ipam<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Ipam")
+            .field("pools", &self.pools)
+            .field("used", &self.used)
+            .finish()
+    }
+}
+
+impl<P> Ipam<P>
+where
+    P: Prefix + Clone,
+{
+    /// Create an allocator without any pool.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a new pool from which prefixes can be allocated. Fails if the pool overlaps with an
+    /// existing pool.
+    pub fn add_pool(&mut self, pool: P) -> Result<(), IpamError> {
+        if self.pools.get_lpm(&pool).is_some() || self.pools.children(pool.clone()).next().is_some()
+        {
+            return Err(IpamError::Overlap);
+        }
+        self.pools.insert(pool.clone());
+        self.free.insert(pool);
+        Ok(())
+    }
+
+    /// Remove a pool. Fails if the pool does not exist, or if any prefix of that pool is still
+    /// allocated or reserved.
+    pub fn remove_pool(&mut self, pool: &P) -> Result<(), IpamError> {
+        if !self.pools.contains(pool) {
+            return Err(IpamError::NotInPool);
+        }
+        if self.used.children(pool.clone()).next().is_some() {
+            return Err(IpamError::InUse);
+        }
+        self.pools.remove(pool);
+        self.free.remove(pool);
+        Ok(())
+    }
+
+    /// Iterate over all pools in lexicographic order.
+    pub fn pools(&self) -> crate::set::Iter<'_, P> {
+        self.pools.iter()
+    }
+
+    /// Iterate over all allocated and reserved prefixes in lexicographic order.
+    pub fn used(&self) -> crate::map::Iter<'_, P, Usage> {
+        self.used.iter()
+    }
+
+    /// Iterate over all free blocks in lexicographic order. Free blocks are disjoint, and two
+    /// siblings are never both free (they are merged into their supernet instead).
+    pub fn free_blocks(&self) -> crate::set::Iter<'_, P> {
+        self.free.iter()
+    }
+
+    /// Get the usage of a prefix, or `None` if that exact prefix is neither allocated nor
+    /// reserved.
+    pub fn get(&self, prefix: &P) -> Option<Usage> {
+        self.used.get(prefix).copied()
+    }
+
+    /// Allocate a prefix of length `len` from any pool. Returns `None` if no free block is large
+    /// enough. This scans all free blocks, and thus runs in `O(n)`.
+    pub fn allocate(&mut self, len: u8, fit: Fit) -> Option<P> {
+        let block = select(self.free.iter(), len, fit)?.clone();
+        Some(self.take(block, len, Usage::Allocated))
+    }
+
+    /// Allocate a prefix of length `len` from the free blocks contained in `parent`, which may be
+    /// a pool or any prefix within a pool. Returns `None` if no free block within `parent` is
+    /// large enough.
+    pub fn allocate_in(&mut self, parent: &P, len: u8, fit: Fit) -> Option<P> {
+        if len < parent.prefix_len() || len as u32 > P::R::zero().count_zeros() {
+            return None;
+        }
+        let block = match self.free.get_lpm(parent) {
+            // `parent` is contained in a free block.
+            Some(block) => block.clone(),
+            None => select(self.free.children(parent.clone()), len, fit)?.clone(),
+        };
+        if block.prefix_len() < parent.prefix_len() {
+            self.split(block, parent);
+            Some(self.take(parent.clone(), len, Usage::Allocated))
+        } else {
+            Some(self.take(block, len, Usage::Allocated))
+        }
+    }
+
+    /// Reserve a specific prefix, such that it is never allocated. Fails if the prefix is not
+    /// contained in any pool, or if it is not entirely free.
+    pub fn reserve(&mut self, prefix: P) -> Result<(), IpamError> {
+        if self.pools.get_lpm(&prefix).is_none() {
+            return Err(IpamError::NotInPool);
+        }
+        let Some(block) = self.free.get_lpm(&prefix).cloned() else {
+            return Err(IpamError::Unavailable);
+        };
+        self.split(block, &prefix);
+        self.free.remove(&prefix);
+        self.used.insert(prefix, Usage::Reserved);
+        Ok(())
+    }
+
+    /// Free an allocated or reserved prefix, and merge it with adjacent free blocks. Returns how
+    /// the prefix was used, or `None` if it was neither allocated nor reserved.
+    pub fn free(&mut self, prefix: &P) -> Option<Usage> {
+        let usage = self.used.remove(prefix)?;
+        let mut block = prefix.clone();
+        while !self.pools.contains(&block) {
+            let (Some(sibling), Some(parent)) = (block.sibling(), block.supernet()) else {
+                break;
+            };
+            if !self.free.remove(&sibling) {
+                break;
+            }
+            block = parent;
+        }
+        self.free.insert(block);
+        Some(usage)
+    }
+
+    /// Compute the utilization of a pool, or `None` if `pool` is not a pool.
+    pub fn utilization(&self, pool: &P) -> Option<Utilization> {
+        if !self.pools.contains(pool) {
+            return None;
+        }
+        let mut util = Utilization {
+            total: num_addrs(pool),
+            allocated: 0,
+            reserved: 0,
+        };
+        for (p, usage) in self.used.children(pool.clone()) {
+            let count = match usage {
+                Usage::Allocated => &mut util.allocated,
+                Usage::Reserved => &mut util.reserved,
+            };
+            *count = count.saturating_add(num_addrs(p));
+        }
+        Some(util)
+    }
+
+    /// Remove the free block `block`, split it down to length `len`, and mark the first half of
+    /// each split as used. The remaining halves are added to the free blocks.
+    fn take(&mut self, block: P, len: u8, usage: Usage) -> P {
+        let target = P::from_repr_len(block.mask(), len);
+        self.split(block, &target);
+        self.free.remove(&target);
+        self.used.insert(target.clone(), usage);
+        target
+    }
+
+    /// Split the free block `block` along the path towards `target`, such that `target` becomes
+    /// a free block. All siblings along that path are added to the free blocks.
+    fn split(&mut self, block: P, target: &P) {
+        self.free.remove(&block);
+        for len in block.prefix_len() + 1..=target.prefix_len() {
+            let half = P::from_repr_len(target.mask() & mask_from_prefix_len(len), len);
+            self.free.insert(half.sibling().unwrap());
+        }
+        self.free.insert(target.clone());
+    }
+}
+
+/// Select the free block to allocate a prefix of length `len` from.
+fn select<'a, P: Prefix + 'a>(
+    blocks: impl Iterator<Item = &'a P>,
+    len: u8,
+    fit: Fit,
+) -> Option<&'a P> {
+    if len as u32 > P::R::zero().count_zeros() {
+        return None;
+    }
+    let mut blocks = blocks.filter(|b| b.prefix_len() <= len);
+    match fit {
+        Fit::First => blocks.next(),
+        // `max_by_key` returns the last maximum, but we prefer the lowest address.
+        Fit::Best => blocks.fold(None, |best: Option<&P>, b| match best {
+            Some(best) if best.prefix_len() >= b.prefix_len() => Some(best),
+            _ => Some(b),
+        }),
+    }
+}
+
+/// Number of addresses in `prefix`, saturating at `u128::MAX`.
+fn num_addrs<P: Prefix>(prefix: &P) -> u128 {
+    let host_bits = P::R::zero().count_zeros() - prefix.prefix_len() as u32;
+    1u128.checked_shl(host_bits).unwrap_or(u128::MAX)
+}
@@ -2,7 +2,17 @@
 //! match. This crate supports both IPv4 and IPv6 (from either [ipnet](https://docs.rs/ipnet/2.10.0)
 //! or [ipnetwork](https://crates.io/crates/ipnetwork) or [cidr](https://crates.io/crates/cidr)).
 //! It also  supports any tuple `(R, u8)`, where `R` is any unsigned primitive integer (`u8`, `u16`,
-//! `u32`, `u64`, `u128`, or `usize`).
+//! `u32`, `u64`, `u128`, or `usize`), as well as `(Ipv4Addr, u8)` and `(Ipv6Addr, u8)` from the
+//! standard library, which do not require any additional dependency. For non-IP hierarchical keys,
+//! use [`Bits`], which masks the bits beyond the prefix length. Finally, [`RdPrefix`] combines a
+//! route distinguisher (matched exactly) with a prefix (matched using longest-prefix match), such
+//! that a single map can hold the routes of many VPNs.
+//!
+//! **Breaking change in 0.7:** `Prefix` is now implemented for each tuple `(R, u8)` of the
+//! integers listed above individually, instead of through a blanket implementation for all
+//! `R: Unsigned + PrimInt`. The blanket implementation would conflict with the implementations for
+//! `(Ipv4Addr, u8)` and `(Ipv6Addr, u8)`. Code that is generic over `R` and relies on `(R, u8)`
+//! being a `Prefix` must now require `(R, u8): Prefix` explicitly (or use [`Bits<R>`](Bits)).
 //!
 //! # Comparison with related projects
 //!
@@ -26,7 +36,85 @@
 //! including union, intersection and difference operations that are implemented as simultaneous
 //! tree traversals. Further, `prefix-trie` has an interface similar to `std::collections`, and
 //! offers a general longest-prefix match that is not limited to individual addresses. Finally,
-//! `prefix-trie` allows you to (mutably) borrow a sub-trie using views.
+//! `prefix-trie` allows you to (mutably) borrow a sub-trie using views. To count how often each
+//! prefix was inserted (e.g., for reference-counted routes), use [`bag::PrefixBag`].
+//!
+//! Beyond IP prefixes, the module [`domain`] provides a [`domain::DomainMap`] that performs
+//! longest-suffix matching on domain names (e.g., for blocklists), and the module [`wildcard`]
+//! provides a [`wildcard::WildcardMap`] for ternary matching with non-contiguous masks (e.g., for
+//! ACLs). To store the routes of multiple VRFs, use [`vrf::VrfPrefixMap`]. Arbitrary address
+//! ranges can be decomposed into prefixes and stored using [`range::RangeMap`]. For tables that
+//! are built once and queried often, [`stride::StrideMap`] offers longest prefix matches with a
+//! bounded number of memory accesses. To hand out prefixes from a set of address pools, use
+//! [`ipam::Ipam`]. Routing tables with multiple weighted paths per prefix (ECMP) are provided by
+//! [`rib::Rib`], which computes the forwarding table.
+//!
+//! # Features
+//!
+//! - `ipnet` (enabled by default): implement [`Prefix`] for `ipnet::Ipv4Net` and `ipnet::Ipv6Net`,
+//!   and provide the dual-stack [`ip::IpPrefixMap`] and [`ip::IpPrefixSet`], keyed by
+//!   `ipnet::IpNet`, as well as parsing and formatting of router prefix-lists in [`prefix_list`].
+//! - `ipnetwork`: implement [`Prefix`] for `ipnetwork::Ipv4Network` and
+//!   `ipnetwork::Ipv6Network`, such that codebases that already use `ipnetwork` can use their
+//!   types as keys without converting them.
+//! - `cidr`: implement [`Prefix`] for `cidr::Ipv4Cidr` and `cidr::Ipv6Cidr`. `cidr::AnyIpCidr` is
+//!   not supported, as a single trie cannot hold both address families (use one map per family
+//!   instead).
+//! - `macaddr`: provide `MacPrefix`, a prefix of a MAC address (`macaddr::MacAddr6`), e.g., for
+//!   vendor OUI lookup tables.
+//! - `mrt`: provide `mrt::load_rib` to stream BGP routing tables from MRT `TABLE_DUMP_V2` files
+//!   into an [`ip::IpPrefixMap`]. Implies `ipnet`.
+//! - `treebitmap`: implement `From<IpLookupTable>` (of `ip_network_table-deps-treebitmap`, the
+//!   table underlying `ip_network_table`) for `PrefixMap<(Ipv4Addr, u8), T>`,
+//!   `PrefixMap<(Ipv6Addr, u8), T>`, and (with `ipnet`) `PrefixMap<Ipv4Net, T>`,
+//!   `PrefixMap<Ipv6Net, T>`, and [`ip::IpPrefixMap`], to simplify migrating from these crates.
+//!   An `ip_network_table::IpNetworkTable` can be converted by collecting its `iter_v4` and
+//!   `iter_v6` iterators.
+//! - `serde`: implement `Serialize` and `Deserialize` for maps and sets.
+//! - `rayon`: build maps and sets from parallel iterators, and fold them in parallel.
+//! - `epoch` (experimental): provide `map::EpochPrefixMap`, a concurrent map whose readers never
+//!   take a lock, and whose replaced versions are reclaimed using `crossbeam-epoch`.
+//! - `futures`: provide `map::SubscribedPrefixMap`, whose changes (inserts, updates, and removes)
+//!   can be consumed as asynchronous streams (implementing `futures_core::Stream`).
+//! - `rand`: sample entries of a map at random, with a probability proportional to a weight of
+//!   each entry, using `PrefixMap::sample_weighted` or `map::AggregateMap::sample_weighted`.
+//! - `ffi`: provide a C interface to maps of IPv4 and IPv6 prefixes with `u64` values in the
+//!   module `ffi`, using opaque handles. Implies `ipnet`.
+//! - `testing`: provide the module `testing` with helpers for property-based testing using
+//!   `quickcheck`, including the small-keyspace prefix type `testing::TestPrefix` and the `qc!`
+//!   macro, such that downstream crates can test their own trie-based logic.
+//! - `proptest`: provide strategies in the module `strategy` to generate prefixes, maps, and sets
+//!   with a controllable size and distribution of prefix lengths.
+//! - `arbitrary`: implement `arbitrary::Arbitrary` for maps, sets, and the prefix types of this
+//!   crate, such that `cargo-fuzz` harnesses can construct tries directly from fuzzer input. Use
+//!   `arbitrary::prefix` to generate prefix types of other crates.
+//! - `debug-invariants`: check the structural invariants of the tree after every mutation (e.g.,
+//!   `insert`, `remove`, inserting through the entry API, or modifying values through a mutable
+//!   view), and panic with a report of the violated invariant. This is slow, and is intended for
+//!   debug and CI builds.
+//! - `tracing`: instrument bulk operations (building maps and sets from iterators, set operations
+//!   on sets, compaction, freezing, and thawing) with debug-level spans of the `tracing` crate, and
+//!   emit debug events for slow paths (unsorted input when building a map, and growing the table).
+//! - `metrics`: count the lookups (including hits and misses of longest-prefix matches, and the
+//!   deepest traversal), inserts, and removes on each map, exposed as a snapshot using
+//!   `PrefixMap::metrics`.
+//! - `derive`: provide `#[derive(Prefix)]` for newtype wrappers around types that already implement
+//!   [`Prefix`]. All methods are forwarded to the wrapped type.
+//!
+//! ```
+//! # #[cfg(all(feature = "derive", feature = "ipnet"))]
+//! # {
+//! use prefix_trie::{Prefix, PrefixMap};
+//!
+//! #[derive(Debug, Clone, Copy, PartialEq, Eq, Prefix)]
+//! struct CustomerNet(ipnet::Ipv4Net);
+//!
+//! let mut pm: PrefixMap<CustomerNet, _> = PrefixMap::new();
+//! pm.insert(CustomerNet("10.0.0.0/8".parse().unwrap()), 1);
+//! let lpm = pm.get_lpm(&CustomerNet("10.1.0.0/16".parse().unwrap()));
+//! assert_eq!(lpm.map(|(p, v)| (p.0.to_string(), *v)), Some(("10.0.0.0/8".to_string(), 1)));
+//! # }
+//! ```
 //!
 //! # Description of the Tree
 //!
@@ -35,6 +123,24 @@
 //! done as follows: we look at the most significant bit that is **not** part of the prefix
 //! itself. If it is not set, then we take the left branch, and otherwise, we take the right one.
 //!
+//! The tree is path-compressed: each node stores its full prefix, and a node only exists if its
+//! prefix is stored in the map, or if it is needed as a branching point with two children. A chain
+//! of nodes with only a single child is therefore never created (except for nodes kept by
+//! `remove_keep_tree`). A child may thus skip any number of bits compared to its parent, which
+//! keeps the depth of the tree small for sparse tables (such as IPv6 routing tables), without the
+//! need for storing explicit skip counts. Level compression (i.e., branching on multiple bits at once)
+//! is not implemented. Since a child may skip bits, its prefix cannot be derived from the path
+//! from the root alone, and each node must store its full prefix. Hence, there is no mode that
+//! reconstructs keys during iteration instead of storing them.
+//!
+//! Small subtrees are not stored as inline sorted vectors either. Every node occupies a single
+//! entry in the node table (the prefix and two 4-byte child indices), and values are stored in a
+//! separate array, so a sparse subtree is already about as compact as a sorted vector of its
+//! entries would be.
+//! Switching the representation of a subtree on insertion and removal would complicate every
+//! traversal, entry, and view for little gain. To reduce pointer chasing in read-heavy workloads,
+//! use [`PrefixMap::compact`], [`PrefixMap::freeze`], or [`stride::StrideMap`] instead.
+//!
 //! # Traversals
 //!
 //! Any iteration over all elements in the tree is implemented as a graph traversal that will yield
@@ -78,6 +184,8 @@
 //! | `get`, `get_lpm`, `get_mut`                | `O(log n)` |
 //! | `retain`                                   | `O(n)`     |
 //! | `clear` (calling `drop` on `T`)            | `O(n)`     |
+//! | `compact`                                  | `O(n)`     |
+//! | `optimize`                                 | `O(n)`     |
 //! | Operations on [`map::Entry`]               | `O(1)`     |
 //! | `len` and `is_empty`                       | `O(1)`     |
 //! | `union`, `intersection`, `difference`, ... | `O(n)`     |
@@ -104,23 +212,68 @@
 #![allow(clippy::collapsible_else_if)]
 #![deny(missing_docs)]
 
+// Allow the derive macro to refer to `::prefix_trie` in the tests of this crate.
+#[cfg(all(test, feature = "derive"))]
+extern crate self as prefix_trie;
+
+/// Enter a `tracing` span at the debug level until the end of the current scope. This does nothing
+/// unless the `tracing` feature is enabled.
+macro_rules! trace_span {
+    ($($args:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!($($args)*).entered();
+    };
+}
+
 mod fmt;
 #[cfg(test)]
 mod fuzzing;
 pub(crate) mod inner;
+#[cfg(feature = "macaddr")]
+mod mac;
 mod prefix;
+#[cfg(feature = "rayon")]
+mod rayon;
 #[cfg(feature = "serde")]
 mod serde;
 #[cfg(feature = "ipnet")]
 #[cfg(test)]
 mod test;
+#[cfg(feature = "treebitmap")]
+mod treebitmap;
 
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary;
+pub mod bag;
+pub mod domain;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "ipnet")]
+pub mod ip;
+pub mod ipam;
 pub mod map;
+#[cfg(feature = "mrt")]
+pub mod mrt;
+#[cfg(feature = "ipnet")]
+pub mod prefix_list;
+pub mod range;
+pub mod rib;
 pub mod set;
+#[cfg(feature = "proptest")]
+pub mod strategy;
+pub mod stride;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 pub mod trieview;
+pub mod vrf;
+pub mod wildcard;
 
+#[cfg(feature = "macaddr")]
+pub use mac::{MacPrefix, MacPrefixError};
 pub use map::PrefixMap;
-pub use prefix::Prefix;
+pub use prefix::{Bits, Prefix, RdPrefix, Strict, StrictError, Subnets};
+#[cfg(feature = "derive")]
+pub use prefix_trie_derive::Prefix;
 pub use set::PrefixSet;
 pub use trieview::{AsView, AsViewMut, TrieView, TrieViewMut};
 
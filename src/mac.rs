@@ -0,0 +1,151 @@
+//! Prefixes of MAC addresses (EUI-48), using the `macaddr` crate.
+
+use std::{fmt, str::FromStr};
+
+use macaddr::MacAddr6;
+
+use crate::{
+    prefix::{mask_from_prefix_len, subnets, Subnets},
+    Prefix,
+};
+
+/// Number of bits in a MAC address.
+const MAC_BITS: u8 = 48;
+
+/// A prefix of a MAC address, e.g., `00:1b:63:00:00:00/24` for a vendor OUI. The address is
+/// always masked, i.e., all bits beyond the prefix length are zero.
+///
+/// The address is represented by the 48 most significant bits of a `u64`. Generic functions that
+/// take the width of that representation (64 bits) as the length of a single address do not
+/// support `MacPrefix`: [`crate::range::RangeMap::get_addr`], [`crate::PrefixMap::next_free`],
+/// [`crate::PrefixMap::prev_free`], and [`crate::PrefixMap::free_block`] panic, as they build a
+/// prefix of length 64. [`crate::PrefixMap::utilization`] and the allocation functions of
+/// [`crate::ipam::Ipam`] may panic for lengths above 48, and the address counts of
+/// [`crate::ipam::Utilization`] are multiplied by `2^16`. [`Prefix::subnets`] is empty for lengths
+/// above 48.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut ouis: PrefixMap<MacPrefix, &str> = PrefixMap::new();
+/// ouis.insert("00:1b:63:00:00:00/24".parse()?, "Apple");
+/// ouis.insert("00:50:56:00:00:00/24".parse()?, "VMware");
+/// let host = MacPrefix::host("00:50:56:12:34:56".parse()?);
+/// assert_eq!(ouis.get_lpm(&host).map(|(_, v)| *v), Some("VMware"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MacPrefix {
+    addr: MacAddr6,
+    len: u8,
+}
+
+/// Error when creating or parsing a [`MacPrefix`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MacPrefixError {
+    /// The address could not be parsed.
+    InvalidAddress,
+    /// The prefix length is missing, could not be parsed, or is larger than 48.
+    InvalidLength,
+}
+
+impl fmt::Display for MacPrefixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MacPrefixError::InvalidAddress => write!(f, "Invalid MAC address"),
+            MacPrefixError::InvalidLength => write!(f, "Invalid prefix length"),
+        }
+    }
+}
+
+impl std::error::Error for MacPrefixError {}
+
+impl MacPrefix {
+    /// Create a new MAC prefix. All bits beyond `len` are set to zero.
+    pub fn new(addr: MacAddr6, len: u8) -> Result<Self, MacPrefixError> {
+        if len > MAC_BITS {
+            return Err(MacPrefixError::InvalidLength);
+        }
+        Ok(Self::from_repr_len(to_repr(addr), len))
+    }
+
+    /// Create a prefix that contains exactly one MAC address (with a prefix length of 48).
+    pub fn host(addr: MacAddr6) -> Self {
+        Self {
+            addr,
+            len: MAC_BITS,
+        }
+    }
+
+    /// Get the (masked) address.
+    pub fn addr(&self) -> MacAddr6 {
+        self.addr
+    }
+
+    /// Get the prefix length.
+    pub fn prefix_len(&self) -> u8 {
+        self.len
+    }
+}
+
+impl fmt::Display for MacPrefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.len)
+    }
+}
+
+impl FromStr for MacPrefix {
+    type Err = MacPrefixError;
+
+    /// Parse a prefix of the form `00:1b:63:00:00:00/24`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, len) = s.split_once('/').ok_or(MacPrefixError::InvalidLength)?;
+        let addr = addr.parse().map_err(|_| MacPrefixError::InvalidAddress)?;
+        let len = len.parse().map_err(|_| MacPrefixError::InvalidLength)?;
+        Self::new(addr, len)
+    }
+}
+
+/// The address is stored in the 48 most significant bits of a `u64`.
+fn to_repr(addr: MacAddr6) -> u64 {
+    let mut bytes = [0; 8];
+    bytes[..6].copy_from_slice(addr.as_bytes());
+    u64::from_be_bytes(bytes)
+}
+
+impl Prefix for MacPrefix {
+    type R = u64;
+
+    fn repr(&self) -> u64 {
+        to_repr(self.addr)
+    }
+
+    fn prefix_len(&self) -> u8 {
+        self.len
+    }
+
+    /// Panics if `len` is larger than 48, like the implementations for `ipnet`.
+    fn from_repr_len(repr: u64, len: u8) -> Self {
+        assert!(len <= MAC_BITS, "invalid MAC prefix length {len}");
+        let bytes = (repr & mask_from_prefix_len::<u64>(len)).to_be_bytes();
+        let mut addr = [0; 6];
+        addr.copy_from_slice(&bytes[..6]);
+        Self {
+            addr: addr.into(),
+            len,
+        }
+    }
+
+    fn mask(&self) -> u64 {
+        to_repr(self.addr)
+    }
+
+    fn is_host(&self) -> bool {
+        self.len == MAC_BITS
+    }
+
+    fn subnets(&self, new_len: u8) -> Subnets<Self> {
+        subnets(self, new_len, MAC_BITS)
+    }
+}
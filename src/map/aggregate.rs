@@ -0,0 +1,262 @@
+//! Prefix map that maintains aggregates over each subtree.
+
+use super::*;
+use crate::to_right;
+
+/// An aggregate over multiple values, such as their sum or their maximum. The aggregate of a
+/// subtree is built by creating an aggregate for each value using [`Aggregate::from_value`], and
+/// combining them using [`Aggregate::combine`]. Since the aggregates are combined in an
+/// unspecified order, `combine` must be associative and commutative.
+pub trait Aggregate<T>: Clone {
+    /// Create the aggregate of a single value.
+    fn from_value(value: &T) -> Self;
+
+    /// Combine `other` into `self`.
+    fn combine(&mut self, other: &Self);
+}
+
+/// A prefix map that stores for each node the aggregate of all values in its subtree, and
+/// maintains them incrementally on each insert and remove. This makes querying the aggregate of
+/// all elements covered by any prefix `O(log n)`, instead of traversing the entire subtree.
+///
+/// Each modification must update the aggregates of all nodes on the path from the root to the
+/// modified element, so `insert` and `remove` become more expensive by a constant factor.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use prefix_trie::map::{Aggregate, AggregateMap};
+/// #[derive(Clone, Debug, PartialEq)]
+/// struct Traffic(u64);
+///
+/// impl Aggregate<u64> for Traffic {
+///     fn from_value(value: &u64) -> Self {
+///         Traffic(*value)
+///     }
+///     fn combine(&mut self, other: &Self) {
+///         self.0 += other.0;
+///     }
+/// }
+///
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut pm: AggregateMap<ipnet::Ipv4Net, u64, Traffic> = AggregateMap::new();
+/// pm.insert("10.0.0.0/8".parse()?, 1);
+/// pm.insert("10.1.0.0/16".parse()?, 10);
+/// pm.insert("10.1.1.0/24".parse()?, 100);
+/// pm.insert("192.168.0.0/16".parse()?, 1000);
+/// assert_eq!(pm.aggregate(&"10.0.0.0/8".parse()?), Some(&Traffic(111)));
+/// assert_eq!(pm.aggregate(&"10.1.0.0/20".parse()?), Some(&Traffic(100)));
+/// assert_eq!(pm.aggregate(&"0.0.0.0/0".parse()?), Some(&Traffic(1111)));
+/// assert_eq!(pm.aggregate(&"172.16.0.0/12".parse()?), None);
+/// pm.remove(&"10.1.1.0/24".parse()?);
+/// assert_eq!(pm.aggregate(&"10.0.0.0/8".parse()?), Some(&Traffic(11)));
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+#[derive(Clone)]
+pub struct AggregateMap<P, T, A> {
+    map: PrefixMap<P, T>,
+    /// Aggregate of each node in the table (indexed by the node index), or `None` if its subtree
+    /// has no values.
+    aggregates: Vec<Option<A>>,
+}
+
+impl<P, T, A> Default for AggregateMap<P, T, A>
+where
+    P: Prefix,
+{
+    fn default() -> Self {
+        Self {
+            map: PrefixMap::new(),
+            aggregates: vec![None],
+        }
+    }
+}
+
+forward_map_traits!(AggregateMap[P, T, A]: Debug, PartialEq);
+
+impl<P, T, A> AggregateMap<P, T, A>
+where
+    P: Prefix,
+    A: Aggregate<T>,
+{
+    /// Create an empty prefix map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    forward_map_methods!(len, is_empty, as_map, get, contains_key, get_lpm, iter);
+
+    /// Get the aggregate of all values whose prefix is contained in `prefix` (including `prefix`
+    /// itself). Returns `None` if there is no such value. This operation is `O(log n)`.
+    pub fn aggregate(&self, prefix: &P) -> Option<&A> {
+        let table = &self.map.table;
+        let mut idx = 0;
+        loop {
+            let node_p = &table[idx].prefix;
+            if prefix.contains(node_p) {
+                return self.aggregates[idx].as_ref();
+            }
+            if !node_p.contains(prefix) {
+                return None;
+            }
+            idx = table.get_child(idx, to_right(node_p, prefix))?;
+        }
+    }
+
+    /// Select a random entry, where each entry is chosen with a probability proportional to its
+    /// weight. The weight of an entry is `weight(&A::from_value(value))`, and `weight` must be
+    /// additive, i.e., the weight of a combined aggregate must be the sum of the weights of its
+    /// parts (like the sum of values). Entries with weight `0` are never chosen. Returns `None` if
+    /// the weight of all entries is `0` (or if the map is empty).
+    ///
+    /// This only descends once from the root towards the selected entry, guided by the weights of
+    /// the aggregates of each subtree, and is thus `O(log n)`.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use prefix_trie::map::{Aggregate, AggregateMap};
+    /// #[derive(Clone)]
+    /// struct Popularity(u64);
+    ///
+    /// impl Aggregate<u64> for Popularity {
+    ///     fn from_value(value: &u64) -> Self {
+    ///         Popularity(*value)
+    ///     }
+    ///     fn combine(&mut self, other: &Self) {
+    ///         self.0 += other.0;
+    ///     }
+    /// }
+    ///
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: AggregateMap<ipnet::Ipv4Net, u64, Popularity> = AggregateMap::new();
+    /// pm.insert("10.0.0.0/8".parse()?, 1);
+    /// pm.insert("10.1.0.0/16".parse()?, 0);
+    /// pm.insert("192.168.0.0/16".parse()?, 9);
+    /// let mut rng = rand::thread_rng();
+    /// let mut hits = 0;
+    /// for _ in 0..1000 {
+    ///     let (p, _) = pm.sample_weighted(&mut rng, |a| a.0).unwrap();
+    ///     assert_ne!(p, &"10.1.0.0/16".parse()?);
+    ///     hits += (p == &"192.168.0.0/16".parse()?) as u32;
+    /// }
+    /// assert!(hits > 800);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg(feature = "rand")]
+    pub fn sample_weighted<R, F>(&self, rng: &mut R, weight: F) -> Option<(&P, &T)>
+    where
+        R: rand::Rng + ?Sized,
+        F: Fn(&A) -> u64,
+    {
+        let total = self.aggregates[0].as_ref().map(&weight).unwrap_or(0);
+        if total == 0 {
+            return None;
+        }
+        self.select_weighted(rng.gen_range(0..total), weight)
+    }
+
+    /// Select the entry at the weighted position `x`, where the entries are arranged in
+    /// lexicographic order, and each entry occupies a range of positions as wide as its weight.
+    #[cfg(any(test, feature = "rand"))]
+    pub(crate) fn select_weighted<F>(&self, mut x: u64, weight: F) -> Option<(&P, &T)>
+    where
+        F: Fn(&A) -> u64,
+    {
+        let table = &self.map.table;
+        let mut idx = 0;
+        loop {
+            if let Some(value) = table.value(idx) {
+                let own = weight(&A::from_value(value));
+                if x < own {
+                    return table.prefix_value(idx);
+                }
+                x -= own;
+            }
+            let left = table[idx].left();
+            let left_weight = left
+                .and_then(|c| self.aggregates[c].as_ref())
+                .map(&weight)
+                .unwrap_or(0);
+            if x < left_weight {
+                idx = left?;
+            } else {
+                x -= left_weight;
+                idx = table[idx].right()?;
+            }
+        }
+    }
+
+    /// Insert a new item into the map. If the key already exists, replace the value and return
+    /// the old one.
+    pub fn insert(&mut self, prefix: P, value: T) -> Option<T> {
+        let key = P::from_repr_len(prefix.repr(), prefix.prefix_len());
+        let old = self.map.insert(prefix, value);
+        self.update(&key);
+        old
+    }
+
+    /// Remove an element from the map and return its value.
+    pub fn remove(&mut self, prefix: &P) -> Option<T> {
+        let old = self.map.remove(prefix)?;
+        self.update(prefix);
+        Some(old)
+    }
+
+    /// Remove all elements.
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.aggregates.clear();
+        self.aggregates.push(None);
+    }
+
+    /// Recompute the aggregates of all nodes on the path from the root towards `prefix`. Only
+    /// their subtrees have changed after inserting or removing `prefix`.
+    fn update(&mut self, prefix: &P) {
+        let table = &self.map.table;
+        self.aggregates.resize(table.len(), None);
+        let mut path = vec![0];
+        let mut idx = 0;
+        while let Direction::Enter { next, .. } = table.get_direction(idx, prefix) {
+            path.push(next);
+            idx = next;
+        }
+        for idx in path.into_iter().rev() {
+            let mut agg = table.value(idx).map(A::from_value);
+            let children = table[idx].left().into_iter().chain(table[idx].right());
+            for child in children.filter_map(|c| self.aggregates[c].as_ref()) {
+                match agg.as_mut() {
+                    Some(agg) => agg.combine(child),
+                    None => agg = Some(child.clone()),
+                }
+            }
+            self.aggregates[idx] = agg;
+        }
+    }
+}
+
+impl<P, T, A> FromIterator<(P, T)> for AggregateMap<P, T, A>
+where
+    P: Prefix,
+    A: Aggregate<T>,
+{
+    fn from_iter<I: IntoIterator<Item = (P, T)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        iter.into_iter().for_each(|(p, t)| {
+            map.insert(p, t);
+        });
+        map
+    }
+}
+
+impl<P, T, A> From<AggregateMap<P, T, A>> for PrefixMap<P, T> {
+    fn from(map: AggregateMap<P, T, A>) -> Self {
+        map.map
+    }
+}
@@ -0,0 +1,80 @@
+//! Owned set operations on maps, building a new map from two existing ones.
+
+use super::*;
+use crate::AsView;
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix + Clone,
+{
+    /// Create a new map that contains all prefixes that are present in both `self` and `other`,
+    /// with the value computed by `f` from the prefix and both values. Both trees are traversed
+    /// simultaneously (see [`crate::TrieView::intersection`]), and the elements are inserted in
+    /// lexicographic order, such that building the result is `O(n)`.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut routes: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// routes.insert("10.0.0.0/8".parse()?, 100);
+    /// routes.insert("10.1.0.0/16".parse()?, 200);
+    /// let mut names: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// names.insert("10.1.0.0/16".parse()?, "office");
+    /// names.insert("10.2.0.0/16".parse()?, "lab");
+    /// let both = routes.intersection_map(&names, |_, metric, name| (*name, *metric));
+    /// assert_eq!(
+    ///     both.into_iter().collect::<Vec<_>>(),
+    ///     vec![("10.1.0.0/16".parse()?, ("office", 200))]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn intersection_map<U, V, F>(&self, other: &PrefixMap<P, U>, mut f: F) -> PrefixMap<P, V>
+    where
+        F: FnMut(&P, &T, &U) -> V,
+    {
+        trace_span!("intersection_map", a = self.len(), b = other.len());
+        self.view()
+            .intersection(other)
+            .map(|(p, t, u)| (p.clone(), f(p, t, u)))
+            .collect()
+    }
+
+    /// Create a new map that contains all elements of `self` whose prefix is not present in
+    /// `other`. Prefixes that are only covered by a shorter prefix in `other` are kept. Both trees
+    /// are traversed simultaneously (see [`crate::TrieView::difference`]), and the elements are
+    /// inserted in lexicographic order, such that building the result is `O(n)`.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut routes: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// routes.insert("10.0.0.0/8".parse()?, 100);
+    /// routes.insert("10.1.0.0/16".parse()?, 200);
+    /// routes.insert("10.1.1.0/24".parse()?, 300);
+    /// let mut withdrawn: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// withdrawn.insert("10.1.0.0/16".parse()?, ());
+    /// assert_eq!(
+    ///     routes.difference_map(&withdrawn).into_iter().collect::<Vec<_>>(),
+    ///     vec![("10.0.0.0/8".parse()?, 100), ("10.1.1.0/24".parse()?, 300)]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn difference_map<U>(&self, other: &PrefixMap<P, U>) -> PrefixMap<P, T>
+    where
+        T: Clone,
+    {
+        trace_span!("difference_map", a = self.len(), b = other.len());
+        self.view()
+            .difference(other)
+            .map(|x| (x.prefix.clone(), x.value.clone()))
+            .collect()
+    }
+}
@@ -0,0 +1,113 @@
+//! Buffer to insert many elements into a map at once.
+
+use super::*;
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Create a buffer for inserting many elements at once. Insertions are only staged in the
+    /// buffer, and merged into the map on [`BulkInsert::commit`] (or when the buffer is dropped).
+    /// Committing sorts the staged elements and inserts them in lexicographic order, such that
+    /// each insertion continues from the previous one, instead of descending from the root. This
+    /// is much faster than inserting a burst of updates one by one.
+    ///
+    /// Elements are inserted in the order they were staged, so if the same prefix is staged
+    /// multiple times, the last value wins. Replaced values are dropped.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// pm.bulk()
+    ///     .insert("10.2.0.0/16".parse()?, 1)
+    ///     .insert("10.0.0.0/8".parse()?, 2)
+    ///     .insert("10.2.0.0/16".parse()?, 3)
+    ///     .commit();
+    /// assert_eq!(
+    ///     pm.into_iter().collect::<Vec<_>>(),
+    ///     vec![("10.0.0.0/8".parse()?, 2), ("10.2.0.0/16".parse()?, 3)]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn bulk(&mut self) -> BulkInsert<'_, P, T> {
+        BulkInsert {
+            map: self,
+            staged: Vec::new(),
+        }
+    }
+}
+
+/// A buffer of elements that are inserted into a [`PrefixMap`] at once. See
+/// [`PrefixMap::bulk`] for how to create it. Staged elements that were not committed yet are
+/// committed when the buffer is dropped.
+pub struct BulkInsert<'a, P: Prefix, T> {
+    map: &'a mut PrefixMap<P, T>,
+    staged: Vec<(P, T)>,
+}
+
+impl<P: Prefix, T> BulkInsert<'_, P, T> {
+    /// Stage the insertion of `value` at `prefix`.
+    pub fn insert(&mut self, prefix: P, value: T) -> &mut Self {
+        self.staged.push((prefix, value));
+        self
+    }
+
+    /// Returns the number of staged elements that were not committed yet.
+    pub fn staged(&self) -> usize {
+        self.staged.len()
+    }
+
+    /// Insert all staged elements into the map. The buffer can be used again afterwards.
+    pub fn commit(&mut self) {
+        if self.staged.is_empty() {
+            return;
+        }
+        // the sort is stable, so the last value staged for a prefix is inserted last.
+        self.staged.sort_by_key(|(p, _)| (p.mask(), p.prefix_len()));
+        self.map.extend_sorted(self.staged.drain(..));
+    }
+
+    /// Insert all staged elements into the map, like [`Self::commit`], but first reserve space
+    /// for them in the map (see [`PrefixMap::try_reserve`]). If that allocation fails, the error
+    /// is returned, and neither the map nor the staged elements are modified.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// let mut bulk = pm.bulk();
+    /// bulk.insert("10.0.0.0/8".parse()?, 1)
+    ///     .insert("10.2.0.0/16".parse()?, 2);
+    /// bulk.try_commit()?;
+    /// assert_eq!(bulk.staged(), 0);
+    /// drop(bulk);
+    /// assert_eq!(pm.len(), 2);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn try_commit(&mut self) -> Result<(), std::collections::TryReserveError> {
+        self.map.try_reserve(self.staged.len())?;
+        self.commit();
+        Ok(())
+    }
+}
+
+impl<P: Prefix, T> Extend<(P, T)> for BulkInsert<'_, P, T> {
+    fn extend<I: IntoIterator<Item = (P, T)>>(&mut self, iter: I) {
+        self.staged.extend(iter)
+    }
+}
+
+impl<P: Prefix, T> Drop for BulkInsert<'_, P, T> {
+    fn drop(&mut self) {
+        self.commit()
+    }
+}
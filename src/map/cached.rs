@@ -0,0 +1,180 @@
+//! Prefix map with a small cache of recent longest prefix matches.
+
+use std::cell::RefCell;
+
+use super::*;
+
+/// The most recent longest prefix matches, stored as `(repr, prefix_len, result)`, where
+/// `result` is the node index of the match.
+#[derive(Clone)]
+struct LpmCache<R> {
+    entries: Vec<(R, u8, Option<usize>)>,
+    /// Position of the entry that is replaced next.
+    next: usize,
+}
+
+/// A prefix map that remembers the results of the last few calls to
+/// [`CachedPrefixMap::get_lpm`], for workloads with a strong temporal locality, like bursts of
+/// packets towards the same destinations. A lookup first scans the cache, and only traverses the
+/// tree on a miss, replacing the oldest cached result.
+///
+/// The cache is invalidated precisely: inserting or removing a prefix only forgets the cached
+/// queries contained in that prefix, as the result of all other queries cannot have changed.
+/// The cache is scanned linearly, so it should only hold a handful of entries. Since lookups
+/// update the cache through a shared reference, this map is not [`Sync`].
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use prefix_trie::map::CachedPrefixMap;
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut pm: CachedPrefixMap<ipnet::Ipv4Net, _> = CachedPrefixMap::new(8);
+/// pm.insert("10.0.0.0/8".parse()?, 1);
+/// let dst = "10.1.1.1/32".parse()?;
+/// assert_eq!(pm.get_lpm(&dst), Some((&"10.0.0.0/8".parse()?, &1)));
+/// // answered from the cache
+/// assert_eq!(pm.get_lpm(&dst), Some((&"10.0.0.0/8".parse()?, &1)));
+/// // only invalidates queries within 10.1.0.0/16
+/// pm.insert("10.1.0.0/16".parse()?, 2);
+/// assert_eq!(pm.get_lpm(&dst), Some((&"10.1.0.0/16".parse()?, &2)));
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+#[derive(Clone)]
+pub struct CachedPrefixMap<P: Prefix, T> {
+    map: PrefixMap<P, T>,
+    cache: RefCell<LpmCache<P::R>>,
+    cache_size: usize,
+}
+
+forward_map_traits!(CachedPrefixMap[P, T]: Debug, PartialEq);
+
+impl<P, T> CachedPrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Create an empty prefix map that caches the last `cache_size` longest prefix matches.
+    pub fn new(cache_size: usize) -> Self {
+        Self::from_map(PrefixMap::new(), cache_size)
+    }
+
+    /// Wrap `map`, caching the last `cache_size` longest prefix matches.
+    pub fn from_map(map: PrefixMap<P, T>, cache_size: usize) -> Self {
+        Self {
+            map,
+            cache: RefCell::new(LpmCache {
+                entries: Vec::with_capacity(cache_size),
+                next: 0,
+            }),
+            cache_size,
+        }
+    }
+
+    forward_map_methods!(len, is_empty, as_map, get, contains_key, iter);
+
+    /// Returns the maximal number of cached results.
+    pub fn cache_size(&self) -> usize {
+        self.cache_size
+    }
+
+    /// Get a mutable reference to the value of an element by matching exactly on the prefix.
+    /// This does not invalidate the cache.
+    pub fn get_mut(&mut self, prefix: &P) -> Option<&mut T> {
+        self.map.get_mut(prefix)
+    }
+
+    /// Get a value of an element by using longest prefix matching (see [`PrefixMap::get_lpm`]).
+    /// The result is taken from the cache if `prefix` was queried recently.
+    pub fn get_lpm(&self, prefix: &P) -> Option<(&P, &T)> {
+        let repr = prefix.mask();
+        let len = prefix.prefix_len();
+        let mut cache = self.cache.borrow_mut();
+        if let Some((_, _, idx)) = cache
+            .entries
+            .iter()
+            .find(|(r, l, _)| *r == repr && *l == len)
+        {
+            return idx.and_then(|idx| self.map.table.prefix_value(idx));
+        }
+
+        let table = &self.map.table;
+        let mut idx = 0;
+        let mut depth = 1;
+        let mut best_match = None;
+        loop {
+            if table.value(idx).is_some() {
+                best_match = Some(idx);
+            }
+            match table.get_direction(idx, prefix) {
+                Direction::Enter { next, .. } => idx = next,
+                _ => break,
+            }
+            depth += 1;
+        }
+        self.map.counters.lpm(depth, best_match.is_some());
+
+        if self.cache_size > 0 {
+            let entry = (repr, len, best_match);
+            if cache.entries.len() < self.cache_size {
+                cache.entries.push(entry);
+            } else {
+                let next = cache.next;
+                cache.entries[next] = entry;
+                cache.next = (next + 1) % self.cache_size;
+            }
+        }
+        best_match.and_then(|idx| table.prefix_value(idx))
+    }
+
+    /// Insert a new item into the map. If the key already exists, replace the value and return
+    /// the old one. This forgets all cached queries contained in `prefix`.
+    pub fn insert(&mut self, prefix: P, value: T) -> Option<T> {
+        self.invalidate(&prefix);
+        self.map.insert(prefix, value)
+    }
+
+    /// Remove an element from the map and return its value. This forgets all cached queries
+    /// contained in `prefix`.
+    pub fn remove(&mut self, prefix: &P) -> Option<T> {
+        self.invalidate(prefix);
+        self.map.remove(prefix)
+    }
+
+    /// Remove all elements, and clear the cache.
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.clear_cache();
+    }
+
+    /// Forget all cached results.
+    pub fn clear_cache(&mut self) {
+        let cache = self.cache.get_mut();
+        cache.entries.clear();
+        cache.next = 0;
+    }
+
+    /// Forget all cached queries contained in `prefix`, whose longest prefix match may change when
+    /// inserting or removing `prefix`. Node indices of all other matches remain valid (see the
+    /// index stability of [`PrefixMap`]).
+    fn invalidate(&mut self, prefix: &P) {
+        let cache = self.cache.get_mut();
+        // keep the remaining entries ordered from oldest to newest.
+        let next = cache.next;
+        cache.entries.rotate_left(next);
+        cache
+            .entries
+            .retain(|(repr, len, _)| !prefix.contains(&P::from_repr_len(*repr, *len)));
+        cache.next = 0;
+    }
+}
+
+impl<P, T> From<CachedPrefixMap<P, T>> for PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    fn from(map: CachedPrefixMap<P, T>) -> Self {
+        map.map
+    }
+}
@@ -0,0 +1,170 @@
+//! Concurrent prefix map with a single writer that publishes immutable snapshots.
+
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock};
+
+use super::*;
+
+/// A concurrent prefix map, where a single writer modifies a private copy of the map, and
+/// atomically publishes snapshots of it to all readers (read-copy-update). This is the typical
+/// pattern for software routers, where lookups vastly outnumber updates.
+///
+/// Readers obtain the latest snapshot using [`ConcurrentPrefixMap::load`]. This only holds a lock
+/// for the duration of cloning an [`Arc`]; all lookups on the snapshot are then performed without
+/// any synchronization, and the snapshot never changes while it is held. Writers apply a batch of
+/// modifications using [`ConcurrentPrefixMap::update`], which publishes a new snapshot once the
+/// batch is complete. Hence, readers never observe a partially applied batch.
+///
+/// Publishing a snapshot clones the private copy, which is `O(n)`. To avoid allocating for every
+/// snapshot, the map keeps the previous snapshot once no reader holds it anymore, and reuses its
+/// allocation for the next snapshot using [`Clone::clone_from`]. Group modifications into as few
+/// calls to `update` as possible.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use prefix_trie::map::ConcurrentPrefixMap;
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let map: ConcurrentPrefixMap<ipnet::Ipv4Net, u32> = ConcurrentPrefixMap::new();
+/// let snapshot = map.load();
+/// std::thread::scope(|s| {
+///     s.spawn(|| {
+///         map.update(|pm| {
+///             pm.insert("10.0.0.0/8".parse().unwrap(), 1);
+///             pm.insert("10.1.0.0/16".parse().unwrap(), 2);
+///         })
+///     });
+/// });
+/// // the old snapshot is not affected by the update.
+/// assert!(snapshot.is_empty());
+/// let snapshot = map.load();
+/// assert_eq!(snapshot.get_lpm(&"10.1.2.0/24".parse()?), Some((&"10.1.0.0/16".parse()?, &2)));
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+pub struct ConcurrentPrefixMap<P, T> {
+    writer: Mutex<Writer<P, T>>,
+    snapshot: RwLock<Arc<PrefixMap<P, T>>>,
+}
+
+/// State of the writer.
+struct Writer<P, T> {
+    /// The private copy of the map that is modified by the writer.
+    map: PrefixMap<P, T>,
+    /// A previous snapshot that is no longer used by any reader.
+    spare: Option<PrefixMap<P, T>>,
+}
+
+impl<P, T> Default for ConcurrentPrefixMap<P, T>
+where
+    P: Prefix + Clone,
+    T: Clone,
+{
+    fn default() -> Self {
+        Self::from(PrefixMap::new())
+    }
+}
+
+impl<P, T> From<PrefixMap<P, T>> for ConcurrentPrefixMap<P, T>
+where
+    P: Prefix + Clone,
+    T: Clone,
+{
+    fn from(map: PrefixMap<P, T>) -> Self {
+        Self {
+            snapshot: RwLock::new(Arc::new(map.clone())),
+            writer: Mutex::new(Writer { map, spare: None }),
+        }
+    }
+}
+
+impl<P, T> std::fmt::Debug for ConcurrentPrefixMap<P, T>
+where
+    P: Prefix + std::fmt::Debug,
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.snapshot
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .fmt(f)
+    }
+}
+
+impl<P, T> ConcurrentPrefixMap<P, T>
+where
+    P: Prefix + Clone,
+    T: Clone,
+{
+    /// Create an empty concurrent prefix map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the latest published snapshot of the map. The snapshot is immutable, and it is not
+    /// affected by any later update.
+    pub fn load(&self) -> Arc<PrefixMap<P, T>> {
+        self.snapshot
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Apply a batch of modifications to the private copy of the map, and publish a new snapshot
+    /// once `f` returns. Concurrent calls to `update` are serialized. Returns the result of `f`.
+    ///
+    /// If `f` panics, no snapshot is published, but the modifications that `f` made before the
+    /// panic remain in the private copy and are published with the next update.
+    pub fn update<R, F: FnOnce(&mut PrefixMap<P, T>) -> R>(&self, f: F) -> R {
+        let mut writer = self.lock_writer();
+        let result = f(&mut writer.map);
+        self.publish(&mut writer);
+        result
+    }
+
+    /// Insert a new item and publish a new snapshot. If the key already exists, replace the value
+    /// and return the old one. Use [`ConcurrentPrefixMap::update`] to insert multiple items at
+    /// once.
+    pub fn insert(&self, prefix: P, value: T) -> Option<T> {
+        self.update(|map| map.insert(prefix, value))
+    }
+
+    /// Remove an element and publish a new snapshot. Use [`ConcurrentPrefixMap::update`] to remove
+    /// multiple items at once.
+    pub fn remove(&self, prefix: &P) -> Option<T> {
+        self.update(|map| map.remove(prefix))
+    }
+
+    /// Consume the concurrent map and return the private copy of the writer.
+    pub fn into_inner(self) -> PrefixMap<P, T> {
+        self.writer
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+            .map
+    }
+
+    fn lock_writer(&self) -> MutexGuard<'_, Writer<P, T>> {
+        self.writer.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Publish a copy of the private map, reusing the spare allocation if possible.
+    fn publish(&self, writer: &mut Writer<P, T>) {
+        let new = match writer.spare.take() {
+            Some(mut spare) => {
+                spare.clone_from(&writer.map);
+                spare
+            }
+            None => writer.map.clone(),
+        };
+        let old = std::mem::replace(
+            &mut *self
+                .snapshot
+                .write()
+                .unwrap_or_else(PoisonError::into_inner),
+            Arc::new(new),
+        );
+        // If no reader holds the old snapshot anymore, keep it for the next update.
+        writer.spare = Arc::try_unwrap(old).ok();
+    }
+}
@@ -0,0 +1,89 @@
+//! Conversion of a map with overlapping prefixes into an equivalent map of disjoint prefixes.
+
+use super::*;
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix + Clone,
+    T: Clone,
+{
+    /// Create an equivalent map, in which no prefix contains another. The value of each entry is
+    /// pushed down into the parts of its prefix that are not covered by more-specific entries.
+    /// Those parts are decomposed into the smallest number of prefixes. Entries without any
+    /// more-specific entry are kept as they are.
+    ///
+    /// The longest prefix match of every host prefix (i.e., every address) is the same in both
+    /// maps, but in the returned map, each address is covered by at most one entry. This is the
+    /// form required by hardware tables and range-based matchers. Use [`PrefixMap::summarize`] to
+    /// compress the result (which may re-introduce overlapping prefixes).
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/14".parse()?, 'a');
+    /// pm.insert("10.1.0.0/16".parse()?, 'b');
+    /// pm.insert("192.168.0.0/16".parse()?, 'c');
+    /// assert_eq!(
+    ///     pm.disjoint().into_iter().collect::<Vec<_>>(),
+    ///     vec![
+    ///         ("10.0.0.0/16".parse()?, 'a'),
+    ///         ("10.1.0.0/16".parse()?, 'b'),
+    ///         ("10.2.0.0/15".parse()?, 'a'),
+    ///         ("192.168.0.0/16".parse()?, 'c'),
+    ///     ]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn disjoint(&self) -> Self {
+        trace_span!("disjoint", len = self.len());
+        let mut result = Self::with_capacity(self.len());
+        // ancestors of the current entry, together with their direct descendants so far.
+        let mut stack: Vec<(&P, &T, Vec<P>)> = Vec::new();
+        for (p, t) in self.iter() {
+            while let Some((q, v, children)) = stack.pop() {
+                if q.contains(p) {
+                    stack.push((q, v, children));
+                    break;
+                }
+                push_down(q.clone(), &children, v, &mut result);
+            }
+            if let Some((_, _, children)) = stack.last_mut() {
+                children.push(p.clone());
+            }
+            stack.push((p, t, Vec::new()));
+        }
+        while let Some((q, v, children)) = stack.pop() {
+            push_down(q.clone(), &children, v, &mut result);
+        }
+        result
+    }
+}
+
+/// Insert the parts of `prefix` that are not covered by `children` with the value `value`.
+/// `children` must be disjoint, contained in `prefix`, and sorted in lexicographic order.
+fn push_down<P, T>(prefix: P, children: &[P], value: &T, result: &mut PrefixMap<P, T>)
+where
+    P: Prefix + Clone,
+    T: Clone,
+{
+    match children {
+        [] => {
+            result.insert(prefix, value.clone());
+        }
+        [child] if child.prefix_len() == prefix.prefix_len() => {}
+        _ => {
+            // since children are strictly contained in `prefix`, it can be split further.
+            let len = prefix.prefix_len();
+            let left = P::from_repr_len(prefix.mask(), len + 1);
+            let right = left.sibling().unwrap();
+            let split = children.partition_point(|c| !c.is_bit_set(len));
+            push_down(left, &children[..split], value, result);
+            push_down(right, &children[split..], value, result);
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! Lookups by XOR distance, as used for routing tables of distributed hash tables.
+
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use num_traits::Zero;
+
+use super::*;
+use crate::prefix::mask_from_prefix_len;
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Get the entry that is closest to `target` by XOR distance (see [`Self::iter_by_distance`]).
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// let mut pm: PrefixMap<(u8, u8), _> = PrefixMap::new();
+    /// pm.insert((0b0000_0001, 8), 'a');
+    /// pm.insert((0b0100_0000, 8), 'b');
+    /// pm.insert((0b1000_0000, 8), 'c');
+    /// assert_eq!(pm.closest(&(0b0100_0011, 8)), Some((&(0b0100_0000, 8), &'b')));
+    /// assert_eq!(pm.closest(&(0b1111_1111, 8)), Some((&(0b1000_0000, 8), &'c')));
+    /// assert_eq!(pm.closest(&(0b0000_0000, 8)), Some((&(0b0000_0001, 8), &'a')));
+    /// ```
+    pub fn closest(&self, target: &P) -> Option<(&P, &T)> {
+        self.iter_by_distance(target).next()
+    }
+
+    /// Iterate over all entries ordered by their XOR distance to `target`, closest first, as
+    /// needed for the routing tables of Kademlia-style distributed hash tables. The distance of
+    /// an entry is the bitwise XOR of its (masked) representation and the one of `target`, i.e.,
+    /// host bits of prefixes are treated as zero. Entries with the same distance are ordered by
+    /// their prefix length, shortest first.
+    ///
+    /// The iterator descends into the tree guided by `target`, always expanding the subtree with
+    /// the smallest possible distance next. Retrieving the first `k` entries only visits the
+    /// subtrees that may contain them.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// let mut pm: PrefixMap<(u8, u8), _> = PrefixMap::new();
+    /// for (i, id) in [0b0000_0001u8, 0b0100_0000, 0b0100_1000, 0b1000_0000].into_iter().enumerate() {
+    ///     pm.insert((id, 8), i);
+    /// }
+    /// assert_eq!(
+    ///     pm.iter_by_distance(&(0b0100_0011, 8)).map(|(_, i)| *i).collect::<Vec<_>>(),
+    ///     vec![1, 2, 0, 3]
+    /// );
+    /// ```
+    pub fn iter_by_distance(&self, target: &P) -> ByDistance<'_, P, T> {
+        let mut heap = BinaryHeap::new();
+        heap.push(Reverse((P::R::zero(), true, 0, 0)));
+        ByDistance {
+            table: &self.table,
+            target: target.mask(),
+            heap,
+        }
+    }
+}
+
+/// An iterator over all entries of a [`PrefixMap`] ordered by their XOR distance to a target.
+/// See [`PrefixMap::iter_by_distance`] for how to create this iterator.
+pub struct ByDistance<'a, P: Prefix, T> {
+    table: &'a Table<P, T>,
+    target: P::R,
+    heap: BinaryHeap<HeapItem<P::R>>,
+}
+
+/// Entries with their exact distance, and subtrees with the smallest possible distance of any
+/// entry within them, stored as `(distance, is_subtree, prefix_len, idx)`.
+type HeapItem<R> = Reverse<(R, bool, u8, usize)>;
+
+impl<'a, P, T> Iterator for ByDistance<'a, P, T>
+where
+    P: Prefix,
+{
+    type Item = (&'a P, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(Reverse((_, is_subtree, _, idx))) = self.heap.pop() {
+            if !is_subtree {
+                return self.table.prefix_value(idx);
+            }
+            let node = &self.table[idx];
+            let len = node.prefix.prefix_len();
+            let distance = node.prefix.mask() ^ self.target;
+            if self.table.value(idx).is_some() {
+                self.heap.push(Reverse((distance, false, len, idx)));
+            }
+            for child in [node.left(), node.right()].into_iter().flatten() {
+                // all entries in the subtree share the bits of the child prefix, and the
+                // remaining bits may match those of the target.
+                let prefix = &self.table[child].prefix;
+                let len = prefix.prefix_len();
+                let bound = (prefix.mask() ^ self.target) & mask_from_prefix_len(len);
+                self.heap.push(Reverse((bound, true, len, child)));
+            }
+        }
+        None
+    }
+}
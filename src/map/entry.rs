@@ -22,7 +22,10 @@ pub struct VacantEntry<'a, P, T> {
 /// A mutable view into an occupied entry. An occupied entry represents a node that is already
 /// present on the tree.
 pub struct OccupiedEntry<'a, P, T> {
-    pub(super) node: &'a mut Node<P, T>,
+    pub(super) node: &'a mut Node<P>,
+    pub(super) value: &'a mut Option<T>,
+    pub(super) counts: &'a mut ValueCounts,
+    pub(super) len: u8,
     pub(super) prefix: P, // needed to replace the prefix on the thing if we perform insert.
 }
 
@@ -45,7 +48,7 @@ impl<P, T> Entry<'_, P, T> {
     pub fn get(&self) -> Option<&T> {
         match self {
             Entry::Vacant(_) => None,
-            Entry::Occupied(e) => e.node.value.as_ref(),
+            Entry::Occupied(e) => e.value.as_ref(),
         }
     }
 
@@ -69,7 +72,7 @@ impl<P, T> Entry<'_, P, T> {
     pub fn get_mut(&mut self) -> Option<&mut T> {
         match self {
             Entry::Vacant(_) => None,
-            Entry::Occupied(e) => e.node.value.as_mut(),
+            Entry::Occupied(e) => e.value.as_mut(),
         }
     }
 
@@ -154,8 +157,8 @@ where
     #[inline(always)]
     pub fn or_insert(self, default: T) -> &'a mut T {
         match self {
-            Entry::Vacant(e) => e._insert(default).value.as_mut().unwrap(),
-            Entry::Occupied(e) => e.node.value.get_or_insert(default),
+            Entry::Vacant(e) => e._insert(default),
+            Entry::Occupied(e) => e.value.get_or_insert(default),
         }
     }
 
@@ -182,8 +185,8 @@ where
     #[inline(always)]
     pub fn or_insert_with<F: FnOnce() -> T>(self, default: F) -> &'a mut T {
         match self {
-            Entry::Vacant(e) => e._insert(default()).value.as_mut().unwrap(),
-            Entry::Occupied(e) => e.node.value.get_or_insert_with(default),
+            Entry::Vacant(e) => e._insert(default()),
+            Entry::Occupied(e) => e.value.get_or_insert_with(default),
         }
     }
 
@@ -208,7 +211,7 @@ where
         match self {
             Entry::Vacant(e) => Entry::Vacant(e),
             Entry::Occupied(e) => {
-                e.node.value.as_mut().map(f);
+                e.value.as_mut().map(f);
                 Entry::Occupied(e)
             }
         }
@@ -251,28 +254,30 @@ impl<'a, P, T> VacantEntry<'a, P, T>
 where
     P: Prefix,
 {
-    fn _insert(self, v: T) -> &'a mut Node<P, T> {
-        match self.direction {
+    fn _insert(self, v: T) -> &'a mut T {
+        self.map.counters.insert();
+        let idx = match self.direction {
             DirectionForInsert::Reached => {
                 // increment the count, as node.value will be `None`. We do it here as we borrow
                 // `map` mutably in the next line.
-                self.map.count += 1;
-                let node = &mut self.map.table[self.idx];
+                self.map.table.counts_mut().add(self.prefix.prefix_len());
+                let (node, value) = self.map.table.node_value_mut(self.idx);
                 node.prefix = self.prefix;
-                debug_assert!(node.value.is_none());
-                node.value = Some(v);
-                node
+                debug_assert!(value.is_none());
+                *value = Some(v);
+                self.map.table.bump_generation(self.idx);
+                self.idx
             }
             DirectionForInsert::NewLeaf { right } => {
                 let new = self.map.new_node(self.prefix, Some(v));
                 self.map.table.set_child(self.idx, new, right);
-                &mut self.map.table[new]
+                new
             }
             DirectionForInsert::NewChild { right, child_right } => {
                 let new = self.map.new_node(self.prefix, Some(v));
                 let child = self.map.table.set_child(self.idx, new, right).unwrap();
                 self.map.table.set_child(new, child, child_right);
-                &mut self.map.table[new]
+                new
             }
             DirectionForInsert::NewBranch {
                 branch_prefix,
@@ -284,10 +289,12 @@ where
                 let child = self.map.table.set_child(self.idx, branch, right).unwrap();
                 self.map.table.set_child(branch, new, prefix_right);
                 self.map.table.set_child(branch, child, !prefix_right);
-                &mut self.map.table[new]
+                new
             }
             DirectionForInsert::Enter { .. } => unreachable!(),
-        }
+        };
+        self.map.debug_check_invariants("entry");
+        self.map.table.value_mut(idx).as_mut().unwrap()
     }
 }
 
@@ -335,7 +342,7 @@ impl<P, T> OccupiedEntry<'_, P, T> {
     /// # fn main() {}
     /// ```
     pub fn get(&self) -> &T {
-        self.node.value.as_ref().unwrap()
+        self.value.as_ref().unwrap()
     }
 
     /// Gets a mutable reference to the value in the entry.
@@ -363,7 +370,7 @@ impl<P, T> OccupiedEntry<'_, P, T> {
     /// # fn main() {}
     /// ```
     pub fn get_mut(&mut self) -> &mut T {
-        self.node.value.as_mut().unwrap()
+        self.value.as_mut().unwrap()
     }
 
     /// Insert a new value into the entry, returning the old value. This operation will also replace
@@ -389,7 +396,7 @@ impl<P, T> OccupiedEntry<'_, P, T> {
     /// ```
     pub fn insert(self, value: T) -> T {
         self.node.prefix = self.prefix;
-        self.node.value.replace(value).unwrap()
+        self.value.replace(value).unwrap()
     }
 
     /// Remove the current value and return it. The tree will not be modified (the same effect as
@@ -408,13 +415,16 @@ impl<P, T> OccupiedEntry<'_, P, T> {
     ///     Entry::Vacant(_) => unreachable!(),
     /// }
     /// assert_eq!(pm.get(&"192.168.1.0/24".parse()?), None);
+    /// assert_eq!(pm.len(), 0);
     /// # Ok(())
     /// # }
     /// # #[cfg(not(feature = "ipnet"))]
     /// # fn main() {}
     /// ```
     pub fn remove(&mut self) -> T {
-        self.node.value.take().unwrap()
+        let value = self.value.take().unwrap();
+        self.counts.sub(self.len);
+        value
     }
 }
 
@@ -465,8 +475,7 @@ where
     /// # fn main() {}
     /// ```
     pub fn insert(self, default: T) -> &'a mut T {
-        let node = self._insert(default);
-        node.value.as_mut().unwrap()
+        self._insert(default)
     }
 
     /// Get a mutable reference to the value. If the value is yet empty, set it to the return value
@@ -489,8 +498,7 @@ where
     /// # fn main() {}
     /// ```
     pub fn insert_with<F: FnOnce() -> T>(self, default: F) -> &'a mut T {
-        let node = self._insert(default());
-        node.value.as_mut().unwrap()
+        self._insert(default())
     }
 }
 
@@ -519,7 +527,6 @@ where
     /// # fn main() {}
     /// ```
     pub fn default(self) -> &'a mut T {
-        let node = self._insert(Default::default());
-        node.value.as_mut().unwrap()
+        self._insert(Default::default())
     }
 }
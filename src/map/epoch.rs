@@ -0,0 +1,183 @@
+//! Concurrent prefix map with lock-free readers and epoch-based reclamation.
+
+use std::sync::atomic::Ordering;
+use std::sync::{Mutex, PoisonError};
+
+use crossbeam_epoch::{self as epoch, Atomic, Guard, Owned};
+
+use super::*;
+
+/// An experimental concurrent prefix map where readers never take a lock, and where replaced
+/// versions of the map are reclaimed using epoch-based reclamation (`crossbeam-epoch`). It targets
+/// workloads with continuous updates under heavy read load, where even the short lock taken by
+/// [`ConcurrentPrefixMap::load`] causes contention.
+///
+/// Readers pin the current epoch using [`EpochPrefixMap::pin`], which returns an
+/// [`EpochSnapshot`] that dereferences to the current version of the map. Pinning is lock-free,
+/// and the version is not freed while any snapshot is alive. A single writer at a time (concurrent
+/// writers are serialized using a lock) applies modifications to a private copy of the map, and
+/// atomically replaces the current version with a copy of it. The replaced version is retired, and
+/// destroyed once all readers that might still access it have unpinned.
+///
+/// Updates replace the entire map instead of individual nodes, so each update is `O(n)`. Group
+/// modifications into as few calls to [`EpochPrefixMap::update`] as possible. Keep snapshots
+/// short-lived: as long as a thread is pinned, no retired version can be destroyed.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use prefix_trie::map::EpochPrefixMap;
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let map: EpochPrefixMap<ipnet::Ipv4Net, u32> = EpochPrefixMap::new();
+/// std::thread::scope(|s| {
+///     s.spawn(|| map.insert("10.0.0.0/8".parse().unwrap(), 1));
+///     s.spawn(|| map.insert("10.1.0.0/16".parse().unwrap(), 2));
+/// });
+/// let snapshot = map.pin();
+/// assert_eq!(snapshot.get_lpm(&"10.1.2.0/24".parse()?), Some((&"10.1.0.0/16".parse()?, &2)));
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+pub struct EpochPrefixMap<P, T> {
+    current: Atomic<PrefixMap<P, T>>,
+    /// The private copy of the map that is modified by the writer.
+    writer: Mutex<PrefixMap<P, T>>,
+}
+
+/// A pinned snapshot of an [`EpochPrefixMap`] that dereferences to a [`PrefixMap`]. The snapshot
+/// is not affected by later updates. The snapshot cannot be sent to other threads, as it pins the
+/// epoch of the current thread.
+pub struct EpochSnapshot<'a, P, T> {
+    map: &'a PrefixMap<P, T>,
+    _guard: Guard,
+}
+
+impl<P, T> std::ops::Deref for EpochSnapshot<'_, P, T> {
+    type Target = PrefixMap<P, T>;
+
+    fn deref(&self) -> &Self::Target {
+        self.map
+    }
+}
+
+impl<P, T> std::fmt::Debug for EpochSnapshot<'_, P, T>
+where
+    P: Prefix + std::fmt::Debug,
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.map.fmt(f)
+    }
+}
+
+impl<P, T> Default for EpochPrefixMap<P, T>
+where
+    P: Prefix + Clone,
+    T: Clone,
+{
+    fn default() -> Self {
+        Self::from(PrefixMap::new())
+    }
+}
+
+impl<P, T> From<PrefixMap<P, T>> for EpochPrefixMap<P, T>
+where
+    P: Prefix + Clone,
+    T: Clone,
+{
+    fn from(map: PrefixMap<P, T>) -> Self {
+        Self {
+            current: Atomic::new(map.clone()),
+            writer: Mutex::new(map),
+        }
+    }
+}
+
+impl<P, T> std::fmt::Debug for EpochPrefixMap<P, T>
+where
+    P: Prefix + std::fmt::Debug,
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.pin().fmt(f)
+    }
+}
+
+impl<P, T> Drop for EpochPrefixMap<P, T> {
+    fn drop(&mut self) {
+        // Safety: We own `self` mutably, so no reader can access the current version anymore.
+        unsafe {
+            let current = self.current.load(Ordering::Relaxed, epoch::unprotected());
+            if !current.is_null() {
+                drop(current.into_owned());
+            }
+        }
+    }
+}
+
+impl<P, T> EpochPrefixMap<P, T> {
+    /// Pin the current thread and get a snapshot of the current version of the map. This never
+    /// blocks.
+    pub fn pin(&self) -> EpochSnapshot<'_, P, T> {
+        let guard = epoch::pin();
+        let current = self.current.load(Ordering::Acquire, &guard);
+        // Safety: The current version is never null, and it is only destroyed after it was
+        // replaced and all threads that were pinned at that time (including this one, as `guard`
+        // is stored in the snapshot) have unpinned. The reference cannot outlive `self`, as
+        // `self` destroys the current version when it is dropped.
+        let map = unsafe { &*current.as_raw() };
+        EpochSnapshot { map, _guard: guard }
+    }
+}
+
+impl<P, T> EpochPrefixMap<P, T>
+where
+    P: Prefix + Clone + Send + 'static,
+    T: Clone + Send + 'static,
+{
+    /// Create an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply a batch of modifications to the private copy of the map, and replace the current
+    /// version with a copy of it once `f` returns. Concurrent calls to `update` are serialized.
+    /// Returns the result of `f`.
+    pub fn update<R, F: FnOnce(&mut PrefixMap<P, T>) -> R>(&self, f: F) -> R {
+        let mut map = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        let result = f(&mut map);
+        let guard = epoch::pin();
+        let old = self
+            .current
+            .swap(Owned::new(map.clone()), Ordering::AcqRel, &guard);
+        // Safety: `old` is no longer reachable from `self.current`, so only readers that pinned
+        // before the swap can access it. The prefixes and values are `'static` and `Send`, so
+        // they can be destroyed later by any thread.
+        unsafe { guard.defer_destroy(old) };
+        result
+    }
+
+    /// Insert a new item and replace the current version. If the key already exists, replace the
+    /// value and return the old one. Use [`EpochPrefixMap::update`] to insert multiple items at
+    /// once.
+    pub fn insert(&self, prefix: P, value: T) -> Option<T> {
+        self.update(|map| map.insert(prefix, value))
+    }
+
+    /// Remove an element and replace the current version. Use [`EpochPrefixMap::update`] to remove
+    /// multiple items at once.
+    pub fn remove(&self, prefix: &P) -> Option<T> {
+        self.update(|map| map.remove(prefix))
+    }
+
+    /// Consume the map and return the private copy of the writer.
+    pub fn into_inner(mut self) -> PrefixMap<P, T> {
+        std::mem::take(
+            self.writer
+                .get_mut()
+                .unwrap_or_else(PoisonError::into_inner),
+        )
+    }
+}
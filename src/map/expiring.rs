@@ -0,0 +1,202 @@
+//! Prefix map whose entries expire after a deadline.
+
+use std::collections::BTreeMap;
+
+use super::*;
+
+/// An entry of an [`ExpiringPrefixMap`].
+#[derive(Clone)]
+struct Slot<T, D> {
+    value: T,
+    deadline: D,
+    /// Disambiguates entries with the same deadline in the deadline index.
+    id: u64,
+}
+
+/// A prefix map in which each entry has a deadline, e.g., for route caches or learned entries
+/// (like ARP or neighbor tables) that age out. Entries are not removed automatically: call
+/// [`ExpiringPrefixMap::expire_before`] periodically to remove and return all entries whose
+/// deadline has passed. Until then, expired entries are still returned by lookups.
+///
+/// The deadline `D` can be any ordered type, e.g., [`std::time::Instant`] (the default), or a
+/// tick counter. Internally, the map keeps an index of all deadlines, such that a sweep only
+/// visits the expired entries.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use prefix_trie::map::ExpiringPrefixMap;
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut pm: ExpiringPrefixMap<ipnet::Ipv4Net, &str, u64> = ExpiringPrefixMap::new();
+/// pm.insert("10.0.0.1/32".parse()?, "aa:bb", 10);
+/// pm.insert("10.0.0.2/32".parse()?, "cc:dd", 20);
+/// pm.insert("10.0.0.3/32".parse()?, "ee:ff", 30);
+/// pm.refresh(&"10.0.0.1/32".parse()?, 40);
+///
+/// assert_eq!(pm.next_deadline(), Some(&20));
+/// assert_eq!(pm.expire_before(&25), vec![("10.0.0.2/32".parse()?, "cc:dd")]);
+/// assert_eq!(pm.len(), 2);
+/// assert_eq!(pm.deadline(&"10.0.0.1/32".parse()?), Some(&40));
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+#[derive(Clone)]
+pub struct ExpiringPrefixMap<P, T, D = std::time::Instant> {
+    map: PrefixMap<P, Slot<T, D>>,
+    deadlines: BTreeMap<(D, u64), P>,
+    next_id: u64,
+}
+
+impl<P: Prefix, T, D> Default for ExpiringPrefixMap<P, T, D> {
+    fn default() -> Self {
+        Self {
+            map: PrefixMap::new(),
+            deadlines: BTreeMap::new(),
+            next_id: 0,
+        }
+    }
+}
+
+impl<P, T, D> std::fmt::Debug for ExpiringPrefixMap<P, T, D>
+where
+    P: Prefix + std::fmt::Debug,
+    T: std::fmt::Debug,
+    D: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map()
+            .entries(self.map.iter().map(|(p, s)| (p, (&s.value, &s.deadline))))
+            .finish()
+    }
+}
+
+impl<P, T, D> ExpiringPrefixMap<P, T, D>
+where
+    P: Prefix,
+{
+    /// Create an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of elements stored in `self`, including expired elements that were not
+    /// yet removed.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    forward_map_methods!(is_empty, contains_key);
+
+    /// Get the value of an element by matching exactly on the prefix.
+    pub fn get(&self, prefix: &P) -> Option<&T> {
+        self.map.get(prefix).map(|s| &s.value)
+    }
+
+    /// Get a mutable reference to the value of an element by matching exactly on the prefix. The
+    /// deadline is not changed.
+    pub fn get_mut(&mut self, prefix: &P) -> Option<&mut T> {
+        self.map.get_mut(prefix).map(|s| &mut s.value)
+    }
+
+    /// Get the deadline of an element by matching exactly on the prefix.
+    pub fn deadline(&self, prefix: &P) -> Option<&D> {
+        self.map.get(prefix).map(|s| &s.deadline)
+    }
+
+    /// Get a value of an element by using longest prefix matching.
+    pub fn get_lpm(&self, prefix: &P) -> Option<(&P, &T)> {
+        self.map.get_lpm(prefix).map(|(p, s)| (p, &s.value))
+    }
+
+    /// Get the earliest deadline of all elements, i.e., when the next element expires.
+    pub fn next_deadline(&self) -> Option<&D> {
+        self.deadlines.keys().next().map(|(d, _)| d)
+    }
+
+    /// Iterate over all entries in the map in lexicographic order, together with their deadline.
+    pub fn iter(&self) -> impl Iterator<Item = (&P, &T, &D)> {
+        self.map.iter().map(|(p, s)| (p, &s.value, &s.deadline))
+    }
+}
+
+impl<P, T, D> ExpiringPrefixMap<P, T, D>
+where
+    P: Prefix + Clone,
+    D: Ord + Clone,
+{
+    /// Insert a new item into the map that expires at `deadline`. If the key already exists, then
+    /// both the value and the deadline are replaced, and the old value is returned.
+    pub fn insert(&mut self, prefix: P, value: T, deadline: D) -> Option<T> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.deadlines
+            .insert((deadline.clone(), id), prefix.clone());
+        let slot = Slot {
+            value,
+            deadline,
+            id,
+        };
+        let old = self.map.insert(prefix, slot)?;
+        self.deadlines.remove(&(old.deadline, old.id));
+        Some(old.value)
+    }
+
+    /// Change the deadline of an element, e.g., to extend its lifetime after it was used again.
+    /// Returns `false` if the prefix is not present.
+    pub fn refresh(&mut self, prefix: &P, deadline: D) -> bool {
+        let Some(slot) = self.map.get_mut(prefix) else {
+            return false;
+        };
+        let key = self
+            .deadlines
+            .remove(&(slot.deadline.clone(), slot.id))
+            .unwrap();
+        self.deadlines.insert((deadline.clone(), slot.id), key);
+        slot.deadline = deadline;
+        true
+    }
+
+    /// Remove an element from the map and return its value.
+    pub fn remove(&mut self, prefix: &P) -> Option<T> {
+        let slot = self.map.remove(prefix)?;
+        self.deadlines.remove(&(slot.deadline, slot.id));
+        Some(slot.value)
+    }
+
+    /// Remove all elements whose deadline is at or before `now`, and return them ordered by their
+    /// deadline. This only visits the expired elements.
+    pub fn expire_before(&mut self, now: &D) -> Vec<(P, T)> {
+        let remaining = self.deadlines.split_off(&(now.clone(), u64::MAX));
+        let expired = std::mem::replace(&mut self.deadlines, remaining);
+        // the id `u64::MAX` is never assigned, so the split point itself is never an element.
+        expired
+            .into_values()
+            .map(|p| {
+                let slot = self.map.remove(&p).unwrap();
+                (p, slot.value)
+            })
+            .collect()
+    }
+
+    /// Remove all elements.
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.deadlines.clear();
+    }
+}
+
+impl<P, T, D> FromIterator<(P, T, D)> for ExpiringPrefixMap<P, T, D>
+where
+    P: Prefix + Clone,
+    D: Ord + Clone,
+{
+    fn from_iter<I: IntoIterator<Item = (P, T, D)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        for (p, t, d) in iter {
+            map.insert(p, t, d);
+        }
+        map
+    }
+}
@@ -0,0 +1,177 @@
+//! Longest prefix match that records the decisions taken while traversing the tree.
+
+use super::*;
+use crate::to_right;
+
+/// The decision taken at a node during a longest prefix match (see [`PrefixMap::get_lpm_traced`]).
+#[derive(Debug, PartialEq, Eq)]
+pub enum LpmAction<'a, P> {
+    /// The child in the given direction contains the searched prefix, so the search continues
+    /// there.
+    Enter {
+        /// Whether the search continues in the right or the left child.
+        right: bool,
+    },
+    /// The node has the searched prefix, so the search stops.
+    Reached,
+    /// The node has no child in the direction of the searched prefix, so the search stops.
+    NoChild {
+        /// Whether the missing child is the right or the left one.
+        right: bool,
+    },
+    /// The child in the direction of the searched prefix does not contain the searched prefix, so
+    /// all (more-specific) prefixes in its sub-tree are rejected, and the search stops.
+    Rejected {
+        /// Whether the rejected child is the right or the left one.
+        right: bool,
+        /// The prefix of the rejected child.
+        child: &'a P,
+    },
+}
+
+impl<P> Clone for LpmAction<'_, P> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P> Copy for LpmAction<'_, P> {}
+
+/// A single node visited during a longest prefix match (see [`PrefixMap::get_lpm_traced`]).
+#[derive(Debug, PartialEq, Eq)]
+pub struct LpmStep<'a, P> {
+    /// The prefix of the visited node.
+    pub prefix: &'a P,
+    /// Whether the node stores a value, i.e., whether it is a candidate for the match. Nodes
+    /// without a value are branching nodes.
+    pub has_value: bool,
+    /// The decision taken at that node.
+    pub action: LpmAction<'a, P>,
+}
+
+impl<P> Clone for LpmStep<'_, P> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P> Copy for LpmStep<'_, P> {}
+
+/// The result of a longest prefix match, together with all nodes visited to obtain it (see
+/// [`PrefixMap::get_lpm_traced`]). The [`std::fmt::Display`] implementation prints one line per
+/// visited node, followed by the result.
+#[derive(Debug, PartialEq, Eq)]
+pub struct LpmTrace<'a, P, T> {
+    /// The longest prefix match, i.e., the same as the result of [`PrefixMap::get_lpm`].
+    pub result: Option<(&'a P, &'a T)>,
+    /// All visited nodes, starting at the root.
+    pub steps: Vec<LpmStep<'a, P>>,
+}
+
+impl<P, T> Clone for LpmTrace<'_, P, T> {
+    fn clone(&self) -> Self {
+        Self {
+            result: self.result,
+            steps: self.steps.clone(),
+        }
+    }
+}
+
+impl<P: std::fmt::Debug, T> std::fmt::Display for LpmTrace<'_, P, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let side = |right: bool| if right { "right" } else { "left" };
+        for step in &self.steps {
+            write!(f, "{:?}", step.prefix)?;
+            if step.has_value {
+                write!(f, " (candidate)")?;
+            }
+            match step.action {
+                LpmAction::Enter { right } => writeln!(f, ": enter {}", side(right))?,
+                LpmAction::Reached => writeln!(f, ": reached")?,
+                LpmAction::NoChild { right } => writeln!(f, ": no {} child", side(right))?,
+                LpmAction::Rejected { right, child } => {
+                    writeln!(f, ": reject {} child {child:?}", side(right))?
+                }
+            }
+        }
+        match self.result {
+            Some((p, _)) => write!(f, "match: {p:?}"),
+            None => write!(f, "no match"),
+        }
+    }
+}
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Get a value of an element by using longest prefix matching (just like
+    /// [`PrefixMap::get_lpm`]), and record the path through the tree: all visited nodes, the
+    /// direction taken at each of them, and where more-specific prefixes were rejected. Use this
+    /// function to debug why a prefix matched a specific entry.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use prefix_trie::map::LpmAction;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/8".parse()?, 1);
+    /// pm.insert("10.1.0.0/16".parse()?, 2);
+    /// let trace = pm.get_lpm_traced(&"10.1.128.0/24".parse()?);
+    /// assert_eq!(trace.result, pm.get_lpm(&"10.1.128.0/24".parse()?));
+    /// assert_eq!(trace.steps.len(), 3);
+    /// assert_eq!(trace.steps[0].action, LpmAction::Enter { right: false });
+    /// assert_eq!(
+    ///     trace.to_string(),
+    ///     "0.0.0.0/0: enter left\n\
+    ///      10.0.0.0/8 (candidate): enter left\n\
+    ///      10.1.0.0/16 (candidate): no right child\n\
+    ///      match: 10.1.0.0/16"
+    /// );
+    ///
+    /// let trace = pm.get_lpm_traced(&"10.2.0.0/16".parse()?);
+    /// assert_eq!(
+    ///     trace.steps[1].action,
+    ///     LpmAction::Rejected { right: false, child: &"10.1.0.0/16".parse()? }
+    /// );
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn get_lpm_traced<'a>(&'a self, prefix: &P) -> LpmTrace<'a, P, T> {
+        let mut idx = 0;
+        let mut result = None;
+        let mut steps = Vec::new();
+        loop {
+            let node = &self.table[idx];
+            let value = self.table.prefix_value(idx);
+            result = value.or(result);
+            let action = if node.prefix.eq(prefix) {
+                LpmAction::Reached
+            } else {
+                let right = to_right(&node.prefix, prefix);
+                match self.table.get_child(idx, right) {
+                    Some(child) if self.table[child].prefix.contains(prefix) => {
+                        LpmAction::Enter { right }
+                    }
+                    Some(child) => LpmAction::Rejected {
+                        right,
+                        child: &self.table[child].prefix,
+                    },
+                    None => LpmAction::NoChild { right },
+                }
+            };
+            steps.push(LpmStep {
+                prefix: &node.prefix,
+                has_value: value.is_some(),
+                action,
+            });
+            match action {
+                LpmAction::Enter { right } => idx = self.table.get_child(idx, right).unwrap(),
+                _ => return LpmTrace { result, steps },
+            }
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! Flat export of the exact tree topology of a [`PrefixMap`].
+
+use super::*;
+use crate::to_right;
+
+/// A node of a [`FlatTrie`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FlatNode<P, T> {
+    /// The prefix of the node.
+    pub prefix: P,
+    /// The value of the node, or `None` if the node only exists as a branching point.
+    pub value: Option<T>,
+    /// Index of the parent node (`None` for the root).
+    pub parent: Option<usize>,
+    /// Index of the left child, i.e., the child whose first bit after `prefix` is not set.
+    pub left: Option<usize>,
+    /// Index of the right child, i.e., the child whose first bit after `prefix` is set.
+    pub right: Option<usize>,
+}
+
+/// The exact tree topology of a [`PrefixMap`], stored as a plain vector of nodes with explicit
+/// indices, e.g., for FFI consumers or external tools that analyze the tree. Create it with
+/// [`PrefixMap::to_flat`], and build a map with the exact same topology using
+/// [`PrefixMap::from_flat`].
+///
+/// The layout is stable: the root is always stored at index 0, and all nodes are stored in
+/// depth-first order (each node is followed by its left subtree, and then by its right subtree).
+/// Hence, the export of two maps with the same topology is identical, independent of the order of
+/// modifications, and independent of the internal memory layout of the map.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use prefix_trie::map::FlatNode;
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+/// pm.insert("10.0.0.0/16".parse()?, 1);
+/// pm.insert("10.1.0.0/16".parse()?, 2);
+/// let flat = pm.to_flat();
+/// assert_eq!(flat.nodes.len(), 4); // the root, a branching node, and two leaves.
+/// assert_eq!(
+///     flat.nodes[1],
+///     FlatNode { prefix: "10.0.0.0/15".parse()?, value: None, parent: Some(0), left: Some(2), right: Some(3) },
+/// );
+/// assert_eq!(PrefixMap::from_flat(flat)?, pm);
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FlatTrie<P, T> {
+    /// All nodes, with the root at index 0, in depth-first order.
+    pub nodes: Vec<FlatNode<P, T>>,
+}
+
+/// Error when building a [`PrefixMap`] from a [`FlatTrie`] that is not a valid tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FlatTrieError {
+    /// The trie contains no nodes, or the first node is not a root with a prefix length of 0.
+    InvalidRoot,
+    /// A node refers to an index that is out of bounds.
+    InvalidIndex(usize),
+    /// A node is not referenced by exactly its parent, or is not contained in its parent.
+    InvalidParent(usize),
+}
+
+impl std::fmt::Display for FlatTrieError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FlatTrieError::InvalidRoot => write!(f, "Invalid root node"),
+            FlatTrieError::InvalidIndex(i) => write!(f, "Invalid index in node {i}"),
+            FlatTrieError::InvalidParent(i) => write!(f, "Invalid parent of node {i}"),
+        }
+    }
+}
+
+impl std::error::Error for FlatTrieError {}
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Export the exact tree topology of the map, cloning all prefixes and values. See
+    /// [`FlatTrie`].
+    pub fn to_flat(&self) -> FlatTrie<P, T>
+    where
+        P: Clone,
+        T: Clone,
+    {
+        let mut nodes: Vec<FlatNode<P, T>> = Vec::new();
+        // stack of the index in the table, and the new index and direction of its parent.
+        let mut stack: Vec<(usize, Option<(usize, bool)>)> = vec![(0, None)];
+        while let Some((idx, parent)) = stack.pop() {
+            let new = nodes.len();
+            match parent {
+                Some((p, true)) => nodes[p].right = Some(new),
+                Some((p, false)) => nodes[p].left = Some(new),
+                None => {}
+            }
+            nodes.push(FlatNode {
+                prefix: self.table[idx].prefix.clone(),
+                value: self.table.value(idx).cloned(),
+                parent: parent.map(|(p, _)| p),
+                left: None,
+                right: None,
+            });
+            stack.extend(self.table[idx].right().map(|c| (c, Some((new, true)))));
+            stack.extend(self.table[idx].left().map(|c| (c, Some((new, false)))));
+        }
+        FlatTrie { nodes }
+    }
+
+    /// Build a map with the exact tree topology of `flat` (see [`FlatTrie`]). This validates that
+    /// `flat` describes a valid tree, i.e., the first node is the root with a prefix length of 0,
+    /// each other node is referenced as a child by exactly its parent, and each child is contained
+    /// in its parent in the correct direction. The nodes do not need to be stored in depth-first
+    /// order.
+    pub fn from_flat(flat: FlatTrie<P, T>) -> Result<Self, FlatTrieError> {
+        let nodes = &flat.nodes;
+        match nodes.first() {
+            Some(root) if root.parent.is_none() && root.prefix.prefix_len() == 0 => {}
+            _ => return Err(FlatTrieError::InvalidRoot),
+        }
+        for (i, node) in nodes.iter().enumerate() {
+            let children = [(node.left, false), (node.right, true)];
+            for (child, right) in children.into_iter().filter_map(|(c, r)| Some((c?, r))) {
+                let c = nodes.get(child).ok_or(FlatTrieError::InvalidIndex(i))?;
+                if c.parent != Some(i)
+                    || c.prefix.prefix_len() <= node.prefix.prefix_len()
+                    || !node.prefix.contains(&c.prefix)
+                    || to_right(&node.prefix, &c.prefix) != right
+                {
+                    return Err(FlatTrieError::InvalidParent(child));
+                }
+            }
+            if i > 0 {
+                // the parent must reference this node as its child.
+                let p = node.parent.ok_or(FlatTrieError::InvalidParent(i))?;
+                let parent = nodes.get(p).ok_or(FlatTrieError::InvalidIndex(i))?;
+                if parent.left != Some(i) && parent.right != Some(i) {
+                    return Err(FlatTrieError::InvalidParent(i));
+                }
+            }
+        }
+        // Each non-root node has a parent with a shorter prefix that references it, so every node
+        // is reachable from the root, and the tree contains no cycles.
+        let mut map = Self::new();
+        for (i, node) in flat.nodes.into_iter().enumerate() {
+            if node.value.is_some() {
+                map.table.counts_mut().add(node.prefix.prefix_len());
+            }
+            if i == 0 {
+                map.table[0].prefix = node.prefix;
+                *map.table.value_mut(0) = node.value;
+            } else {
+                map.table.push(node.prefix, node.value);
+            }
+            map.table[i].set_left(node.left);
+            map.table[i].set_right(node.right);
+        }
+        Ok(map)
+    }
+}
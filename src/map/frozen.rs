@@ -0,0 +1,248 @@
+//! Immutable, read-optimized form of a [`PrefixMap`].
+
+use super::*;
+use crate::to_right;
+
+/// Marker for a node without a value, or without a right child.
+pub(super) const NONE: u32 = u32::MAX;
+
+#[derive(Clone)]
+pub(super) struct FrozenNode<P> {
+    pub(super) prefix: P,
+    /// Index of the value in `values`, or `NONE`.
+    pub(super) value: u32,
+    /// Index of the right child, or `NONE`. The left child (if present) is always stored directly
+    /// after its parent.
+    pub(super) right: u32,
+    pub(super) has_left: bool,
+}
+
+/// An immutable prefix map with a flat layout, created by [`PrefixMap::freeze`].
+///
+/// All nodes are stored in a single array in depth-first (lexicographic) order, such that the
+/// left child of each node is stored right next to it, and only the index of the right child must
+/// be stored explicitly. Values are stored in a separate array in lexicographic order. Hence,
+/// lookups mostly access consecutive memory, and iterating over all elements is a linear scan.
+///
+/// The map cannot be modified. Use [`FrozenPrefixMap::thaw`] to convert it back into a
+/// [`PrefixMap`].
+///
+/// ```
+/// # use prefix_trie::*;
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+/// pm.insert("192.168.0.0/22".parse()?, 1);
+/// pm.insert("192.168.0.0/24".parse()?, 2);
+/// let frozen = pm.freeze();
+/// assert_eq!(frozen.get(&"192.168.0.0/24".parse()?), Some(&2));
+/// assert_eq!(frozen.get_lpm(&"192.168.1.0/24".parse()?), Some((&"192.168.0.0/22".parse()?, &1)));
+/// let mut pm = frozen.thaw();
+/// pm.insert("10.0.0.0/8".parse()?, 3);
+/// assert_eq!(pm.len(), 3);
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+#[derive(Clone)]
+pub struct FrozenPrefixMap<P, T> {
+    pub(super) nodes: Vec<FrozenNode<P>>,
+    pub(super) values: Vec<T>,
+}
+
+impl<P, T> std::fmt::Debug for FrozenPrefixMap<P, T>
+where
+    P: std::fmt::Debug,
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map()
+            .entries(
+                self.nodes
+                    .iter()
+                    .filter(|n| n.value != NONE)
+                    .map(|n| (&n.prefix, &self.values[n.value as usize])),
+            )
+            .finish()
+    }
+}
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Convert the map into an immutable [`FrozenPrefixMap`] that is optimized for lookups and
+    /// iteration. This operation is `O(n)`.
+    pub fn freeze(mut self) -> FrozenPrefixMap<P, T> {
+        trace_span!("freeze", len = self.len());
+        let mut nodes: Vec<FrozenNode<P>> = Vec::new();
+        let mut values = Vec::with_capacity(self.len());
+        // stack of the index in `self.table`, and the new index of the parent if it is a right
+        // child. Right children are pushed first, such that left children are visited right after
+        // their parent.
+        let mut stack: Vec<(usize, Option<usize>)> = vec![(0, None)];
+        while let Some((idx, right_of)) = stack.pop() {
+            let new = nodes.len();
+            if let Some(parent) = right_of {
+                nodes[parent].right = u32::try_from(new).expect("too many nodes");
+            }
+            let value = match self.table.value_mut(idx).take() {
+                Some(v) => {
+                    values.push(v);
+                    (values.len() - 1) as u32
+                }
+                None => NONE,
+            };
+            let node = &mut self.table[idx];
+            if let Some(right) = node.right() {
+                stack.push((right, Some(new)));
+            }
+            if let Some(left) = node.left() {
+                stack.push((left, None));
+            }
+            nodes.push(FrozenNode {
+                prefix: std::mem::replace(&mut node.prefix, P::zero()),
+                value,
+                right: NONE,
+                has_left: node.left().is_some(),
+            });
+        }
+        FrozenPrefixMap { nodes, values }
+    }
+}
+
+impl<P, T> FrozenPrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Returns the number of elements stored in `self`.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns `true` if the map contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Get the child of a node that is in the direction of `prefix`, if that child contains
+    /// `prefix`.
+    #[inline(always)]
+    fn next(&self, idx: usize, prefix: &P) -> Option<usize> {
+        let node = &self.nodes[idx];
+        let child = if to_right(&node.prefix, prefix) {
+            (node.right != NONE).then_some(node.right as usize)
+        } else {
+            node.has_left.then_some(idx + 1)
+        }?;
+        self.nodes[child].prefix.contains(prefix).then_some(child)
+    }
+
+    #[inline(always)]
+    fn prefix_value(&self, idx: usize) -> Option<(&P, &T)> {
+        let node = &self.nodes[idx];
+        (node.value != NONE).then(|| (&node.prefix, &self.values[node.value as usize]))
+    }
+
+    /// Get the value of an element by matching exactly on the prefix.
+    pub fn get(&self, prefix: &P) -> Option<&T> {
+        self.get_key_value(prefix).map(|(_, t)| t)
+    }
+
+    /// Get the key and the value of an element by matching exactly on the prefix.
+    pub fn get_key_value(&self, prefix: &P) -> Option<(&P, &T)> {
+        let mut idx = 0;
+        loop {
+            if self.nodes[idx].prefix.eq(prefix) {
+                return self.prefix_value(idx);
+            }
+            idx = self.next(idx, prefix)?;
+        }
+    }
+
+    /// Check if a key is present in the datastructure.
+    pub fn contains_key(&self, prefix: &P) -> bool {
+        self.get_key_value(prefix).is_some()
+    }
+
+    /// Get a value of an element by using longest prefix matching.
+    pub fn get_lpm(&self, prefix: &P) -> Option<(&P, &T)> {
+        let mut idx = 0;
+        let mut best_match = None;
+        loop {
+            best_match = self.prefix_value(idx).or(best_match);
+            if self.nodes[idx].prefix.eq(prefix) {
+                return best_match;
+            }
+            match self.next(idx, prefix) {
+                Some(next) => idx = next,
+                None => return best_match,
+            }
+        }
+    }
+
+    /// Iterate over all entries in the map in lexicographic order.
+    pub fn iter(&self) -> impl Iterator<Item = (&P, &T)> {
+        self.nodes
+            .iter()
+            .filter(|n| n.value != NONE)
+            .map(|n| (&n.prefix, &self.values[n.value as usize]))
+    }
+
+    /// Iterate over all keys in lexicographic order.
+    pub fn keys(&self) -> impl Iterator<Item = &P> {
+        self.iter().map(|(p, _)| p)
+    }
+
+    /// Iterate over all values in lexicographic order of their keys.
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.values.iter()
+    }
+
+    /// Convert the map back into a mutable [`PrefixMap`], keeping the tree structure. This
+    /// operation is `O(n)`.
+    pub fn thaw(self) -> PrefixMap<P, T> {
+        trace_span!("thaw", len = self.values.len());
+        let mut map = PrefixMap::new();
+        let mut values: Vec<Option<T>> = self.values.into_iter().map(Some).collect();
+        // Nodes are stored in depth-first order, so they obtain the same index in the new table.
+        for (idx, node) in self.nodes.into_iter().enumerate() {
+            let value = values.get_mut(node.value as usize).and_then(Option::take);
+            if value.is_some() {
+                map.table.counts_mut().add(node.prefix.prefix_len());
+            }
+            if idx == 0 {
+                map.table[0].prefix = node.prefix;
+                *map.table.value_mut(0) = value;
+            } else {
+                map.table.push(node.prefix, value);
+            }
+            if node.has_left {
+                map.table[idx].set_left(Some(idx + 1));
+            }
+            if node.right != NONE {
+                map.table[idx].set_right(Some(node.right as usize));
+            }
+        }
+        map
+    }
+}
+
+impl<P: Prefix, T> From<PrefixMap<P, T>> for FrozenPrefixMap<P, T> {
+    fn from(map: PrefixMap<P, T>) -> Self {
+        map.freeze()
+    }
+}
+
+impl<P: Prefix, T> From<FrozenPrefixMap<P, T>> for PrefixMap<P, T> {
+    fn from(map: FrozenPrefixMap<P, T>) -> Self {
+        map.thaw()
+    }
+}
+
+impl<P: Prefix, T> FromIterator<(P, T)> for FrozenPrefixMap<P, T> {
+    fn from_iter<I: IntoIterator<Item = (P, T)>>(iter: I) -> Self {
+        PrefixMap::from_iter(iter).freeze()
+    }
+}
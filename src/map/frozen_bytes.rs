@@ -0,0 +1,424 @@
+//! Stable byte layout of a [`FrozenPrefixMap`] that can be queried without deserialization.
+
+use num_traits::{NumCast, PrimInt, ToPrimitive, Zero};
+
+use super::frozen::{FrozenNode, NONE};
+use super::*;
+use crate::prefix::mask_from_prefix_len;
+
+/// Magic bytes at the beginning of each serialized map.
+const MAGIC: &[u8; 8] = b"PTFROZEN";
+/// Current version of the byte layout.
+const VERSION: u16 = 1;
+/// Size of the header in bytes.
+const HEADER_LEN: usize = 40;
+/// Size of a node in bytes, excluding the prefix.
+const NODE_LEN: usize = 12;
+
+/// A value that can be stored in the byte layout of a [`FrozenPrefixMap`] (see
+/// [`FrozenPrefixMap::to_bytes`]), or in the binary format of a [`PrefixMap`] (see
+/// [`PrefixMap::write_to`]). Values are stored with a fixed size in little-endian byte order.
+pub trait FrozenValue: Sized {
+    /// The number of bytes of each value.
+    const SIZE: usize;
+
+    /// Append the encoded value (exactly `SIZE` bytes) to `buf`.
+    fn write_bytes(&self, buf: &mut Vec<u8>);
+
+    /// Decode a value from exactly `SIZE` bytes.
+    fn read_bytes(bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_frozen_value {
+    ($($t:ty),*) => {
+        $(
+            impl FrozenValue for $t {
+                const SIZE: usize = std::mem::size_of::<$t>();
+
+                fn write_bytes(&self, buf: &mut Vec<u8>) {
+                    buf.extend_from_slice(&self.to_le_bytes());
+                }
+
+                fn read_bytes(bytes: &[u8]) -> Self {
+                    <$t>::from_le_bytes(bytes.try_into().unwrap())
+                }
+            }
+        )*
+    };
+}
+
+impl_frozen_value!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl FrozenValue for () {
+    const SIZE: usize = 0;
+
+    fn write_bytes(&self, _buf: &mut Vec<u8>) {}
+
+    fn read_bytes(_bytes: &[u8]) -> Self {}
+}
+
+/// Error when opening a byte buffer as [`FrozenBytes`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FrozenBytesError {
+    /// The buffer does not start with the expected magic bytes.
+    InvalidMagic,
+    /// The buffer was written with an unsupported version of the layout.
+    UnsupportedVersion(u16),
+    /// The width of the prefix or the size of the values do not match the expected types.
+    TypeMismatch,
+    /// The buffer is shorter or longer than described in its header.
+    InvalidLength,
+    /// The checksum of the buffer does not match.
+    ChecksumMismatch,
+    /// The nodes do not describe a valid tree.
+    Corrupted,
+}
+
+impl std::fmt::Display for FrozenBytesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FrozenBytesError::InvalidMagic => write!(f, "Not a serialized prefix map"),
+            FrozenBytesError::UnsupportedVersion(v) => write!(f, "Unsupported version {v}"),
+            FrozenBytesError::TypeMismatch => write!(f, "Prefix or value type mismatch"),
+            FrozenBytesError::InvalidLength => write!(f, "Invalid buffer length"),
+            FrozenBytesError::ChecksumMismatch => write!(f, "Checksum mismatch"),
+            FrozenBytesError::Corrupted => write!(f, "Corrupted tree structure"),
+        }
+    }
+}
+
+impl std::error::Error for FrozenBytesError {}
+
+/// The number of bytes used to store the representation of `P`.
+pub(super) fn key_len<P: Prefix>() -> usize {
+    std::mem::size_of::<P::R>()
+}
+
+/// Initial state of the checksum.
+pub(super) const CHECKSUM_INIT: u64 = 0xcbf29ce484222325;
+
+/// 64-bit FNV-1a hash.
+pub(crate) fn checksum(data: &[u8]) -> u64 {
+    checksum_update(CHECKSUM_INIT, data)
+}
+
+/// Update the 64-bit FNV-1a hash `hash` with `data`.
+pub(super) fn checksum_update(hash: u64, data: &[u8]) -> u64 {
+    data.iter()
+        .fold(hash, |h, b| (h ^ *b as u64).wrapping_mul(0x100000001b3))
+}
+
+fn read_u16(data: &[u8], pos: usize) -> u16 {
+    u16::from_le_bytes(data[pos..pos + 2].try_into().unwrap())
+}
+
+fn read_u32(data: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], pos: usize) -> u64 {
+    u64::from_le_bytes(data[pos..pos + 8].try_into().unwrap())
+}
+
+impl<P, T> FrozenPrefixMap<P, T>
+where
+    P: Prefix,
+    T: FrozenValue,
+{
+    /// Serialize the map into a stable, versioned byte layout that can be queried directly using
+    /// [`FrozenBytes`] (e.g., after memory-mapping a file), without deserializing it first.
+    ///
+    /// All integers are stored in little-endian byte order, independent of the platform. The
+    /// layout consists of a 40-byte header (magic bytes, version, key width, value size, number of
+    /// nodes and values, and a checksum of the remaining data), followed by all nodes, and all
+    /// values.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use prefix_trie::map::FrozenBytes;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, u32> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/8".parse()?, 1);
+    /// pm.insert("10.1.0.0/16".parse()?, 2);
+    /// let bytes = pm.freeze().to_bytes();
+    /// // `bytes` could be written to a file, and later be memory-mapped.
+    /// let fib = FrozenBytes::<ipnet::Ipv4Net, u32>::new(&bytes)?;
+    /// assert_eq!(fib.get_lpm(&"10.1.2.0/24".parse()?), Some(("10.1.0.0/16".parse()?, 2)));
+    /// assert_eq!(fib.get(&"10.0.0.0/8".parse()?), Some(1));
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let kl = key_len::<P>();
+        let mut body =
+            Vec::with_capacity(self.nodes.len() * (kl + NODE_LEN) + self.values.len() * T::SIZE);
+        for node in &self.nodes {
+            let repr = node.prefix.mask().to_u128().unwrap();
+            body.extend_from_slice(&repr.to_le_bytes()[..kl]);
+            body.push(node.prefix.prefix_len());
+            body.push(node.has_left as u8);
+            body.extend_from_slice(&[0, 0]);
+            body.extend_from_slice(&node.right.to_le_bytes());
+            body.extend_from_slice(&node.value.to_le_bytes());
+        }
+        for value in &self.values {
+            value.write_bytes(&mut body);
+        }
+
+        let mut buf = Vec::with_capacity(HEADER_LEN + body.len());
+        buf.extend_from_slice(MAGIC);
+        buf.extend_from_slice(&VERSION.to_le_bytes());
+        buf.extend_from_slice(&(kl as u16).to_le_bytes());
+        buf.extend_from_slice(&(T::SIZE as u16).to_le_bytes());
+        buf.extend_from_slice(&[0, 0]);
+        buf.extend_from_slice(&(self.nodes.len() as u64).to_le_bytes());
+        buf.extend_from_slice(&(self.values.len() as u64).to_le_bytes());
+        buf.extend_from_slice(&checksum(&body).to_le_bytes());
+        buf.extend_from_slice(&body);
+        buf
+    }
+}
+
+/// A read-only view of a [`FrozenPrefixMap`] serialized with [`FrozenPrefixMap::to_bytes`]. All
+/// lookups operate directly on the borrowed bytes, which may be memory-mapped from a file. The
+/// buffer does not need to be aligned.
+///
+/// This is the zero-copy form of a prefix map, e.g., to ship prebuilt tables in a binary (using
+/// `include_bytes!`) or to load them from disk. There is no integration with `rkyv`: its archived
+/// form of a [`PrefixMap`] would be the archived node table, on which lookups cannot reuse the
+/// existing traversal code. [`FrozenBytes`] provides the same archived lookup path without an
+/// additional dependency.
+pub struct FrozenBytes<'a, P, T> {
+    nodes: &'a [u8],
+    values: &'a [u8],
+    num_nodes: usize,
+    num_values: usize,
+    _marker: std::marker::PhantomData<(P, T)>,
+}
+
+impl<P, T> Clone for FrozenBytes<'_, P, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P, T> Copy for FrozenBytes<'_, P, T> {}
+
+impl<'a, P, T> FrozenBytes<'a, P, T>
+where
+    P: Prefix,
+    T: FrozenValue,
+{
+    /// Open a serialized map. This verifies the header, the checksum, and the tree structure,
+    /// which requires reading the entire buffer once (`O(n)`).
+    pub fn new(data: &'a [u8]) -> Result<Self, FrozenBytesError> {
+        if data.len() < HEADER_LEN {
+            return Err(FrozenBytesError::InvalidLength);
+        }
+        if &data[..8] != MAGIC {
+            return Err(FrozenBytesError::InvalidMagic);
+        }
+        let version = read_u16(data, 8);
+        if version != VERSION {
+            return Err(FrozenBytesError::UnsupportedVersion(version));
+        }
+        let kl = key_len::<P>();
+        if read_u16(data, 10) as usize != kl || read_u16(data, 12) as usize != T::SIZE {
+            return Err(FrozenBytesError::TypeMismatch);
+        }
+        let num_nodes = read_u64(data, 16) as usize;
+        let num_values = read_u64(data, 24) as usize;
+        let body = &data[HEADER_LEN..];
+        let nodes_len = num_nodes.checked_mul(kl + NODE_LEN);
+        let values_len = num_values.checked_mul(T::SIZE);
+        match (nodes_len, values_len) {
+            (Some(n), Some(v)) if n.checked_add(v) == Some(body.len()) => {}
+            _ => return Err(FrozenBytesError::InvalidLength),
+        }
+        if checksum(body) != read_u64(data, 32) {
+            return Err(FrozenBytesError::ChecksumMismatch);
+        }
+        let (nodes, values) = body.split_at(num_nodes * (kl + NODE_LEN));
+        let map = Self {
+            nodes,
+            values,
+            num_nodes,
+            num_values,
+            _marker: std::marker::PhantomData,
+        };
+        if num_values > NONE as usize || !map.valid_tree() {
+            return Err(FrozenBytesError::Corrupted);
+        }
+        Ok(map)
+    }
+
+    /// Check that the nodes form a tree rooted at node 0 with length 0, in which every node except
+    /// the root is referenced exactly once, and children are stored after their parent. Each child
+    /// must be longer than its parent, be contained in it, and lie on the side of the parent's bit
+    /// at its length. Each value must be referenced by exactly one node.
+    fn valid_tree(&self) -> bool {
+        let n = self.num_nodes;
+        let width = P::R::zero().count_zeros();
+        let mut has_parent = vec![false; n];
+        let mut value_used = vec![false; self.num_values];
+        if n == 0 || self.node(0).1 != 0 {
+            return false;
+        }
+        for i in 0..n {
+            let (repr, len, has_left, right, value) = self.node(i);
+            if len as u32 > width || repr & mask_from_prefix_len(len) != repr {
+                return false;
+            }
+            if i > 0 && !has_parent[i] {
+                return false;
+            }
+            if value != NONE {
+                match value_used.get_mut(value as usize) {
+                    Some(used) if !*used => *used = true,
+                    _ => return false,
+                }
+            }
+            let left = has_left.then_some((i + 1, false));
+            let right = (right != NONE).then_some((right as usize, true));
+            for (child, bit) in left.into_iter().chain(right) {
+                if child <= i || child >= n || has_parent[child] {
+                    return false;
+                }
+                has_parent[child] = true;
+                let (c_repr, c_len, ..) = self.node(child);
+                if c_len <= len
+                    || c_len as u32 > width
+                    || c_repr & mask_from_prefix_len(len) != repr
+                {
+                    return false;
+                }
+                // `c_len > len` ensures that `len + 1` does not exceed the width.
+                let bit_mask = mask_from_prefix_len::<P::R>(len + 1) ^ mask_from_prefix_len(len);
+                if (c_repr & bit_mask != P::R::zero()) != bit {
+                    return false;
+                }
+            }
+        }
+        value_used.iter().all(|used| *used)
+    }
+
+    /// Returns the number of elements stored in the map.
+    pub fn len(&self) -> usize {
+        self.num_values
+    }
+
+    /// Returns `true` if the map contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Decode the node at `idx` into its representation, prefix length, whether it has a left
+    /// child, the index of its right child, and the index of its value.
+    #[inline(always)]
+    fn node(&self, idx: usize) -> (P::R, u8, bool, u32, u32) {
+        let kl = key_len::<P>();
+        let pos = idx * (kl + NODE_LEN);
+        let mut repr = [0u8; 16];
+        repr[..kl].copy_from_slice(&self.nodes[pos..pos + kl]);
+        let repr = <P::R as NumCast>::from(u128::from_le_bytes(repr)).unwrap();
+        let pos = pos + kl;
+        (
+            repr,
+            self.nodes[pos],
+            self.nodes[pos + 1] != 0,
+            read_u32(self.nodes, pos + 4),
+            read_u32(self.nodes, pos + 8),
+        )
+    }
+
+    fn value(&self, idx: u32) -> Option<T> {
+        (idx != NONE).then(|| {
+            let pos = idx as usize * T::SIZE;
+            T::read_bytes(&self.values[pos..pos + T::SIZE])
+        })
+    }
+
+    /// Traverse the tree towards `prefix`, calling `f` for each node along the path (including the
+    /// node of `prefix` itself, if present).
+    fn traverse(&self, prefix: &P, mut f: impl FnMut(usize, P::R, u8, u32)) {
+        let q = prefix.mask();
+        let q_len = prefix.prefix_len();
+        let mut idx = 0;
+        loop {
+            let (repr, len, has_left, right, value) = self.node(idx);
+            f(idx, repr, len, value);
+            if len >= q_len {
+                return;
+            }
+            let next = if prefix.is_bit_set(len) {
+                (right != NONE).then_some(right as usize)
+            } else {
+                has_left.then_some(idx + 1)
+            };
+            let Some(next) = next else { return };
+            let (c_repr, c_len, ..) = self.node(next);
+            if c_len > q_len || q & mask_from_prefix_len(c_len) != c_repr {
+                return;
+            }
+            idx = next;
+        }
+    }
+
+    /// Get the value of an element by matching exactly on the prefix.
+    pub fn get(&self, prefix: &P) -> Option<T> {
+        let mut result = None;
+        self.traverse(prefix, |_, repr, len, value| {
+            if len == prefix.prefix_len() && repr == prefix.mask() {
+                result = self.value(value);
+            }
+        });
+        result
+    }
+
+    /// Check if a key is present in the map.
+    pub fn contains_key(&self, prefix: &P) -> bool {
+        self.get(prefix).is_some()
+    }
+
+    /// Get the longest prefix in the map that contains `prefix`, along with its value.
+    pub fn get_lpm(&self, prefix: &P) -> Option<(P, T)> {
+        let mut best = None;
+        self.traverse(prefix, |_, repr, len, value| {
+            if value != NONE {
+                best = Some((repr, len, value));
+            }
+        });
+        let (repr, len, value) = best?;
+        Some((P::from_repr_len(repr, len), self.value(value)?))
+    }
+
+    /// Iterate over all elements in lexicographic order.
+    pub fn iter(&self) -> impl Iterator<Item = (P, T)> + '_ {
+        (0..self.num_nodes).filter_map(move |i| {
+            let (repr, len, _, _, value) = self.node(i);
+            Some((P::from_repr_len(repr, len), self.value(value)?))
+        })
+    }
+
+    /// Copy all elements into a [`FrozenPrefixMap`].
+    pub fn to_frozen(&self) -> FrozenPrefixMap<P, T> {
+        let nodes = (0..self.num_nodes)
+            .map(|i| {
+                let (repr, len, has_left, right, value) = self.node(i);
+                FrozenNode {
+                    prefix: P::from_repr_len(repr, len),
+                    value,
+                    right,
+                    has_left,
+                }
+            })
+            .collect();
+        let values = (0..self.num_values)
+            .map(|i| self.value(i as u32).unwrap())
+            .collect();
+        FrozenPrefixMap { nodes, values }
+    }
+}
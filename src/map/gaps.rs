@@ -0,0 +1,82 @@
+//! Queries for addresses that are not covered by any entry of a map.
+
+use num_traits::{CheckedAdd, CheckedSub, One, PrimInt, Zero};
+
+use super::*;
+use crate::{prefix::mask_from_prefix_len, range::AddrRange};
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Find the first address at or after `addr` that is not covered by any entry. Returns `None`
+    /// if all addresses from `addr` up to the last address are covered.
+    ///
+    /// Each step jumps over the shortest entry that covers the current address, so the number of
+    /// steps is bounded by the number of adjacent entries that must be skipped.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// let mut pm: PrefixMap<(u32, u8), _> = PrefixMap::new();
+    /// pm.insert((0x0a000000, 24), 1);
+    /// pm.insert((0x0a000100, 25), 2);
+    /// pm.insert((0x0a000100, 32), 3);
+    /// assert_eq!(pm.next_free(0x0a000042), Some(0x0a000180));
+    /// assert_eq!(pm.next_free(0x0a000200), Some(0x0a000200));
+    /// assert_eq!(pm.prev_free(0x0a000142), Some(0x09ffffff));
+    /// ```
+    pub fn next_free(&self, addr: P::R) -> Option<P::R> {
+        let width = P::R::zero().count_zeros() as u8;
+        let mut addr = addr;
+        loop {
+            let host = P::from_repr_len(addr, width);
+            let Some((p, _)) = self.cover(&host).next() else {
+                return Some(addr);
+            };
+            addr = AddrRange::from_prefix(p).end().checked_add(&P::R::one())?;
+        }
+    }
+
+    /// Find the last address at or before `addr` that is not covered by any entry. Returns `None`
+    /// if all addresses from the first address up to `addr` are covered.
+    pub fn prev_free(&self, addr: P::R) -> Option<P::R> {
+        let width = P::R::zero().count_zeros() as u8;
+        let mut addr = addr;
+        loop {
+            let host = P::from_repr_len(addr, width);
+            let Some((p, _)) = self.cover(&host).next() else {
+                return Some(addr);
+            };
+            addr = AddrRange::from_prefix(p)
+                .start()
+                .checked_sub(&P::R::one())?;
+        }
+    }
+
+    /// Get the largest prefix that contains `addr`, and that neither contains, nor is contained in
+    /// any entry of the map. Returns `None` if `addr` is covered by an entry. Combined with
+    /// [`Self::next_free`], this yields the next free block after an address.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// let mut pm: PrefixMap<(u32, u8), _> = PrefixMap::new();
+    /// pm.insert((0x0a000000, 24), 1);
+    /// pm.insert((0x0a000300, 24), 2);
+    /// let free = pm.next_free(0x0a000000).unwrap();
+    /// assert_eq!(pm.free_block(free), Some((0x0a000100, 24)));
+    /// assert_eq!(pm.free_block(0x0a000242), Some((0x0a000200, 24)));
+    /// assert_eq!(pm.free_block(0x0a000042), None);
+    /// ```
+    pub fn free_block(&self, addr: P::R) -> Option<P> {
+        let width = P::R::zero().count_zeros() as u8;
+        if self.get_lpm(&P::from_repr_len(addr, width)).is_some() {
+            return None;
+        }
+        // `addr` is not covered, so a prefix containing `addr` is free iff it contains no entry.
+        // This is monotone in the prefix length, and the host prefix is always free.
+        let block = |len: u8| P::from_repr_len(addr & mask_from_prefix_len(len), len);
+        (0..=width)
+            .find(|len| self.children(block(*len)).next().is_none())
+            .map(block)
+    }
+}
@@ -0,0 +1,139 @@
+//! Prefix map with an additional hash index for exact-match lookups.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::*;
+
+/// A prefix map that maintains a hash index from each prefix to its node in the tree. Exact-match
+/// lookups ([`IndexedPrefixMap::get`], [`IndexedPrefixMap::get_mut`], and
+/// [`IndexedPrefixMap::contains_key`]) take `O(1)` time, while longest prefix matches and ordered
+/// iteration still use the tree.
+///
+/// The index requires additional memory for each element, and each insert and remove must update
+/// the index.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use prefix_trie::map::IndexedPrefixMap;
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut pm: IndexedPrefixMap<ipnet::Ipv4Net, _> = IndexedPrefixMap::new();
+/// pm.insert("192.168.0.0/22".parse()?, 1);
+/// pm.insert("192.168.0.0/24".parse()?, 2);
+/// assert_eq!(pm.get(&"192.168.0.0/24".parse()?), Some(&2));
+/// assert_eq!(pm.get(&"192.168.1.0/24".parse()?), None);
+/// *pm.get_mut(&"192.168.0.0/22".parse()?).unwrap() += 10;
+/// assert_eq!(pm.get_lpm(&"192.168.1.0/24".parse()?), Some((&"192.168.0.0/22".parse()?, &11)));
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+#[derive(Clone)]
+pub struct IndexedPrefixMap<P: Prefix, T> {
+    map: PrefixMap<P, T>,
+    /// Index of the node of each prefix (identified by its mask and length).
+    index: HashMap<(P::R, u8), usize>,
+}
+
+impl<P, T> Default for IndexedPrefixMap<P, T>
+where
+    P: Prefix,
+{
+    fn default() -> Self {
+        Self {
+            map: PrefixMap::new(),
+            index: HashMap::new(),
+        }
+    }
+}
+
+forward_map_traits!(IndexedPrefixMap[P, T]: Debug, PartialEq);
+
+/// The key of `prefix` in the index.
+fn key<P: Prefix>(prefix: &P) -> (P::R, u8) {
+    (prefix.mask(), prefix.prefix_len())
+}
+
+impl<P, T> IndexedPrefixMap<P, T>
+where
+    P: Prefix,
+    P::R: Hash,
+{
+    /// Create an empty prefix map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    forward_map_methods!(len, is_empty, as_map, get_lpm, iter);
+
+    /// Get the value of an element by matching exactly on the prefix. This operation is `O(1)`.
+    pub fn get(&self, prefix: &P) -> Option<&T> {
+        self.get_key_value(prefix).map(|(_, t)| t)
+    }
+
+    /// Get the key and the value of an element by matching exactly on the prefix. This operation
+    /// is `O(1)`.
+    pub fn get_key_value(&self, prefix: &P) -> Option<(&P, &T)> {
+        let idx = *self.index.get(&key(prefix))?;
+        self.map.table.prefix_value(idx)
+    }
+
+    /// Get a mutable reference to the value of an element by matching exactly on the prefix. This
+    /// operation is `O(1)`.
+    pub fn get_mut(&mut self, prefix: &P) -> Option<&mut T> {
+        let idx = *self.index.get(&key(prefix))?;
+        self.map.table.value_mut(idx).as_mut()
+    }
+
+    /// Check if a key is present in the datastructure. This operation is `O(1)`.
+    pub fn contains_key(&self, prefix: &P) -> bool {
+        self.index.contains_key(&key(prefix))
+    }
+
+    /// Insert a new item into the map. If the key already exists, replace the value and return
+    /// the old one.
+    pub fn insert(&mut self, prefix: P, value: T) -> Option<T> {
+        let k = key(&prefix);
+        // if the prefix is already present, start at its node to replace it without a search.
+        let start = self.index.get(&k).copied().unwrap_or(0);
+        let (idx, old) = self.map.insert_at(start, prefix, value);
+        self.map.debug_check_invariants("insert");
+        // the index of the node remains valid until it is removed (see `PrefixMap`).
+        self.index.insert(k, idx);
+        old
+    }
+
+    /// Remove an element from the map and return its value.
+    pub fn remove(&mut self, prefix: &P) -> Option<T> {
+        self.index.remove(&key(prefix))?;
+        self.map.remove(prefix)
+    }
+
+    /// Remove all elements.
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.index.clear();
+    }
+}
+
+impl<P, T> FromIterator<(P, T)> for IndexedPrefixMap<P, T>
+where
+    P: Prefix,
+    P::R: Hash,
+{
+    fn from_iter<I: IntoIterator<Item = (P, T)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        iter.into_iter().for_each(|(p, t)| {
+            map.insert(p, t);
+        });
+        map
+    }
+}
+
+impl<P: Prefix, T> From<IndexedPrefixMap<P, T>> for PrefixMap<P, T> {
+    fn from(map: IndexedPrefixMap<P, T>) -> Self {
+        map.map
+    }
+}
@@ -0,0 +1,228 @@
+//! Prefix map that stores each distinct value only once.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::*;
+
+/// A prefix map that stores each distinct value only once, and references it from all prefixes
+/// that map to that value. This is useful if many prefixes share only few distinct values (e.g., a
+/// FIB with millions of prefixes, but only hundreds of distinct next hops). Each prefix then only
+/// stores a 4-byte reference to its value.
+///
+/// Values are reference-counted, and a value is dropped once no prefix refers to it anymore.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use prefix_trie::map::InternedPrefixMap;
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut pm: InternedPrefixMap<ipnet::Ipv4Net, String> = InternedPrefixMap::new();
+/// pm.insert("10.0.0.0/8".parse()?, "eth0".to_string());
+/// pm.insert("10.1.0.0/16".parse()?, "eth1".to_string());
+/// pm.insert("192.168.0.0/16".parse()?, "eth0".to_string());
+/// assert_eq!(pm.len(), 3);
+/// assert_eq!(pm.num_values(), 2);
+/// assert_eq!(pm.get_lpm(&"192.168.1.0/24".parse()?), Some((&"192.168.0.0/16".parse()?, &"eth0".to_string())));
+/// pm.remove(&"10.1.0.0/16".parse()?);
+/// assert_eq!(pm.num_values(), 1);
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+#[derive(Clone)]
+pub struct InternedPrefixMap<P, T> {
+    map: PrefixMap<P, u32>,
+    /// The value and the number of prefixes referring to it. Unused slots are `None`.
+    values: Vec<Option<(T, usize)>>,
+    index: HashMap<T, u32>,
+    free: Vec<u32>,
+}
+
+impl<P, T> Default for InternedPrefixMap<P, T>
+where
+    P: Prefix,
+{
+    fn default() -> Self {
+        Self {
+            map: PrefixMap::new(),
+            values: Vec::new(),
+            index: HashMap::new(),
+            free: Vec::new(),
+        }
+    }
+}
+
+impl<P, T> std::fmt::Debug for InternedPrefixMap<P, T>
+where
+    P: Prefix + std::fmt::Debug,
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<P, T> InternedPrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Create an empty prefix map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of elements stored in `self`.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the map contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns the number of distinct values stored in `self`.
+    pub fn num_values(&self) -> usize {
+        self.index.len()
+    }
+
+    fn value(&self, id: &u32) -> &T {
+        &self.values[*id as usize].as_ref().unwrap().0
+    }
+
+    /// Get the value of an element by matching exactly on the prefix.
+    pub fn get(&self, prefix: &P) -> Option<&T> {
+        self.map.get(prefix).map(|id| self.value(id))
+    }
+
+    /// Check if a key is present in the datastructure.
+    pub fn contains_key(&self, prefix: &P) -> bool {
+        self.map.contains_key(prefix)
+    }
+
+    /// Get a value of an element by using longest prefix matching.
+    pub fn get_lpm(&self, prefix: &P) -> Option<(&P, &T)> {
+        self.map.get_lpm(prefix).map(|(p, id)| (p, self.value(id)))
+    }
+
+    /// Iterate over all entries in the map in lexicographic order.
+    pub fn iter(&self) -> impl Iterator<Item = (&P, &T)> {
+        self.map.iter().map(|(p, id)| (p, self.value(id)))
+    }
+
+    /// Iterate over all distinct values in an arbitrary order.
+    pub fn distinct_values(&self) -> impl Iterator<Item = &T> {
+        self.values.iter().flatten().map(|(t, _)| t)
+    }
+}
+
+impl<P, T> InternedPrefixMap<P, T>
+where
+    P: Prefix,
+    T: Eq + Hash + Clone,
+{
+    /// Insert a new item into the map. If the key already exists, replace the value and return
+    /// the old one. If an equal value is already stored in the map, `value` is dropped, and the
+    /// existing value is referenced instead.
+    pub fn insert(&mut self, prefix: P, value: T) -> Option<T> {
+        let id = match self.index.get(&value) {
+            Some(id) => {
+                self.values[*id as usize].as_mut().unwrap().1 += 1;
+                *id
+            }
+            None => {
+                let id = match self.free.pop() {
+                    Some(id) => id,
+                    None => {
+                        self.values.push(None);
+                        u32::try_from(self.values.len() - 1).expect("too many values")
+                    }
+                };
+                self.values[id as usize] = Some((value.clone(), 1));
+                self.index.insert(value, id);
+                id
+            }
+        };
+        let old = self.map.insert(prefix, id)?;
+        Some(self.release(old))
+    }
+
+    /// Remove an element from the map and return its value.
+    pub fn remove(&mut self, prefix: &P) -> Option<T> {
+        let id = self.map.remove(prefix)?;
+        Some(self.release(id))
+    }
+
+    /// Clear the map but keep the allocated memory.
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.values.clear();
+        self.index.clear();
+        self.free.clear();
+    }
+
+    /// Decrement the reference count of a value, dropping it from the map if it is no longer
+    /// referenced. Returns the value (or a clone of it, if it is still referenced).
+    fn release(&mut self, id: u32) -> T {
+        let slot = &mut self.values[id as usize];
+        let (value, count) = slot.as_mut().unwrap();
+        *count -= 1;
+        if *count > 0 {
+            return value.clone();
+        }
+        let (value, _) = slot.take().unwrap();
+        self.index.remove(&value);
+        self.free.push(id);
+        value
+    }
+}
+
+impl<P, T> PartialEq for InternedPrefixMap<P, T>
+where
+    P: Prefix + PartialEq,
+    T: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<P, T> Eq for InternedPrefixMap<P, T>
+where
+    P: Prefix + Eq,
+    T: Eq,
+{
+}
+
+impl<P, T> FromIterator<(P, T)> for InternedPrefixMap<P, T>
+where
+    P: Prefix,
+    T: Eq + Hash + Clone,
+{
+    fn from_iter<I: IntoIterator<Item = (P, T)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        iter.into_iter().for_each(|(p, t)| {
+            map.insert(p, t);
+        });
+        map
+    }
+}
+
+impl<P, T> From<InternedPrefixMap<P, T>> for PrefixMap<P, T>
+where
+    P: Prefix,
+    T: Clone,
+{
+    /// Convert the map into a regular `PrefixMap`, cloning each value for every prefix that
+    /// refers to it.
+    fn from(map: InternedPrefixMap<P, T>) -> Self {
+        let InternedPrefixMap {
+            map: ids, values, ..
+        } = map;
+        ids.into_iter()
+            .map(|(p, id)| (p, values[id as usize].as_ref().unwrap().0.clone()))
+            .collect()
+    }
+}
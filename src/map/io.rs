@@ -0,0 +1,165 @@
+//! Versioned binary format to save and load a [`PrefixMap`].
+
+use std::io::{Error, ErrorKind, Read, Result, Write};
+
+use num_traits::{NumCast, PrimInt, ToPrimitive, Zero};
+
+use super::frozen_bytes::{checksum_update, key_len, CHECKSUM_INIT};
+use super::*;
+
+/// Magic bytes at the beginning of each saved map.
+const MAGIC: &[u8; 8] = b"PTPREFIX";
+/// Current version of the format.
+const VERSION: u16 = 1;
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+/// Writer that keeps track of the checksum of all bytes written.
+struct ChecksumWriter<W> {
+    inner: W,
+    hash: u64,
+}
+
+impl<W: Write> ChecksumWriter<W> {
+    fn write(&mut self, data: &[u8]) -> Result<()> {
+        self.hash = checksum_update(self.hash, data);
+        self.inner.write_all(data)
+    }
+}
+
+/// Reader that keeps track of the checksum of all bytes read.
+struct ChecksumReader<R> {
+    inner: R,
+    hash: u64,
+}
+
+impl<R: Read> ChecksumReader<R> {
+    fn read<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut buf = [0; N];
+        self.read_into(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn read_into(&mut self, buf: &mut [u8]) -> Result<()> {
+        self.inner.read_exact(buf)?;
+        self.hash = checksum_update(self.hash, buf);
+        Ok(())
+    }
+}
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+    T: FrozenValue,
+{
+    /// Write the map to `writer` in a versioned binary format. Use [`PrefixMap::read_from`] to load
+    /// it again. The writer is not buffered; wrap it into a [`std::io::BufWriter`] when writing to
+    /// a file.
+    ///
+    /// The format consists of the following parts, where all integers are stored in little-endian
+    /// byte order:
+    ///
+    /// - The magic bytes `PTPREFIX`, followed by the version of the format (`u16`), the number of
+    ///   bytes of the prefix representation (`u16`, which identifies the prefix type), the number
+    ///   of bytes of each value (`u16`), two reserved bytes, and the number of elements (`u64`).
+    /// - Each element in lexicographic order: the masked representation of the prefix, the prefix
+    ///   length (`u8`), and the value (see [`FrozenValue`]).
+    /// - A 64-bit FNV-1a checksum over all bytes before.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, u32> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/8".parse()?, 1);
+    /// pm.insert("10.1.0.0/16".parse()?, 2);
+    /// let mut buf = Vec::new();
+    /// pm.write_to(&mut buf)?;
+    /// let loaded = PrefixMap::<ipnet::Ipv4Net, u32>::read_from(buf.as_slice())?;
+    /// assert_eq!(loaded, pm);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<()> {
+        let kl = key_len::<P>();
+        let mut w = ChecksumWriter {
+            inner: writer,
+            hash: CHECKSUM_INIT,
+        };
+        w.write(MAGIC)?;
+        w.write(&VERSION.to_le_bytes())?;
+        w.write(&(kl as u16).to_le_bytes())?;
+        w.write(&(T::SIZE as u16).to_le_bytes())?;
+        w.write(&[0, 0])?;
+        w.write(&(self.len() as u64).to_le_bytes())?;
+        let mut buf = Vec::with_capacity(kl + 1 + T::SIZE);
+        for (p, t) in self.iter() {
+            buf.clear();
+            buf.extend_from_slice(&p.mask().to_u128().unwrap().to_le_bytes()[..kl]);
+            buf.push(p.prefix_len());
+            t.write_bytes(&mut buf);
+            w.write(&buf)?;
+        }
+        let hash = w.hash;
+        w.write(&hash.to_le_bytes())
+    }
+
+    /// Read a map from `reader` that was written using [`PrefixMap::write_to`]. Returns an error
+    /// of kind [`ErrorKind::InvalidData`] if the data is not a saved map, if it was written with an
+    /// incompatible prefix or value type, or if its checksum does not match. The reader is not
+    /// buffered; wrap it into a [`std::io::BufReader`] when reading from a file.
+    ///
+    /// Since the elements are stored in lexicographic order, the map is built in `O(n)`.
+    pub fn read_from<R: Read>(reader: R) -> Result<Self> {
+        let kl = key_len::<P>();
+        let mut r = ChecksumReader {
+            inner: reader,
+            hash: CHECKSUM_INIT,
+        };
+        if &r.read::<8>()? != MAGIC {
+            return Err(invalid("not a saved prefix map"));
+        }
+        if u16::from_le_bytes(r.read()?) != VERSION {
+            return Err(invalid("unsupported version"));
+        }
+        let key_bytes = u16::from_le_bytes(r.read()?) as usize;
+        let value_bytes = u16::from_le_bytes(r.read()?) as usize;
+        if key_bytes != kl || value_bytes != T::SIZE {
+            return Err(invalid("prefix or value type mismatch"));
+        }
+        r.read::<2>()?;
+        let count = u64::from_le_bytes(r.read()?);
+
+        let max_len = P::R::zero().count_zeros();
+        let mut map = Self::new();
+        let mut buf = vec![0; kl + 1 + T::SIZE];
+        let mut error = None;
+        let elements = (0..count).map_while(|_| {
+            let result = r.read_into(&mut buf).and_then(|_| {
+                let mut repr = [0u8; 16];
+                repr[..kl].copy_from_slice(&buf[..kl]);
+                let repr = <P::R as NumCast>::from(u128::from_le_bytes(repr)).unwrap();
+                let len = buf[kl];
+                if len as u32 > max_len {
+                    return Err(invalid("invalid prefix length"));
+                }
+                Ok((P::from_repr_len(repr, len), T::read_bytes(&buf[kl + 1..])))
+            });
+            result.map_err(|e| error = Some(e)).ok()
+        });
+        map.extend_sorted(elements);
+        if let Some(e) = error {
+            return Err(e);
+        }
+
+        let hash = r.hash;
+        if u64::from_le_bytes(r.read()?) != hash {
+            return Err(invalid("checksum mismatch"));
+        }
+        Ok(map)
+    }
+}
@@ -1,11 +1,11 @@
 //! Module that contains the implementation for the iterators
 
 use map::Table;
+use range::{AddrRange, Cidrs};
+use trieview::FoldStep;
 
 use crate::*;
 
-use super::Node;
-
 /// An iterator over all entries of a [`PrefixMap`] in lexicographic order.
 #[derive(Clone)]
 pub struct Iter<'a, P, T> {
@@ -36,14 +36,15 @@ impl<'a, P, T> Iterator for Iter<'a, P, T> {
 
     fn next(&mut self) -> Option<(&'a P, &'a T)> {
         while let Some(cur) = self.nodes.pop() {
-            let node = &self.table.as_ref()?[cur];
-            if let Some(right) = node.right {
+            let table = self.table?;
+            let node = &table[cur];
+            if let Some(right) = node.right() {
                 self.nodes.push(right);
             }
-            if let Some(left) = node.left {
+            if let Some(left) = node.left() {
                 self.nodes.push(left);
             }
-            if let Some(v) = &node.value {
+            if let Some(v) = table.value(cur) {
                 return Some((&node.prefix, v));
             }
         }
@@ -83,7 +84,7 @@ impl<'a, P, T> Iterator for Values<'a, P, T> {
 /// An iterator over all owned entries of a [`PrefixMap`] in lexicographic order.
 #[derive(Clone)]
 pub struct IntoIter<P, T> {
-    table: Vec<Node<P, T>>,
+    table: Table<P, T>,
     nodes: Vec<usize>,
 }
 
@@ -92,14 +93,14 @@ impl<P: Prefix, T> Iterator for IntoIter<P, T> {
 
     fn next(&mut self) -> Option<(P, T)> {
         while let Some(cur) = self.nodes.pop() {
-            let node = &mut self.table[cur];
-            if let Some(right) = node.right {
+            let (node, value) = self.table.node_value_mut(cur);
+            if let Some(right) = node.right() {
                 self.nodes.push(right);
             }
-            if let Some(left) = node.left {
+            if let Some(left) = node.left() {
                 self.nodes.push(left);
             }
-            if let Some(v) = node.value.take() {
+            if let Some(v) = value.take() {
                 return Some((std::mem::replace(&mut node.prefix, P::zero()), v));
             }
         }
@@ -143,7 +144,7 @@ impl<P: Prefix, T> IntoIterator for PrefixMap<P, T> {
 
     fn into_iter(self) -> Self::IntoIter {
         IntoIter {
-            table: self.table.into_inner(),
+            table: self.table,
             nodes: vec![0],
         }
     }
@@ -205,17 +206,17 @@ impl<'a, P, T> Iterator for IterMut<'a, P, T> {
             // linked to a mutable reference. Then, we must ensure that we only ever construct a
             // mutable reference to each element exactly once. We ensure this by the fact that we
             // iterate over a tree. Thus, each node is visited exactly once.
-            let node: &'a mut Node<P, T> = unsafe { self.table.as_ref()?.get_mut(cur) };
+            let table = self.table?;
+            let (prefix, value) = unsafe { table.get_mut(cur) };
 
-            if let Some(right) = node.right {
+            if let Some(right) = table[cur].right() {
                 self.nodes.push(right);
             }
-            if let Some(left) = node.left {
+            if let Some(left) = table[cur].left() {
                 self.nodes.push(left);
             }
-            if node.value.is_some() {
-                let v = node.value.as_mut().unwrap();
-                return Some((&node.prefix, v));
+            if let Some(v) = value.as_mut() {
+                return Some((prefix, v));
             }
         }
         None
@@ -279,8 +280,13 @@ impl<P, T> PrefixMap<P, T> {
     pub fn iter_mut(&mut self) -> IterMut<'_, P, T> {
         // Safety: We get the pointer to the table by and construct the `IterMut`. Its lifetime is
         // now tied to the mutable borrow of `self`, so we are allowed to access elements of that
-        // table mutably.
-        unsafe { IterMut::new(&self.table, vec![0]) }
+        // table mutably. If the root is not yet allocated, the map is empty.
+        let roots = if self.table.len() == 0 {
+            Vec::new()
+        } else {
+            vec![0]
+        };
+        unsafe { IterMut::new(&self.table, roots) }
     }
 
     /// An iterator visiting all keys in lexicographic order. The iterator element type is `&P`.
@@ -321,7 +327,7 @@ impl<P, T> PrefixMap<P, T> {
     pub fn into_keys(self) -> IntoKeys<P, T> {
         IntoKeys {
             inner: IntoIter {
-                table: self.table.into_inner(),
+                table: self.table,
                 nodes: vec![0],
             },
         }
@@ -356,7 +362,7 @@ impl<P, T> PrefixMap<P, T> {
     pub fn into_values(self) -> IntoValues<P, T> {
         IntoValues {
             inner: IntoIter {
-                table: self.table.into_inner(),
+                table: self.table,
                 nodes: vec![0],
             },
         }
@@ -409,6 +415,76 @@ where
             .unwrap_or_default()
     }
 
+    /// Get an iterator over all entries whose prefix has at least one address in common with
+    /// `range`, which does not need to be aligned to a prefix. These are the entries that either
+    /// contain, or are contained in one of the prefixes of the decomposition of `range` (see
+    /// [`AddrRange::cidrs`]). The iterator yields elements in lexicographic order.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use prefix_trie::range::AddrRange;
+    /// let mut pm: PrefixMap<(u32, u8), _> = PrefixMap::new();
+    /// pm.insert((0x0a000000, 8), 1);
+    /// pm.insert((0x0a000000, 24), 2);
+    /// pm.insert((0x0a000100, 24), 3);
+    /// pm.insert((0x0a000200, 24), 4);
+    /// let range = AddrRange::new(0x0a0000ff, 0x0a000100).unwrap();
+    /// assert_eq!(
+    ///     pm.overlapping(&range).collect::<Vec<_>>(),
+    ///     vec![(&(0x0a000000, 8), &1), (&(0x0a000000, 24), &2), (&(0x0a000100, 24), &3)]
+    /// );
+    /// ```
+    pub fn overlapping(&self, range: &AddrRange<P::R>) -> Overlapping<'_, P, T> {
+        Overlapping {
+            map: self,
+            cidrs: range.cidrs(),
+            first: true,
+            supernets: Vec::new(),
+            children: Iter::default(),
+        }
+    }
+
+    /// Fold over the node itself and all its children in lexicographic order, with the ability to
+    /// skip entire sub-branches (see [`FoldStep`]). All elements visited have a prefix that is
+    /// contained within `prefix` itself (or are the same). In contrast to folding over
+    /// [`Self::children`], the traversal does not allocate.
+    ///
+    /// **Note**: This is equivalent to [`TrieView::fold`] of [`AsView::view_at`].
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use prefix_trie::trieview::FoldStep;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/8".parse()?, 100);
+    /// pm.insert("10.1.0.0/16".parse()?, 10);
+    /// pm.insert("10.1.1.0/24".parse()?, 1);
+    /// pm.insert("10.2.0.0/16".parse()?, 20);
+    /// // count the number of entries, but not within a /16.
+    /// let count = pm.fold_under("10.0.0.0/8".parse()?, 0, |n, p, _| {
+    ///     if p.prefix_len() < 16 {
+    ///         FoldStep::Continue(n + 1)
+    ///     } else {
+    ///         FoldStep::Skip(n + 1)
+    ///     }
+    /// });
+    /// assert_eq!(count, 3);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn fold_under<A, F>(&self, prefix: P, init: A, f: F) -> A
+    where
+        F: FnMut(A, &P, &T) -> FoldStep<A>,
+    {
+        match self.view_at(prefix) {
+            Some(view) => view.fold(init, f),
+            None => init,
+        }
+    }
+
     /// Get an iterator of mutable references of the node itself and all its children. All elements
     /// returned have a prefix that is contained within `prefix` itself (or are the same). The
     /// iterator yields references to the keys, and mutable references to the values, i.e., type
@@ -477,7 +553,7 @@ where
     pub fn into_children(self, prefix: &P) -> IntoIter<P, T> {
         let nodes = lpm_children_iter_start(&self.table, prefix);
         IntoIter {
-            table: self.table.into_inner(),
+            table: self.table,
             nodes,
         }
     }
@@ -515,13 +591,85 @@ where
 {
     fn from_iter<I: IntoIterator<Item = (P, T)>>(iter: I) -> Self {
         let mut map = Self::new();
-        iter.into_iter().for_each(|(p, v)| {
-            map.insert(p, v);
-        });
+        map.extend_sorted(iter);
         map
     }
 }
 
+impl<P, T, S> From<std::collections::HashMap<P, T, S>> for PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Build a `PrefixMap` from all elements of a `HashMap`. Keys that only differ in their host
+    /// part are merged (see [`PrefixMap::try_from_iter`] to detect this).
+    fn from(map: std::collections::HashMap<P, T, S>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+impl<P, T> From<std::collections::BTreeMap<P, T>> for PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Build a `PrefixMap` from all elements of a `BTreeMap`. Keys that only differ in their host
+    /// part are merged (see [`PrefixMap::try_from_iter`] to detect this).
+    fn from(map: std::collections::BTreeMap<P, T>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+impl<P, T> From<PrefixMap<P, T>> for std::collections::BTreeMap<P, T>
+where
+    P: Prefix + Ord,
+{
+    fn from(map: PrefixMap<P, T>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+impl<P, T> From<PrefixMap<P, T>> for std::collections::HashMap<P, T>
+where
+    P: Prefix + Eq + std::hash::Hash,
+{
+    fn from(map: PrefixMap<P, T>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Build a `PrefixMap` from an iterator, but fail if two elements have the same prefix, i.e.,
+    /// the same prefix length and the same bits within the prefix (they may only differ in their
+    /// host part). In that case, the second of the two prefixes is returned. Use this instead of
+    /// `PrefixMap::from` (which silently keeps the last value) to validate that no element of
+    /// another map (e.g., a `HashMap`) gets lost. (This is not a `TryFrom` implementation, as it
+    /// would conflict with the `From` implementations.)
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use std::collections::HashMap;
+    /// let ok = HashMap::from([((0x0a000000u32, 8), 1), ((0x0a010000u32, 16), 2)]);
+    /// assert_eq!(PrefixMap::try_from_iter(ok.clone()).map(|m| m.len()), Ok(2));
+    /// assert_eq!(PrefixMap::from(ok).len(), 2);
+    ///
+    /// let bad = HashMap::from([((0x0a000000u32, 8), 1), ((0x0a000001u32, 8), 2)]);
+    /// assert!(PrefixMap::try_from_iter(bad.clone()).is_err());
+    /// assert_eq!(PrefixMap::from(bad).len(), 1);
+    /// ```
+    pub fn try_from_iter<I: IntoIterator<Item = (P, T)>>(iter: I) -> Result<Self, P> {
+        let mut map = Self::new();
+        for (p, t) in iter {
+            if map.contains_key(&p) {
+                return Err(p);
+            }
+            map.insert(p, t);
+        }
+        Ok(map)
+    }
+}
+
 /// An iterator that yields all items in a `PrefixMap` that covers a given prefix (including the
 /// prefix itself if preseint). See [`PrefixMap::cover`] for how to create this iterator.
 pub struct Cover<'a, P, T> {
@@ -540,9 +688,8 @@ where
         // check if self.idx is None. If so, then check if the first branch is present in the map
         if self.idx.is_none() {
             self.idx = Some(0);
-            let entry = &self.table[0];
-            if let Some(value) = entry.value.as_ref() {
-                return Some((&entry.prefix, value));
+            if let Some(entry) = self.table.prefix_value(0) {
+                return Some(entry);
             }
         }
 
@@ -555,9 +702,8 @@ where
                 return None;
             };
             self.idx = Some(next);
-            let entry = &self.table[next];
-            if let Some(value) = entry.value.as_ref() {
-                return Some((&entry.prefix, value));
+            if let Some(entry) = self.table.prefix_value(next) {
+                return Some(entry);
             }
         }
     }
@@ -593,3 +739,53 @@ where
         self.0.next().map(|(_, t)| t)
     }
 }
+
+/// An iterator over all entries of a `PrefixMap` whose prefix overlaps with an address range, in
+/// lexicographic order. See [`PrefixMap::overlapping`] for how to create this iterator.
+pub struct Overlapping<'a, P: Prefix, T> {
+    map: &'a PrefixMap<P, T>,
+    cidrs: Cidrs<P>,
+    /// Whether the next part of the decomposition is the first one.
+    first: bool,
+    /// Strict supernets of the current part (in reverse order).
+    supernets: Vec<(&'a P, &'a T)>,
+    /// Entries contained in the current part.
+    children: Iter<'a, P, T>,
+}
+
+impl<'a, P, T> Iterator for Overlapping<'a, P, T>
+where
+    P: Prefix,
+{
+    type Item = (&'a P, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(entry) = self.supernets.pop() {
+                return Some(entry);
+            }
+            if let Some(entry) = self.children.next() {
+                return Some(entry);
+            }
+            let part = self.cidrs.next()?;
+            // a supernet that starts before `part` also contains the previous part, so it was
+            // already yielded.
+            let table = &self.map.table;
+            let mut idx = 0;
+            while table[idx].prefix.prefix_len() < part.prefix_len() {
+                if let Some((p, t)) = table.prefix_value(idx) {
+                    if self.first || p.mask() == part.mask() {
+                        self.supernets.push((p, t));
+                    }
+                }
+                let map::Direction::Enter { next, .. } = table.get_direction(idx, &part) else {
+                    break;
+                };
+                idx = next;
+            }
+            self.supernets.reverse();
+            self.first = false;
+            self.children = self.map.children(part);
+        }
+    }
+}
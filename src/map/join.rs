@@ -0,0 +1,93 @@
+//! Joining the entries of a map with their longest prefix match in another map.
+
+use super::*;
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Iterate over all entries of `self` in lexicographic order, together with their longest
+    /// prefix match in `other` (see [`Self::get_lpm`]), e.g., to annotate each route with the
+    /// attributes of its covering aggregate. The iterator yields `(prefix, value, lpm)`, where
+    /// `lpm` is `None` if no entry of `other` contains `prefix`.
+    ///
+    /// Both trees are traversed simultaneously: the search in `other` continues from the match of
+    /// the parent node, instead of starting at the root for each entry.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut routes: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// routes.insert("10.1.1.0/24".parse()?, "a");
+    /// routes.insert("10.2.1.0/24".parse()?, "b");
+    /// routes.insert("192.168.1.0/24".parse()?, "c");
+    /// let mut aggregates: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// aggregates.insert("10.0.0.0/8".parse()?, 1);
+    /// aggregates.insert("10.2.0.0/16".parse()?, 2);
+    /// assert_eq!(
+    ///     routes.lpm_join(&aggregates).collect::<Vec<_>>(),
+    ///     vec![
+    ///         (&"10.1.1.0/24".parse()?, &"a", Some((&"10.0.0.0/8".parse()?, &1))),
+    ///         (&"10.2.1.0/24".parse()?, &"b", Some((&"10.2.0.0/16".parse()?, &2))),
+    ///         (&"192.168.1.0/24".parse()?, &"c", None),
+    ///     ]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn lpm_join<'a, U>(&'a self, other: &'a PrefixMap<P, U>) -> LpmJoin<'a, P, T, U> {
+        LpmJoin {
+            table: &self.table,
+            other: &other.table,
+            nodes: vec![(0, 0, None)],
+        }
+    }
+}
+
+/// An iterator over all entries of a [`PrefixMap`], together with their longest prefix match in
+/// another map. See [`PrefixMap::lpm_join`] for how to create this iterator.
+#[derive(Clone)]
+pub struct LpmJoin<'a, P, T, U> {
+    table: &'a Table<P, T>,
+    other: &'a Table<P, U>,
+    /// Nodes that remain to be visited, together with a node of `other` that contains them, and
+    /// the longest prefix match in `other` up to that node.
+    nodes: Vec<(usize, usize, Option<usize>)>,
+}
+
+impl<'a, P, T, U> Iterator for LpmJoin<'a, P, T, U>
+where
+    P: Prefix,
+{
+    type Item = (&'a P, &'a T, Option<(&'a P, &'a U)>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((cur, mut cursor, mut best)) = self.nodes.pop() {
+            let node = &self.table[cur];
+            // descend in `other` towards the prefix of this node.
+            loop {
+                if self.other.value(cursor).is_some() {
+                    best = Some(cursor);
+                }
+                match self.other.get_direction(cursor, &node.prefix) {
+                    Direction::Enter { next, .. } => cursor = next,
+                    _ => break,
+                }
+            }
+            if let Some(right) = node.right() {
+                self.nodes.push((right, cursor, best));
+            }
+            if let Some(left) = node.left() {
+                self.nodes.push((left, cursor, best));
+            }
+            if let Some(v) = self.table.value(cur) {
+                let lpm = best.and_then(|idx| self.other.prefix_value(idx));
+                return Some((&node.prefix, v, lpm));
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,255 @@
+//! Prefix map that records all mutations in a journal, e.g., for replication.
+
+use std::io::{Error, ErrorKind, Read, Result, Write};
+
+use num_traits::{NumCast, PrimInt, ToPrimitive, Zero};
+
+use super::frozen_bytes::key_len;
+use super::*;
+
+/// A single mutation recorded in the journal of a [`JournaledPrefixMap`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum JournalOp<P, T> {
+    /// Insert (or overwrite) the prefix with the value.
+    Insert(P, T),
+    /// Remove the prefix.
+    Remove(P),
+    /// Remove all elements.
+    Clear,
+}
+
+/// Tags of the operations in the binary encoding.
+const TAG_INSERT: u8 = 1;
+const TAG_REMOVE: u8 = 2;
+const TAG_CLEAR: u8 = 3;
+
+/// A prefix map that records every mutation (inserting, overwriting, and removing elements) in a
+/// journal. The journal can be drained using [`JournaledPrefixMap::drain_journal`], and applied
+/// to a replica using [`PrefixMap::apply_journal`], e.g., to synchronize routing tables between a
+/// primary and its replicas. Use [`JournalOp::write_all`] and [`JournalOp::read_all`] to send the
+/// journal to another process.
+///
+/// Values can only be modified through [`JournaledPrefixMap::modify`], which records the new
+/// value. The journal is only cleared when drained.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use prefix_trie::map::{JournalOp, JournaledPrefixMap};
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut primary: JournaledPrefixMap<ipnet::Ipv4Net, u32> = JournaledPrefixMap::new();
+/// let mut replica = PrefixMap::new();
+/// primary.insert("10.0.0.0/8".parse()?, 1);
+/// primary.insert("10.1.0.0/16".parse()?, 2);
+/// primary.modify(&"10.0.0.0/8".parse()?, |x| *x += 10);
+/// primary.remove(&"10.1.0.0/16".parse()?);
+///
+/// let mut buf = Vec::new();
+/// JournalOp::write_all(&primary.drain_journal(), &mut buf)?;
+/// replica.apply_journal(JournalOp::read_all(buf.as_slice())?);
+/// assert_eq!(&replica, primary.as_map());
+/// assert_eq!(replica.get(&"10.0.0.0/8".parse()?), Some(&11));
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+#[derive(Clone)]
+pub struct JournaledPrefixMap<P, T> {
+    map: PrefixMap<P, T>,
+    journal: Vec<JournalOp<P, T>>,
+}
+
+impl<P: Prefix, T> Default for JournaledPrefixMap<P, T> {
+    fn default() -> Self {
+        Self {
+            map: PrefixMap::new(),
+            journal: Vec::new(),
+        }
+    }
+}
+
+forward_map_traits!(JournaledPrefixMap[P, T]: Debug);
+
+impl<P: Prefix, T> From<PrefixMap<P, T>> for JournaledPrefixMap<P, T> {
+    /// Start recording mutations of `map`. The journal is initially empty, so replicas must start
+    /// with a copy of `map`.
+    fn from(map: PrefixMap<P, T>) -> Self {
+        Self {
+            map,
+            journal: Vec::new(),
+        }
+    }
+}
+
+/// Create an owned copy of the prefix, based on its representation.
+fn copy<P: Prefix>(prefix: &P) -> P {
+    P::from_repr_len(prefix.repr(), prefix.prefix_len())
+}
+
+impl<P, T> JournaledPrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Create an empty map with an empty journal.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    forward_map_methods!(len, is_empty, as_map, get, contains_key, get_lpm, iter);
+
+    /// Get all operations that were recorded since the journal was last drained.
+    pub fn journal(&self) -> &[JournalOp<P, T>] {
+        &self.journal
+    }
+
+    /// Take all operations that were recorded since the journal was last drained.
+    pub fn drain_journal(&mut self) -> Vec<JournalOp<P, T>> {
+        std::mem::take(&mut self.journal)
+    }
+
+    /// Remove an element from the map and return its value. The removal is only recorded if the
+    /// prefix was present.
+    pub fn remove(&mut self, prefix: &P) -> Option<T> {
+        let old = self.map.remove(prefix)?;
+        self.journal.push(JournalOp::Remove(copy(prefix)));
+        Some(old)
+    }
+
+    /// Remove all elements.
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.journal.push(JournalOp::Clear);
+    }
+
+    /// Stop recording and return the underlying map. Operations in the journal are discarded.
+    pub fn into_inner(self) -> PrefixMap<P, T> {
+        self.map
+    }
+}
+
+impl<P, T> JournaledPrefixMap<P, T>
+where
+    P: Prefix,
+    T: Clone,
+{
+    /// Insert a new item into the map. If the key already exists, replace the value and return
+    /// the old one. The journal records a copy of the value.
+    pub fn insert(&mut self, prefix: P, value: T) -> Option<T> {
+        self.journal
+            .push(JournalOp::Insert(copy(&prefix), value.clone()));
+        self.map.insert(prefix, value)
+    }
+
+    /// Modify the value of an element using `f`, and record the new value. Returns `None` (without
+    /// calling `f`) if the prefix is not present.
+    pub fn modify<R>(&mut self, prefix: &P, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        let value = self.map.get_mut(prefix)?;
+        let result = f(value);
+        self.journal
+            .push(JournalOp::Insert(copy(prefix), value.clone()));
+        Some(result)
+    }
+}
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Apply all operations of a journal (see [`JournaledPrefixMap`]) in order.
+    pub fn apply_journal<I: IntoIterator<Item = JournalOp<P, T>>>(&mut self, ops: I) {
+        for op in ops {
+            match op {
+                JournalOp::Insert(p, t) => {
+                    self.insert(p, t);
+                }
+                JournalOp::Remove(p) => {
+                    self.remove(&p);
+                }
+                JournalOp::Clear => self.clear(),
+            }
+        }
+    }
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+/// Take the first `n` bytes of `data`, failing if it is too short.
+fn take<'a>(data: &mut &'a [u8], n: usize) -> Result<&'a [u8]> {
+    if data.len() < n {
+        return Err(Error::from(ErrorKind::UnexpectedEof));
+    }
+    let (head, tail) = data.split_at(n);
+    *data = tail;
+    Ok(head)
+}
+
+impl<P, T> JournalOp<P, T>
+where
+    P: Prefix,
+    T: FrozenValue,
+{
+    /// Write all operations to `writer` in a compact binary encoding. Each operation is encoded
+    /// as a tag (`u8`), followed by the masked representation of the prefix (little-endian) and
+    /// its length (`u8`) for insert and remove operations, and the value for insert operations
+    /// (see [`FrozenValue`]). The encoding does not contain any header, so the output of multiple
+    /// calls can be concatenated.
+    pub fn write_all<W: Write>(ops: &[Self], mut writer: W) -> Result<()> {
+        let kl = key_len::<P>();
+        let mut buf = Vec::new();
+        for op in ops {
+            let (tag, prefix, value) = match op {
+                JournalOp::Insert(p, t) => (TAG_INSERT, Some(p), Some(t)),
+                JournalOp::Remove(p) => (TAG_REMOVE, Some(p), None),
+                JournalOp::Clear => (TAG_CLEAR, None, None),
+            };
+            buf.push(tag);
+            if let Some(p) = prefix {
+                buf.extend_from_slice(&p.mask().to_u128().unwrap().to_le_bytes()[..kl]);
+                buf.push(p.prefix_len());
+            }
+            if let Some(t) = value {
+                t.write_bytes(&mut buf);
+            }
+        }
+        writer.write_all(&buf)
+    }
+
+    /// Read all operations from `reader` until it reaches the end, as written by
+    /// [`JournalOp::write_all`]. Returns an error of kind [`ErrorKind::InvalidData`] if the data is
+    /// malformed, and of kind [`ErrorKind::UnexpectedEof`] if it ends within an operation.
+    pub fn read_all<R: Read>(mut reader: R) -> Result<Vec<Self>> {
+        let kl = key_len::<P>();
+        let max_len = P::R::zero().count_zeros();
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let mut data = data.as_slice();
+        let mut ops = Vec::new();
+        while let Ok(tag) = take(&mut data, 1) {
+            let tag = tag[0];
+            if tag == TAG_CLEAR {
+                ops.push(JournalOp::Clear);
+                continue;
+            } else if tag != TAG_INSERT && tag != TAG_REMOVE {
+                return Err(invalid("invalid journal operation"));
+            }
+            let bytes = take(&mut data, kl + 1)?;
+            let mut repr = [0u8; 16];
+            repr[..kl].copy_from_slice(&bytes[..kl]);
+            let repr = <P::R as NumCast>::from(u128::from_le_bytes(repr)).unwrap();
+            let len = bytes[kl];
+            if len as u32 > max_len {
+                return Err(invalid("invalid prefix length"));
+            }
+            let prefix = P::from_repr_len(repr, len);
+            ops.push(if tag == TAG_INSERT {
+                JournalOp::Insert(prefix, T::read_bytes(take(&mut data, T::SIZE)?))
+            } else {
+                JournalOp::Remove(prefix)
+            });
+        }
+        Ok(ops)
+    }
+}
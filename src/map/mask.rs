@@ -0,0 +1,149 @@
+//! Filtering a map by the prefixes of a set, e.g., to apply an allow or deny list.
+
+use super::*;
+use crate::PrefixSet;
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Keep only the entries whose prefix is covered by an element of `set`, i.e., that are
+    /// contained in (or equal to) any prefix of `set`. Both trees are traversed simultaneously,
+    /// and entries covered by an element of `set` are not visited.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/8".parse()?, 1);
+    /// pm.insert("10.1.0.0/16".parse()?, 2);
+    /// pm.insert("192.168.0.0/16".parse()?, 3);
+    /// pm.insert("192.168.1.0/24".parse()?, 4);
+    /// let allow: PrefixSet<ipnet::Ipv4Net> = ["10.1.0.0/16".parse()?, "192.168.0.0/16".parse()?]
+    ///     .into_iter()
+    ///     .collect();
+    /// pm.retain_covered_by(&allow);
+    /// assert_eq!(
+    ///     pm.into_iter().collect::<Vec<_>>(),
+    ///     vec![
+    ///         ("10.1.0.0/16".parse()?, 2),
+    ///         ("192.168.0.0/16".parse()?, 3),
+    ///         ("192.168.1.0/24".parse()?, 4),
+    ///     ]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn retain_covered_by(&mut self, set: &PrefixSet<P>) {
+        if self.table.len() > 0 {
+            self._retain_covered(0, None, false, None, false, &set.0.table, 0, false, true);
+        }
+        self.debug_check_invariants("retain_covered_by");
+    }
+
+    /// Remove all entries whose prefix is covered by an element of `set`, i.e., that are
+    /// contained in (or equal to) any prefix of `set`. Both trees are traversed simultaneously.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/8".parse()?, 1);
+    /// pm.insert("10.1.0.0/16".parse()?, 2);
+    /// pm.insert("192.168.0.0/16".parse()?, 3);
+    /// pm.insert("192.168.1.0/24".parse()?, 4);
+    /// let deny: PrefixSet<ipnet::Ipv4Net> = ["10.1.0.0/16".parse()?, "192.168.0.0/16".parse()?]
+    ///     .into_iter()
+    ///     .collect();
+    /// pm.remove_covered_by(&deny);
+    /// assert_eq!(pm.into_iter().collect::<Vec<_>>(), vec![("10.0.0.0/8".parse()?, 1)]);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn remove_covered_by(&mut self, set: &PrefixSet<P>) {
+        if self.table.len() > 0 {
+            self._retain_covered(0, None, false, None, false, &set.0.table, 0, false, false);
+        }
+        self.debug_check_invariants("remove_covered_by");
+    }
+
+    /// Recursive implementation of [`Self::retain_covered_by`] (if `keep` is `true`) and
+    /// [`Self::remove_covered_by`] (if `keep` is `false`), following [`Self::_retain`]. `cursor`
+    /// is a node of `set` that contains the prefix of `idx`, and `covered` is `true` if any node
+    /// from the root of `set` to `cursor` holds an element. Returns `true` if the parent was
+    /// removed.
+    #[allow(clippy::too_many_arguments)]
+    fn _retain_covered(
+        &mut self,
+        idx: usize,
+        par: Option<usize>,
+        par_right: bool,
+        grp: Option<usize>,
+        grp_right: bool,
+        set: &Table<P, ()>,
+        mut cursor: usize,
+        mut covered: bool,
+        keep: bool,
+    ) -> bool {
+        // descend in `set` towards the prefix of this node.
+        while !covered {
+            covered = set.value(cursor).is_some();
+            match set.get_direction(cursor, &self.table[idx].prefix) {
+                Direction::Enter { next, .. } => cursor = next,
+                _ => break,
+            }
+        }
+        if covered && keep {
+            // all entries in this subtree are kept.
+            return false;
+        }
+
+        // first, do the recursion
+        let mut idx_removed = false;
+        let mut par_removed = false;
+        if let Some(left) = self.table[idx].left() {
+            idx_removed = self._retain_covered(
+                left,
+                Some(idx),
+                false,
+                par,
+                par_right,
+                set,
+                cursor,
+                covered,
+                keep,
+            );
+        }
+        if let Some(right) = self.table[idx].right() {
+            if idx_removed {
+                par_removed = self._retain_covered(
+                    right, par, par_right, grp, grp_right, set, cursor, covered, keep,
+                );
+            } else {
+                self._retain_covered(
+                    right,
+                    Some(idx),
+                    true,
+                    par,
+                    par_right,
+                    set,
+                    cursor,
+                    covered,
+                    keep,
+                );
+            }
+        }
+        // then, check if we need to delete the node
+        if self.table.value(idx).is_some() && covered != keep {
+            let (_, par_del) = self._remove_node(idx, par, par_right, grp, grp_right);
+            par_removed = par_del;
+        }
+        par_removed
+    }
+}
@@ -0,0 +1,234 @@
+//! Prefix map that maintains fingerprints of each subtree, to efficiently compare replicas.
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{BuildHasher, BuildHasherDefault, Hash, Hasher},
+};
+
+use super::*;
+use crate::to_right;
+
+/// A prefix map that stores for each node a fingerprint of all entries in its subtree, and
+/// maintains them incrementally on each insert and remove. Two replicas of a map (e.g., in two
+/// routing daemons) can compare the fingerprint of any prefix to check if they contain the same
+/// entries within that prefix. Starting from the root, only the halves of differing prefixes must
+/// be compared, such that divergent entries are found while exchanging only few fingerprints.
+///
+/// The fingerprint of a prefix is the (wrapping) sum of the hashes of all entries contained in
+/// it, where each entry hashes its prefix and its value. It only depends on the entries, and not
+/// on the tree structure or on the order of operations. Replicas must use the same hasher `S`
+/// (whose hashes must not be randomized) for their fingerprints to be comparable. Fingerprints
+/// are not cryptographic, and collisions are possible.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use prefix_trie::map::MerkleMap;
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut a: MerkleMap<ipnet::Ipv4Net, u32> = MerkleMap::new();
+/// let mut b: MerkleMap<ipnet::Ipv4Net, u32> = MerkleMap::new();
+/// for map in [&mut a, &mut b] {
+///     map.insert("10.0.0.0/8".parse()?, 1);
+///     map.insert("192.168.0.0/16".parse()?, 2);
+/// }
+/// a.insert("10.1.0.0/16".parse()?, 3);
+/// b.insert("192.168.0.0/16".parse()?, 4);
+///
+/// assert_ne!(a.root_fingerprint(), b.root_fingerprint());
+/// let p = "10.0.0.0/8".parse()?;
+/// assert_ne!(a.fingerprint(&p), b.fingerprint(&p));
+/// a.remove(&"10.1.0.0/16".parse()?);
+/// assert_eq!(a.fingerprint(&p), b.fingerprint(&p));
+/// assert_eq!(a.differences(&b), vec!["192.168.0.0/16".parse()?]);
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+#[derive(Clone)]
+pub struct MerkleMap<P, T, S = BuildHasherDefault<DefaultHasher>> {
+    map: PrefixMap<P, T>,
+    /// Fingerprint of each node in the table (indexed by the node index).
+    fingerprints: Vec<u64>,
+    hasher: S,
+}
+
+impl<P, T, S> Default for MerkleMap<P, T, S>
+where
+    P: Prefix,
+    S: Default,
+{
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+forward_map_traits!(MerkleMap[P, T, S]: Debug, PartialEq);
+
+impl<P, T> MerkleMap<P, T>
+where
+    P: Prefix,
+{
+    /// Create an empty prefix map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<P, T, S> MerkleMap<P, T, S>
+where
+    P: Prefix,
+{
+    /// Create an empty prefix map that uses `hasher` to compute fingerprints.
+    pub fn with_hasher(hasher: S) -> Self {
+        Self {
+            map: PrefixMap::new(),
+            fingerprints: vec![0],
+            hasher,
+        }
+    }
+
+    forward_map_methods!(len, is_empty, as_map, get, contains_key, get_lpm, iter);
+
+    /// Get the fingerprint of all entries whose prefix is contained in `prefix` (including
+    /// `prefix` itself). The fingerprint of a prefix without entries is `0`. This operation is
+    /// `O(log n)`.
+    pub fn fingerprint(&self, prefix: &P) -> u64 {
+        let table = &self.map.table;
+        let mut idx = 0;
+        loop {
+            let node_p = &table[idx].prefix;
+            if prefix.contains(node_p) {
+                return self.fingerprints[idx];
+            }
+            if !node_p.contains(prefix) {
+                return 0;
+            }
+            match table.get_child(idx, to_right(node_p, prefix)) {
+                Some(child) => idx = child,
+                None => return 0,
+            }
+        }
+    }
+
+    /// Get the fingerprint of all entries in the map.
+    pub fn root_fingerprint(&self) -> u64 {
+        self.fingerprints[0]
+    }
+
+    /// Remove all elements.
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.fingerprints.clear();
+        self.fingerprints.push(0);
+    }
+}
+
+impl<P, T, S> MerkleMap<P, T, S>
+where
+    P: Prefix,
+    P::R: Hash,
+    T: Hash,
+    S: BuildHasher,
+{
+    /// Insert a new item into the map. If the key already exists, replace the value and return
+    /// the old one.
+    pub fn insert(&mut self, prefix: P, value: T) -> Option<T> {
+        let key = P::from_repr_len(prefix.repr(), prefix.prefix_len());
+        let old = self.map.insert(prefix, value);
+        self.update(&key);
+        old
+    }
+
+    /// Remove an element from the map and return its value.
+    pub fn remove(&mut self, prefix: &P) -> Option<T> {
+        let old = self.map.remove(prefix)?;
+        self.update(prefix);
+        Some(old)
+    }
+
+    /// Find all prefixes whose entry differs between `self` and `other`, i.e., that are present
+    /// in only one of them, or that have a different value. The result is sorted in
+    /// lexicographic order. Starting at the root, this only descends into the two halves of a
+    /// prefix if its fingerprints differ, which is how two remote replicas would compare their
+    /// fingerprints. Both maps must use the same hasher.
+    pub fn differences(&self, other: &Self) -> Vec<P> {
+        let mut result = Vec::new();
+        self.differences_within(other, P::zero(), &mut result);
+        result
+    }
+
+    /// Recursively collect the differences within `prefix`.
+    fn differences_within(&self, other: &Self, prefix: P, result: &mut Vec<P>) {
+        if self.fingerprint(&prefix) == other.fingerprint(&prefix) {
+            return;
+        }
+        let entry = |map: &Self| map.get(&prefix).map(|t| map.hash_entry(&prefix, t));
+        let len = prefix.prefix_len();
+        let halves = (!prefix.is_host()).then(|| {
+            let left = P::from_repr_len(prefix.mask(), len + 1);
+            let right = left.sibling().unwrap();
+            (left, right)
+        });
+        if entry(self) != entry(other) {
+            result.push(prefix);
+        }
+        if let Some((left, right)) = halves {
+            self.differences_within(other, left, result);
+            self.differences_within(other, right, result);
+        }
+    }
+
+    /// Hash a single entry.
+    fn hash_entry(&self, prefix: &P, value: &T) -> u64 {
+        let mut state = self.hasher.build_hasher();
+        prefix.mask().hash(&mut state);
+        prefix.prefix_len().hash(&mut state);
+        value.hash(&mut state);
+        state.finish()
+    }
+
+    /// Recompute the fingerprints of all nodes on the path from the root towards `prefix`. The
+    /// fingerprints of all other nodes remain valid.
+    fn update(&mut self, prefix: &P) {
+        let table = &self.map.table;
+        self.fingerprints.resize(table.len(), 0);
+        let mut path = vec![0];
+        let mut idx = 0;
+        while let Direction::Enter { next, .. } = table.get_direction(idx, prefix) {
+            path.push(next);
+            idx = next;
+        }
+        for idx in path.into_iter().rev() {
+            let own = table
+                .prefix_value(idx)
+                .map(|(p, t)| self.hash_entry(p, t))
+                .unwrap_or(0);
+            let children = table[idx].left().into_iter().chain(table[idx].right());
+            self.fingerprints[idx] =
+                children.fold(own, |fp, c| fp.wrapping_add(self.fingerprints[c]));
+        }
+    }
+}
+
+impl<P, T, S> FromIterator<(P, T)> for MerkleMap<P, T, S>
+where
+    P: Prefix,
+    P::R: Hash,
+    T: Hash,
+    S: BuildHasher + Default,
+{
+    fn from_iter<I: IntoIterator<Item = (P, T)>>(iter: I) -> Self {
+        let mut map = Self::default();
+        iter.into_iter().for_each(|(p, t)| {
+            map.insert(p, t);
+        });
+        map
+    }
+}
+
+impl<P, T, S> From<MerkleMap<P, T, S>> for PrefixMap<P, T> {
+    fn from(map: MerkleMap<P, T, S>) -> Self {
+        map.map
+    }
+}
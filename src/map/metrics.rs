@@ -0,0 +1,198 @@
+//! Lightweight counters of the operations performed on a prefix map.
+
+#[cfg(feature = "metrics")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(feature = "metrics")]
+use super::PrefixMap;
+
+/// A snapshot of the counters of a [`PrefixMap`](super::PrefixMap), obtained using
+/// [`PrefixMap::metrics`](super::PrefixMap::metrics).
+///
+/// - `lookups` counts all exact-match (e.g., `get` or `contains_key`) and longest-prefix-match
+///   (e.g., `get_lpm`) lookups.
+/// - `lpm_hits` and `lpm_misses` count the longest-prefix-match lookups that found and did not find
+///   a matching prefix.
+/// - `max_depth` is the largest number of nodes visited during any lookup.
+/// - `inserts` counts all elements inserted (or overwritten) using `insert`, by inserting into a
+///   vacant entry, or by building the map from an iterator.
+/// - `removes` counts all elements removed using `remove`, `remove_by`, or `remove_keep_tree`.
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct MapMetrics {
+    /// Number of exact-match and longest-prefix-match lookups.
+    pub lookups: usize,
+    /// Number of longest-prefix-match lookups that found a matching prefix.
+    pub lpm_hits: usize,
+    /// Number of longest-prefix-match lookups that did not find a matching prefix.
+    pub lpm_misses: usize,
+    /// Largest number of nodes visited during a single lookup.
+    pub max_depth: usize,
+    /// Number of inserted (or overwritten) elements.
+    pub inserts: usize,
+    /// Number of removed elements.
+    pub removes: usize,
+}
+
+/// The counters stored in each map. All counters are atomic (using relaxed ordering), such that
+/// lookups (which only borrow the map immutably) can update them, and the map remains `Sync`.
+/// Without the `metrics` feature, this type is empty, and all methods do nothing.
+pub(crate) struct Counters {
+    #[cfg(feature = "metrics")]
+    lookups: AtomicUsize,
+    #[cfg(feature = "metrics")]
+    lpm_hits: AtomicUsize,
+    #[cfg(feature = "metrics")]
+    lpm_misses: AtomicUsize,
+    #[cfg(feature = "metrics")]
+    max_depth: AtomicUsize,
+    #[cfg(feature = "metrics")]
+    inserts: AtomicUsize,
+    #[cfg(feature = "metrics")]
+    removes: AtomicUsize,
+}
+
+impl Default for Counters {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for Counters {
+    fn clone(&self) -> Self {
+        #[cfg(feature = "metrics")]
+        {
+            let counters = Self::default();
+            counters.set(self.snapshot());
+            counters
+        }
+        #[cfg(not(feature = "metrics"))]
+        Self {}
+    }
+}
+
+impl Counters {
+    /// Create new counters, all set to zero.
+    pub(crate) const fn new() -> Self {
+        Self {
+            #[cfg(feature = "metrics")]
+            lookups: AtomicUsize::new(0),
+            #[cfg(feature = "metrics")]
+            lpm_hits: AtomicUsize::new(0),
+            #[cfg(feature = "metrics")]
+            lpm_misses: AtomicUsize::new(0),
+            #[cfg(feature = "metrics")]
+            max_depth: AtomicUsize::new(0),
+            #[cfg(feature = "metrics")]
+            inserts: AtomicUsize::new(0),
+            #[cfg(feature = "metrics")]
+            removes: AtomicUsize::new(0),
+        }
+    }
+
+    /// Count an exact-match lookup that visited `depth` nodes.
+    #[inline(always)]
+    pub(crate) fn lookup(&self, depth: usize) {
+        #[cfg(feature = "metrics")]
+        {
+            self.lookups.fetch_add(1, Ordering::Relaxed);
+            // only write the maximum depth if it changes, to avoid contention between readers.
+            if depth > self.max_depth.load(Ordering::Relaxed) {
+                self.max_depth.fetch_max(depth, Ordering::Relaxed);
+            }
+        }
+        let _ = depth;
+    }
+
+    /// Count a longest-prefix-match lookup that visited `depth` nodes.
+    #[inline(always)]
+    pub(crate) fn lpm(&self, depth: usize, hit: bool) {
+        self.lookup(depth);
+        #[cfg(feature = "metrics")]
+        if hit {
+            self.lpm_hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.lpm_misses.fetch_add(1, Ordering::Relaxed);
+        }
+        let _ = hit;
+    }
+
+    /// Count an inserted element.
+    #[inline(always)]
+    pub(crate) fn insert(&self) {
+        #[cfg(feature = "metrics")]
+        self.inserts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a removed element.
+    #[inline(always)]
+    pub(crate) fn remove(&self) {
+        #[cfg(feature = "metrics")]
+        self.removes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Read all counters.
+    #[cfg(feature = "metrics")]
+    pub(crate) fn snapshot(&self) -> MapMetrics {
+        MapMetrics {
+            lookups: self.lookups.load(Ordering::Relaxed),
+            lpm_hits: self.lpm_hits.load(Ordering::Relaxed),
+            lpm_misses: self.lpm_misses.load(Ordering::Relaxed),
+            max_depth: self.max_depth.load(Ordering::Relaxed),
+            inserts: self.inserts.load(Ordering::Relaxed),
+            removes: self.removes.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Overwrite all counters.
+    #[cfg(feature = "metrics")]
+    pub(crate) fn set(&self, metrics: MapMetrics) {
+        self.lookups.store(metrics.lookups, Ordering::Relaxed);
+        self.lpm_hits.store(metrics.lpm_hits, Ordering::Relaxed);
+        self.lpm_misses.store(metrics.lpm_misses, Ordering::Relaxed);
+        self.max_depth.store(metrics.max_depth, Ordering::Relaxed);
+        self.inserts.store(metrics.inserts, Ordering::Relaxed);
+        self.removes.store(metrics.removes, Ordering::Relaxed);
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl<P, T> PrefixMap<P, T> {
+    /// Get a snapshot of the counters of all lookups, inserts, and removes performed on this map
+    /// (see [`MapMetrics`]). Counting is cheap (a few relaxed atomic additions per operation), but
+    /// only enabled with the `metrics` feature. Cloning a map also clones its counters.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/8".parse()?, 1);
+    /// pm.insert("10.1.0.0/16".parse()?, 2);
+    /// pm.get_lpm(&"10.1.2.0/24".parse()?);
+    /// pm.get_lpm(&"192.168.0.0/24".parse()?);
+    /// pm.get(&"10.0.0.0/8".parse()?);
+    /// pm.remove(&"10.1.0.0/16".parse()?);
+    ///
+    /// let metrics = pm.metrics();
+    /// assert_eq!(metrics.lookups, 3);
+    /// assert_eq!((metrics.lpm_hits, metrics.lpm_misses), (1, 1));
+    /// assert_eq!(metrics.max_depth, 3);
+    /// assert_eq!((metrics.inserts, metrics.removes), (2, 1));
+    ///
+    /// pm.reset_metrics();
+    /// assert_eq!(pm.metrics(), Default::default());
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn metrics(&self) -> MapMetrics {
+        self.counters.snapshot()
+    }
+
+    /// Reset all counters to zero, e.g., after exporting them (see [`PrefixMap::metrics`]).
+    pub fn reset_metrics(&self) {
+        self.counters.set(MapMetrics::default());
+    }
+}
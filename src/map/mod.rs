@@ -1,25 +1,256 @@
 //! Implementation of the Prefix Map.
 
 use crate::{
-    inner::{Direction, DirectionForInsert, Node, Table},
+    inner::{Direction, DirectionForInsert, Node, Table, ValueCounts},
     Prefix,
 };
+use metrics::Counters;
 
+/// Implement read-only methods of a type that wraps a [`PrefixMap`] in its field `map`, by
+/// forwarding them to that map. Only the listed methods are implemented, such that the wrapper can
+/// implement the remaining ones differently. The macro must be invoked inside an `impl` block whose
+/// generic parameters include `P: Prefix`, and for `as_map`, `get`, `get_lpm`, and `iter`, the
+/// wrapped map must be a `PrefixMap<P, T>`.
+macro_rules! forward_map_methods {
+    ($($method:ident),* $(,)?) => {
+        $(forward_map_methods!(@ $method);)*
+    };
+    (@ len) => {
+        /// Returns the number of elements stored in `self`.
+        pub fn len(&self) -> usize {
+            self.map.len()
+        }
+    };
+    (@ is_empty) => {
+        /// Returns `true` if the map contains no elements.
+        pub fn is_empty(&self) -> bool {
+            self.map.is_empty()
+        }
+    };
+    (@ as_map) => {
+        /// Get a reference to the underlying map, e.g., to create a view.
+        pub fn as_map(&self) -> &$crate::PrefixMap<P, T> {
+            &self.map
+        }
+    };
+    (@ get) => {
+        /// Get the value of an element by matching exactly on the prefix.
+        pub fn get(&self, prefix: &P) -> Option<&T> {
+            self.map.get(prefix)
+        }
+    };
+    (@ contains_key) => {
+        /// Check if a key is present in the datastructure.
+        pub fn contains_key(&self, prefix: &P) -> bool {
+            self.map.contains_key(prefix)
+        }
+    };
+    (@ get_lpm) => {
+        /// Get a value of an element by using longest prefix matching.
+        pub fn get_lpm(&self, prefix: &P) -> Option<(&P, &T)> {
+            self.map.get_lpm(prefix)
+        }
+    };
+    (@ iter) => {
+        /// Iterate over all entries in the map in lexicographic order.
+        pub fn iter(&self) -> $crate::map::Iter<'_, P, T> {
+            self.map.iter()
+        }
+    };
+}
+
+/// Implement traits for a type `Name<P, T, ...>` that wraps a `PrefixMap<P, T>` in its field
+/// `map`, by forwarding them to that map. `Debug` prints the elements of the map, and `PartialEq`
+/// (which also implements `Eq`) compares only the elements, not any additional state of the
+/// wrapper. For example, `forward_map_traits!(Name[P, T, A]: Debug, PartialEq)`.
+macro_rules! forward_map_traits {
+    ($name:ident $params:tt: $($tr:ident),+) => {
+        $(forward_map_traits!(@ $tr $name $params);)+
+    };
+    (@ Debug $name:ident [$($param:ident),+]) => {
+        impl<$($param),+> std::fmt::Debug for $name<$($param),+>
+        where
+            P: $crate::Prefix + std::fmt::Debug,
+            T: std::fmt::Debug,
+        {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                self.map.fmt(f)
+            }
+        }
+    };
+    (@ PartialEq $name:ident [$($param:ident),+]) => {
+        impl<$($param),+> PartialEq for $name<$($param),+>
+        where
+            P: $crate::Prefix + PartialEq,
+            T: PartialEq,
+        {
+            fn eq(&self, other: &Self) -> bool {
+                self.map == other.map
+            }
+        }
+
+        impl<$($param),+> Eq for $name<$($param),+>
+        where
+            P: $crate::Prefix + Eq,
+            T: Eq,
+        {
+        }
+    };
+}
+
+mod aggregate;
+mod algebra;
+mod bulk;
+mod cached;
+mod concurrent;
+mod disjoint;
+mod distance;
 mod entry;
+#[cfg(feature = "epoch")]
+mod epoch;
+mod expiring;
+mod explain;
+mod flat;
+mod frozen;
+mod frozen_bytes;
+mod gaps;
+mod indexed;
+mod interned;
+mod io;
 mod iter;
+mod join;
+mod journal;
+mod mask;
+mod merkle;
+mod metrics;
+mod node_id;
+mod observer;
+mod priority;
+mod reader;
+mod reverse;
+mod rooted;
+#[cfg(feature = "rand")]
+mod sample;
+mod sharded;
+mod static_map;
+#[cfg(feature = "futures")]
+mod subscribe;
+mod summarize;
+mod sync;
+mod total;
+mod utilization;
 
+pub use aggregate::*;
+pub use bulk::*;
+pub use cached::*;
+pub use concurrent::*;
+pub use distance::*;
 pub use entry::*;
+#[cfg(feature = "epoch")]
+pub use epoch::*;
+pub use expiring::*;
+pub use explain::*;
+pub use flat::*;
+pub use frozen::*;
+pub use frozen_bytes::*;
+pub use indexed::*;
+pub use interned::*;
 pub use iter::*;
+pub use join::*;
+pub use journal::*;
+pub use merkle::*;
+#[cfg(feature = "metrics")]
+pub use metrics::MapMetrics;
+pub use node_id::*;
+pub use observer::*;
+pub use priority::*;
+pub use reader::*;
+pub use reverse::*;
+pub use rooted::*;
+pub use sharded::*;
+pub use static_map::*;
+#[cfg(feature = "futures")]
+pub use subscribe::*;
+pub use sync::*;
+pub use total::*;
+pub use utilization::*;
 
 /// Prefix map implemented as a prefix tree.
 ///
 /// You can perform union, intersection, and (covering) difference operations by first creating a
-/// view over the map using [`crate::AsView`] or [`crate::AsViewMut`].
-#[derive(Clone)]
+/// view over the map using [`crate::AsView`] or [`crate::AsViewMut`]. To build a new map from an
+/// intersection or difference, use [`PrefixMap::intersection_map`] or
+/// [`PrefixMap::difference_map`].
+///
+/// Each element is stored in a node of an internal table. Inserting and removing elements never
+/// moves a node that remains in the tree: removed nodes are only recycled by later inserts. Hence,
+/// the index of an element (see [`NodeId`]) is stable from its insertion until its removal, except
+/// for [`PrefixMap::compact`], [`PrefixMap::optimize`], and [`PrefixMap::clear`], which renumber or
+/// drop all nodes. Wrappers that store data per node (like [`AggregateMap`]) rely on this, and
+/// must not expose these operations without rebuilding their data.
+///
+/// Cloning a map is `O(n)`, but it only copies two contiguous vectors (the nodes and the values),
+/// which is a plain memory copy if the prefixes and values are `Copy`. Subtrees are not shared
+/// between clones (copy-on-write), as this would require reference counting on every node and
+/// slow down all lookups. To take frequent snapshots of a large map, either reuse the allocation
+/// of the previous snapshot using [`Clone::clone_from`], or share an immutable snapshot (e.g., a
+/// [`FrozenPrefixMap`]) behind an [`std::sync::Arc`] that is only replaced when the map changes.
+///
+/// The map is [`Send`] if `P` and `T` are [`Send`], and [`Sync`] if `P` and `T` are both [`Send`]
+/// and [`Sync`]. Hence, a read-only map can be shared between threads behind an
+/// [`std::sync::Arc`]. `T` must be [`Send`] for the map to be [`Sync`], because a
+/// [`crate::TrieViewMut`] (that can be sent to another thread) allows moving values out of the map.
+/// Similarly, `P` must be [`Send`], because the root of an empty map is created lazily by the first
+/// thread that reads it:
+///
+/// ```compile_fail
+/// # use prefix_trie::*;
+/// fn assert_sync<S: Sync>(_: &S) {}
+/// // `MutexGuard` is `Sync`, but not `Send`.
+/// let mutex = std::sync::Mutex::new(0);
+/// let mut pm: PrefixMap<(std::net::Ipv4Addr, u8), std::sync::MutexGuard<'_, i32>> = PrefixMap::new();
+/// pm.insert(("10.0.0.0".parse().unwrap(), 8), mutex.lock().unwrap());
+/// assert_sync(&pm);
+/// ```
 pub struct PrefixMap<P, T> {
     pub(crate) table: Table<P, T>,
     free: Vec<usize>,
-    count: usize,
+    counters: Counters,
+}
+
+impl<P: Clone, T: Clone> Clone for PrefixMap<P, T> {
+    fn clone(&self) -> Self {
+        Self {
+            table: self.table.clone(),
+            free: self.free.clone(),
+            counters: self.counters.clone(),
+        }
+    }
+
+    /// Overwrite `self` with a copy of `source`, reusing the existing allocations of `self`.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// let mut snapshot = PrefixMap::with_capacity(1024);
+    /// pm.insert("192.168.0.0/24".parse()?, 1);
+    /// snapshot.clone_from(&pm);
+    /// pm.insert("192.168.1.0/24".parse()?, 2);
+    /// assert_eq!(snapshot.len(), 1);
+    /// snapshot.clone_from(&pm);
+    /// assert_eq!(snapshot, pm);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    fn clone_from(&mut self, source: &Self) {
+        self.table.clone_from(&source.table);
+        self.free.clone_from(&source.free);
+        self.counters.clone_from(&source.counters);
+    }
 }
 
 impl<P, T> Default for PrefixMap<P, T>
@@ -27,11 +258,7 @@ where
     P: Prefix,
 {
     fn default() -> Self {
-        Self {
-            table: Default::default(),
-            free: Vec::new(),
-            count: 0,
-        }
+        Self::new()
     }
 }
 
@@ -39,21 +266,151 @@ impl<P, T> PrefixMap<P, T>
 where
     P: Prefix,
 {
-    /// Create an empty prefix map.
-    pub fn new() -> Self {
-        Self::default()
+    /// Create an empty prefix map. This does not allocate: the root of the tree is only allocated
+    /// when inserting the first element. Hence, creating maps that remain empty is cheap, and a map
+    /// can be created in a `const` context, e.g., to initialize a `static` item.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// use std::sync::Mutex;
+    ///
+    /// static ROUTES: Mutex<PrefixMap<(u32, u8), &str>> = Mutex::new(PrefixMap::new());
+    ///
+    /// ROUTES.lock().unwrap().insert((0x0a000000, 8), "internal");
+    /// assert_eq!(ROUTES.lock().unwrap().get_lpm(&(0x0a010203, 32)), Some((&(0x0a000000, 8), &"internal")));
+    /// ```
+    pub const fn new() -> Self {
+        Self {
+            table: Table::new(),
+            free: Vec::new(),
+            counters: Counters::new(),
+        }
+    }
+
+    /// Create an empty prefix map with space for at least `capacity` elements without
+    /// reallocating. Each element requires at most two nodes (the node itself, and a branching
+    /// node), so this reserves space for `2 * capacity` nodes.
+    ///
+    /// All nodes and values are stored in two contiguous vectors, so a map uses only a constant
+    /// number of allocations. If the prefixes and values do not implement `Drop`, dropping a map is
+    /// `O(1)`. For short-lived maps that are rebuilt repeatedly (e.g., once per request), use
+    /// `with_capacity` once and call [`PrefixMap::clear`] instead of creating a new map, which
+    /// reuses the existing allocations just like an arena allocator would. (Custom allocators
+    /// are not supported, as the allocator API is not yet stable.)
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, u32> = PrefixMap::with_capacity(1024);
+    /// for request in 0..3 {
+    ///     pm.insert("192.168.0.0/24".parse()?, request);
+    ///     assert_eq!(pm.len(), 1);
+    ///     pm.clear();
+    /// }
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            table: Table::with_capacity(capacity.saturating_mul(2)),
+            free: Vec::new(),
+            counters: Counters::new(),
+        }
+    }
+
+    /// Create an empty prefix map with space for at least `capacity` elements, like
+    /// [`Self::with_capacity`], but return an error instead of aborting if the allocation fails.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// let pm: Result<PrefixMap<(u32, u8), u32>, _> = PrefixMap::try_with_capacity(1024);
+    /// assert!(pm.is_ok());
+    /// let pm: Result<PrefixMap<(u32, u8), u32>, _> = PrefixMap::try_with_capacity(usize::MAX);
+    /// assert!(pm.is_err());
+    /// ```
+    pub fn try_with_capacity(capacity: usize) -> Result<Self, std::collections::TryReserveError> {
+        let mut map = Self::new();
+        map.try_reserve(capacity)?;
+        Ok(map)
+    }
+
+    /// Try to reserve space for at least `additional` more elements (i.e., `2 * additional`
+    /// nodes, see [`Self::with_capacity`]), such that inserting them does not allocate. If the
+    /// allocation fails (or the capacity would overflow), an error is returned instead of
+    /// aborting, and the elements of the map remain unchanged. Use this before loading a large
+    /// table to degrade gracefully if it does not fit into memory.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/8".parse()?, 1);
+    /// assert!(pm.try_reserve(usize::MAX).is_err());
+    /// pm.try_reserve(1024)?;
+    /// pm.insert("10.1.0.0/16".parse()?, 2);
+    /// assert_eq!(pm.len(), 2);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn try_reserve(
+        &mut self,
+        additional: usize,
+    ) -> Result<(), std::collections::TryReserveError> {
+        self.table.try_reserve(additional.saturating_mul(2))
     }
 
     /// Returns the number of elements stored in `self`.
     #[inline(always)]
     pub fn len(&self) -> usize {
-        self.count
+        self.table.count()
     }
 
     /// Returns `true` if the map contains no elements.
     #[inline(always)]
     pub fn is_empty(&self) -> bool {
-        self.count == 0
+        self.table.count() == 0
+    }
+
+    /// Get the length of the shortest prefix in the map, or `None` if the map is empty. The number
+    /// of elements per prefix length is maintained on every insert and remove, so this takes
+    /// constant time (at most one step per possible prefix length), independent of the size of the
+    /// map.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// assert_eq!(pm.min_prefix_len(), None);
+    /// pm.insert("192.168.0.0/16".parse()?, 1);
+    /// pm.insert("192.168.1.0/24".parse()?, 2);
+    /// pm.insert("10.0.0.0/8".parse()?, 3);
+    /// assert_eq!(pm.min_prefix_len(), Some(8));
+    /// assert_eq!(pm.max_prefix_len(), Some(24));
+    /// pm.remove(&"10.0.0.0/8".parse()?);
+    /// pm.remove(&"192.168.1.0/24".parse()?);
+    /// assert_eq!(pm.min_prefix_len(), Some(16));
+    /// assert_eq!(pm.max_prefix_len(), Some(16));
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn min_prefix_len(&self) -> Option<u8> {
+        self.table.counts().lens().next()
+    }
+
+    /// Get the length of the longest prefix in the map, or `None` if the map is empty. See
+    /// [`Self::min_prefix_len`]. No longest prefix match is longer than this length, so lookups
+    /// of longer prefixes may be truncated to it.
+    pub fn max_prefix_len(&self) -> Option<u8> {
+        self.table.counts().lens().next_back()
     }
 
     /// Get the value of an element by matching exactly on the prefix.
@@ -75,13 +432,17 @@ where
     /// ```
     pub fn get(&self, prefix: &P) -> Option<&T> {
         let mut idx = 0;
-        loop {
+        let mut depth = 1;
+        let value = loop {
             match self.table.get_direction(idx, prefix) {
-                Direction::Reached => return self.table[idx].value.as_ref(),
+                Direction::Reached => break self.table.value(idx),
                 Direction::Enter { next, .. } => idx = next,
-                Direction::Missing => return None,
+                Direction::Missing => break None,
             }
-        }
+            depth += 1;
+        };
+        self.counters.lookup(depth);
+        value
     }
 
     /// Get a mutable reference to a value of an element by matching exactly on the prefix.
@@ -103,13 +464,72 @@ where
     /// ```
     pub fn get_mut(&mut self, prefix: &P) -> Option<&mut T> {
         let mut idx = 0;
+        let mut depth = 1;
         loop {
             match self.table.get_direction(idx, prefix) {
-                Direction::Reached => return self.table[idx].value.as_mut(),
+                Direction::Reached => break,
                 Direction::Enter { next, .. } => idx = next,
-                Direction::Missing => return None,
+                Direction::Missing => {
+                    self.counters.lookup(depth);
+                    return None;
+                }
+            }
+            depth += 1;
+        }
+        self.counters.lookup(depth);
+        self.table.value_mut(idx).as_mut()
+    }
+
+    /// Get mutable references to the values of several elements at once, by matching exactly on
+    /// each prefix. Returns `None` if any of the prefixes is not present, or if the same prefix is
+    /// given more than once.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// let a = "10.0.0.0/8".parse()?;
+    /// let b = "10.1.0.0/16".parse()?;
+    /// pm.insert(a, 10);
+    /// pm.insert(b, 20);
+    /// let [x, y] = pm.get_many_mut([&a, &b]).unwrap();
+    /// std::mem::swap(x, y);
+    /// assert_eq!(pm.get(&a), Some(&20));
+    /// assert_eq!(pm.get(&b), Some(&10));
+    /// assert!(pm.get_many_mut([&a, &a]).is_none());
+    /// assert!(pm.get_many_mut([&a, &"10.2.0.0/16".parse()?]).is_none());
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn get_many_mut<const N: usize>(&mut self, prefixes: [&P; N]) -> Option<[&mut T; N]> {
+        let mut idxs = [0; N];
+        for (i, prefix) in prefixes.into_iter().enumerate() {
+            let mut idx = 0;
+            let mut depth = 1;
+            loop {
+                match self.table.get_direction(idx, prefix) {
+                    Direction::Reached => break,
+                    Direction::Enter { next, .. } => idx = next,
+                    Direction::Missing => {
+                        self.counters.lookup(depth);
+                        return None;
+                    }
+                }
+                depth += 1;
             }
+            self.counters.lookup(depth);
+            if self.table.value(idx).is_none() || idxs[..i].contains(&idx) {
+                return None;
+            }
+            idxs[i] = idx;
         }
+        let table = &self.table;
+        // Safety: all indices are distinct, and `self` is borrowed mutably for the lifetime of
+        // the returned references, so no other reference to these values can be constructed.
+        Some(idxs.map(|idx| unsafe { table.get_mut(idx) }.1.as_mut().unwrap()))
     }
 
     /// Get the value of an element by matching exactly on the prefix. Notice, that the returned
@@ -130,16 +550,22 @@ where
     /// ```
     pub fn get_key_value(&self, prefix: &P) -> Option<(&P, &T)> {
         let mut idx = 0;
-        loop {
+        let mut depth = 1;
+        let value = loop {
             match self.table.get_direction(idx, prefix) {
-                Direction::Reached => return self.table[idx].prefix_value(),
+                Direction::Reached => break self.table.prefix_value(idx),
                 Direction::Enter { next, .. } => idx = next,
-                Direction::Missing => return None,
+                Direction::Missing => break None,
             }
-        }
+            depth += 1;
+        };
+        self.counters.lookup(depth);
+        value
     }
 
-    /// Get a value of an element by using longest prefix matching
+    /// Get a value of an element by using longest prefix matching. The default route (the prefix
+    /// of length zero, see [`Self::set_default`]) contains every prefix, so if it is present, this
+    /// function falls back to it and never returns `None`.
     ///
     /// ```
     /// # use prefix_trie::*;
@@ -152,6 +578,8 @@ where
     /// assert_eq!(pm.get_lpm(&"192.168.1.0/24".parse()?), Some((&"192.168.1.0/24".parse()?, &1)));
     /// assert_eq!(pm.get_lpm(&"192.168.0.0/24".parse()?), Some((&"192.168.0.0/23".parse()?, &2)));
     /// assert_eq!(pm.get_lpm(&"192.168.2.0/24".parse()?), None);
+    /// pm.set_default(0);
+    /// assert_eq!(pm.get_lpm(&"192.168.2.0/24".parse()?), Some((&"0.0.0.0/0".parse()?, &0)));
     /// # Ok(())
     /// # }
     /// # #[cfg(not(feature = "ipnet"))]
@@ -159,14 +587,79 @@ where
     /// ```
     pub fn get_lpm<'a>(&'a self, prefix: &P) -> Option<(&'a P, &'a T)> {
         let mut idx = 0;
+        let mut depth = 1;
         let mut best_match: Option<(&P, &T)> = None;
         loop {
-            best_match = self.table[idx].prefix_value().or(best_match);
+            best_match = self.table.prefix_value(idx).or(best_match);
             match self.table.get_direction(idx, prefix) {
                 Direction::Enter { next, .. } => idx = next,
-                _ => return best_match,
+                _ => break,
+            }
+            depth += 1;
+        }
+        self.counters.lpm(depth, best_match.is_some());
+        best_match
+    }
+
+    /// Perform a longest prefix match for each element of `prefixes`, returning the results in the
+    /// same order. The result is identical to calling [`PrefixMap::get_lpm`] for each prefix.
+    ///
+    /// Instead of performing one query after the other, the queries are processed in groups of 8
+    /// in lockstep, i.e., each query of a group descends by one level before any query descends
+    /// further. The memory accesses of independent queries are interleaved, such that the CPU can
+    /// fetch the nodes of several queries at the same time, instead of waiting for each node of a
+    /// single query in turn.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.1.0/24".parse()?, 1);
+    /// pm.insert("192.168.0.0/23".parse()?, 2);
+    /// let queries = ["192.168.1.1/32".parse()?, "192.168.0.0/24".parse()?, "10.0.0.0/8".parse()?];
+    /// assert_eq!(
+    ///     pm.get_lpm_batch(&queries),
+    ///     vec![
+    ///         Some((&"192.168.1.0/24".parse()?, &1)),
+    ///         Some((&"192.168.0.0/23".parse()?, &2)),
+    ///         None,
+    ///     ]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn get_lpm_batch<'a>(&'a self, prefixes: &[P]) -> Vec<Option<(&'a P, &'a T)>> {
+        const LANES: usize = 8;
+        let mut result = Vec::with_capacity(prefixes.len());
+        for chunk in prefixes.chunks(LANES) {
+            let mut idx = [0usize; LANES];
+            let mut depth = [0usize; LANES];
+            let mut best: [Option<(&'a P, &'a T)>; LANES] = [None; LANES];
+            let mut active = chunk.len();
+            let mut done = [false; LANES];
+            while active > 0 {
+                for (lane, prefix) in chunk.iter().enumerate() {
+                    if done[lane] {
+                        continue;
+                    }
+                    best[lane] = self.table.prefix_value(idx[lane]).or(best[lane]);
+                    depth[lane] += 1;
+                    match self.table.get_direction(idx[lane], prefix) {
+                        Direction::Enter { next, .. } => idx[lane] = next,
+                        _ => {
+                            done[lane] = true;
+                            active -= 1;
+                            self.counters.lpm(depth[lane], best[lane].is_some());
+                        }
+                    }
+                }
             }
+            result.extend_from_slice(&best[..chunk.len()]);
         }
+        result
     }
 
     /// Get a mutable reference to a value of an element by using longest prefix matching
@@ -188,9 +681,10 @@ where
     /// ```
     pub fn get_lpm_mut(&mut self, prefix: &P) -> Option<(&P, &mut T)> {
         let mut idx = 0;
+        let mut depth = 1;
         let mut best_match: Option<usize> = None;
         loop {
-            best_match = if self.table[idx].value.is_some() {
+            best_match = if self.table.value(idx).is_some() {
                 Some(idx)
             } else {
                 best_match
@@ -199,9 +693,11 @@ where
                 Direction::Enter { next, .. } => idx = next,
                 _ => break,
             }
+            depth += 1;
         }
+        self.counters.lpm(depth, best_match.is_some());
         if let Some(idx) = best_match {
-            self.table[idx].prefix_value_mut()
+            self.table.prefix_value_mut(idx)
         } else {
             None
         }
@@ -226,13 +722,132 @@ where
     /// ```
     pub fn contains_key(&self, prefix: &P) -> bool {
         let mut idx = 0;
-        loop {
+        let mut depth = 1;
+        let found = loop {
             match self.table.get_direction(idx, prefix) {
-                Direction::Reached => return self.table[idx].value.is_some(),
+                Direction::Reached => break self.table.value(idx).is_some(),
                 Direction::Enter { next, .. } => idx = next,
-                Direction::Missing => return false,
+                Direction::Missing => break false,
             }
+            depth += 1;
+        };
+        self.counters.lookup(depth);
+        found
+    }
+
+    /// Insert the default route, i.e., the prefix of length zero that contains all prefixes. If
+    /// the default route already exists, replace its value and return the old one. This is
+    /// equivalent to `self.insert(P::zero(), value)`.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// assert!(!pm.has_default());
+    /// assert_eq!(pm.set_default(1), None);
+    /// assert_eq!(pm.set_default(2), Some(1));
+    /// assert!(pm.has_default());
+    /// assert_eq!(pm.get_default(), Some(&2));
+    /// assert_eq!(pm.get(&"0.0.0.0/0".parse()?), Some(&2));
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn set_default(&mut self, value: T) -> Option<T> {
+        self.insert(P::zero(), value)
+    }
+
+    /// Get the value of the default route (the prefix of length zero), if present. This is
+    /// equivalent to `self.get(&P::zero())`, but does not need to search the tree.
+    pub fn get_default(&self) -> Option<&T> {
+        self.table.value(0)
+    }
+
+    /// Check if the default route (the prefix of length zero) is present in the map.
+    pub fn has_default(&self) -> bool {
+        self.table.value(0).is_some()
+    }
+
+    /// Get the value of an element by matching exactly on a prefix of a different type `Q` that
+    /// shares the same representation. This allows querying a map with a cheaper (e.g.,
+    /// non-allocating) prefix type, without constructing a `P`. Prefixes are compared only using
+    /// [`Prefix::mask`] and [`Prefix::prefix_len`].
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.1.0/24".parse()?, 1);
+    /// assert_eq!(pm.get_by(&(0xc0a80100u32, 24)), Some(&1));
+    /// assert_eq!(pm.get_by(&(0xc0a80100u32, 25)), None);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn get_by<Q>(&self, prefix: &Q) -> Option<&T>
+    where
+        Q: Prefix<R = P::R>,
+    {
+        let mut idx = 0;
+        let mut depth = 1;
+        let value = loop {
+            match self.table.get_direction_by(idx, prefix) {
+                Direction::Reached => break self.table.value(idx),
+                Direction::Enter { next, .. } => idx = next,
+                Direction::Missing => break None,
+            }
+            depth += 1;
+        };
+        self.counters.lookup(depth);
+        value
+    }
+
+    /// Get a value of an element by using longest prefix matching, where the prefix is of a
+    /// different type `Q` that shares the same representation. See [`Self::get_by`].
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.0.0/23".parse()?, 1);
+    /// assert_eq!(pm.get_lpm_by(&(0xc0a80101u32, 32)), Some((&"192.168.0.0/23".parse()?, &1)));
+    /// assert_eq!(pm.get_lpm_by(&(0xc0a80201u32, 32)), None);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn get_lpm_by<'a, Q>(&'a self, prefix: &Q) -> Option<(&'a P, &'a T)>
+    where
+        Q: Prefix<R = P::R>,
+    {
+        let mut idx = 0;
+        let mut depth = 1;
+        let mut best_match: Option<(&P, &T)> = None;
+        loop {
+            best_match = self.table.prefix_value(idx).or(best_match);
+            match self.table.get_direction_by(idx, prefix) {
+                Direction::Enter { next, .. } => idx = next,
+                _ => break,
+            }
+            depth += 1;
         }
+        self.counters.lpm(depth, best_match.is_some());
+        best_match
+    }
+
+    /// Check if a key is present in the datastructure, where the key is of a different type `Q`
+    /// that shares the same representation. See [`Self::get_by`].
+    pub fn contains_key_by<Q>(&self, prefix: &Q) -> bool
+    where
+        Q: Prefix<R = P::R>,
+    {
+        self.get_by(prefix).is_some()
     }
 
     /// Get the longest prefix in the datastructure that matches the given `prefix`.
@@ -255,17 +870,18 @@ where
     /// ```
     pub fn get_lpm_prefix(&self, prefix: &P) -> Option<&P> {
         let mut idx = 0;
+        let mut depth = 1;
         let mut best_match: Option<&P> = None;
         loop {
-            best_match = self.table[idx]
-                .prefix_value()
-                .map(|(p, _)| p)
-                .or(best_match);
+            best_match = self.table.prefix_value(idx).map(|(p, _)| p).or(best_match);
             match self.table.get_direction(idx, prefix) {
                 Direction::Enter { next, .. } => idx = next,
-                _ => return best_match,
+                _ => break,
             }
+            depth += 1;
         }
+        self.counters.lpm(depth, best_match.is_some());
+        best_match
     }
 
     /// Get a value of an element by using shortest prefix matching.
@@ -287,16 +903,16 @@ where
     /// # fn main() {}
     pub fn get_spm<'a>(&'a self, prefix: &P) -> Option<(&'a P, &'a T)> {
         // Handle the special case, where the root is populated
-        if let Some(x) = self.table[0].prefix_value() {
+        if let Some(x) = self.table.prefix_value(0) {
             return Some(x);
         }
         let mut idx = 0;
         loop {
             match self.table.get_direction(idx, prefix) {
-                Direction::Reached => return self.table[idx].prefix_value(),
+                Direction::Reached => return self.table.prefix_value(idx),
                 Direction::Enter { next, .. } => {
                     // Go until the first node with a value
-                    match self.table[next].prefix_value() {
+                    match self.table.prefix_value(next) {
                         Some(x) => return Some(x),
                         None => idx = next,
                     }
@@ -347,33 +963,39 @@ where
     /// # fn main() {}
     /// ```
     pub fn insert(&mut self, prefix: P, value: T) -> Option<T> {
-        let mut idx = 0;
+        let old_value = self.insert_at(0, prefix, value).1;
+        self.debug_check_invariants("insert");
+        old_value
+    }
+
+    /// Insert a new item into the map, starting the search at node `idx`, which must contain
+    /// `prefix`. Returns the index of the node that stores `prefix`, and the old value.
+    fn insert_at(&mut self, mut idx: usize, prefix: P, value: T) -> (usize, Option<T>) {
+        self.counters.insert();
         loop {
             match self.table.get_direction_for_insert(idx, &prefix) {
                 DirectionForInsert::Enter { next, .. } => idx = next,
                 DirectionForInsert::Reached => {
-                    let mut inc = 0;
-                    let node = &mut self.table[idx];
+                    let len = prefix.prefix_len();
                     // replace the prefix
-                    node.prefix = prefix;
-                    let old_value = node.value.take();
+                    self.table[idx].prefix = prefix;
+                    let old_value = self.table.value_mut(idx).replace(value);
                     if old_value.is_none() {
-                        inc = 1;
+                        self.table.counts_mut().add(len);
+                        self.table.bump_generation(idx);
                     }
-                    node.value = Some(value);
-                    self.count += inc;
-                    return old_value;
+                    return (idx, old_value);
                 }
                 DirectionForInsert::NewLeaf { right } => {
                     let new = self.new_node(prefix, Some(value));
                     self.table.set_child(idx, new, right);
-                    return None;
+                    return (new, None);
                 }
                 DirectionForInsert::NewChild { right, child_right } => {
                     let new = self.new_node(prefix, Some(value));
                     let child = self.table.set_child(idx, new, right).unwrap();
                     self.table.set_child(new, child, child_right);
-                    return None;
+                    return (new, None);
                 }
                 DirectionForInsert::NewBranch {
                     branch_prefix,
@@ -385,10 +1007,55 @@ where
                     let child = self.table.set_child(idx, branch, right).unwrap();
                     self.table.set_child(branch, new, prefix_right);
                     self.table.set_child(branch, child, !prefix_right);
-                    return None;
+                    return (new, None);
+                }
+            }
+        }
+    }
+
+    /// Insert all elements of `iter` into `self`. Each element is inserted starting from the
+    /// deepest node on the path to the previously inserted element that contains it (instead of
+    /// from the root). If the elements are yielded in lexicographic order (e.g., when collecting
+    /// an iterator over another map or a set operation), each insertion is amortized `O(1)`, and
+    /// building the map is `O(n)`.
+    pub(crate) fn extend_sorted<I: IntoIterator<Item = (P, T)>>(&mut self, iter: I) {
+        trace_span!("from_iter", len = self.len());
+        // number of elements that are smaller than their predecessor (the slow path).
+        #[cfg(feature = "tracing")]
+        let (mut last, mut unsorted) = (None, 0usize);
+        // path from the root to the previously inserted element.
+        let mut path = vec![0];
+        for (prefix, value) in iter {
+            #[cfg(feature = "tracing")]
+            {
+                // the lexicographic order is the order of the masked representation and the length.
+                let key = (prefix.mask(), prefix.prefix_len());
+                if matches!(last, Some(last) if last > key) {
+                    unsorted += 1;
                 }
+                last = Some(key);
             }
+            while !self.table[*path.last().unwrap()].prefix.contains(&prefix) {
+                path.pop();
+            }
+            let start = *path.last().unwrap();
+            let (idx, _) = self.insert_at(start, prefix, value);
+            let target = &self.table[idx].prefix;
+            let mut cur = start;
+            while let Direction::Enter { next, .. } = self.table.get_direction(cur, target) {
+                path.push(next);
+                cur = next;
+            }
+        }
+        #[cfg(feature = "tracing")]
+        if unsorted > 0 {
+            tracing::debug!(
+                unsorted,
+                len = self.len(),
+                "elements are not in lexicographic order"
+            );
         }
+        self.debug_check_invariants("extend_sorted");
     }
 
     /// Gets the given key’s corresponding entry in the map for in-place manipulation. In case you
@@ -416,11 +1083,15 @@ where
         loop {
             match self.table.get_direction_for_insert(idx, &prefix) {
                 DirectionForInsert::Enter { next, .. } => idx = next,
-                DirectionForInsert::Reached if self.table[idx].value.is_some() => {
+                DirectionForInsert::Reached if self.table.value(idx).is_some() => {
+                    let (node, value, counts) = self.table.node_value_count_mut(idx);
                     return Entry::Occupied(OccupiedEntry {
-                        node: &mut self.table[idx],
+                        node,
+                        value,
+                        counts,
+                        len: prefix.prefix_len(),
                         prefix,
-                    })
+                    });
                 }
                 direction => {
                     return Entry::Vacant(VacantEntry {
@@ -456,34 +1127,37 @@ where
     /// # fn main() {}
     /// ```
     pub fn remove(&mut self, prefix: &P) -> Option<T> {
-        let mut idx = 0;
-        let mut grandparent = None;
-        let mut grandparent_right = false;
-        let mut parent = None;
-        let mut parent_right = false;
-        // first, search for the element
-        loop {
-            match self.table.get_direction(idx, prefix) {
-                Direction::Reached => break,
-                Direction::Enter { next, right } => {
-                    grandparent_right = parent_right;
-                    parent_right = right;
-                    grandparent = parent;
-                    parent = Some(idx);
-                    idx = next;
-                }
-                Direction::Missing => return None,
-            }
-        }
-        self._remove_node(idx, parent, parent_right, grandparent, grandparent_right)
-            .0
+        self._remove_by_direction(|table, idx| table.get_direction(idx, prefix))
+    }
+
+    /// Removes a key from the map, where the key is of a different type `Q` that shares the same
+    /// representation. See [`Self::get_by`] and [`Self::remove`].
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.1.0/24".parse()?, 1);
+    /// assert_eq!(pm.remove_by(&(0xc0a80100u32, 24)), Some(1));
+    /// assert!(pm.is_empty());
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn remove_by<Q>(&mut self, prefix: &Q) -> Option<T>
+    where
+        Q: Prefix<R = P::R>,
+    {
+        self._remove_by_direction(|table, idx| table.get_direction_by(idx, prefix))
     }
 
     /// Removes a key from the map, returning the value at the key if the key was previously in the
     /// map. In contrast to [`Self::remove`], his operation will keep the tree structure as is, but
     /// only remove the element from it. This allows any future `insert` on the same prefix to be
     /// faster. However future reads from the tree might be a bit slower because they need to
-    /// traverse more nodes.
+    /// traverse more nodes. Use [`Self::cleanup_tree`] to remove the nodes that are left behind.
     ///
     /// ```
     /// # use prefix_trie::*;
@@ -507,7 +1181,7 @@ where
         let mut idx = 0;
         let value = loop {
             match self.table.get_direction(idx, prefix) {
-                Direction::Reached => break self.table[idx].value.take(),
+                Direction::Reached => break self.table.value_mut(idx).take(),
                 Direction::Enter { next, .. } => idx = next,
                 Direction::Missing => break None,
             }
@@ -515,9 +1189,11 @@ where
 
         // decrease the count if the value is something
         if value.is_some() {
-            self.count -= 1;
+            self.table.counts_mut().sub(prefix.prefix_len());
+            self.counters.remove();
         }
 
+        self.debug_check_invariants("remove_keep_tree");
         value
     }
 
@@ -569,6 +1245,131 @@ where
         }
     }
 
+    /// Remove all entries that contain `prefix` (including `prefix` itself), and return them
+    /// ordered by prefix length, shortest first (like [`Self::cover`]). This will change the tree
+    /// structure. The covering chain is only traversed once.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/8".parse()?, 1);
+    /// pm.insert("10.1.0.0/16".parse()?, 2);
+    /// pm.insert("10.1.1.0/24".parse()?, 3);
+    /// pm.insert("10.2.0.0/16".parse()?, 4);
+    /// assert_eq!(
+    ///     pm.remove_cover(&"10.1.0.0/20".parse()?),
+    ///     vec![("10.0.0.0/8".parse()?, 1), ("10.1.0.0/16".parse()?, 2)]
+    /// );
+    /// assert_eq!(
+    ///     pm.into_iter().collect::<Vec<_>>(),
+    ///     vec![("10.1.1.0/24".parse()?, 3), ("10.2.0.0/16".parse()?, 4)]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn remove_cover(&mut self, prefix: &P) -> Vec<(P, T)>
+    where
+        P: Clone,
+    {
+        // all nodes on the path towards `prefix` contain it, together with their direction.
+        let mut path = vec![(0, false)];
+        let mut idx = 0;
+        while let Direction::Enter { next, right } = self.table.get_direction(idx, prefix) {
+            path.push((next, right));
+            idx = next;
+        }
+
+        // remove bottom-up, such that the parent and grandparent of each node are still in the
+        // tree. Only nodes without a value are spliced out, which are never removed again.
+        let mut removed = Vec::new();
+        for i in (0..path.len()).rev() {
+            let (idx, right) = path[i];
+            if self.table.value(idx).is_none() {
+                continue;
+            }
+            let prefix = self.table[idx].prefix.clone();
+            let par = i.checked_sub(1).map(|j| path[j]);
+            let grp = i.checked_sub(2).map(|j| path[j]);
+            let (value, _) = self._remove_node(
+                idx,
+                par.map(|(p, _)| p),
+                right,
+                grp.map(|(g, _)| g),
+                par.map(|(_, r)| r).unwrap_or_default(),
+            );
+            removed.push((prefix, value.unwrap()));
+            self.counters.remove();
+        }
+        removed.reverse();
+
+        self.debug_check_invariants("remove_cover");
+        removed
+    }
+
+    /// Remove the longest prefix match of `prefix` (see [`Self::get_lpm`]), and return it
+    /// together with its value. This will change the tree structure. In contrast to calling
+    /// [`Self::get_lpm`] followed by [`Self::remove`], the tree is only traversed once.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/8".parse()?, 1);
+    /// pm.insert("10.1.0.0/16".parse()?, 2);
+    /// assert_eq!(pm.remove_lpm(&"10.1.1.0/24".parse()?), Some(("10.1.0.0/16".parse()?, 2)));
+    /// assert_eq!(pm.remove_lpm(&"10.1.1.0/24".parse()?), Some(("10.0.0.0/8".parse()?, 1)));
+    /// assert_eq!(pm.remove_lpm(&"10.1.1.0/24".parse()?), None);
+    /// assert!(pm.is_empty());
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn remove_lpm(&mut self, prefix: &P) -> Option<(P, T)>
+    where
+        P: Clone,
+    {
+        // all nodes on the path towards `prefix` contain it, together with their direction.
+        let mut path = vec![(0, false)];
+        let mut best_match = None;
+        let mut idx = 0;
+        loop {
+            if self.table.value(idx).is_some() {
+                best_match = Some(path.len() - 1);
+            }
+            match self.table.get_direction(idx, prefix) {
+                Direction::Enter { next, right } => {
+                    path.push((next, right));
+                    idx = next;
+                }
+                _ => break,
+            }
+        }
+        self.counters.lpm(path.len(), best_match.is_some());
+
+        let i = best_match?;
+        let (idx, right) = path[i];
+        let prefix = self.table[idx].prefix.clone();
+        let par = i.checked_sub(1).map(|j| path[j]);
+        let grp = i.checked_sub(2).map(|j| path[j]);
+        let (value, _) = self._remove_node(
+            idx,
+            par.map(|(p, _)| p),
+            right,
+            grp.map(|(g, _)| g),
+            par.map(|(_, r)| r).unwrap_or_default(),
+        );
+        self.counters.remove();
+
+        self.debug_check_invariants("remove_lpm");
+        Some((prefix, value.unwrap()))
+    }
+
     /// Clear the map but keep the allocated memory.
     ///
     /// ```
@@ -587,15 +1388,118 @@ where
     /// # fn main() {}
     /// ```
     pub fn clear(&mut self) {
-        self.table.as_mut().clear();
+        self.table.clear();
         self.free.clear();
-        self.table.as_mut().push(Node {
-            prefix: P::zero(),
-            value: None,
-            left: None,
-            right: None,
-        });
-        self.count = 0;
+        self.debug_check_invariants("clear");
+    }
+
+    /// Remove all nodes without a value that are no longer required for the tree structure, i.e.,
+    /// that have less than two children. Such nodes are left behind by
+    /// [`Self::remove_keep_tree`] and by removing elements through a [`crate::TrieViewMut`], and
+    /// they slow down lookups without ever being reclaimed otherwise. The removed nodes are reused
+    /// by subsequent inserts. Call [`Self::compact`] afterwards to release their memory. This
+    /// operation is `O(n)`.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// for i in 0..=255u8 {
+    ///     pm.insert(format!("10.{i}.0.0/16").parse()?, i);
+    /// }
+    /// for i in 3..=255u8 {
+    ///     pm.remove_keep_tree(&format!("10.{i}.0.0/16").parse()?);
+    /// }
+    /// pm.cleanup_tree();
+    /// assert_eq!(pm.len(), 3);
+    /// assert_eq!(pm.get(&"10.2.0.0/16".parse()?), Some(&2));
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn cleanup_tree(&mut self) {
+        if self.table.len() > 0 {
+            self._cleanup_tree(0);
+        }
+        self.debug_check_invariants("cleanup_tree");
+    }
+
+    /// Recursively remove the unnecessary nodes in the subtree rooted at `idx`, and return the
+    /// node that replaces `idx` in its parent (or `None` if the entire subtree was removed). The
+    /// root is never removed.
+    fn _cleanup_tree(&mut self, idx: usize) -> Option<usize> {
+        let left = self.table[idx].left().and_then(|c| self._cleanup_tree(c));
+        let right = self.table[idx].right().and_then(|c| self._cleanup_tree(c));
+        let node = &mut self.table[idx];
+        node.set_left(left);
+        node.set_right(right);
+        if idx == 0 || self.table.value(idx).is_some() || (left.is_some() && right.is_some()) {
+            return Some(idx);
+        }
+        self.free.push(idx);
+        left.or(right)
+    }
+
+    /// Rebuild the internal table densely. Removed elements leave unused slots in the table, which
+    /// are reused by subsequent inserts. After heavy churn (or after removing many elements), this
+    /// function releases these slots, and it stores all nodes in traversal order, which improves
+    /// the cache locality of lookups and iterations. This operation is `O(n)`.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// for i in 0..=255u8 {
+    ///     pm.insert(format!("10.{i}.0.0/16").parse()?, i);
+    /// }
+    /// pm.retain(|_, i| *i < 3);
+    /// pm.compact();
+    /// assert_eq!(pm.len(), 3);
+    /// assert_eq!(pm.get(&"10.2.0.0/16".parse()?), Some(&2));
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn compact(&mut self) {
+        trace_span!("compact", nodes = self.table.len(), free = self.free.len());
+        self.table.compact();
+        self.free.clear();
+        self.free.shrink_to_fit();
+        self.debug_check_invariants("compact");
+    }
+
+    /// Rebuild the internal table densely (just like [`PrefixMap::compact`]), and store the nodes
+    /// in a cache-conscious order (van Emde Boas layout): nodes that are visited together during a
+    /// lookup are stored close to each other in memory. After heavy insert and remove churn, the
+    /// nodes are stored in allocation order, which is effectively random. Call this function once
+    /// the map is built to improve lookup throughput. This operation is `O(n)`.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// for i in (0..=255u8).rev() {
+    ///     pm.insert(format!("10.{i}.0.0/16").parse()?, i);
+    /// }
+    /// pm.optimize();
+    /// assert_eq!(pm.len(), 256);
+    /// assert_eq!(pm.get_lpm(&"10.2.1.0/24".parse()?), Some((&"10.2.0.0/16".parse()?, &2)));
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn optimize(&mut self) {
+        trace_span!("optimize", nodes = self.table.len(), free = self.free.len());
+        self.table.optimize();
+        self.free.clear();
+        self.free.shrink_to_fit();
+        self.debug_check_invariants("optimize");
     }
 
     /// Keep only the elements in the map that satisfy the given condition `f`.
@@ -624,6 +1528,7 @@ where
         F: FnMut(&P, &T) -> bool,
     {
         self._retain(0, None, false, None, false, f);
+        self.debug_check_invariants("retain");
     }
 
     /// Iterate over all entries in the map that covers the given `prefix` (including `prefix`
@@ -758,49 +1663,153 @@ where
         let mut to_free = vec![self.table.get_child(idx, right).unwrap()];
         self.table.clear_child(idx, right);
         while let Some(idx) = to_free.pop() {
-            let mut dec = 0;
-            let node = &mut self.table[idx];
-            let value = node.value.take();
+            let value = self.table.value_mut(idx).take();
             // decrease the count if `value` is something
             if value.is_some() {
-                dec = 1;
+                let len = self.table[idx].prefix.prefix_len();
+                self.table.counts_mut().sub(len);
             }
-            if let Some(left) = node.left.take() {
+            let node = &mut self.table[idx];
+            if let Some(left) = node.set_left(None) {
                 to_free.push(left)
             }
-            if let Some(right) = node.right.take() {
+            if let Some(right) = node.set_right(None) {
                 to_free.push(right)
             }
             self.free.push(idx);
-            self.count -= dec;
         }
+        self.debug_check_invariants("remove_children");
+    }
+
+    /// Check all invariants of the map: the structure of the tree (see
+    /// `Table::check_structure`), that only reachable nodes hold a value, that the length matches
+    /// the number of values, and that the free list contains exactly the unreachable nodes.
+    #[cfg(any(test, feature = "debug-invariants"))]
+    pub(crate) fn check_invariants(&self) -> Result<(), String> {
+        let report = |e: String| {
+            format!(
+                "{e} (the table has {} nodes, {} free nodes, and a length of {})",
+                self.table.len(),
+                self.free.len(),
+                self.len()
+            )
+        };
+        let reachable = self.table.check_structure().map_err(report)?;
+        let mut count = 0;
+        let mut per_len = std::collections::BTreeMap::<u8, usize>::new();
+        for (idx, reachable) in reachable.iter().enumerate() {
+            if self.table.value(idx).is_some() {
+                if !reachable {
+                    return Err(report(format!(
+                        "node {idx} ({:?}) holds a value, but is not reachable from the root",
+                        self.table[idx].prefix
+                    )));
+                }
+                count += 1;
+                *per_len
+                    .entry(self.table[idx].prefix.prefix_len())
+                    .or_default() += 1;
+            }
+        }
+        if count != self.len() {
+            return Err(report(format!("the tree holds {count} values")));
+        }
+        if let Some(len) = (0..=u8::MAX)
+            .find(|len| per_len.get(len).copied().unwrap_or(0) != self.table.counts().of_len(*len))
+        {
+            return Err(report(format!(
+                "the tree holds {} values of prefix length {len}, but {} are counted",
+                per_len.get(&len).copied().unwrap_or(0),
+                self.table.counts().of_len(len)
+            )));
+        }
+        let mut free = vec![false; reachable.len()];
+        for &idx in &self.free {
+            if idx == 0 || idx >= reachable.len() || free[idx] {
+                return Err(report(format!("the free list contains invalid node {idx}")));
+            }
+            if reachable[idx] {
+                return Err(report(format!(
+                    "node {idx} ({:?}) is in the free list, but is reachable from the root",
+                    self.table[idx].prefix
+                )));
+            }
+            free[idx] = true;
+        }
+        if let Some(idx) = (0..reachable.len()).find(|&idx| !reachable[idx] && !free[idx]) {
+            return Err(report(format!(
+                "node {idx} ({:?}) is neither reachable from the root nor in the free list",
+                self.table[idx].prefix
+            )));
+        }
+        Ok(())
+    }
+
+    /// Panic with a report of the violated invariant (see `check_invariants`) after the operation
+    /// `op`. This only does something if the `debug-invariants` feature is enabled.
+    #[inline(always)]
+    pub(crate) fn debug_check_invariants(&self, op: &str) {
+        #[cfg(feature = "debug-invariants")]
+        if let Err(e) = self.check_invariants() {
+            panic!("prefix-trie invariant violated after `{op}`: {e}");
+        }
+        let _ = op;
     }
 
     /// insert a new node into the table and return its index. This function also increments the
-    /// count by 1, but only if `value` is `Some`.
+    /// count by 1, but only if `value` is `Some`. A reused node obtains a new generation.
     #[inline(always)]
     fn new_node(&mut self, prefix: P, value: Option<T>) -> usize {
         if value.is_some() {
-            self.count += 1;
+            self.table.counts_mut().add(prefix.prefix_len());
         }
         if let Some(idx) = self.free.pop() {
+            *self.table.value_mut(idx) = value;
+            self.table.bump_generation(idx);
             let node = &mut self.table[idx];
             node.prefix = prefix;
-            node.value = value;
-            node.left = None;
-            node.right = None;
+            node.set_left(None);
+            node.set_right(None);
             idx
         } else {
-            let table = self.table.as_mut();
-            let idx = table.len();
-            table.push(Node {
-                prefix,
-                value,
-                left: None,
-                right: None,
-            });
-            idx
+            self.table.push(prefix, value)
+        }
+    }
+
+    /// Search for the node to remove by following `direction` from the root, and remove it (see
+    /// [`Self::remove`]).
+    #[inline(always)]
+    fn _remove_by_direction<F>(&mut self, mut direction: F) -> Option<T>
+    where
+        F: FnMut(&Table<P, T>, usize) -> Direction,
+    {
+        let mut idx = 0;
+        let mut grandparent = None;
+        let mut grandparent_right = false;
+        let mut parent = None;
+        let mut parent_right = false;
+        // first, search for the element
+        loop {
+            match direction(&self.table, idx) {
+                Direction::Reached => break,
+                Direction::Enter { next, right } => {
+                    grandparent_right = parent_right;
+                    parent_right = right;
+                    grandparent = parent;
+                    parent = Some(idx);
+                    idx = next;
+                }
+                Direction::Missing => return None,
+            }
+        }
+        let value = self
+            ._remove_node(idx, parent, parent_right, grandparent, grandparent_right)
+            .0;
+        if value.is_some() {
+            self.counters.remove();
         }
+        self.debug_check_invariants("remove");
+        value
     }
 
     /// Remove a child from the tree. If the parent was removed, return `true` as a second return parameter
@@ -814,14 +1823,15 @@ where
     ) -> (Option<T>, bool) {
         // if we reach this point, then `idx` is the element to remove, `parent` is its parent,
         // and `parent_right` stores the direction of `idx` at `parent`.
-        let node = &mut self.table[idx];
-        let value = node.value.take();
-        let has_left = node.left.is_some();
-        let has_right = node.right.is_some();
+        let value = self.table.value_mut(idx).take();
+        let node = &self.table[idx];
+        let has_left = node.left().is_some();
+        let has_right = node.right().is_some();
 
         // decrease the number of elements if value is something
         if value.is_some() {
-            self.count -= 1;
+            let len = self.table[idx].prefix.prefix_len();
+            self.table.counts_mut().sub(len);
         }
 
         if has_left && has_right {
@@ -834,12 +1844,14 @@ where
                 // now, if the parent has no value, also remove the parent and replace it with the
                 // current node. but only do that if the grandparent is something.
                 if let Some(grp) = grp {
-                    if self.table[par].value.is_none() {
+                    if self.table.value(par).is_none() {
                         if let Some(sibling) = self.table.get_child(par, !par_right) {
                             self.table.set_child(grp, sibling, grp_right);
+                            self.free.push(par);
                             return (value, true);
                         } else {
                             self.table.clear_child(grp, grp_right);
+                            self.free.push(par);
                         }
                     }
                 }
@@ -872,10 +1884,10 @@ where
         // first, do the recursion
         let mut idx_removed = false;
         let mut par_removed = false;
-        if let Some(left) = self.table[idx].left {
+        if let Some(left) = self.table[idx].left() {
             (f, idx_removed) = self._retain(left, Some(idx), false, par, par_right, f);
         }
-        if let Some(right) = self.table[idx].right {
+        if let Some(right) = self.table[idx].right() {
             if idx_removed {
                 (f, par_removed) = self._retain(right, par, par_right, grp, grp_right, f);
             } else {
@@ -883,7 +1895,7 @@ where
             }
         }
         // then, check if we need to delete the node
-        if let Some(val) = self.table[idx].value.as_ref() {
+        if let Some(val) = self.table.value(idx) {
             if !f(&self.table[idx].prefix, val) {
                 // deletion is necessary.
                 let (_, par_del) = self._remove_node(idx, par, par_right, grp, grp_right);
@@ -900,7 +1912,7 @@ where
     T: PartialEq,
 {
     fn eq(&self, other: &Self) -> bool {
-        self.iter().zip(other.iter()).all(|(a, b)| a == b)
+        self.len() == other.len() && self.iter().eq(other.iter())
     }
 }
 
@@ -910,3 +1922,37 @@ where
     T: Eq,
 {
 }
+
+/// Maps are compared lexicographically over their entries, in the order of [`PrefixMap::iter`]
+/// (like a `BTreeMap`).
+///
+/// ```
+/// # use prefix_trie::*;
+/// let a: PrefixMap<(u32, u8), _> = [((0x0a000000, 8), 1)].into_iter().collect();
+/// let b: PrefixMap<(u32, u8), _> = [((0x0a000000, 8), 2)].into_iter().collect();
+/// let c: PrefixMap<(u32, u8), _> = [((0x0a000000, 8), 1), ((0x0a000000, 16), 1)]
+///     .into_iter()
+///     .collect();
+/// let mut maps = vec![b.clone(), c.clone(), a.clone()];
+/// maps.sort();
+/// assert_eq!(maps, vec![a, c, b]);
+/// ```
+impl<P, T> PartialOrd for PrefixMap<P, T>
+where
+    P: Prefix + PartialOrd,
+    T: PartialOrd,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<P, T> Ord for PrefixMap<P, T>
+where
+    P: Prefix + Ord,
+    T: Ord,
+{
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
@@ -0,0 +1,136 @@
+//! Stable handles to elements of a prefix map.
+
+use super::*;
+
+/// An opaque handle to an element of a [`PrefixMap`], obtained from
+/// [`PrefixMap::insert_with_id`], [`PrefixMap::node_id`], or [`crate::TrieView::node_id`]. Use
+/// [`PrefixMap::get_by_id`] to access the element in `O(1)` instead of looking up its prefix
+/// again, e.g., when storing references to routes in external data structures.
+///
+/// A handle remains valid while the element is present in the map, even if other elements are
+/// inserted or removed, or if its value is overwritten. It is invalidated once the element is
+/// removed (even if the same prefix is inserted again afterwards), and when the map is compacted,
+/// optimized, or cleared (see [`PrefixMap::compact`]). Accessing the map with an invalidated
+/// handle returns `None`.
+///
+/// A handle must only be used with the map from which it was obtained (or a clone of it).
+/// Otherwise, it may refer to an arbitrary element of the other map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId {
+    idx: u32,
+    generation: u32,
+    epoch: u32,
+}
+
+impl NodeId {
+    /// Create the handle of the node at `idx` in `table`.
+    pub(crate) fn new<P, T>(table: &Table<P, T>, idx: usize) -> Self {
+        Self {
+            // the table never holds more than `u32::MAX` nodes.
+            idx: idx as u32,
+            generation: table.generation(idx),
+            epoch: table.epoch(),
+        }
+    }
+
+    /// Get the index of the node if the handle is still valid for `table`.
+    fn idx<P: Prefix, T>(self, table: &Table<P, T>) -> Option<usize> {
+        let idx = self.idx as usize;
+        (self.epoch == table.epoch()
+            && idx < table.len()
+            && table.generation(idx) == self.generation
+            && table.value(idx).is_some())
+        .then_some(idx)
+    }
+}
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Insert a new item into the map (just like [`PrefixMap::insert`]), and return a handle to
+    /// the element along with the old value. If the prefix was already present, the handle equals
+    /// the one obtained before.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// let (id, _) = pm.insert_with_id("10.0.0.0/8".parse()?, 1);
+    /// pm.insert("10.1.0.0/16".parse()?, 2);
+    /// assert_eq!(pm.get_by_id(id), Some((&"10.0.0.0/8".parse()?, &1)));
+    /// assert_eq!(pm.insert_with_id("10.0.0.0/8".parse()?, 3), (id, Some(1)));
+    ///
+    /// pm.remove(&"10.0.0.0/8".parse()?);
+    /// assert_eq!(pm.get_by_id(id), None);
+    /// pm.insert("10.0.0.0/8".parse()?, 4);
+    /// assert_eq!(pm.get_by_id(id), None);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn insert_with_id(&mut self, prefix: P, value: T) -> (NodeId, Option<T>) {
+        let (idx, old_value) = self.insert_at(0, prefix, value);
+        self.debug_check_invariants("insert_with_id");
+        (NodeId::new(&self.table, idx), old_value)
+    }
+
+    /// Get the handle of an element by matching exactly on the prefix.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/8".parse()?, 1);
+    /// let id = pm.node_id(&"10.0.0.0/8".parse()?).unwrap();
+    /// assert_eq!(pm.get_by_id(id), Some((&"10.0.0.0/8".parse()?, &1)));
+    /// assert_eq!(pm.node_id(&"10.0.0.0/16".parse()?), None);
+    ///
+    /// pm.compact();
+    /// assert_eq!(pm.get_by_id(id), None);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn node_id(&self, prefix: &P) -> Option<NodeId> {
+        let mut idx = 0;
+        loop {
+            match self.table.get_direction(idx, prefix) {
+                Direction::Reached => break,
+                Direction::Enter { next, .. } => idx = next,
+                Direction::Missing => return None,
+            }
+        }
+        self.table.value(idx).map(|_| NodeId::new(&self.table, idx))
+    }
+
+    /// Get the element of a handle, or `None` if the handle was invalidated (see [`NodeId`]).
+    pub fn get_by_id(&self, id: NodeId) -> Option<(&P, &T)> {
+        self.table.prefix_value(id.idx(&self.table)?)
+    }
+
+    /// Get a mutable reference to the value of a handle, or `None` if the handle was invalidated
+    /// (see [`NodeId`]).
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// let (id, _) = pm.insert_with_id("10.0.0.0/8".parse()?, 1);
+    /// *pm.get_by_id_mut(id).unwrap().1 += 1;
+    /// assert_eq!(pm.get(&"10.0.0.0/8".parse()?), Some(&2));
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn get_by_id_mut(&mut self, id: NodeId) -> Option<(&P, &mut T)> {
+        let idx = id.idx(&self.table)?;
+        self.table.prefix_value_mut(idx)
+    }
+}
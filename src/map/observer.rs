@@ -0,0 +1,150 @@
+//! Prefix map that notifies an observer about all mutations.
+
+use super::*;
+
+/// Callbacks that are invoked by an [`ObservedPrefixMap`] whenever it is modified. All methods
+/// do nothing by default.
+pub trait Observer<P, T> {
+    /// Called after `prefix` was inserted with `value`, and the prefix was not present before.
+    fn on_insert(&mut self, prefix: &P, value: &T) {
+        let _ = (prefix, value);
+    }
+
+    /// Called after the value of `prefix` was replaced from `old` to `new`.
+    fn on_overwrite(&mut self, prefix: &P, old: &T, new: &T) {
+        let _ = (prefix, old, new);
+    }
+
+    /// Called when `prefix` with value `old` is removed from the map.
+    fn on_remove(&mut self, prefix: &P, old: &T) {
+        let _ = (prefix, old);
+    }
+}
+
+/// A prefix map that notifies an [`Observer`] about every insert, overwrite, and remove. Use it to
+/// keep caches or derived indexes that are layered on top of the map consistent, without wrapping
+/// every call site that modifies the map.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use prefix_trie::map::{ObservedPrefixMap, Observer};
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// #[derive(Default)]
+/// struct Counter(usize);
+///
+/// impl<P> Observer<P, u32> for Counter {
+///     fn on_insert(&mut self, _: &P, value: &u32) {
+///         self.0 += *value as usize;
+///     }
+///     fn on_overwrite(&mut self, _: &P, old: &u32, new: &u32) {
+///         self.0 = self.0 - *old as usize + *new as usize;
+///     }
+///     fn on_remove(&mut self, _: &P, old: &u32) {
+///         self.0 -= *old as usize;
+///     }
+/// }
+///
+/// let mut pm: ObservedPrefixMap<ipnet::Ipv4Net, u32, Counter> = ObservedPrefixMap::default();
+/// pm.insert("10.0.0.0/8".parse()?, 1);
+/// pm.insert("10.1.0.0/16".parse()?, 2);
+/// pm.insert("10.0.0.0/8".parse()?, 5);
+/// assert_eq!(pm.observer().0, 7);
+/// pm.remove(&"10.1.0.0/16".parse()?);
+/// assert_eq!(pm.observer().0, 5);
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+#[derive(Clone)]
+pub struct ObservedPrefixMap<P, T, O> {
+    map: PrefixMap<P, T>,
+    observer: O,
+}
+
+impl<P: Prefix, T, O: Default> Default for ObservedPrefixMap<P, T, O> {
+    fn default() -> Self {
+        Self::new(O::default())
+    }
+}
+
+forward_map_traits!(ObservedPrefixMap[P, T, O]: Debug);
+
+impl<P, T, O> ObservedPrefixMap<P, T, O>
+where
+    P: Prefix,
+{
+    /// Create an empty prefix map that notifies `observer`.
+    pub fn new(observer: O) -> Self {
+        Self::from_parts(PrefixMap::new(), observer)
+    }
+
+    /// Create an observed map from an existing `map`. The observer is not notified about the
+    /// elements that are already present in `map`.
+    pub fn from_parts(map: PrefixMap<P, T>, observer: O) -> Self {
+        Self { map, observer }
+    }
+
+    /// Stop observing and return the underlying map and the observer.
+    pub fn into_parts(self) -> (PrefixMap<P, T>, O) {
+        (self.map, self.observer)
+    }
+
+    /// Get a reference to the observer.
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    /// Get a mutable reference to the observer.
+    pub fn observer_mut(&mut self) -> &mut O {
+        &mut self.observer
+    }
+
+    forward_map_methods!(len, is_empty, as_map, get, contains_key, get_lpm, iter);
+}
+
+impl<P, T, O> ObservedPrefixMap<P, T, O>
+where
+    P: Prefix,
+    O: Observer<P, T>,
+{
+    /// Insert a new item into the map. If the key already exists, replace the value and return
+    /// the old one. Calls [`Observer::on_insert`] or [`Observer::on_overwrite`].
+    pub fn insert(&mut self, prefix: P, value: T) -> Option<T> {
+        let key = P::from_repr_len(prefix.repr(), prefix.prefix_len());
+        let old = self.map.insert(prefix, value);
+        let (p, new) = self.map.get_key_value(&key).unwrap();
+        match &old {
+            Some(old) => self.observer.on_overwrite(p, old, new),
+            None => self.observer.on_insert(p, new),
+        }
+        old
+    }
+
+    /// Remove an element from the map and return its value. Calls [`Observer::on_remove`] if the
+    /// prefix was present.
+    pub fn remove(&mut self, prefix: &P) -> Option<T> {
+        let old = self.map.remove(prefix)?;
+        self.observer.on_remove(prefix, &old);
+        Some(old)
+    }
+
+    /// Keep only the elements for which `f` returns `true`. Calls [`Observer::on_remove`] for
+    /// each removed element.
+    pub fn retain<F: FnMut(&P, &T) -> bool>(&mut self, mut f: F) {
+        let observer = &mut self.observer;
+        self.map.retain(|p, t| {
+            let keep = f(p, t);
+            if !keep {
+                observer.on_remove(p, t);
+            }
+            keep
+        });
+    }
+
+    /// Remove all elements. Calls [`Observer::on_remove`] for each element.
+    pub fn clear(&mut self) {
+        self.retain(|_, _| false);
+    }
+}
@@ -0,0 +1,182 @@
+//! Prefix map with entries from multiple sources, resolved by priority.
+
+use super::*;
+
+/// An entry of a single source in a [`PriorityPrefixMap`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Candidate<S, T> {
+    /// The source that provided the entry.
+    pub source: S,
+    /// The priority (or administrative distance) of the entry. Lower values are preferred.
+    pub distance: u32,
+    /// The value of the entry.
+    pub value: T,
+}
+
+/// A prefix map in which each prefix can have one entry per source, each with a priority (or
+/// administrative distance, where lower values are preferred). Lookups and iteration only see the
+/// best entry of each prefix, i.e., the one with the lowest distance (with ties broken by the
+/// source). Sources can be withdrawn as a whole, which reveals the entries of the next-best
+/// sources.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use prefix_trie::map::PriorityPrefixMap;
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut pm: PriorityPrefixMap<ipnet::Ipv4Net, &str, &str> = PriorityPrefixMap::new();
+/// pm.insert("10.0.0.0/8".parse()?, "ospf", 110, "via 192.0.2.1");
+/// pm.insert("10.0.0.0/8".parse()?, "static", 1, "via 192.0.2.2");
+/// pm.insert("10.1.0.0/16".parse()?, "ospf", 110, "via 192.0.2.1");
+/// assert_eq!(pm.get(&"10.0.0.0/8".parse()?), Some(&"via 192.0.2.2"));
+///
+/// pm.withdraw_source(&"static");
+/// assert_eq!(pm.get(&"10.0.0.0/8".parse()?), Some(&"via 192.0.2.1"));
+/// assert_eq!(pm.withdraw_source(&"ospf"), 2);
+/// assert!(pm.is_empty());
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+#[derive(Clone)]
+pub struct PriorityPrefixMap<P, S, T> {
+    /// All candidates of each prefix, sorted by distance and source. There is always at least one
+    /// candidate.
+    map: PrefixMap<P, Vec<Candidate<S, T>>>,
+}
+
+impl<P: Prefix, S, T> Default for PriorityPrefixMap<P, S, T> {
+    fn default() -> Self {
+        Self {
+            map: PrefixMap::new(),
+        }
+    }
+}
+
+impl<P, S, T> std::fmt::Debug for PriorityPrefixMap<P, S, T>
+where
+    P: Prefix + std::fmt::Debug,
+    S: std::fmt::Debug,
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.map.fmt(f)
+    }
+}
+
+impl<P, S, T> PriorityPrefixMap<P, S, T>
+where
+    P: Prefix,
+{
+    /// Create an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    forward_map_methods!(contains_key);
+
+    /// Returns the number of prefixes that have an entry of at least one source.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Get the underlying map from prefixes to all their candidates (sorted by preference), e.g.,
+    /// to create views or to perform set operations.
+    pub fn as_map(&self) -> &PrefixMap<P, Vec<Candidate<S, T>>> {
+        &self.map
+    }
+
+    /// Get the value of the best entry of a prefix, by matching exactly on the prefix.
+    pub fn get(&self, prefix: &P) -> Option<&T> {
+        self.best(prefix).map(|c| &c.value)
+    }
+
+    /// Get the best entry of a prefix (including its source and distance), by matching exactly on
+    /// the prefix.
+    pub fn best(&self, prefix: &P) -> Option<&Candidate<S, T>> {
+        self.map.get(prefix).map(|c| &c[0])
+    }
+
+    /// Get all entries of a prefix, sorted by preference.
+    pub fn candidates(&self, prefix: &P) -> &[Candidate<S, T>] {
+        self.map
+            .get(prefix)
+            .map(|c| c.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Get the value of the best entry of the longest prefix match.
+    pub fn get_lpm(&self, prefix: &P) -> Option<(&P, &T)> {
+        self.map.get_lpm(prefix).map(|(p, c)| (p, &c[0].value))
+    }
+
+    /// Iterate over the best entry of each prefix in lexicographic order.
+    pub fn iter(&self) -> impl Iterator<Item = (&P, &T)> {
+        self.map.iter().map(|(p, c)| (p, &c[0].value))
+    }
+
+    /// Create a regular map that contains the value of the best entry of each prefix.
+    pub fn to_map(&self) -> PrefixMap<P, T>
+    where
+        P: Clone,
+        T: Clone,
+    {
+        self.iter().map(|(p, t)| (p.clone(), t.clone())).collect()
+    }
+}
+
+impl<P, S, T> PriorityPrefixMap<P, S, T>
+where
+    P: Prefix,
+    S: Ord,
+{
+    /// Insert the entry of `source` for `prefix`. If `source` already has an entry for that
+    /// prefix, then it is replaced, and its old value is returned.
+    pub fn insert(&mut self, prefix: P, source: S, distance: u32, value: T) -> Option<T> {
+        let candidates = self.map.entry(prefix).or_default();
+        let old = candidates
+            .iter()
+            .position(|c| c.source == source)
+            .map(|i| candidates.remove(i).value);
+        let idx = candidates.partition_point(|c| (c.distance, &c.source) < (distance, &source));
+        candidates.insert(
+            idx,
+            Candidate {
+                source,
+                distance,
+                value,
+            },
+        );
+        old
+    }
+
+    /// Remove the entry of `source` for `prefix`, and return its value. The prefix is removed once
+    /// it has no entries left.
+    pub fn remove(&mut self, prefix: &P, source: &S) -> Option<T> {
+        let candidates = self.map.get_mut(prefix)?;
+        let idx = candidates.iter().position(|c| &c.source == source)?;
+        let value = candidates.remove(idx).value;
+        if candidates.is_empty() {
+            self.map.remove(prefix);
+        }
+        Some(value)
+    }
+
+    /// Remove all entries of `source`, and return the number of removed entries.
+    pub fn withdraw_source(&mut self, source: &S) -> usize {
+        let mut num = 0;
+        for (_, candidates) in self.map.iter_mut() {
+            let len = candidates.len();
+            candidates.retain(|c| &c.source != source);
+            num += len - candidates.len();
+        }
+        self.map.retain(|_, candidates| !candidates.is_empty());
+        num
+    }
+}
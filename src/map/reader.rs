@@ -0,0 +1,90 @@
+//! Cheap read-only handle of a prefix map that can be shared between threads.
+
+use std::sync::Arc;
+
+use super::*;
+use crate::{AsView, TrieView};
+
+/// A cheap, read-only handle of a [`PrefixMap`] that can be cloned and distributed to worker
+/// threads. Cloning a reader only increments a reference count. The reader only exposes the read
+/// API, so workers cannot modify the table. Create a reader using [`PrefixMap::into_reader`].
+///
+/// The reader is [`Send`] and [`Sync`] if `P` and `T` are both [`Send`] and [`Sync`].
+///
+/// ```
+/// # use prefix_trie::*;
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+/// pm.insert("10.0.0.0/8".parse()?, 1);
+/// pm.insert("10.1.0.0/16".parse()?, 2);
+/// let reader = pm.into_reader();
+/// let handles: Vec<_> = (0..4)
+///     .map(|_| {
+///         let reader = reader.clone();
+///         std::thread::spawn(move || reader.get_lpm(&"10.1.2.0/24".parse().unwrap()).map(|(_, t)| *t))
+///     })
+///     .collect();
+/// for h in handles {
+///     assert_eq!(h.join().unwrap(), Some(2));
+/// }
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+pub struct PrefixReader<P, T> {
+    map: Arc<PrefixMap<P, T>>,
+}
+
+impl<P, T> Clone for PrefixReader<P, T> {
+    fn clone(&self) -> Self {
+        Self {
+            map: self.map.clone(),
+        }
+    }
+}
+
+forward_map_traits!(PrefixReader[P, T]: Debug);
+
+impl<P, T> From<PrefixMap<P, T>> for PrefixReader<P, T> {
+    fn from(map: PrefixMap<P, T>) -> Self {
+        Self { map: Arc::new(map) }
+    }
+}
+
+impl<P, T> From<Arc<PrefixMap<P, T>>> for PrefixReader<P, T> {
+    fn from(map: Arc<PrefixMap<P, T>>) -> Self {
+        Self { map }
+    }
+}
+
+impl<P, T> PrefixMap<P, T> {
+    /// Convert the map into a cheap, read-only handle that can be cloned and shared between
+    /// threads. See [`PrefixReader`].
+    pub fn into_reader(self) -> PrefixReader<P, T> {
+        PrefixReader::from(self)
+    }
+}
+
+impl<P, T> PrefixReader<P, T>
+where
+    P: Prefix,
+{
+    forward_map_methods!(len, is_empty, get, contains_key, get_lpm, iter, as_map);
+
+    /// Get a value of an element by using shortest prefix matching.
+    pub fn get_spm(&self, prefix: &P) -> Option<(&P, &T)> {
+        self.map.get_spm(prefix)
+    }
+
+    /// Get a view of the entire map, e.g., to perform set operations.
+    pub fn view(&self) -> TrieView<'_, P, T> {
+        self.map.view()
+    }
+
+    /// Get the underlying map if this is the only handle. Otherwise, return `self`.
+    pub fn try_into_map(self) -> Result<PrefixMap<P, T>, Self> {
+        Arc::try_unwrap(self.map).map_err(|map| Self { map })
+    }
+}
@@ -0,0 +1,179 @@
+//! Prefix map with a reverse index from values to the prefixes that use them.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::*;
+use crate::PrefixSet;
+
+/// A prefix map that maintains a reverse index from each value (or a key extracted from the
+/// value, like the next-hop of a route) to the set of prefixes that use it. The index is kept
+/// consistent on every insert and remove, such that all prefixes with a given key can be found
+/// (and removed) without scanning the entire map.
+///
+/// Use [`ReverseIndexedMap::new`] to index the values themselves, or
+/// [`ReverseIndexedMap::with_key`] to index a key extracted from each value. Values cannot be
+/// modified in place, as this could change their key; use [`ReverseIndexedMap::insert`] to
+/// replace them instead.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use prefix_trie::map::ReverseIndexedMap;
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// #[derive(Clone, Debug, PartialEq)]
+/// struct Route {
+///     next_hop: &'static str,
+///     metric: u32,
+/// }
+///
+/// let mut pm: ReverseIndexedMap<ipnet::Ipv4Net, Route, &str> =
+///     ReverseIndexedMap::with_key(|r| r.next_hop);
+/// pm.insert("10.0.0.0/8".parse()?, Route { next_hop: "192.0.2.1", metric: 10 });
+/// pm.insert("10.1.0.0/16".parse()?, Route { next_hop: "192.0.2.2", metric: 20 });
+/// pm.insert("10.2.0.0/16".parse()?, Route { next_hop: "192.0.2.1", metric: 30 });
+/// assert_eq!(pm.prefixes_with(&"192.0.2.1").map(|s| s.len()), Some(2));
+///
+/// let withdrawn = pm.remove_with(&"192.0.2.1");
+/// assert_eq!(withdrawn.len(), 2);
+/// assert_eq!(pm.len(), 1);
+/// assert_eq!(pm.prefixes_with(&"192.0.2.1"), None);
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+#[derive(Clone)]
+pub struct ReverseIndexedMap<P, T, K> {
+    map: PrefixMap<P, T>,
+    /// The prefixes of each key. There is never an empty set in the index.
+    index: HashMap<K, PrefixSet<P>>,
+    key: fn(&T) -> K,
+}
+
+forward_map_traits!(ReverseIndexedMap[P, T, K]: Debug, PartialEq);
+
+impl<P, T> ReverseIndexedMap<P, T, T>
+where
+    P: Prefix,
+    T: Clone,
+{
+    /// Create an empty prefix map that indexes the values themselves.
+    pub fn new() -> Self {
+        Self::with_key(T::clone)
+    }
+}
+
+impl<P, T> Default for ReverseIndexedMap<P, T, T>
+where
+    P: Prefix,
+    T: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P, T, K> ReverseIndexedMap<P, T, K>
+where
+    P: Prefix,
+{
+    /// Create an empty prefix map that indexes the key that `key` extracts from each value.
+    pub fn with_key(key: fn(&T) -> K) -> Self {
+        Self {
+            map: PrefixMap::new(),
+            index: HashMap::new(),
+            key,
+        }
+    }
+
+    forward_map_methods!(len, is_empty, as_map, get, contains_key, get_lpm, iter);
+
+    /// Iterate over all distinct keys of the values in the map, in arbitrary order.
+    pub fn indexed_keys(&self) -> impl Iterator<Item = &K> {
+        self.index.keys()
+    }
+
+    /// Remove all elements.
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.index.clear();
+    }
+}
+
+impl<P, T, K> ReverseIndexedMap<P, T, K>
+where
+    P: Prefix + Clone,
+    K: Hash + Eq,
+{
+    /// Get the set of all prefixes whose value has the key `key`. Returns `None` if there is no
+    /// such prefix. This operation is `O(1)`.
+    pub fn prefixes_with(&self, key: &K) -> Option<&PrefixSet<P>> {
+        self.index.get(key)
+    }
+
+    /// Insert a new item into the map. If the key already exists, replace the value and return
+    /// the old one.
+    pub fn insert(&mut self, prefix: P, value: T) -> Option<T> {
+        let key = (self.key)(&value);
+        let old = self.map.insert(prefix.clone(), value);
+        if let Some(old) = &old {
+            self.unindex(&prefix, old);
+        }
+        self.index.entry(key).or_default().insert(prefix);
+        old
+    }
+
+    /// Remove an element from the map and return its value.
+    pub fn remove(&mut self, prefix: &P) -> Option<T> {
+        let old = self.map.remove(prefix)?;
+        self.unindex(prefix, &old);
+        Some(old)
+    }
+
+    /// Remove all elements whose value has the key `key`, and return them in lexicographic order.
+    /// This only visits the removed elements.
+    pub fn remove_with(&mut self, key: &K) -> Vec<(P, T)> {
+        let Some(prefixes) = self.index.remove(key) else {
+            return Vec::new();
+        };
+        prefixes
+            .into_iter()
+            .map(|p| {
+                let value = self.map.remove(&p).unwrap();
+                (p, value)
+            })
+            .collect()
+    }
+
+    /// Remove `prefix` from the set of prefixes of the key of `value`.
+    fn unindex(&mut self, prefix: &P, value: &T) {
+        let key = (self.key)(value);
+        if let Some(prefixes) = self.index.get_mut(&key) {
+            prefixes.remove(prefix);
+            if prefixes.is_empty() {
+                self.index.remove(&key);
+            }
+        }
+    }
+}
+
+impl<P, T> FromIterator<(P, T)> for ReverseIndexedMap<P, T, T>
+where
+    P: Prefix + Clone,
+    T: Clone + Hash + Eq,
+{
+    fn from_iter<I: IntoIterator<Item = (P, T)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        iter.into_iter().for_each(|(p, t)| {
+            map.insert(p, t);
+        });
+        map
+    }
+}
+
+impl<P, T, K> From<ReverseIndexedMap<P, T, K>> for PrefixMap<P, T> {
+    fn from(map: ReverseIndexedMap<P, T, K>) -> Self {
+        map.map
+    }
+}
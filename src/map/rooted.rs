@@ -0,0 +1,408 @@
+//! Prefix maps and sets that are restricted to a universe prefix, e.g., a delegated block.
+
+use num_traits::{PrimInt, Zero};
+
+use super::*;
+use crate::{range::AddrRange, PrefixSet};
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Create an empty map that only accepts prefixes contained in `universe`. See
+    /// [`RootedPrefixMap`].
+    pub fn rooted_at(universe: P) -> RootedPrefixMap<P, T> {
+        RootedPrefixMap {
+            universe: P::from_repr_len(universe.mask(), universe.prefix_len()),
+            map: PrefixMap::new(),
+        }
+    }
+}
+
+impl<P> PrefixSet<P>
+where
+    P: Prefix,
+{
+    /// Create an empty set that only accepts prefixes contained in `universe`. See
+    /// [`RootedPrefixSet`].
+    pub fn rooted_at(universe: P) -> RootedPrefixSet<P> {
+        RootedPrefixSet {
+            universe: P::from_repr_len(universe.mask(), universe.prefix_len()),
+            set: PrefixSet::new(),
+        }
+    }
+}
+
+/// A prefix map that only contains prefixes within a universe prefix (including the universe
+/// itself), like a block that was delegated to a network. Inserting a prefix outside of the
+/// universe fails. Queries for free space (like [`RootedPrefixMap::complement`] and
+/// [`RootedPrefixMap::next_free`]) and for the coverage treat the universe as the entire address
+/// space, such that addresses outside of it never show up in their results.
+///
+/// Create the map using [`PrefixMap::rooted_at`].
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use prefix_trie::map::Coverage;
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut pm: map::RootedPrefixMap<ipnet::Ipv4Net, _> =
+///     PrefixMap::rooted_at("10.0.0.0/22".parse()?);
+/// assert_eq!(pm.insert("10.0.0.0/24".parse()?, 1), Ok(None));
+/// assert_eq!(pm.insert("10.0.2.0/24".parse()?, 2), Ok(None));
+/// assert_eq!(pm.insert("192.168.0.0/24".parse()?, 3), Err(3));
+///
+/// assert_eq!(
+///     pm.complement(),
+///     vec!["10.0.1.0/24".parse()?, "10.0.3.0/24".parse()?]
+/// );
+/// assert_eq!(pm.next_free(0x0a000000), Some(0x0a000100));
+/// assert_eq!(pm.next_free(0x0a000300), Some(0x0a000300));
+/// assert_eq!(pm.next_free(0x0a000400), None);
+/// assert_eq!(pm.coverage(), Coverage::Partial);
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+#[derive(Clone)]
+pub struct RootedPrefixMap<P, T> {
+    universe: P,
+    map: PrefixMap<P, T>,
+}
+
+forward_map_traits!(RootedPrefixMap[P, T]: Debug);
+
+impl<P, T> RootedPrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Get the universe of the map, which contains all of its prefixes.
+    pub fn universe(&self) -> &P {
+        &self.universe
+    }
+
+    forward_map_methods!(len, is_empty, as_map, get, contains_key, get_lpm, iter);
+
+    /// Get a mutable reference to the value of an element by matching exactly on the prefix.
+    pub fn get_mut(&mut self, prefix: &P) -> Option<&mut T> {
+        self.map.get_mut(prefix)
+    }
+
+    /// Insert a new item into the map. If the key already exists, replace the value and return
+    /// the old one. This operation fails if `prefix` is not contained in the universe, returning
+    /// `Err(value)`.
+    pub fn insert(&mut self, prefix: P, value: T) -> Result<Option<T>, T> {
+        if !self.universe.contains(&prefix) {
+            return Err(value);
+        }
+        Ok(self.map.insert(prefix, value))
+    }
+
+    /// Remove an element from the map and return its value.
+    pub fn remove(&mut self, prefix: &P) -> Option<T> {
+        self.map.remove(prefix)
+    }
+
+    /// Remove all elements.
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+
+    /// Get the maximal prefixes within the universe that are not covered by any entry, in
+    /// lexicographic order. Together with the entries, they cover the universe exactly.
+    pub fn complement(&self) -> Vec<P>
+    where
+        P: Clone,
+    {
+        complement(&self.map, &self.universe)
+    }
+
+    /// Find the first address at or after `addr` that is not covered by any entry, within the
+    /// universe. Returns `None` if `addr` is outside of the universe, or if all addresses from
+    /// `addr` to the end of the universe are covered. See [`PrefixMap::next_free`].
+    pub fn next_free(&self, addr: P::R) -> Option<P::R> {
+        next_free(&self.map, &self.universe, addr)
+    }
+
+    /// Find the last address at or before `addr` that is not covered by any entry, within the
+    /// universe. Returns `None` if `addr` is outside of the universe, or if all addresses from
+    /// the start of the universe to `addr` are covered. See [`PrefixMap::prev_free`].
+    pub fn prev_free(&self, addr: P::R) -> Option<P::R> {
+        prev_free(&self.map, &self.universe, addr)
+    }
+
+    /// Get the largest prefix within the universe that contains `addr`, and that neither
+    /// contains, nor is contained in any entry. Returns `None` if `addr` is outside of the
+    /// universe, or if it is covered by an entry. See [`PrefixMap::free_block`].
+    pub fn free_block(&self, addr: P::R) -> Option<P> {
+        free_block(&self.map, &self.universe, addr)
+    }
+
+    /// Get how much of the universe is covered by the entries of the map.
+    pub fn coverage(&self) -> Coverage
+    where
+        P: Clone,
+    {
+        coverage(&self.map, &self.universe)
+    }
+
+    /// Classify all subnets of the universe with prefix length `len` by how much of them is
+    /// covered by the entries of the map. See [`PrefixMap::utilization`].
+    pub fn utilization(&self, len: u8) -> SubnetUsage<P>
+    where
+        P: Clone,
+    {
+        self.map.utilization(&self.universe, len)
+    }
+}
+
+impl<P, T> PartialEq for RootedPrefixMap<P, T>
+where
+    P: Prefix + PartialEq,
+    T: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.universe == other.universe && self.map == other.map
+    }
+}
+
+impl<P, T> Eq for RootedPrefixMap<P, T>
+where
+    P: Prefix + Eq,
+    T: Eq,
+{
+}
+
+impl<P, T> From<RootedPrefixMap<P, T>> for PrefixMap<P, T> {
+    fn from(map: RootedPrefixMap<P, T>) -> Self {
+        map.map
+    }
+}
+
+/// A prefix set that only contains prefixes within a universe prefix. See [`RootedPrefixMap`].
+///
+/// Create the set using [`PrefixSet::rooted_at`].
+///
+/// ```
+/// # use prefix_trie::*;
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut set: map::RootedPrefixSet<ipnet::Ipv4Net> = PrefixSet::rooted_at("10.0.0.0/23".parse()?);
+/// assert_eq!(set.insert("10.0.0.0/24".parse()?), Ok(true));
+/// assert_eq!(set.insert("10.0.2.0/24".parse()?), Err("10.0.2.0/24".parse()?));
+/// assert_eq!(set.complement(), vec!["10.0.1.0/24".parse()?]);
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+#[derive(Clone)]
+pub struct RootedPrefixSet<P> {
+    universe: P,
+    set: PrefixSet<P>,
+}
+
+impl<P> std::fmt::Debug for RootedPrefixSet<P>
+where
+    P: Prefix + std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.set.fmt(f)
+    }
+}
+
+impl<P> RootedPrefixSet<P>
+where
+    P: Prefix,
+{
+    /// Get the universe of the set, which contains all of its prefixes.
+    pub fn universe(&self) -> &P {
+        &self.universe
+    }
+
+    /// Returns the number of elements stored in `self`.
+    pub fn len(&self) -> usize {
+        self.set.len()
+    }
+
+    /// Returns `true` if the set contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty()
+    }
+
+    /// Get a reference to the underlying set.
+    pub fn as_set(&self) -> &PrefixSet<P> {
+        &self.set
+    }
+
+    /// Check wether some prefix is present in the set, without using longest prefix match.
+    pub fn contains(&self, prefix: &P) -> bool {
+        self.set.contains(prefix)
+    }
+
+    /// Get the longest prefix in the set that contains the given prefix.
+    pub fn get_lpm<'a>(&'a self, prefix: &P) -> Option<&'a P> {
+        self.set.get_lpm(prefix)
+    }
+
+    /// Iterate over all prefixes in the set in lexicographic order.
+    pub fn iter(&self) -> crate::set::Iter<'_, P> {
+        self.set.iter()
+    }
+
+    /// Add a new prefix to the set. Returns whether the prefix was newly inserted. This operation
+    /// fails if `prefix` is not contained in the universe, returning `Err(prefix)`.
+    pub fn insert(&mut self, prefix: P) -> Result<bool, P> {
+        if !self.universe.contains(&prefix) {
+            return Err(prefix);
+        }
+        Ok(self.set.insert(prefix))
+    }
+
+    /// Remove an element from the set. Returns whether the prefix was present.
+    pub fn remove(&mut self, prefix: &P) -> bool {
+        self.set.remove(prefix)
+    }
+
+    /// Remove all elements.
+    pub fn clear(&mut self) {
+        self.set.clear()
+    }
+
+    /// Get the maximal prefixes within the universe that are not covered by any element. See
+    /// [`RootedPrefixMap::complement`].
+    pub fn complement(&self) -> Vec<P>
+    where
+        P: Clone,
+    {
+        complement(&self.set.0, &self.universe)
+    }
+
+    /// Find the first address at or after `addr` that is not covered by any element, within the
+    /// universe. See [`RootedPrefixMap::next_free`].
+    pub fn next_free(&self, addr: P::R) -> Option<P::R> {
+        next_free(&self.set.0, &self.universe, addr)
+    }
+
+    /// Find the last address at or before `addr` that is not covered by any element, within the
+    /// universe. See [`RootedPrefixMap::prev_free`].
+    pub fn prev_free(&self, addr: P::R) -> Option<P::R> {
+        prev_free(&self.set.0, &self.universe, addr)
+    }
+
+    /// Get the largest free prefix within the universe that contains `addr`. See
+    /// [`RootedPrefixMap::free_block`].
+    pub fn free_block(&self, addr: P::R) -> Option<P> {
+        free_block(&self.set.0, &self.universe, addr)
+    }
+
+    /// Get how much of the universe is covered by the elements of the set.
+    pub fn coverage(&self) -> Coverage
+    where
+        P: Clone,
+    {
+        coverage(&self.set.0, &self.universe)
+    }
+
+    /// Classify all subnets of the universe with prefix length `len` by how much of them is
+    /// covered by the elements of the set. See [`PrefixMap::utilization`].
+    pub fn utilization(&self, len: u8) -> SubnetUsage<P>
+    where
+        P: Clone,
+    {
+        self.set.utilization(&self.universe, len)
+    }
+}
+
+impl<P> PartialEq for RootedPrefixSet<P>
+where
+    P: Prefix + PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.universe == other.universe && self.set == other.set
+    }
+}
+
+impl<P> Eq for RootedPrefixSet<P> where P: Prefix + Eq {}
+
+impl<P> From<RootedPrefixSet<P>> for PrefixSet<P> {
+    fn from(set: RootedPrefixSet<P>) -> Self {
+        set.set
+    }
+}
+
+/// Check if `addr` is an address within `universe`.
+fn in_universe<P: Prefix>(universe: &P, addr: P::R) -> bool {
+    let width = P::R::zero().count_zeros() as u8;
+    universe.contains(&P::from_repr_len(addr, width))
+}
+
+fn complement<P, T>(map: &PrefixMap<P, T>, universe: &P) -> Vec<P>
+where
+    P: Prefix + Clone,
+{
+    let mut result = Vec::new();
+    let entries: Vec<&P> = map.children(universe.clone()).map(|(p, _)| p).collect();
+    uncovered(universe.clone(), &entries, &mut result);
+    result
+}
+
+/// Add the maximal prefixes within `prefix` that are not covered by any of the `entries` to
+/// `result`, given all entries contained in `prefix` (in lexicographic order).
+fn uncovered<P>(prefix: P, entries: &[&P], result: &mut Vec<P>)
+where
+    P: Prefix + Clone,
+{
+    let plen = prefix.prefix_len();
+    // since all entries are contained in `prefix`, only the first one can be `prefix` itself.
+    match entries.first() {
+        Some(first) if first.prefix_len() != plen => {
+            let left = P::from_repr_len(prefix.mask(), plen + 1);
+            let right = left.sibling().unwrap();
+            let split = entries.partition_point(|p| !p.is_bit_set(plen));
+            uncovered(left, &entries[..split], result);
+            uncovered(right, &entries[split..], result);
+        }
+        Some(_) => {}
+        None => result.push(prefix),
+    }
+}
+
+fn next_free<P: Prefix, T>(map: &PrefixMap<P, T>, universe: &P, addr: P::R) -> Option<P::R> {
+    if !in_universe(universe, addr) {
+        return None;
+    }
+    map.next_free(addr)
+        .filter(|free| *free <= AddrRange::from_prefix(universe).end())
+}
+
+fn prev_free<P: Prefix, T>(map: &PrefixMap<P, T>, universe: &P, addr: P::R) -> Option<P::R> {
+    if !in_universe(universe, addr) {
+        return None;
+    }
+    map.prev_free(addr)
+        .filter(|free| *free >= AddrRange::from_prefix(universe).start())
+}
+
+fn free_block<P: Prefix, T>(map: &PrefixMap<P, T>, universe: &P, addr: P::R) -> Option<P> {
+    if !in_universe(universe, addr) {
+        return None;
+    }
+    // all entries are within the universe, so a free block that contains the universe implies
+    // that the entire universe is free.
+    map.free_block(addr).map(|block| {
+        if block.contains(universe) {
+            P::from_repr_len(universe.mask(), universe.prefix_len())
+        } else {
+            block
+        }
+    })
+}
+
+fn coverage<P, T>(map: &PrefixMap<P, T>, universe: &P) -> Coverage
+where
+    P: Prefix + Clone,
+{
+    map.utilization(universe, universe.prefix_len()).subnets[0].1
+}
@@ -0,0 +1,56 @@
+//! Random sampling of entries, weighted by a per-entry weight.
+
+use rand::Rng;
+
+use super::*;
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Select a random entry, where each entry is chosen with a probability proportional to
+    /// `weight(prefix, value)`. Entries with weight `0` are never chosen. Returns `None` if the
+    /// weight of all entries is `0` (or if the map is empty). The sum of all weights must not
+    /// overflow a `u64`.
+    ///
+    /// This traverses all entries, calling `weight` once for each of them. To sample repeatedly
+    /// in `O(log n)`, store the weights in an [`AggregateMap`] and use
+    /// [`AggregateMap::sample_weighted`].
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, u64> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/8".parse()?, 0);
+    /// pm.insert("192.168.0.0/16".parse()?, 3);
+    /// let mut rng = rand::thread_rng();
+    /// let (p, _) = pm.sample_weighted(&mut rng, |_, popularity| *popularity).unwrap();
+    /// assert_eq!(p, &"192.168.0.0/16".parse()?);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn sample_weighted<R, F>(&self, rng: &mut R, mut weight: F) -> Option<(&P, &T)>
+    where
+        R: Rng + ?Sized,
+        F: FnMut(&P, &T) -> u64,
+    {
+        // weighted reservoir sampling: after visiting some entries, each of them is selected
+        // with a probability proportional to its weight.
+        let mut total = 0u64;
+        let mut selected = None;
+        for (p, t) in self.iter() {
+            let w = weight(p, t);
+            if w == 0 {
+                continue;
+            }
+            total += w;
+            if rng.gen_range(0..total) < w {
+                selected = Some((p, t));
+            }
+        }
+        selected
+    }
+}
@@ -0,0 +1,282 @@
+//! Concurrent prefix map that is sharded by the first bits of each prefix.
+
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use num_traits::{CheckedShr, PrimInt, ToPrimitive, Zero};
+
+use super::*;
+
+/// A concurrent prefix map that partitions the key space by the first `k` bits of each prefix into
+/// `2^k` independent shards, each protected by its own [`RwLock`]. Many threads can insert,
+/// remove, and look up prefixes at the same time, as long as they access different shards.
+///
+/// Prefixes shorter than `k` bits span multiple shards. They are stored in a separate map (with its
+/// own lock) that is accessed by all longest prefix matches that find no match in their shard.
+/// Keep such short prefixes rare (e.g., by choosing `k` at most 8 for IPv4 routing tables).
+///
+/// Operations on the whole map (e.g., [`ShardedPrefixMap::len`] or [`ShardedPrefixMap::to_map`])
+/// lock one shard after the other, so they do not observe a consistent state of the map if it is
+/// modified concurrently. To perform set operations, first collect the map into a [`PrefixMap`]
+/// using [`ShardedPrefixMap::to_map`], and then use its views.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use prefix_trie::map::ShardedPrefixMap;
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let map: ShardedPrefixMap<ipnet::Ipv4Net, u32> = ShardedPrefixMap::new(4);
+/// std::thread::scope(|s| {
+///     for i in 0..16u32 {
+///         let map = &map;
+///         s.spawn(move || map.insert(ipnet::Ipv4Net::new((i << 28).into(), 8).unwrap(), i));
+///     }
+/// });
+/// map.insert("0.0.0.0/0".parse()?, 100);
+/// assert_eq!(map.len(), 17);
+/// assert_eq!(map.get_lpm(&"16.1.0.0/16".parse()?), Some(("16.0.0.0/8".parse()?, 1)));
+/// assert_eq!(map.get_lpm(&"17.1.0.0/16".parse()?), Some(("0.0.0.0/0".parse()?, 100)));
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+pub struct ShardedPrefixMap<P, T> {
+    /// The number of bits used to select the shard.
+    bits: u8,
+    /// Prefixes that are shorter than `bits`.
+    short: RwLock<PrefixMap<P, T>>,
+    shards: Vec<RwLock<PrefixMap<P, T>>>,
+}
+
+impl<P, T> std::fmt::Debug for ShardedPrefixMap<P, T>
+where
+    P: Prefix + std::fmt::Debug,
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShardedPrefixMap")
+            .field("bits", &self.bits)
+            .field("short", &self.short)
+            .field("shards", &self.shards)
+            .finish()
+    }
+}
+
+fn read<P, T>(lock: &RwLock<PrefixMap<P, T>>) -> RwLockReadGuard<'_, PrefixMap<P, T>> {
+    lock.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn write<P, T>(lock: &RwLock<PrefixMap<P, T>>) -> RwLockWriteGuard<'_, PrefixMap<P, T>> {
+    lock.write().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl<P, T> ShardedPrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Create an empty map with `2^bits` shards.
+    ///
+    /// # Panics
+    /// Panics if `bits` is larger than 16, or larger than the number of bits of the prefix type.
+    pub fn new(bits: u8) -> Self {
+        assert!(bits <= 16, "At most 2^16 shards are supported");
+        assert!(
+            bits as u32 <= P::R::zero().count_zeros(),
+            "The prefix type has less than {bits} bits",
+        );
+        Self {
+            bits,
+            short: RwLock::new(PrefixMap::new()),
+            shards: (0..1usize << bits)
+                .map(|_| RwLock::new(PrefixMap::new()))
+                .collect(),
+        }
+    }
+
+    /// Returns the number of bits used to select the shard.
+    pub fn shard_bits(&self) -> u8 {
+        self.bits
+    }
+
+    /// Get the lock of the map that stores `prefix`.
+    fn lock_for(&self, prefix: &P) -> &RwLock<PrefixMap<P, T>> {
+        if prefix.prefix_len() < self.bits {
+            return &self.short;
+        }
+        let shift = P::R::zero().count_zeros() - self.bits as u32;
+        // `checked_shr` handles `bits == 0`, where the shift equals the number of bits.
+        let idx = prefix.mask().checked_shr(shift).unwrap_or_else(P::R::zero);
+        &self.shards[idx.to_usize().unwrap()]
+    }
+
+    /// Returns the number of elements stored in `self`.
+    pub fn len(&self) -> usize {
+        read(&self.short).len() + self.shards.iter().map(|s| read(s).len()).sum::<usize>()
+    }
+
+    /// Returns `true` if the map contains no elements.
+    pub fn is_empty(&self) -> bool {
+        read(&self.short).is_empty() && self.shards.iter().all(|s| read(s).is_empty())
+    }
+
+    /// Get a copy of the value of an element by matching exactly on the prefix.
+    pub fn get(&self, prefix: &P) -> Option<T>
+    where
+        T: Clone,
+    {
+        read(self.lock_for(prefix)).get(prefix).cloned()
+    }
+
+    /// Check if a key is present in the datastructure.
+    pub fn contains_key(&self, prefix: &P) -> bool {
+        read(self.lock_for(prefix)).contains_key(prefix)
+    }
+
+    /// Get a copy of the key and the value of an element by using longest prefix matching. This
+    /// first searches the shard of `prefix`, and only searches the prefixes shorter than the
+    /// number of shard bits if the shard contains no match.
+    pub fn get_lpm(&self, prefix: &P) -> Option<(P, T)>
+    where
+        P: Clone,
+        T: Clone,
+    {
+        let lock = self.lock_for(prefix);
+        let clone = |(p, t): (&P, &T)| (p.clone(), t.clone());
+        if !std::ptr::eq(lock, &self.short) {
+            if let Some(x) = read(lock).get_lpm(prefix).map(clone) {
+                return Some(x);
+            }
+        }
+        read(&self.short).get_lpm(prefix).map(clone)
+    }
+
+    /// Call `f` with the map that stores `prefix` while holding a read lock on it. Notice, that
+    /// this map only contains those elements that share the first bits with `prefix` (or all
+    /// elements that are shorter than the number of shard bits, if `prefix` is one of them).
+    pub fn with_shard<R>(&self, prefix: &P, f: impl FnOnce(&PrefixMap<P, T>) -> R) -> R {
+        f(&read(self.lock_for(prefix)))
+    }
+
+    /// Call `f` with the map that stores `prefix` while holding a write lock on it, e.g., to
+    /// perform multiple operations atomically. The map can only be modified through a
+    /// [`ShardMut`], which rejects prefixes that belong to a different map.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use prefix_trie::map::ShardedPrefixMap;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let map: ShardedPrefixMap<ipnet::Ipv4Net, u32> = ShardedPrefixMap::new(4);
+    /// let a: ipnet::Ipv4Net = "16.0.0.0/8".parse()?;
+    /// let b: ipnet::Ipv4Net = "16.1.0.0/16".parse()?;
+    /// let c: ipnet::Ipv4Net = "32.0.0.0/8".parse()?;
+    /// map.with_shard_mut(&a, |shard| {
+    ///     assert_eq!(shard.insert(a, 1), Ok(None));
+    ///     assert_eq!(shard.insert(b, 2), Ok(None));
+    ///     // `c` is stored in a different shard.
+    ///     assert_eq!(shard.insert(c, 3), Err(3));
+    /// });
+    /// assert_eq!(map.len(), 2);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn with_shard_mut<R>(&self, prefix: &P, f: impl FnOnce(&mut ShardMut<'_, P, T>) -> R) -> R {
+        let lock = self.lock_for(prefix);
+        f(&mut ShardMut {
+            sharded: self,
+            lock,
+            map: write(lock),
+        })
+    }
+
+    /// Insert a new item into the map. If the key already exists, replace the value and return
+    /// the old one. This only locks the shard of `prefix`.
+    pub fn insert(&self, prefix: P, value: T) -> Option<T> {
+        write(self.lock_for(&prefix)).insert(prefix, value)
+    }
+
+    /// Remove an element from the map and return its value. This only locks the shard of
+    /// `prefix`.
+    pub fn remove(&self, prefix: &P) -> Option<T> {
+        write(self.lock_for(prefix)).remove(prefix)
+    }
+
+    /// Remove all elements.
+    pub fn clear(&self) {
+        write(&self.short).clear();
+        self.shards.iter().for_each(|s| write(s).clear());
+    }
+
+    /// Collect all elements into a single [`PrefixMap`], e.g., to iterate over all elements in
+    /// lexicographic order, or to perform set operations.
+    pub fn to_map(&self) -> PrefixMap<P, T>
+    where
+        P: Clone,
+        T: Clone,
+    {
+        let mut map = read(&self.short).clone();
+        for shard in &self.shards {
+            for (p, t) in read(shard).iter() {
+                map.insert(p.clone(), t.clone());
+            }
+        }
+        map
+    }
+
+    /// Consume `self` and merge all shards into a single [`PrefixMap`].
+    pub fn into_map(self) -> PrefixMap<P, T> {
+        let mut map = self
+            .short
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner);
+        for shard in self.shards {
+            for (p, t) in shard.into_inner().unwrap_or_else(PoisonError::into_inner) {
+                map.insert(p, t);
+            }
+        }
+        map
+    }
+}
+
+/// Write access to one of the maps of a [`ShardedPrefixMap`] (see
+/// [`ShardedPrefixMap::with_shard_mut`]), while holding its write lock. All elements of that map
+/// can be read and modified, but only prefixes that belong to the same map can be inserted.
+pub struct ShardMut<'a, P, T> {
+    sharded: &'a ShardedPrefixMap<P, T>,
+    lock: &'a RwLock<PrefixMap<P, T>>,
+    map: RwLockWriteGuard<'a, PrefixMap<P, T>>,
+}
+
+impl<P, T> ShardMut<'_, P, T>
+where
+    P: Prefix,
+{
+    forward_map_methods!(len, is_empty, as_map, get, contains_key, get_lpm, iter);
+
+    /// Check if `prefix` belongs to this map, i.e., if it can be inserted.
+    pub fn belongs(&self, prefix: &P) -> bool {
+        std::ptr::eq(self.sharded.lock_for(prefix), self.lock)
+    }
+
+    /// Get a mutable reference to the value of an element by matching exactly on the prefix.
+    pub fn get_mut(&mut self, prefix: &P) -> Option<&mut T> {
+        self.map.get_mut(prefix)
+    }
+
+    /// Insert a new item into the map. If the key already exists, replace the value and return
+    /// the old one. This operation fails if `prefix` belongs to a different map (see
+    /// [`Self::belongs`]), returning `Err(value)`.
+    pub fn insert(&mut self, prefix: P, value: T) -> Result<Option<T>, T> {
+        if !self.belongs(&prefix) {
+            return Err(value);
+        }
+        Ok(self.map.insert(prefix, value))
+    }
+
+    /// Remove an element from the map and return its value.
+    pub fn remove(&mut self, prefix: &P) -> Option<T> {
+        self.map.remove(prefix)
+    }
+}
@@ -0,0 +1,231 @@
+//! Read-only prefix map that can be embedded into a binary as a `static`.
+
+use std::marker::PhantomData;
+
+use num_traits::ToPrimitive;
+
+use super::frozen::NONE;
+use super::*;
+use crate::prefix::mask_from_prefix_len;
+
+/// A node of a [`StaticPrefixMap`], with the same layout as the nodes of a [`FrozenPrefixMap`].
+/// The fields are public, such that generated code can construct the nodes in a `const`
+/// context. Use [`FrozenPrefixMap::to_static_expr`] to generate them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StaticNode<R> {
+    /// The representation of the prefix (with the host part cleared).
+    pub repr: R,
+    /// The prefix length.
+    pub len: u8,
+    /// Whether the node has a left child, which is always stored directly after the node.
+    pub has_left: bool,
+    /// Index of the right child, or `u32::MAX`.
+    pub right: u32,
+    /// Index of the value, or `u32::MAX` if the node only exists as a branching point.
+    pub value: u32,
+}
+
+/// An immutable prefix map that borrows its nodes and values, such that it can be constructed in
+/// a `const` context. This allows bundled tables (like bogon lists or special-use address
+/// registries) to be stored as a `static` in the binary, which costs nothing at startup: no
+/// allocation, no parsing, and no validation.
+///
+/// The nodes are generated from a [`FrozenPrefixMap`] using [`FrozenPrefixMap::to_static_expr`],
+/// typically in a build script that writes the expression into a file that is included using
+/// `include!`. The map does not check that the nodes describe a valid tree. Lookups on nodes that
+/// were not generated by [`FrozenPrefixMap::to_static_expr`] may return wrong results or panic.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use prefix_trie::map::*;
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// // in the build script:
+/// let mut pm: PrefixMap<ipnet::Ipv4Net, &str> = PrefixMap::new();
+/// pm.insert("10.0.0.0/8".parse()?, "private");
+/// pm.insert("127.0.0.0/8".parse()?, "loopback");
+/// let source = pm.freeze().to_static_expr(|v| format!("{v:?}"));
+/// assert!(source.starts_with("::prefix_trie::map::StaticPrefixMap::new("));
+///
+/// // the generated expression (written with `include!`):
+/// static BOGONS: StaticPrefixMap<'static, ipnet::Ipv4Net, &str> = StaticPrefixMap::new(
+///     &[
+///         StaticNode { repr: 0, len: 0, has_left: true, right: 4294967295, value: 4294967295 },
+///         StaticNode { repr: 0, len: 1, has_left: true, right: 3, value: 4294967295 },
+///         StaticNode { repr: 167772160, len: 8, has_left: false, right: 4294967295, value: 0 },
+///         StaticNode { repr: 2130706432, len: 8, has_left: false, right: 4294967295, value: 1 },
+///     ],
+///     &["private", "loopback"],
+/// );
+///
+/// assert_eq!(BOGONS.len(), 2);
+/// assert_eq!(BOGONS.get_lpm(&"127.0.0.1/32".parse()?), Some(("127.0.0.0/8".parse()?, &"loopback")));
+/// assert_eq!(BOGONS.get_lpm(&"192.168.0.1/32".parse()?), None);
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+pub struct StaticPrefixMap<'a, P: Prefix, T> {
+    nodes: &'a [StaticNode<P::R>],
+    values: &'a [T],
+    _prefix: PhantomData<fn() -> P>,
+}
+
+impl<P: Prefix, T> Clone for StaticPrefixMap<'_, P, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P: Prefix, T> Copy for StaticPrefixMap<'_, P, T> {}
+
+impl<P, T> std::fmt::Debug for StaticPrefixMap<'_, P, T>
+where
+    P: Prefix,
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, P: Prefix, T> StaticPrefixMap<'a, P, T> {
+    /// Create a map from its nodes and values, as generated by
+    /// [`FrozenPrefixMap::to_static_expr`].
+    pub const fn new(nodes: &'a [StaticNode<P::R>], values: &'a [T]) -> Self {
+        Self {
+            nodes,
+            values,
+            _prefix: PhantomData,
+        }
+    }
+
+    /// Returns the number of elements stored in `self`.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns `true` if the map contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    fn value(&self, idx: u32) -> Option<&'a T> {
+        (idx != NONE).then(|| &self.values[idx as usize])
+    }
+
+    /// Traverse the tree towards `prefix`, calling `f` for each node along the path (including the
+    /// node of `prefix` itself, if present).
+    fn traverse(&self, prefix: &P, mut f: impl FnMut(&'a StaticNode<P::R>)) {
+        let q = prefix.mask();
+        let q_len = prefix.prefix_len();
+        let nodes = self.nodes;
+        let mut idx = 0;
+        while let Some(node) = nodes.get(idx) {
+            f(node);
+            if node.len >= q_len {
+                return;
+            }
+            let next = if prefix.is_bit_set(node.len) {
+                (node.right != NONE).then_some(node.right as usize)
+            } else {
+                node.has_left.then_some(idx + 1)
+            };
+            let Some(child) = next.and_then(|next| nodes.get(next)) else {
+                return;
+            };
+            if child.len > q_len || q & mask_from_prefix_len(child.len) != child.repr {
+                return;
+            }
+            idx = next.unwrap();
+        }
+    }
+
+    /// Get the value of an element by matching exactly on the prefix.
+    pub fn get(&self, prefix: &P) -> Option<&'a T> {
+        let mut result = None;
+        self.traverse(prefix, |node| {
+            if node.len == prefix.prefix_len() && node.repr == prefix.mask() {
+                result = self.value(node.value);
+            }
+        });
+        result
+    }
+
+    /// Check if a key is present in the map.
+    pub fn contains_key(&self, prefix: &P) -> bool {
+        self.get(prefix).is_some()
+    }
+
+    /// Get the longest prefix in the map that contains `prefix`, along with its value.
+    pub fn get_lpm(&self, prefix: &P) -> Option<(P, &'a T)> {
+        let mut best = None;
+        self.traverse(prefix, |node| {
+            if node.value != NONE {
+                best = Some(node);
+            }
+        });
+        let node = best?;
+        Some((
+            P::from_repr_len(node.repr, node.len),
+            self.value(node.value)?,
+        ))
+    }
+
+    /// Iterate over all elements in lexicographic order.
+    pub fn iter(&self) -> impl Iterator<Item = (P, &'a T)> + 'a {
+        let values = self.values;
+        self.nodes.iter().filter_map(move |node| {
+            let value = values.get(node.value as usize)?;
+            Some((P::from_repr_len(node.repr, node.len), value))
+        })
+    }
+}
+
+impl<P, T> FrozenPrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// The nodes of the map in the layout of a [`StaticPrefixMap`].
+    pub(crate) fn static_nodes(&self) -> Vec<StaticNode<P::R>> {
+        self.nodes
+            .iter()
+            .map(|node| StaticNode {
+                repr: node.prefix.mask(),
+                len: node.prefix.prefix_len(),
+                has_left: node.has_left,
+                right: node.right,
+                value: node.value,
+            })
+            .collect()
+    }
+
+    /// Generate a Rust expression that constructs a [`StaticPrefixMap`] with the same elements,
+    /// e.g., in a build script, to embed the map as a `static` (see [`StaticPrefixMap`]). Each
+    /// value is turned into a Rust expression using `value`, which must be evaluable in a `const`
+    /// context. The expression refers to this crate as `::prefix_trie`, and the type of the map
+    /// (i.e., the prefix and value types) is inferred from the `static` it is assigned to.
+    pub fn to_static_expr<F>(&self, mut value: F) -> String
+    where
+        F: FnMut(&T) -> String,
+    {
+        let mut s = String::from("::prefix_trie::map::StaticPrefixMap::new(\n    &[\n");
+        for node in self.static_nodes() {
+            s.push_str(&format!(
+                "        ::prefix_trie::map::StaticNode {{ repr: {}, len: {}, has_left: {}, right: {}, value: {} }},\n",
+                node.repr.to_u128().unwrap(),
+                node.len,
+                node.has_left,
+                node.right,
+                node.value,
+            ));
+        }
+        s.push_str("    ],\n    &[\n");
+        for v in &self.values {
+            s.push_str(&format!("        {},\n", value(v)));
+        }
+        s.push_str("    ],\n)");
+        s
+    }
+}
@@ -0,0 +1,173 @@
+//! Asynchronous streams of the changes of an observed prefix map.
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use futures_core::Stream;
+
+use super::*;
+
+/// A change of a prefix map, as yielded by a [`Subscription`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Change<P, T> {
+    /// `prefix` was inserted with `value`, and the prefix was not present before.
+    Insert {
+        /// The inserted prefix.
+        prefix: P,
+        /// The inserted value.
+        value: T,
+    },
+    /// The value of `prefix` was replaced from `old` to `new`.
+    Update {
+        /// The updated prefix.
+        prefix: P,
+        /// The value before the update.
+        old: T,
+        /// The value after the update.
+        new: T,
+    },
+    /// `prefix` with value `old` was removed.
+    Remove {
+        /// The removed prefix.
+        prefix: P,
+        /// The value of the removed prefix.
+        old: T,
+    },
+}
+
+/// An [`Observer`] that sends every change to all of its subscriptions. Subscriptions that were
+/// dropped are forgotten on the next change.
+pub struct Subscribers<P, T> {
+    senders: Vec<UnboundedSender<Change<P, T>>>,
+}
+
+impl<P, T> Default for Subscribers<P, T> {
+    fn default() -> Self {
+        Self {
+            senders: Vec::new(),
+        }
+    }
+}
+
+impl<P, T> Subscribers<P, T> {
+    /// Create a new observer without any subscription.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new subscription that yields all changes from now on.
+    pub fn subscribe(&mut self) -> Subscription<P, T> {
+        let (sender, receiver) = unbounded();
+        self.senders.push(sender);
+        Subscription(receiver)
+    }
+
+    /// Returns the number of subscriptions that were not yet dropped.
+    pub fn len(&self) -> usize {
+        self.senders.iter().filter(|s| !s.is_closed()).count()
+    }
+
+    /// Returns `true` if all subscriptions were dropped.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Send `change` to all subscriptions.
+    fn send(&mut self, change: Change<P, T>)
+    where
+        P: Clone,
+        T: Clone,
+    {
+        self.senders
+            .retain(|s| s.unbounded_send(change.clone()).is_ok());
+    }
+}
+
+impl<P: Clone, T: Clone> Observer<P, T> for Subscribers<P, T> {
+    fn on_insert(&mut self, prefix: &P, value: &T) {
+        self.send(Change::Insert {
+            prefix: prefix.clone(),
+            value: value.clone(),
+        })
+    }
+
+    fn on_overwrite(&mut self, prefix: &P, old: &T, new: &T) {
+        self.send(Change::Update {
+            prefix: prefix.clone(),
+            old: old.clone(),
+            new: new.clone(),
+        })
+    }
+
+    fn on_remove(&mut self, prefix: &P, old: &T) {
+        self.send(Change::Remove {
+            prefix: prefix.clone(),
+            old: old.clone(),
+        })
+    }
+}
+
+/// A stream of all changes of a [`SubscribedPrefixMap`] (or of any [`ObservedPrefixMap`] with
+/// [`Subscribers`] as observer), created by [`ObservedPrefixMap::subscribe`]. Changes are
+/// buffered until they are consumed. The stream ends once the map is dropped.
+pub struct Subscription<P, T>(UnboundedReceiver<Change<P, T>>);
+
+impl<P, T> Subscription<P, T> {
+    /// Get the next change without waiting. Returns `None` if there is no buffered change.
+    pub fn try_recv(&mut self) -> Option<Change<P, T>> {
+        self.0.try_recv().ok()
+    }
+}
+
+impl<P, T> Stream for Subscription<P, T> {
+    type Item = Change<P, T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.0).poll_next(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+/// A prefix map whose changes can be subscribed to as an asynchronous stream.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use prefix_trie::map::{Change, SubscribedPrefixMap};
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut pm: SubscribedPrefixMap<ipnet::Ipv4Net, u32> = SubscribedPrefixMap::default();
+/// pm.insert("10.0.0.0/8".parse()?, 1);
+/// let mut changes = pm.subscribe();
+/// pm.insert("10.0.0.0/8".parse()?, 2);
+/// pm.remove(&"10.0.0.0/8".parse()?);
+/// assert_eq!(
+///     changes.try_recv(),
+///     Some(Change::Update { prefix: "10.0.0.0/8".parse()?, old: 1, new: 2 })
+/// );
+/// assert_eq!(
+///     changes.try_recv(),
+///     Some(Change::Remove { prefix: "10.0.0.0/8".parse()?, old: 2 })
+/// );
+/// assert_eq!(changes.try_recv(), None);
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+pub type SubscribedPrefixMap<P, T> = ObservedPrefixMap<P, T, Subscribers<P, T>>;
+
+impl<P, T> ObservedPrefixMap<P, T, Subscribers<P, T>>
+where
+    P: Prefix,
+{
+    /// Subscribe to all future changes of the map. See [`Subscribers::subscribe`].
+    pub fn subscribe(&mut self) -> Subscription<P, T> {
+        self.observer_mut().subscribe()
+    }
+}
@@ -0,0 +1,123 @@
+//! Value-aware summarization of a map into a smaller, forwarding-equivalent map.
+
+use num_traits::{PrimInt, Zero};
+
+use super::*;
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix + Clone,
+{
+    /// Reduce the number of entries without changing the longest prefix match of any address.
+    /// Two values are considered equivalent if `eq` returns `true`. This is a compression of
+    /// forwarding tables in the spirit of ORTC:
+    ///
+    /// - An entry whose value is equivalent to the value of its longest prefix match among its
+    ///   strict supernets is removed, as the shorter prefix produces the same result.
+    /// - Two siblings with equivalent values are replaced by their common supernet (taking the
+    ///   value of the left sibling). This is repeated up the tree.
+    /// - An entry whose two halves are both present is removed, as it never is the longest prefix
+    ///   match of any address.
+    ///
+    /// After summarizing, `get_lpm` returns an equivalent value for every host prefix (i.e., every
+    /// address), but the longest prefix match of shorter prefixes may change. The result is not
+    /// guaranteed to be the smallest possible table, as values are never assigned to prefixes
+    /// that are not covered entirely by entries of the same value.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/8".parse()?, 'a');
+    /// pm.insert("10.1.0.0/16".parse()?, 'a'); // shadowed by 10.0.0.0/8
+    /// pm.insert("192.168.0.0/24".parse()?, 'b');
+    /// pm.insert("192.168.1.0/25".parse()?, 'b');
+    /// pm.insert("192.168.1.128/25".parse()?, 'b');
+    /// pm.insert("172.16.0.0/24".parse()?, 'c'); // never matched
+    /// pm.insert("172.16.0.0/25".parse()?, 'd');
+    /// pm.insert("172.16.0.128/25".parse()?, 'e');
+    /// pm.summarize(|a, b| a == b);
+    /// assert_eq!(
+    ///     pm.into_iter().collect::<Vec<_>>(),
+    ///     vec![
+    ///         ("10.0.0.0/8".parse()?, 'a'),
+    ///         ("172.16.0.0/25".parse()?, 'd'),
+    ///         ("172.16.0.128/25".parse()?, 'e'),
+    ///         ("192.168.0.0/23".parse()?, 'b'),
+    ///     ]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn summarize<F>(&mut self, mut eq: F)
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        trace_span!("summarize", len = self.len());
+        // remove shadowed entries top-down, such that each entry is compared with the supernets
+        // that remain in the map.
+        let keys: Vec<P> = self.keys().cloned().collect();
+        let width = P::R::zero().count_zeros() as usize;
+        let mut by_len: Vec<Vec<P>> = (0..=width).map(|_| Vec::new()).collect();
+        for p in keys {
+            if self.is_shadowed(&p, &mut eq) {
+                self.remove(&p);
+            } else {
+                by_len[p.prefix_len() as usize].push(p);
+            }
+        }
+
+        // merge siblings bottom-up.
+        for len in (1..=width).rev() {
+            for p in std::mem::take(&mut by_len[len]) {
+                let (Some(sibling), Some(parent)) = (p.sibling(), p.supernet()) else {
+                    continue;
+                };
+                let (Some(a), Some(b)) = (self.get(&p), self.get(&sibling)) else {
+                    continue;
+                };
+                let equivalent = eq(a, b);
+                // both halves are present, so `parent` is never matched.
+                if self.remove(&parent).is_some() && !equivalent {
+                    // without `parent`, the halves may be shadowed by a shorter prefix.
+                    for half in [p, sibling] {
+                        if self.is_shadowed(&half, &mut eq) {
+                            self.remove(&half);
+                        }
+                    }
+                    continue;
+                }
+                if !equivalent {
+                    continue;
+                }
+                self.remove(&sibling);
+                let value = self.remove(&p).unwrap();
+                self.insert(parent.clone(), value);
+                if self.is_shadowed(&parent, &mut eq) {
+                    self.remove(&parent);
+                } else {
+                    by_len[len - 1].push(parent);
+                }
+            }
+        }
+    }
+
+    /// Check if the value of `prefix` is equivalent to the value of its longest prefix match
+    /// among its strict supernets.
+    fn is_shadowed<F>(&self, prefix: &P, eq: &mut F) -> bool
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        let Some(value) = self.get(prefix) else {
+            return false;
+        };
+        prefix
+            .supernet()
+            .and_then(|supernet| self.get_lpm(&supernet))
+            .map(|(_, parent)| eq(value, parent))
+            .unwrap_or(false)
+    }
+}
@@ -0,0 +1,205 @@
+//! Prefix map that allows multiple threads to lock and modify disjoint sub-trees concurrently.
+
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+
+use super::*;
+use crate::{AsView, TrieView, TrieViewMut};
+
+/// A prefix map that allows multiple threads to lock disjoint sub-trees and modify their values
+/// concurrently. Locking a prefix using [`SyncPrefixMap::lock`] returns a [`SubtreeGuard`] that
+/// provides a [`TrieViewMut`] rooted at that prefix. Two prefixes can be locked at the same time if
+/// neither contains the other; otherwise, `lock` blocks until the overlapping guard is dropped.
+///
+/// This is built on the same argument that makes multiple [`TrieViewMut`] on separate sub-trees
+/// safe (see [`TrieViewMut::split`]): the tree structure is never modified while the map is
+/// shared, and each value is only accessed through the single guard whose prefix contains it.
+/// Hence, guards can only modify values of existing nodes (e.g., using [`TrieViewMut::set`],
+/// [`TrieViewMut::remove`], or [`TrieViewMut::iter_mut`]). To insert new nodes, obtain exclusive
+/// access to the map using [`SyncPrefixMap::get_mut`].
+///
+/// Locking a prefix on a thread that already holds a guard of an overlapping prefix deadlocks.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use prefix_trie::map::SyncPrefixMap;
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let map: PrefixMap<ipnet::Ipv4Net, u32> = (0..16u32)
+///     .map(|i| (ipnet::Ipv4Net::new((i << 24).into(), 8).unwrap(), 0))
+///     .collect();
+/// let map = SyncPrefixMap::new(map);
+/// std::thread::scope(|s| {
+///     for i in 0..4u32 {
+///         let map = &map;
+///         s.spawn(move || {
+///             let mut guard = map.lock(ipnet::Ipv4Net::new((i << 26).into(), 6).unwrap());
+///             for (_, value) in guard.view_mut().unwrap().iter_mut() {
+///                 *value += i;
+///             }
+///         });
+///     }
+/// });
+/// let map = map.into_inner();
+/// assert_eq!(map.get(&"3.0.0.0/8".parse()?), Some(&0));
+/// assert_eq!(map.get(&"15.0.0.0/8".parse()?), Some(&3));
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+pub struct SyncPrefixMap<P, T> {
+    map: PrefixMap<P, T>,
+    /// All prefixes that are currently locked. No two of them overlap.
+    locked: Mutex<Vec<P>>,
+    released: Condvar,
+}
+
+/// A guard that holds the lock of a prefix in a [`SyncPrefixMap`]. The lock is released when the
+/// guard is dropped.
+pub struct SubtreeGuard<'a, P: Prefix, T> {
+    map: &'a SyncPrefixMap<P, T>,
+    prefix: P,
+    view: Option<TrieViewMut<'a, P, T>>,
+}
+
+impl<P, T> std::fmt::Debug for SyncPrefixMap<P, T>
+where
+    P: Prefix + std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SyncPrefixMap")
+            .field("locked", &*self.lock_list())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<P: Prefix + std::fmt::Debug, T> std::fmt::Debug for SubtreeGuard<'_, P, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SubtreeGuard").field(&self.prefix).finish()
+    }
+}
+
+impl<P: Prefix, T> Default for SyncPrefixMap<P, T> {
+    fn default() -> Self {
+        Self::new(PrefixMap::new())
+    }
+}
+
+impl<P: Prefix, T> From<PrefixMap<P, T>> for SyncPrefixMap<P, T> {
+    fn from(map: PrefixMap<P, T>) -> Self {
+        Self::new(map)
+    }
+}
+
+/// Check whether `a` contains `b` or `b` contains `a`.
+fn overlaps<P: Prefix>(a: &P, b: &P) -> bool {
+    a.contains(b) || b.contains(a)
+}
+
+impl<P: Prefix, T> SyncPrefixMap<P, T> {
+    /// Wrap `map` to allow locking disjoint sub-trees concurrently.
+    pub fn new(map: PrefixMap<P, T>) -> Self {
+        Self {
+            map,
+            locked: Mutex::new(Vec::new()),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Get exclusive access to the map, e.g., to insert new elements. This requires that no
+    /// guard is alive.
+    pub fn get_mut(&mut self) -> &mut PrefixMap<P, T> {
+        &mut self.map
+    }
+
+    /// Consume `self` and return the inner map.
+    pub fn into_inner(self) -> PrefixMap<P, T> {
+        self.map
+    }
+
+    fn lock_list(&self) -> MutexGuard<'_, Vec<P>> {
+        self.locked.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Lock the sub-tree of all prefixes contained in `prefix`, blocking the current thread until
+    /// no other guard of an overlapping prefix (i.e., one that contains `prefix` or that is
+    /// contained in `prefix`) is alive.
+    pub fn lock(&self, prefix: P) -> SubtreeGuard<'_, P, T> {
+        let mut locked = self.lock_list();
+        while locked.iter().any(|l| overlaps(l, &prefix)) {
+            locked = self
+                .released
+                .wait(locked)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        self.guard(locked, prefix)
+    }
+
+    /// Lock the sub-tree of all prefixes contained in `prefix` without blocking. Returns `None`
+    /// if a guard of an overlapping prefix is alive.
+    pub fn try_lock(&self, prefix: P) -> Option<SubtreeGuard<'_, P, T>> {
+        let locked = self.lock_list();
+        if locked.iter().any(|l| overlaps(l, &prefix)) {
+            return None;
+        }
+        Some(self.guard(locked, prefix))
+    }
+
+    /// Register `prefix` as locked and create the guard.
+    fn guard(&self, mut locked: MutexGuard<'_, Vec<P>>, prefix: P) -> SubtreeGuard<'_, P, T> {
+        locked.push(P::from_repr_len(prefix.repr(), prefix.prefix_len()));
+        drop(locked);
+        // Safety: The root view is only used to search for the first node contained in `prefix`,
+        // which only reads the tree structure. The tree structure is never modified while `self`
+        // is borrowed. The resulting view only covers nodes that are contained in `prefix`, and
+        // no other guard of an overlapping prefix exists until the guard is dropped. Hence, all
+        // views handed out by guards are located on separate sub-trees.
+        // If the root is not yet allocated, the map contains no element at all.
+        let view = (self.map.table.len() > 0)
+            .then(|| unsafe { TrieViewMut::root(&self.map.table) })
+            .and_then(|root| {
+                root.find(P::from_repr_len(prefix.repr(), prefix.prefix_len()))
+                    .ok()
+            });
+        SubtreeGuard {
+            map: self,
+            prefix,
+            view,
+        }
+    }
+}
+
+impl<'a, P: Prefix, T> SubtreeGuard<'a, P, T> {
+    /// The prefix that is locked by this guard.
+    pub fn prefix(&self) -> &P {
+        &self.prefix
+    }
+
+    /// Get an immutable view of the locked sub-tree. Returns `None` if the map contains no
+    /// element within the locked prefix.
+    pub fn view(&self) -> Option<TrieView<'_, P, T>>
+    where
+        P: Clone,
+    {
+        self.view.as_ref().map(|v| v.view())
+    }
+
+    /// Get a mutable view of the locked sub-tree. Returns `None` if the map contains no element
+    /// within the locked prefix.
+    pub fn view_mut(&mut self) -> Option<TrieViewMut<'_, P, T>> {
+        self.view.as_mut().map(|v| v.reborrow())
+    }
+}
+
+impl<P: Prefix, T> Drop for SubtreeGuard<'_, P, T> {
+    fn drop(&mut self) {
+        let mut locked = self.map.lock_list();
+        if let Some(pos) = locked.iter().position(|l| {
+            l.prefix_len() == self.prefix.prefix_len() && l.mask() == self.prefix.mask()
+        }) {
+            locked.swap_remove(pos);
+        }
+        drop(locked);
+        self.map.released.notify_all();
+    }
+}
@@ -0,0 +1,154 @@
+//! Prefix map that always contains a default value, such that every lookup succeeds.
+
+use super::*;
+
+/// A prefix map that always contains a default value at the root (the prefix of length zero),
+/// like a forwarding table with a default route. Since the root contains every prefix,
+/// [`TotalPrefixMap::get_lpm`] always finds a match, and returns it without an `Option`. The
+/// default can be replaced, but not removed.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use prefix_trie::map::TotalPrefixMap;
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut fib: TotalPrefixMap<ipnet::Ipv4Net, &str> = TotalPrefixMap::new("upstream");
+/// fib.insert("10.0.0.0/8".parse()?, "internal");
+/// assert_eq!(fib.get_lpm(&"10.1.1.1/32".parse()?).1, &"internal");
+/// assert_eq!(fib.get_lpm(&"192.168.1.1/32".parse()?).1, &"upstream");
+///
+/// // the default cannot be removed, only replaced.
+/// assert_eq!(fib.remove(&"0.0.0.0/0".parse()?), None);
+/// assert_eq!(fib.set_default("backup"), "upstream");
+/// assert_eq!(fib.get_lpm(&"192.168.1.1/32".parse()?).1, &"backup");
+/// assert_eq!(fib.len(), 1);
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+#[derive(Clone)]
+pub struct TotalPrefixMap<P, T> {
+    /// Always contains a value at the root.
+    map: PrefixMap<P, T>,
+}
+
+forward_map_traits!(TotalPrefixMap[P, T]: Debug, PartialEq);
+
+impl<P, T> TotalPrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Create a map that only contains the `default` value.
+    pub fn new(default: T) -> Self {
+        let mut map = PrefixMap::new();
+        map.set_default(default);
+        Self { map }
+    }
+
+    /// Returns the number of elements stored in `self`, excluding the default.
+    pub fn len(&self) -> usize {
+        self.map.len() - 1
+    }
+
+    /// Returns `true` if the map contains no elements apart from the default.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    forward_map_methods!(as_map, get, contains_key);
+
+    /// Get the default value.
+    pub fn get_default(&self) -> &T {
+        self.map
+            .get_default()
+            .expect("the default is always present")
+    }
+
+    /// Get a mutable reference to the default value.
+    pub fn get_default_mut(&mut self) -> &mut T {
+        self.map
+            .table
+            .value_mut(0)
+            .as_mut()
+            .expect("the default is always present")
+    }
+
+    /// Replace the default value, and return the old one.
+    pub fn set_default(&mut self, value: T) -> T {
+        self.map
+            .set_default(value)
+            .expect("the default is always present")
+    }
+
+    /// Get a mutable reference to the value of an element by matching exactly on the prefix.
+    pub fn get_mut(&mut self, prefix: &P) -> Option<&mut T> {
+        self.map.get_mut(prefix)
+    }
+
+    /// Get a value of an element by using longest prefix matching. If no other element contains
+    /// `prefix`, this returns the default.
+    pub fn get_lpm(&self, prefix: &P) -> (&P, &T) {
+        self.map
+            .get_lpm(prefix)
+            .expect("the default is always present")
+    }
+
+    /// Get a mutable reference to a value of an element by using longest prefix matching. If no
+    /// other element contains `prefix`, this returns the default.
+    pub fn get_lpm_mut(&mut self, prefix: &P) -> (&P, &mut T) {
+        self.map
+            .get_lpm_mut(prefix)
+            .expect("the default is always present")
+    }
+
+    /// Iterate over all entries in the map in lexicographic order, starting with the default.
+    pub fn iter(&self) -> Iter<'_, P, T> {
+        self.map.iter()
+    }
+
+    /// Insert a new item into the map. If the key already exists, replace the value and return
+    /// the old one. Inserting the prefix of length zero replaces the default.
+    pub fn insert(&mut self, prefix: P, value: T) -> Option<T> {
+        self.map.insert(prefix, value)
+    }
+
+    /// Remove an element from the map and return its value. The default cannot be removed, so
+    /// removing the prefix of length zero does nothing and returns `None`.
+    pub fn remove(&mut self, prefix: &P) -> Option<T> {
+        if prefix.prefix_len() == 0 {
+            return None;
+        }
+        self.map.remove(prefix)
+    }
+
+    /// Remove all elements apart from the default.
+    pub fn clear(&mut self) {
+        let default = self.map.table.value_mut(0).take();
+        self.map.clear();
+        self.map
+            .set_default(default.expect("the default is always present"));
+    }
+}
+
+impl<P, T> TryFrom<PrefixMap<P, T>> for TotalPrefixMap<P, T>
+where
+    P: Prefix,
+{
+    type Error = PrefixMap<P, T>;
+
+    /// Wrap `map`, which fails (returning `map` back) if it has no default.
+    fn try_from(map: PrefixMap<P, T>) -> Result<Self, Self::Error> {
+        if map.has_default() {
+            Ok(Self { map })
+        } else {
+            Err(map)
+        }
+    }
+}
+
+impl<P, T> From<TotalPrefixMap<P, T>> for PrefixMap<P, T> {
+    fn from(map: TotalPrefixMap<P, T>) -> Self {
+        map.map
+    }
+}
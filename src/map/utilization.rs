@@ -0,0 +1,138 @@
+//! Classification of the subnets of a prefix by how much of them is covered by a map.
+
+use num_traits::{PrimInt, Zero};
+
+use super::*;
+
+/// How much of a subnet is covered by the entries of a map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Coverage {
+    /// Every address of the subnet is covered by at least one entry.
+    Full,
+    /// Some, but not all addresses of the subnet are covered.
+    Partial,
+    /// No address of the subnet is covered.
+    Free,
+}
+
+/// The usage of all subnets of a given length, created by [`PrefixMap::utilization`] or
+/// [`crate::PrefixSet::utilization`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SubnetUsage<P> {
+    /// All subnets in ascending order, together with their coverage.
+    pub subnets: Vec<(P, Coverage)>,
+    /// Number of subnets that are fully covered.
+    pub full: usize,
+    /// Number of subnets that are partially covered.
+    pub partial: usize,
+    /// Number of subnets that are not covered at all.
+    pub free: usize,
+}
+
+impl<P> SubnetUsage<P> {
+    fn push(&mut self, prefix: P, coverage: Coverage) {
+        match coverage {
+            Coverage::Full => self.full += 1,
+            Coverage::Partial => self.partial += 1,
+            Coverage::Free => self.free += 1,
+        }
+        self.subnets.push((prefix, coverage));
+    }
+}
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix + Clone,
+{
+    /// Enumerate all subnets of `parent` with prefix length `len`, and classify each one by how
+    /// much of it is covered by the entries of the map (ignoring their values). A subnet is fully
+    /// covered if it is contained in an entry, or if it is covered entirely by more-specific
+    /// entries. The result is computed in a single traversal of the entries within `parent`.
+    ///
+    /// The result contains `2^(len - parent.prefix_len())` subnets, so `len` should not be much
+    /// longer than the prefix length of `parent`. If `len` is shorter than the prefix length of
+    /// `parent`, or longer than the number of bits in the representation, then no subnet is
+    /// returned.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use prefix_trie::map::Coverage;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/24".parse()?, 1);
+    /// pm.insert("10.0.1.0/25".parse()?, 2);
+    /// pm.insert("10.0.1.128/25".parse()?, 3);
+    /// pm.insert("10.0.2.0/26".parse()?, 4);
+    /// let usage = pm.utilization(&"10.0.0.0/22".parse()?, 24);
+    /// assert_eq!(
+    ///     usage.subnets,
+    ///     vec![
+    ///         ("10.0.0.0/24".parse()?, Coverage::Full),
+    ///         ("10.0.1.0/24".parse()?, Coverage::Full),
+    ///         ("10.0.2.0/24".parse()?, Coverage::Partial),
+    ///         ("10.0.3.0/24".parse()?, Coverage::Free),
+    ///     ]
+    /// );
+    /// assert_eq!((usage.full, usage.partial, usage.free), (2, 1, 1));
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn utilization(&self, parent: &P, len: u8) -> SubnetUsage<P> {
+        trace_span!("utilization", len = len);
+        let mut usage = SubnetUsage {
+            subnets: Vec::new(),
+            full: 0,
+            partial: 0,
+            free: 0,
+        };
+        let width = P::R::zero().count_zeros();
+        if len < parent.prefix_len() || len as u32 > width {
+            return usage;
+        }
+        let parent = P::from_repr_len(parent.mask(), parent.prefix_len());
+        if self.get_lpm(&parent).is_some() {
+            for p in parent.subnets(len) {
+                usage.push(p, Coverage::Full);
+            }
+            return usage;
+        }
+        let entries: Vec<&P> = self.children(parent.clone()).map(|(p, _)| p).collect();
+        classify(parent, &entries, len, &mut usage);
+        usage
+    }
+}
+
+/// Compute the coverage of `prefix`, given all `entries` contained in it (in lexicographic
+/// order), and add all subnets of length `len` within `prefix` to `usage`.
+fn classify<P>(prefix: P, entries: &[&P], len: u8, usage: &mut SubnetUsage<P>) -> Coverage
+where
+    P: Prefix + Clone,
+{
+    let plen = prefix.prefix_len();
+    // since all entries are contained in `prefix`, only the first one can be `prefix` itself.
+    let coverage = match entries.first() {
+        Some(first) if first.prefix_len() != plen => {
+            // since entries are strictly contained in `prefix`, it can be split further.
+            let left = P::from_repr_len(prefix.mask(), plen + 1);
+            let right = left.sibling().unwrap();
+            let split = entries.partition_point(|p| !p.is_bit_set(plen));
+            let l = classify(left, &entries[..split], len, usage);
+            let r = classify(right, &entries[split..], len, usage);
+            let coverage = if l == r { l } else { Coverage::Partial };
+            if plen == len {
+                usage.push(prefix, coverage);
+            }
+            return coverage;
+        }
+        Some(_) => Coverage::Full,
+        None => Coverage::Free,
+    };
+    // all subnets of `prefix` have the same coverage (empty if `plen` is longer than `len`).
+    for p in prefix.subnets(len) {
+        usage.push(p, coverage);
+    }
+    coverage
+}
@@ -0,0 +1,253 @@
+//! Import BGP routing tables from MRT `TABLE_DUMP_V2` files (RFC 6396).
+//!
+//! MRT files (as published by RouteViews or RIPE RIS) store a snapshot of the routing table of a
+//! collector. [`load_rib`] streams such a file directly into an [`IpPrefixMap`], without first
+//! collecting all routes into an intermediate vector. The BGP path attributes are not decoded;
+//! they are passed to the user-supplied closure as raw bytes.
+//!
+//! Only the unicast RIBs of `TABLE_DUMP_V2` records are considered (including the `ADDPATH`
+//! variants of RFC 8050). All other records (including the multicast RIBs, whose prefixes would
+//! otherwise overwrite the unicast routes) are skipped. Compressed files must be decompressed by
+//! the caller (e.g., by wrapping the reader into a decoder).
+
+use std::io::{Error, ErrorKind, Read, Result};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use ipnet::{IpNet, Ipv4Net, Ipv6Net};
+
+use crate::ip::IpPrefixMap;
+
+/// MRT type of `TABLE_DUMP_V2` records.
+const TABLE_DUMP_V2: u16 = 13;
+const PEER_INDEX_TABLE: u16 = 1;
+const RIB_IPV4_UNICAST: u16 = 2;
+const RIB_IPV6_UNICAST: u16 = 4;
+const RIB_IPV4_UNICAST_ADDPATH: u16 = 8;
+const RIB_IPV6_UNICAST_ADDPATH: u16 = 10;
+
+/// A peer of the collector, as listed in the `PEER_INDEX_TABLE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Peer {
+    /// The BGP identifier of the peer.
+    pub bgp_id: u32,
+    /// The address of the peer.
+    pub addr: IpAddr,
+    /// The AS number of the peer.
+    pub asn: u32,
+}
+
+/// A single route for a prefix, as learned from one peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RibEntry<'a> {
+    /// The peer from which the route was learned (if it is listed in the `PEER_INDEX_TABLE`).
+    pub peer: Option<&'a Peer>,
+    /// The time at which the route was received (in seconds since the UNIX epoch).
+    pub originated_time: u32,
+    /// The path identifier (only present in `ADDPATH` records).
+    pub path_id: Option<u32>,
+    /// The raw BGP path attributes.
+    pub attributes: &'a [u8],
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+/// Cursor over the body of a record that fails on truncated data.
+struct Cursor<'a>(&'a [u8]);
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        if self.0.len() < n {
+            return Err(invalid("truncated MRT record"));
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn ip(&mut self, v6: bool) -> Result<IpAddr> {
+        Ok(if v6 {
+            IpAddr::V6(Ipv6Addr::from(
+                <[u8; 16]>::try_from(self.take(16)?).unwrap(),
+            ))
+        } else {
+            IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(self.take(4)?).unwrap()))
+        })
+    }
+
+    /// Read a prefix, encoded as its length followed by the minimal number of address bytes.
+    fn prefix(&mut self, v6: bool) -> Result<IpNet> {
+        let len = self.u8()?;
+        let bytes = self.take((len as usize + 7) / 8)?;
+        let prefix = if v6 {
+            let mut addr = [0; 16];
+            addr.get_mut(..bytes.len())
+                .ok_or_else(|| invalid("invalid prefix length"))?
+                .copy_from_slice(bytes);
+            Ipv6Net::new(addr.into(), len).map(IpNet::V6)
+        } else {
+            let mut addr = [0; 4];
+            addr.get_mut(..bytes.len())
+                .ok_or_else(|| invalid("invalid prefix length"))?
+                .copy_from_slice(bytes);
+            Ipv4Net::new(addr.into(), len).map(IpNet::V4)
+        };
+        prefix
+            .map(|p| p.trunc())
+            .map_err(|_| invalid("invalid prefix length"))
+    }
+}
+
+/// Parse the `PEER_INDEX_TABLE`.
+fn parse_peers(mut c: Cursor<'_>) -> Result<Vec<Peer>> {
+    c.u32()?; // collector BGP ID
+    let name_len = c.u16()?;
+    c.take(name_len as usize)?;
+    let count = c.u16()?;
+    (0..count)
+        .map(|_| {
+            let peer_type = c.u8()?;
+            let bgp_id = c.u32()?;
+            let addr = c.ip(peer_type & 1 != 0)?;
+            let asn = if peer_type & 2 != 0 {
+                c.u32()?
+            } else {
+                c.u16()? as u32
+            };
+            Ok(Peer { bgp_id, addr, asn })
+        })
+        .collect()
+}
+
+/// Read all routes from an MRT `TABLE_DUMP_V2` file, and insert them into `map`. For each prefix,
+/// `f` is called with the prefix and all its RIB entries (i.e., the routes from all peers). If
+/// `f` returns `Some(value)`, then the prefix is inserted into `map` with that value (replacing
+/// any previous value). Returns the number of distinct prefixes that were inserted, i.e., that were
+/// not yet present in `map`. Multicast RIBs are skipped.
+///
+/// The reader is not buffered; wrap it into a [`std::io::BufReader`] when reading from a file.
+/// Returns an error of kind [`ErrorKind::InvalidData`] if the file is malformed.
+///
+/// ```
+/// # use prefix_trie::ip::IpPrefixMap;
+/// # use prefix_trie::mrt::load_rib;
+/// # fn main() -> std::io::Result<()> {
+/// // a PEER_INDEX_TABLE with a single IPv4 peer in AS 65001
+/// let mut file: Vec<u8> = vec![0, 0, 0, 0, 0, 13, 0, 1, 0, 0, 0, 19];
+/// file.extend([10, 0, 0, 1, 0, 0, 0, 1, 0, 1, 1, 1, 1, 192, 0, 2, 1, 0xfd, 0xe9]);
+/// // a RIB_IPV4_UNICAST record for 10.0.0.0/8 with one entry and 4 bytes of attributes
+/// file.extend([0, 0, 0, 0, 0, 13, 0, 2, 0, 0, 0, 20]);
+/// let rib = [0, 0, 0, 0, 8, 10, 0, 1, 0, 0, 0, 0, 0, 0, 0, 4, 0x40, 1, 1, 0];
+/// file.extend(rib);
+/// // the same record as RIB_IPV4_MULTICAST (which is skipped), and again as RIB_IPV4_UNICAST
+/// file.extend([0, 0, 0, 0, 0, 13, 0, 3, 0, 0, 0, 20]);
+/// file.extend(rib);
+/// file.extend([0, 0, 0, 0, 0, 13, 0, 2, 0, 0, 0, 20]);
+/// file.extend(rib);
+///
+/// let mut map = IpPrefixMap::new();
+/// let n = load_rib(file.as_slice(), &mut map, |_, entries| {
+///     Some(entries.iter().filter_map(|e| e.peer).map(|p| p.asn).collect::<Vec<_>>())
+/// })?;
+/// assert_eq!(n, 1);
+/// assert_eq!(map.get(&"10.0.0.0/8".parse().unwrap()), Some(&vec![65001]));
+///
+/// // a header that announces a body of 4 GiB, but the file ends
+/// let truncated = [0, 0, 0, 0, 0, 13, 0, 2, 0xff, 0xff, 0xff, 0xff, 0];
+/// let err = load_rib(&truncated[..], &mut map, |_, _| Some(vec![])).unwrap_err();
+/// assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+/// # Ok(())
+/// # }
+/// ```
+pub fn load_rib<R, V, F>(mut reader: R, map: &mut IpPrefixMap<V>, mut f: F) -> Result<usize>
+where
+    R: Read,
+    F: FnMut(&IpNet, &[RibEntry<'_>]) -> Option<V>,
+{
+    let mut peers = Vec::new();
+    let mut body = Vec::new();
+    let mut count = 0;
+    loop {
+        let mut header = [0; 12];
+        // stop at the end of the file, but only if it does not end within a header.
+        let n = read_full(&mut reader, &mut header)?;
+        if n == 0 {
+            break;
+        } else if n < header.len() {
+            return Err(invalid("truncated MRT header"));
+        }
+        let mrt_type = u16::from_be_bytes([header[4], header[5]]);
+        let subtype = u16::from_be_bytes([header[6], header[7]]);
+        let len = u32::from_be_bytes([header[8], header[9], header[10], header[11]]);
+        // read the body incrementally, such that a corrupt length cannot allocate gigabytes.
+        body.clear();
+        reader.by_ref().take(len as u64).read_to_end(&mut body)?;
+        if body.len() != len as usize {
+            return Err(invalid("truncated MRT record"));
+        }
+        if mrt_type != TABLE_DUMP_V2 {
+            continue;
+        }
+        let (v6, addpath) = match subtype {
+            PEER_INDEX_TABLE => {
+                peers = parse_peers(Cursor(&body))?;
+                continue;
+            }
+            RIB_IPV4_UNICAST => (false, false),
+            RIB_IPV6_UNICAST => (true, false),
+            RIB_IPV4_UNICAST_ADDPATH => (false, true),
+            RIB_IPV6_UNICAST_ADDPATH => (true, true),
+            _ => continue,
+        };
+        let mut c = Cursor(&body);
+        c.u32()?; // sequence number
+        let prefix = c.prefix(v6)?;
+        let num_entries = c.u16()?;
+        let mut entries = Vec::with_capacity(num_entries as usize);
+        for _ in 0..num_entries {
+            let peer_index = c.u16()?;
+            let originated_time = c.u32()?;
+            let path_id = if addpath { Some(c.u32()?) } else { None };
+            let attr_len = c.u16()?;
+            entries.push(RibEntry {
+                peer: peers.get(peer_index as usize),
+                originated_time,
+                path_id,
+                attributes: c.take(attr_len as usize)?,
+            });
+        }
+        if let Some(value) = f(&prefix, &entries) {
+            if map.insert(prefix, value).is_none() {
+                count += 1;
+            }
+        }
+    }
+    Ok(count)
+}
+
+/// Read until `buf` is full or the reader reaches the end. Returns the number of bytes read.
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize> {
+    let mut n = 0;
+    while n < buf.len() {
+        match reader.read(&mut buf[n..]) {
+            Ok(0) => break,
+            Ok(k) => n += k,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(n)
+}
@@ -6,9 +6,29 @@ use cidr::{Ipv4Cidr, Ipv6Cidr};
 use ipnet::{Ipv4Net, Ipv6Net};
 #[cfg(feature = "ipnetwork")]
 use ipnetwork::{Ipv4Network, Ipv6Network};
-use num_traits::{CheckedShr, PrimInt, Unsigned, Zero};
+use std::{
+    marker::PhantomData,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+use num_traits::{CheckedShr, NumCast, One, PrimInt, Unsigned, Zero};
 
 /// Trait for defining prefixes.
+///
+/// The trie creates new branch nodes by computing the longest common prefix of two keys (see
+/// [`Prefix::longest_common_prefix`] and [`Prefix::from_repr_len`]). Therefore, a type can only
+/// implement `Prefix` if it can represent prefixes of *any* length between `0` and the number of
+/// bits in `Self::R`. This is why bare addresses like [`std::net::Ipv4Addr`] cannot be used as
+/// keys. To store host routes, use a prefix type with the maximum length instead, e.g.,
+/// `Ipv4Net` with a prefix length of 32, or `(u32, 32)`.
+///
+/// Further, each prefix must be representable by a primitive unsigned integer (`Self::R`), as the
+/// trie relies on fast bit-wise operations on that representation, and the serialized and
+/// lookup-optimized forms of a map (like [`crate::map::FrozenPrefixMap`] or
+/// [`crate::stride::StrideMap`]) store it with a fixed width. Keys of dynamic width (e.g., backed
+/// by `Vec<u8>` or a `bitvec::BitVec`) are therefore not supported, and neither are keys wider
+/// than 128 bits, such as NSAP addresses (up to 160 bits). Bitstrings of at most 128 bits (e.g.,
+/// hash prefixes) can be converted into a prefix using [`Bits::from_be_slice`].
 pub trait Prefix: Sized + std::fmt::Debug {
     /// How can the prefix be represented. This must be one of `u8`, `u16`, `u32`, `u64`, or `u128`.
     type R: Unsigned + PrimInt + Zero + CheckedShr;
@@ -70,6 +90,115 @@ pub trait Prefix: Sized + std::fmt::Debug {
     fn eq(&self, other: &Self) -> bool {
         self.mask() == other.mask() && self.prefix_len() == other.prefix_len()
     }
+
+    /// Get the prefix that is one bit shorter and contains `self`. Returns `None` if the prefix
+    /// length of `self` is zero.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// let p: (u32, u8) = (0x0a010000, 16);
+    /// assert_eq!(Prefix::supernet(&p), Some((0x0a000000, 15)));
+    /// assert_eq!(Prefix::supernet(&(0u32, 0)), None);
+    /// ```
+    fn supernet(&self) -> Option<Self> {
+        let len = self.prefix_len().checked_sub(1)?;
+        Some(Self::from_repr_len(
+            self.mask() & mask_from_prefix_len(len),
+            len,
+        ))
+    }
+
+    /// Get the other half of [`Prefix::supernet`], i.e., the prefix with the same length that
+    /// only differs in the last bit. Returns `None` if the prefix length of `self` is zero.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// let p: (u32, u8) = (0x0a010000, 16);
+    /// assert_eq!(Prefix::sibling(&p), Some((0x0a000000, 16)));
+    /// assert_eq!(Prefix::sibling(&(0u32, 0)), None);
+    /// ```
+    fn sibling(&self) -> Option<Self> {
+        let len = self.prefix_len();
+        if len == 0 {
+            return None;
+        }
+        let bit = mask_from_prefix_len::<Self::R>(len) ^ mask_from_prefix_len(len - 1);
+        Some(Self::from_repr_len(self.mask() ^ bit, len))
+    }
+
+    /// Check if the prefix length is equal to the number of bits in the representation, i.e., if
+    /// `self` contains a single address.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// assert!(Prefix::is_host(&(0x0a000001u32, 32)));
+    /// assert!(!Prefix::is_host(&(0x0a000000u32, 24)));
+    /// ```
+    fn is_host(&self) -> bool {
+        self.prefix_len() as u32 == Self::R::zero().count_zeros()
+    }
+
+    /// Iterate over all subnets of `self` with prefix length `new_len`, in ascending order. The
+    /// iterator is empty if `new_len` is smaller than the prefix length of `self`, or larger than
+    /// the number of bits in the representation.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// let p: (u8, u8) = (0b1010_0000, 3);
+    /// assert_eq!(
+    ///     Prefix::subnets(&p, 5).collect::<Vec<_>>(),
+    ///     vec![(0b1010_0000, 5), (0b1010_1000, 5), (0b1011_0000, 5), (0b1011_1000, 5)],
+    /// );
+    /// assert_eq!(Prefix::subnets(&p, 2).count(), 0);
+    /// ```
+    fn subnets(&self, new_len: u8) -> Subnets<Self> {
+        subnets(self, new_len, Self::R::zero().count_zeros() as u8)
+    }
+}
+
+/// Implementation of [`Prefix::subnets`] for prefixes whose length is at most `max_len`, which
+/// may be smaller than the number of bits in the representation (like for `MacPrefix`).
+pub(crate) fn subnets<P: Prefix>(prefix: &P, new_len: u8, max_len: u8) -> Subnets<P> {
+    if new_len < prefix.prefix_len() || new_len > max_len {
+        return Subnets {
+            next: None,
+            last: P::R::zero(),
+            len: new_len,
+            _prefix: PhantomData,
+        };
+    }
+    let first = prefix.mask();
+    let last = first
+        | (!mask_from_prefix_len::<P::R>(prefix.prefix_len()) & mask_from_prefix_len(new_len));
+    Subnets {
+        next: Some(first),
+        last,
+        len: new_len,
+        _prefix: PhantomData,
+    }
+}
+
+/// Iterator over all subnets of a prefix with a given length, created by [`Prefix::subnets`].
+pub struct Subnets<P: Prefix> {
+    next: Option<P::R>,
+    last: P::R,
+    len: u8,
+    _prefix: PhantomData<P>,
+}
+
+impl<P: Prefix> Iterator for Subnets<P> {
+    type Item = P;
+
+    fn next(&mut self) -> Option<P> {
+        let repr = self.next?;
+        self.next = if repr == self.last {
+            None
+        } else {
+            let width = P::R::zero().count_zeros();
+            Some(repr + (P::R::one() << (width - self.len as u32) as usize))
+        };
+        Some(P::from_repr_len(repr, self.len))
+    }
 }
 
 pub(crate) fn mask_from_prefix_len<R>(len: u8) -> R
@@ -85,6 +214,68 @@ where
     }
 }
 
+/// Branchless implementations of the hot operations of [`Prefix`] for 128-bit representations
+/// (i.e., IPv6). The generic implementations branch on the prefix length to avoid shift overflows
+/// (see [`mask_from_prefix_len`]), which is noticeably slower on `u128`, as each shift is emulated
+/// with two 64-bit registers. Implementations of `Prefix` with `R = u128` override the generic
+/// methods using [`u128_fast_paths`].
+pub(crate) mod fast_u128 {
+    /// Mask with the first `len` bits set (`len <= 128`). Each shift is by at most 64 bits, such
+    /// that it never overflows.
+    #[inline(always)]
+    pub(crate) fn mask(len: u8) -> u128 {
+        let len = len as u32;
+        !(u128::MAX >> (len / 2) >> (len - len / 2))
+    }
+
+    /// Check if the prefix `(a, a_len)` contains `(b, b_len)`.
+    #[inline(always)]
+    pub(crate) fn contains(a: u128, a_len: u8, b: u128, b_len: u8) -> bool {
+        (a_len <= b_len) & ((a ^ b) & mask(a_len) == 0)
+    }
+
+    /// Length of the longest common prefix of `(a, a_len)` and `(b, b_len)`.
+    #[inline(always)]
+    pub(crate) fn lcp_len(a: u128, a_len: u8, b: u128, b_len: u8) -> u8 {
+        ((a ^ b).leading_zeros() as u8).min(a_len).min(b_len)
+    }
+
+    /// Check if bit `bit` (counted from the left) of the prefix `(repr, len)` is set. Bits beyond
+    /// the prefix length are never set.
+    #[inline(always)]
+    pub(crate) fn is_bit_set(repr: u128, len: u8, bit: u8) -> bool {
+        (bit < len) & ((repr << bit.min(127)) >> 127 == 1)
+    }
+}
+
+/// Override `contains`, `longest_common_prefix`, and `is_bit_set` of a `Prefix` implementation
+/// with `R = u128` by the functions in [`fast_u128`].
+macro_rules! u128_fast_paths {
+    () => {
+        #[inline(always)]
+        fn longest_common_prefix(&self, other: &Self) -> Self {
+            let a = self.repr();
+            let len = fast_u128::lcp_len(a, self.prefix_len(), other.repr(), other.prefix_len());
+            Self::from_repr_len(a & fast_u128::mask(len), len)
+        }
+
+        #[inline(always)]
+        fn contains(&self, other: &Self) -> bool {
+            fast_u128::contains(
+                self.repr(),
+                self.prefix_len(),
+                other.repr(),
+                other.prefix_len(),
+            )
+        }
+
+        #[inline(always)]
+        fn is_bit_set(&self, bit: u8) -> bool {
+            fast_u128::is_bit_set(self.repr(), self.prefix_len(), bit)
+        }
+    };
+}
+
 #[cfg(feature = "ipnet")]
 impl Prefix for Ipv4Net {
     type R = u32;
@@ -148,21 +339,23 @@ impl Prefix for Ipv6Net {
         Default::default()
     }
 
-    fn longest_common_prefix(&self, other: &Self) -> Self {
-        let a = self.repr();
-        let b = other.repr();
-        let len = ((a ^ b).leading_zeros() as u8)
-            .min(self.prefix_len())
-            .min(other.prefix_len());
-        let repr = a & mask_from_prefix_len::<u128>(len);
-        Ipv6Net::new(repr.into(), len).unwrap()
-    }
-
-    fn contains(&self, other: &Self) -> bool {
-        self.contains(other)
-    }
+    u128_fast_paths!();
 }
 
+/// Use `ipnetwork::Ipv4Network` as a key. This requires the feature `ipnetwork`.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # #[cfg(feature = "ipnetwork")]
+/// # {
+/// use ipnetwork::Ipv4Network;
+/// let mut pm: PrefixMap<Ipv4Network, _> = PrefixMap::new();
+/// pm.insert("192.168.0.0/16".parse().unwrap(), 1);
+/// pm.insert("192.168.1.0/24".parse().unwrap(), 2);
+/// let lpm = pm.get_lpm(&"192.168.1.1/32".parse().unwrap());
+/// assert_eq!(lpm, Some((&"192.168.1.0/24".parse().unwrap(), &2)));
+/// # }
+/// ```
 #[cfg(feature = "ipnetwork")]
 impl Prefix for Ipv4Network {
     type R = u32;
@@ -184,6 +377,7 @@ impl Prefix for Ipv4Network {
     }
 }
 
+/// Use `ipnetwork::Ipv6Network` as a key. This requires the feature `ipnetwork`.
 #[cfg(feature = "ipnetwork")]
 impl Prefix for Ipv6Network {
     type R = u128;
@@ -203,8 +397,24 @@ impl Prefix for Ipv6Network {
     fn mask(&self) -> u128 {
         self.network().into()
     }
+
+    u128_fast_paths!();
 }
 
+/// Use `cidr::Ipv4Cidr` as a key. This requires the feature `cidr`.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # #[cfg(feature = "cidr")]
+/// # {
+/// use cidr::Ipv4Cidr;
+/// let mut pm: PrefixMap<Ipv4Cidr, _> = PrefixMap::new();
+/// pm.insert("192.168.0.0/16".parse().unwrap(), 1);
+/// pm.insert("192.168.1.0/24".parse().unwrap(), 2);
+/// let lpm = pm.get_lpm(&"192.168.1.1/32".parse().unwrap());
+/// assert_eq!(lpm, Some((&"192.168.1.0/24".parse().unwrap(), &2)));
+/// # }
+/// ```
 #[cfg(feature = "cidr")]
 impl Prefix for Ipv4Cidr {
     type R = u32;
@@ -231,6 +441,7 @@ impl Prefix for Ipv4Cidr {
     }
 }
 
+/// Use `cidr::Ipv6Cidr` as a key. This requires the feature `cidr`.
 #[cfg(feature = "cidr")]
 impl Prefix for Ipv6Cidr {
     type R = u128;
@@ -255,15 +466,38 @@ impl Prefix for Ipv6Cidr {
     fn eq(&self, other: &Self) -> bool {
         self == other
     }
+
+    u128_fast_paths!();
 }
 
-impl<R> Prefix for (R, u8)
-where
-    R: Unsigned + PrimInt + Zero + CheckedShr + std::fmt::Debug,
-{
-    type R = R;
+macro_rules! impl_prefix_for_tuple {
+    ($($r:ty),*) => {
+        $(
+            impl Prefix for ($r, u8) {
+                type R = $r;
+
+                fn repr(&self) -> $r {
+                    self.0
+                }
+
+                fn prefix_len(&self) -> u8 {
+                    self.1
+                }
 
-    fn repr(&self) -> R {
+                fn from_repr_len(repr: $r, len: u8) -> Self {
+                    (repr, len)
+                }
+            }
+        )*
+    };
+}
+
+impl_prefix_for_tuple!(u8, u16, u32, u64, usize);
+
+impl Prefix for (u128, u8) {
+    type R = u128;
+
+    fn repr(&self) -> u128 {
         self.0
     }
 
@@ -271,9 +505,447 @@ where
         self.1
     }
 
-    fn from_repr_len(repr: R, len: u8) -> Self {
+    fn from_repr_len(repr: u128, len: u8) -> Self {
         (repr, len)
     }
+
+    fn mask(&self) -> u128 {
+        self.0 & fast_u128::mask(self.1)
+    }
+
+    u128_fast_paths!();
+}
+
+/// Prefix consisting of a standard-library IPv4 address and a prefix length. Host bits are
+/// ignored (masked) when comparing or looking up prefixes, but they are preserved when storing the
+/// prefix, exactly like `(u32, u8)`.
+///
+/// ```
+/// # use prefix_trie::*;
+/// use std::net::Ipv4Addr;
+/// let mut pm: PrefixMap<(Ipv4Addr, u8), _> = PrefixMap::new();
+/// pm.insert((Ipv4Addr::new(192, 168, 0, 0), 16), 1);
+/// pm.insert((Ipv4Addr::new(192, 168, 1, 0), 24), 2);
+/// let host = (Ipv4Addr::new(192, 168, 2, 1), 32);
+/// assert_eq!(pm.get_lpm(&host), Some((&(Ipv4Addr::new(192, 168, 0, 0), 16), &1)));
+/// assert_eq!(pm.get(&(Ipv4Addr::new(192, 168, 1, 25), 24)), Some(&2));
+/// ```
+impl Prefix for (Ipv4Addr, u8) {
+    type R = u32;
+
+    fn repr(&self) -> u32 {
+        self.0.into()
+    }
+
+    fn prefix_len(&self) -> u8 {
+        self.1
+    }
+
+    fn from_repr_len(repr: u32, len: u8) -> Self {
+        (repr.into(), len)
+    }
+}
+
+/// Prefix consisting of a standard-library IPv6 address and a prefix length. Host bits are
+/// ignored (masked) when comparing or looking up prefixes, but they are preserved when storing the
+/// prefix, exactly like `(u128, u8)`.
+///
+/// ```
+/// # use prefix_trie::*;
+/// use std::net::Ipv6Addr;
+/// let mut pm: PrefixMap<(Ipv6Addr, u8), _> = PrefixMap::new();
+/// pm.insert(("2001:db8::".parse().unwrap(), 32), 1);
+/// let host = ("2001:db8::1".parse().unwrap(), 128);
+/// assert_eq!(pm.get_lpm(&host).map(|(_, v)| *v), Some(1));
+/// ```
+impl Prefix for (Ipv6Addr, u8) {
+    type R = u128;
+
+    fn repr(&self) -> u128 {
+        self.0.into()
+    }
+
+    fn prefix_len(&self) -> u8 {
+        self.1
+    }
+
+    fn from_repr_len(repr: u128, len: u8) -> Self {
+        (repr.into(), len)
+    }
+
+    fn mask(&self) -> u128 {
+        self.repr() & fast_u128::mask(self.1)
+    }
+
+    u128_fast_paths!();
+}
+
+/// A prefix of an arbitrary unsigned integer, consisting of the bits and a prefix length. Use this
+/// type to store hierarchical keys that are not IP addresses (like MPLS label spaces or
+/// hierarchical identifiers). As opposed to the tuple `(R, u8)`, `Bits` always masks the host part,
+/// i.e., all bits beyond the prefix length are set to zero.
+///
+/// ```
+/// # use prefix_trie::*;
+/// let mut pm: PrefixMap<Bits<u16>, &str> = PrefixMap::new();
+/// pm.insert(Bits::new(0x1200, 8), "region");
+/// pm.insert(Bits::new(0x1234, 16), "device");
+/// assert_eq!(Bits::new(0x12ffu16, 8), Bits::new(0x1200, 8));
+/// assert_eq!(pm.get_lpm(&Bits::new(0x1235, 16)), Some((&Bits::new(0x1200, 8), &"region")));
+/// assert_eq!(pm.get_lpm(&Bits::new(0x1234, 16)), Some((&Bits::new(0x1234, 16), &"device")));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Bits<I> {
+    bits: I,
+    len: u8,
+}
+
+impl<I> Bits<I>
+where
+    I: Unsigned + PrimInt + Zero + CheckedShr,
+{
+    /// Create a new prefix from `bits` and the prefix length `len`. All bits beyond `len` are set
+    /// to zero.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `len` is larger than the number of bits in `I`.
+    pub fn new(bits: I, len: u8) -> Self {
+        assert!(
+            len as u32 <= I::zero().count_zeros(),
+            "Prefix length {len} is too large"
+        );
+        Self {
+            bits: bits & mask_from_prefix_len(len),
+            len,
+        }
+    }
+
+    /// Create a new prefix from a big-endian byte slice, using the first `len` bits of `bytes`.
+    /// Missing bytes are treated as zero, and bits beyond `len` are ignored. This allows
+    /// variable-length bitstrings (such as hash prefixes) to be used as keys, as long as they are
+    /// not longer than the number of bits in `I`. Longer keys are not supported, as the trie
+    /// requires each prefix to be represented by a primitive integer.
+    ///
+    /// Returns `None` if `len` is larger than the number of bits in `I`, or than `8 * bytes.len()`.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// let p = Bits::<u128>::from_be_slice(&[0x49, 0x00, 0x01, 0xff], 28).unwrap();
+    /// assert_eq!(p.prefix_len(), 28);
+    /// assert_eq!(p.bits(), 0x490001f0 << 96);
+    /// assert!(Bits::<u32>::from_be_slice(&[0; 5], 33).is_none());
+    /// assert!(Bits::<u32>::from_be_slice(&[0; 2], 17).is_none());
+    /// ```
+    pub fn from_be_slice(bytes: &[u8], len: u8) -> Option<Self> {
+        let width = I::zero().count_zeros();
+        if len as u32 > width || len as usize > 8 * bytes.len() {
+            return None;
+        }
+        let num_bytes = (len as usize + 7) / 8;
+        let bits = bytes[..num_bytes]
+            .iter()
+            .enumerate()
+            .fold(I::zero(), |acc, (i, b)| {
+                acc | (I::from(*b).unwrap() << (width as usize - 8 * (i + 1)))
+            });
+        Some(Self::new(bits, len))
+    }
+
+    /// Get the (masked) bits of the prefix.
+    pub fn bits(&self) -> I {
+        self.bits
+    }
+
+    /// Get the prefix length.
+    pub fn prefix_len(&self) -> u8 {
+        self.len
+    }
+}
+
+impl<I> Prefix for Bits<I>
+where
+    I: Unsigned + PrimInt + Zero + CheckedShr + std::fmt::Debug,
+{
+    type R = I;
+
+    fn repr(&self) -> I {
+        self.bits
+    }
+
+    fn prefix_len(&self) -> u8 {
+        self.len
+    }
+
+    fn from_repr_len(repr: I, len: u8) -> Self {
+        Self::new(repr, len)
+    }
+
+    fn mask(&self) -> I {
+        self.bits
+    }
+
+    fn eq(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+/// Composite key consisting of a route distinguisher (RD) and a prefix, e.g., for VPNv4 tables.
+/// The RD is matched exactly, while the prefix is matched using longest-prefix matching. This
+/// allows a single map to store the routes of all RDs. The prefix type `P` must not be wider than
+/// 64 bits (e.g., `Ipv4Net`, but not `Ipv6Net`).
+///
+/// The key is encoded as a 128-bit prefix, where the first 64 bits are the RD, and the remaining
+/// bits are the prefix. Therefore, branching nodes in the trie may have a prefix length smaller
+/// than 64. For those, [`RdPrefix::rd`] and [`RdPrefix::prefix`] return `None`.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use ipnet::Ipv4Net;
+/// let mut pm: PrefixMap<RdPrefix<Ipv4Net>, _> = PrefixMap::new();
+/// pm.insert(RdPrefix::new(100, "10.0.0.0/8".parse()?), 1);
+/// pm.insert(RdPrefix::new(200, "10.1.0.0/16".parse()?), 2);
+/// pm.insert(RdPrefix::new(200, "0.0.0.0/0".parse()?), 3);
+///
+/// let (key, value) = pm.get_lpm(&RdPrefix::new(100, "10.1.1.0/24".parse()?)).unwrap();
+/// assert_eq!((key.rd(), key.prefix()), (Some(100), Some("10.0.0.0/8".parse()?)));
+/// assert_eq!(value, &1);
+/// assert_eq!(pm.get_lpm(&RdPrefix::new(200, "10.1.1.0/24".parse()?)).unwrap().1, &2);
+/// assert_eq!(pm.get_lpm(&RdPrefix::new(200, "192.168.0.0/24".parse()?)).unwrap().1, &3);
+/// assert_eq!(pm.get_lpm(&RdPrefix::new(300, "10.1.1.0/24".parse()?)), None);
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+pub struct RdPrefix<P> {
+    repr: u128,
+    len: u8,
+    _prefix: PhantomData<P>,
+}
+
+impl<P> Clone for RdPrefix<P> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P> Copy for RdPrefix<P> {}
+
+impl<P> PartialEq for RdPrefix<P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.repr == other.repr && self.len == other.len
+    }
+}
+
+impl<P> Eq for RdPrefix<P> {}
+
+impl<P> std::hash::Hash for RdPrefix<P> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.repr.hash(state);
+        self.len.hash(state);
+    }
+}
+
+impl<P: Prefix> std::fmt::Debug for RdPrefix<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.rd(), self.prefix()) {
+            (Some(rd), Some(prefix)) => write!(f, "{rd}:{prefix:?}"),
+            _ => write!(f, "{:#034x}/{}", self.repr, self.len),
+        }
+    }
+}
+
+impl<P: Prefix> RdPrefix<P> {
+    /// Create a new composite key from a route distinguisher and a prefix.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `P::R` has more than 64 bits.
+    pub fn new(rd: u64, prefix: P) -> Self {
+        let width = Self::width();
+        assert!(width <= 64, "The prefix must not be wider than 64 bits");
+        let repr = <u128 as NumCast>::from(prefix.repr()).unwrap() << (128 - width);
+        Self {
+            repr: ((rd as u128) << 64) | (repr >> 64),
+            len: 64 + prefix.prefix_len(),
+            _prefix: PhantomData,
+        }
+    }
+
+    /// Get the route distinguisher, or `None` if the prefix length is smaller than 64 (which is
+    /// only the case for branching nodes in the trie).
+    pub fn rd(&self) -> Option<u64> {
+        (self.len >= 64).then_some((self.repr >> 64) as u64)
+    }
+
+    /// Get the prefix, or `None` if the prefix length is smaller than 64 (which is only the case
+    /// for branching nodes in the trie).
+    pub fn prefix(&self) -> Option<P> {
+        if self.len < 64 {
+            return None;
+        }
+        let repr = (self.repr as u64) >> (64 - Self::width());
+        Some(P::from_repr_len(
+            <P::R as NumCast>::from(repr).unwrap(),
+            self.len - 64,
+        ))
+    }
+
+    fn width() -> u32 {
+        P::R::zero().count_zeros()
+    }
+}
+
+impl<P: Prefix> Prefix for RdPrefix<P> {
+    type R = u128;
+
+    fn repr(&self) -> u128 {
+        self.repr
+    }
+
+    fn prefix_len(&self) -> u8 {
+        self.len
+    }
+
+    fn from_repr_len(repr: u128, len: u8) -> Self {
+        Self {
+            repr,
+            len,
+            _prefix: PhantomData,
+        }
+    }
+
+    fn mask(&self) -> u128 {
+        self.repr & fast_u128::mask(self.len)
+    }
+
+    u128_fast_paths!();
+}
+
+/// Wrapper around a prefix that is guaranteed to be canonical, i.e., all host bits (beyond the
+/// prefix length) are zero, and the prefix length is valid. A map keyed by `Strict<P>` therefore
+/// never stores prefixes with host bits, and lookups can skip masking the representation.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use ipnet::Ipv4Net;
+/// let mut pm: PrefixMap<Strict<Ipv4Net>, _> = PrefixMap::new();
+/// pm.insert(Strict::new("10.0.0.0/8".parse::<Ipv4Net>()?)?, 1);
+/// assert_eq!(
+///     Strict::new("10.1.0.0/8".parse::<Ipv4Net>()?),
+///     Err(StrictError::HostBitsSet)
+/// );
+/// let (p, v) = pm.get_lpm(&Strict::new("10.1.0.0/16".parse::<Ipv4Net>()?)?).unwrap();
+/// assert_eq!((p.into_inner(), v), ("10.0.0.0/8".parse()?, &1));
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Strict<P>(P);
+
+/// Error when creating a [`Strict`] prefix from a non-canonical prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StrictError {
+    /// Some bits beyond the prefix length are set.
+    HostBitsSet,
+    /// The prefix length is larger than the number of bits in the representation.
+    InvalidLength(u8),
+}
+
+impl std::fmt::Display for StrictError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StrictError::HostBitsSet => write!(f, "Host bits of the prefix are set"),
+            StrictError::InvalidLength(len) => write!(f, "Invalid prefix length {len}"),
+        }
+    }
+}
+
+impl std::error::Error for StrictError {}
+
+impl<P: Prefix> Strict<P> {
+    /// Wrap a prefix, checking that it is canonical.
+    pub fn new(prefix: P) -> Result<Self, StrictError> {
+        let len = prefix.prefix_len();
+        if len as u32 > P::R::zero().count_zeros() {
+            return Err(StrictError::InvalidLength(len));
+        }
+        if prefix.repr() != prefix.repr() & mask_from_prefix_len(len) {
+            return Err(StrictError::HostBitsSet);
+        }
+        Ok(Self(prefix))
+    }
+
+    /// Create a prefix from its representation and length, checking that it is canonical.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// assert!(Strict::<(u32, u8)>::from_repr_len_checked(0x0a000000, 8).is_ok());
+    /// assert_eq!(
+    ///     Strict::<(u32, u8)>::from_repr_len_checked(0x0a000001, 8),
+    ///     Err(StrictError::HostBitsSet)
+    /// );
+    /// assert_eq!(
+    ///     Strict::<(u32, u8)>::from_repr_len_checked(0, 33),
+    ///     Err(StrictError::InvalidLength(33))
+    /// );
+    /// ```
+    pub fn from_repr_len_checked(repr: P::R, len: u8) -> Result<Self, StrictError> {
+        if len as u32 > P::R::zero().count_zeros() {
+            return Err(StrictError::InvalidLength(len));
+        }
+        if repr != repr & mask_from_prefix_len(len) {
+            return Err(StrictError::HostBitsSet);
+        }
+        Ok(Self(P::from_repr_len(repr, len)))
+    }
+}
+
+impl<P> Strict<P> {
+    /// Get a reference to the wrapped prefix.
+    pub fn inner(&self) -> &P {
+        &self.0
+    }
+
+    /// Get the wrapped prefix.
+    pub fn into_inner(self) -> P {
+        self.0
+    }
+}
+
+impl<P: Prefix> Prefix for Strict<P> {
+    type R = P::R;
+
+    fn repr(&self) -> P::R {
+        self.0.repr()
+    }
+
+    fn prefix_len(&self) -> u8 {
+        self.0.prefix_len()
+    }
+
+    fn from_repr_len(repr: P::R, len: u8) -> Self {
+        Self(P::from_repr_len(repr & mask_from_prefix_len(len), len))
+    }
+
+    fn mask(&self) -> P::R {
+        self.0.repr()
+    }
+
+    fn zero() -> Self {
+        Self(P::zero())
+    }
+
+    fn contains(&self, other: &Self) -> bool {
+        self.0.contains(&other.0)
+    }
 }
 
 #[cfg(test)]
@@ -350,6 +1022,59 @@ mod test {
         assert!(!pfx!("255.255.0.0/8").is_bit_set(8));
     }
 
+    #[test]
+    fn fast_u128_mask() {
+        for len in 0..=128 {
+            assert_eq!(fast_u128::mask(len), mask_from_prefix_len::<u128>(len));
+        }
+    }
+
+    /// Compare the fast paths of `(u128, u8)`, `(Ipv6Addr, u8)`, and `Ipv6Net` with the generic
+    /// implementation (of `Bits<u128>`). `b` shares the first `shift` bits with `a`.
+    fn check_fast_u128(a: u128, a_len: u8, x: u128, shift: u8, b_len: u8) -> bool {
+        let (a_len, b_len) = (a_len % 129, b_len % 129);
+        let b = a ^ x.checked_shr(shift as u32 % 129).unwrap_or(0);
+        let (ga, gb) = (Bits::new(a, a_len), Bits::new(b, b_len));
+        let lcp = ga.longest_common_prefix(&gb);
+        let want = (lcp.repr(), lcp.prefix_len());
+
+        fn same<P: Prefix<R = u128>>(
+            a: P,
+            b: P,
+            want: (u128, u8),
+            ga: &Bits<u128>,
+            gb: &Bits<u128>,
+        ) -> bool {
+            let lcp = a.longest_common_prefix(&b);
+            (lcp.mask(), lcp.prefix_len()) == want
+                && a.mask() == ga.mask()
+                && a.contains(&b) == ga.contains(gb)
+                && b.contains(&a) == gb.contains(ga)
+                && (0..=128).all(|bit| a.is_bit_set(bit) == ga.is_bit_set(bit))
+        }
+
+        same((a, a_len), (b, b_len), want, &ga, &gb)
+            && same(
+                (Ipv6Addr::from(a), a_len),
+                (Ipv6Addr::from(b), b_len),
+                want,
+                &ga,
+                &gb,
+            )
+            && same(
+                Ipv6Net::new(a.into(), a_len).unwrap(),
+                Ipv6Net::new(b.into(), b_len).unwrap(),
+                want,
+                &ga,
+                &gb,
+            )
+    }
+
+    #[test]
+    fn fast_u128() {
+        quickcheck::quickcheck(check_fast_u128 as fn(u128, u8, u128, u8, u8) -> bool);
+    }
+
     #[generic_tests::define]
     mod t {
         use num_traits::NumCast;
@@ -430,6 +1155,36 @@ mod test {
             }
         }
 
+        #[test]
+        fn supernet_sibling<P: Prefix>() {
+            let p: P = new(0x0a010000, 16);
+            let sup = p.supernet().unwrap();
+            assert!(sup.eq(&new(0x0a000000, 15)));
+            assert!(sup.contains(&p));
+            assert!(p.sibling().unwrap().eq(&new(0x0a000000, 16)));
+            assert!(sup.contains(&p.sibling().unwrap()));
+            assert!(p.sibling().unwrap().sibling().unwrap().eq(&p));
+            assert!(P::zero().supernet().is_none());
+            assert!(P::zero().sibling().is_none());
+        }
+
+        #[test]
+        fn subnets<P: Prefix>() {
+            let num_zeros = <<P as Prefix>::R as Zero>::zero().count_zeros() as u8;
+            let offset = num_zeros - 32;
+            let p: P = new(0x0a010000, 16);
+            let subnets: Vec<P> = p.subnets(18 + offset).collect();
+            assert_eq!(subnets.len(), 4);
+            for (i, s) in subnets.iter().enumerate() {
+                assert!(s.eq(&new(0x0a010000 + ((i as u32) << 14), 18)));
+                assert!(p.contains(s));
+            }
+            assert_eq!(p.subnets(16 + offset).count(), 1);
+            assert_eq!(p.subnets(15 + offset).count(), 0);
+            assert_eq!(P::zero().subnets(0).count(), 1);
+            assert_eq!(P::zero().subnets(2).count(), 4);
+        }
+
         #[instantiate_tests(<Ipv4Net>)]
         mod ipv4net {}
 
@@ -441,5 +1196,82 @@ mod test {
 
         #[instantiate_tests(<(u64, u8)>)]
         mod u64_u8 {}
+
+        #[instantiate_tests(<(u128, u8)>)]
+        mod u128_u8 {}
+
+        #[instantiate_tests(<Bits<u32>>)]
+        mod bits_u32 {}
+
+        #[instantiate_tests(<Bits<u128>>)]
+        mod bits_u128 {}
+
+        #[instantiate_tests(<RdPrefix<Ipv4Net>>)]
+        mod rd_ipv4net {}
+
+        #[instantiate_tests(<Strict<Ipv4Net>>)]
+        mod strict_ipv4net {}
+
+        #[instantiate_tests(<Strict<(u64, u8)>>)]
+        mod strict_u64_u8 {}
+    }
+
+    /// `MacPrefix` cannot use the generic tests above, as its prefix lengths are limited to 48
+    /// bits, while its representation has 64 bits.
+    #[cfg(feature = "macaddr")]
+    mod mac_prefix {
+        use crate::MacPrefix;
+
+        use super::*;
+
+        fn mac(s: &str) -> MacPrefix {
+            s.parse().unwrap()
+        }
+
+        #[test]
+        fn contains() {
+            assert!(mac("00:1b:63:00:00:00/24").contains(&mac("00:1b:63:12:00:00/32")));
+            assert!(mac("00:1b:63:00:00:00/24").contains(&mac("00:1b:63:00:00:00/24")));
+            assert!(!mac("00:1b:63:12:00:00/32").contains(&mac("00:1b:63:00:00:00/24")));
+            assert!(!mac("00:1b:63:00:00:00/24").contains(&mac("00:1b:64:00:00:00/32")));
+        }
+
+        #[test]
+        fn longest_common_prefix() {
+            let a = mac("00:1b:63:12:34:56/48");
+            let b = mac("00:1b:63:12:34:57/48");
+            assert_eq!(a.longest_common_prefix(&b), mac("00:1b:63:12:34:56/47"));
+            let c = mac("00:1b:63:80:00:00/24");
+            assert_eq!(a.longest_common_prefix(&c), mac("00:1b:63:00:00:00/24"));
+        }
+
+        #[test]
+        fn supernet_sibling() {
+            let p = mac("00:1b:63:12:34:57/48");
+            assert!(p.is_host());
+            assert!(!mac("00:1b:63:00:00:00/24").is_host());
+            assert_eq!(p.supernet(), Some(mac("00:1b:63:12:34:56/47")));
+            assert_eq!(p.sibling(), Some(mac("00:1b:63:12:34:56/48")));
+        }
+
+        #[test]
+        fn subnets() {
+            let p = mac("00:1b:63:12:34:00/46");
+            assert_eq!(
+                p.subnets(48).collect::<Vec<_>>(),
+                vec![
+                    mac("00:1b:63:12:34:00/48"),
+                    mac("00:1b:63:12:34:01/48"),
+                    mac("00:1b:63:12:34:02/48"),
+                    mac("00:1b:63:12:34:03/48"),
+                ]
+            );
+            assert_eq!(p.subnets(46).count(), 1);
+            assert_eq!(p.subnets(45).count(), 0);
+            for len in 49..=64 {
+                assert_eq!(p.subnets(len).count(), 0);
+                assert_eq!(MacPrefix::zero().subnets(len).count(), 0);
+            }
+        }
     }
 }
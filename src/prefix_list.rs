@@ -0,0 +1,383 @@
+//! Parse and format router prefix-lists.
+//!
+//! This module understands the prefix-list syntax that is common to many router operating systems
+//! (e.g., Cisco IOS, Arista EOS, or FRRouting):
+//!
+//! ```text
+//! ip prefix-list NAME [seq N] (permit|deny) A.B.C.D/L [ge X] [le Y]
+//! ipv6 prefix-list NAME [seq N] (permit|deny) X:X::X:X/L [ge X] [le Y]
+//! ```
+//!
+//! An entry matches all prefixes that are contained in `A.B.C.D/L` and whose length is in the
+//! range given by `ge` and `le`. Without `ge` and `le`, the entry only matches `A.B.C.D/L` itself.
+//! With only `ge`, the range extends up to the maximum length (32 or 128); with only `le`, it
+//! starts at `L`. A prefix-list is evaluated in the order of the sequence numbers, and the first
+//! matching entry decides whether a prefix is permitted or denied.
+//!
+//! ```
+//! # use prefix_trie::prefix_list::{self, Action};
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let entries = prefix_list::parse(
+//!     "ip prefix-list CUSTOMER seq 5 deny 10.1.0.0/16 le 24\n\
+//!      ip prefix-list CUSTOMER seq 10 permit 10.0.0.0/8 ge 16 le 17\n",
+//! )?;
+//! let map = prefix_list::to_map(&entries, "CUSTOMER")?;
+//! assert_eq!(map.get(&"10.1.0.0/16".parse()?), Some(&Action::Deny));
+//! assert_eq!(map.get(&"10.2.0.0/16".parse()?), Some(&Action::Permit));
+//! assert_eq!(map.get(&"10.2.128.0/17".parse()?), Some(&Action::Permit));
+//! assert_eq!(map.get(&"10.2.0.0/18".parse()?), None);
+//! // 255 prefixes of length 16, and 510 prefixes of length 17
+//! assert_eq!(prefix_list::to_set(&entries, "CUSTOMER")?.len(), 255 + 510);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use ipnet::IpNet;
+
+use crate::ip::{IpPrefixMap, IpPrefixSet};
+
+/// Whether an entry permits or denies the matching prefixes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    /// The matching prefixes are permitted.
+    Permit,
+    /// The matching prefixes are denied.
+    Deny,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Permit => write!(f, "permit"),
+            Action::Deny => write!(f, "deny"),
+        }
+    }
+}
+
+/// Error when parsing a prefix-list. Each variant contains the (1-based) line number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PrefixListError {
+    /// The line does not follow the prefix-list syntax.
+    InvalidSyntax(usize),
+    /// The prefix could not be parsed, or its family does not match `ip` or `ipv6`.
+    InvalidPrefix(usize),
+    /// The `ge` or `le` values are invalid, i.e., not `len <= ge <= le <= max`.
+    InvalidLength(usize),
+}
+
+impl fmt::Display for PrefixListError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrefixListError::InvalidSyntax(l) => write!(f, "Invalid syntax in line {l}"),
+            PrefixListError::InvalidPrefix(l) => write!(f, "Invalid prefix in line {l}"),
+            PrefixListError::InvalidLength(l) => write!(f, "Invalid ge or le in line {l}"),
+        }
+    }
+}
+
+impl std::error::Error for PrefixListError {}
+
+/// The maximum number of prefixes that [`to_map`] and [`to_set`] expand a prefix-list into.
+pub const MAX_EXPANSION: u128 = 1 << 20;
+
+/// Error when expanding a prefix-list that matches more than [`MAX_EXPANSION`] prefixes. It
+/// contains the number of prefixes that the entries match (counting overlapping entries twice).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ExpansionTooLarge(pub u128);
+
+impl fmt::Display for ExpansionTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The prefix-list matches {} prefixes, but at most {MAX_EXPANSION} can be expanded",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ExpansionTooLarge {}
+
+/// A single entry of a prefix-list.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PrefixListEntry {
+    /// The name of the prefix-list.
+    pub name: String,
+    /// The sequence number (if given).
+    pub seq: Option<u32>,
+    /// Whether the entry permits or denies the matching prefixes.
+    pub action: Action,
+    /// The prefix that must contain all matching prefixes.
+    pub prefix: IpNet,
+    /// The minimum length of matching prefixes.
+    pub ge: Option<u8>,
+    /// The maximum length of matching prefixes.
+    pub le: Option<u8>,
+}
+
+impl PrefixListEntry {
+    /// The range of prefix lengths that this entry matches.
+    pub fn length_range(&self) -> RangeInclusive<u8> {
+        let len = self.prefix.prefix_len();
+        match (self.ge, self.le) {
+            (None, None) => len..=len,
+            (Some(ge), None) => ge..=self.prefix.max_prefix_len(),
+            (None, Some(le)) => len..=le,
+            (Some(ge), Some(le)) => ge..=le,
+        }
+    }
+
+    /// Check whether `prefix` matches this entry.
+    pub fn matches(&self, prefix: &IpNet) -> bool {
+        self.prefix.contains(prefix) && self.length_range().contains(&prefix.prefix_len())
+    }
+
+    /// The number of prefixes that match this entry, saturating at `u128::MAX`. It grows
+    /// exponentially with the length range.
+    ///
+    /// ```
+    /// # use prefix_trie::prefix_list::PrefixListEntry;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let entry: PrefixListEntry = "ip prefix-list X permit 10.0.0.0/8 le 24".parse()?;
+    /// assert_eq!(entry.num_prefixes(), (1 << 17) - 1);
+    /// let entry: PrefixListEntry = "ip prefix-list X permit 0.0.0.0/0 le 32".parse()?;
+    /// assert_eq!(entry.num_prefixes(), (1 << 33) - 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn num_prefixes(&self) -> u128 {
+        let len = self.prefix.prefix_len();
+        self.length_range()
+            .map(|l| 1u128.checked_shl((l - len) as u32).unwrap_or(u128::MAX))
+            .fold(0, u128::saturating_add)
+    }
+
+    /// Lazily iterate over all prefixes that match this entry. Notice, that the number of prefixes
+    /// grows exponentially with the length range (see [`Self::num_prefixes`]).
+    pub fn expand(&self) -> impl Iterator<Item = IpNet> + '_ {
+        self.length_range()
+            .flat_map(|len| self.prefix.subnets(len).into_iter().flatten())
+    }
+
+    fn parse_line(line: &str, n: usize) -> Result<Self, PrefixListError> {
+        let syntax = PrefixListError::InvalidSyntax(n);
+        let mut tokens = line.split_whitespace();
+        let v6 = match tokens.next() {
+            Some("ip") => false,
+            Some("ipv6") => true,
+            _ => return Err(syntax),
+        };
+        if tokens.next() != Some("prefix-list") {
+            return Err(syntax);
+        }
+        let name = tokens.next().ok_or(syntax)?.to_string();
+        let mut token = tokens.next().ok_or(syntax)?;
+        let mut seq = None;
+        if token == "seq" {
+            seq = Some(tokens.next().and_then(|s| s.parse().ok()).ok_or(syntax)?);
+            token = tokens.next().ok_or(syntax)?;
+        }
+        let action = match token {
+            "permit" => Action::Permit,
+            "deny" => Action::Deny,
+            _ => return Err(syntax),
+        };
+        let prefix: IpNet = tokens
+            .next()
+            .ok_or(syntax)?
+            .parse()
+            .map_err(|_| PrefixListError::InvalidPrefix(n))?;
+        if matches!(prefix, IpNet::V6(_)) != v6 {
+            return Err(PrefixListError::InvalidPrefix(n));
+        }
+        let mut ge = None;
+        let mut le = None;
+        while let Some(token) = tokens.next() {
+            let value = tokens.next().and_then(|s| s.parse::<u8>().ok());
+            match (token, value) {
+                ("ge", Some(v)) if ge.is_none() && le.is_none() => ge = Some(v),
+                ("le", Some(v)) if le.is_none() => le = Some(v),
+                _ => return Err(syntax),
+            }
+        }
+        let entry = Self {
+            name,
+            seq,
+            action,
+            prefix: prefix.trunc(),
+            ge,
+            le,
+        };
+        let range = entry.length_range();
+        if *range.start() < prefix.prefix_len()
+            || range.start() > range.end()
+            || *range.end() > prefix.max_prefix_len()
+        {
+            return Err(PrefixListError::InvalidLength(n));
+        }
+        Ok(entry)
+    }
+}
+
+impl fmt::Display for PrefixListEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let family = match self.prefix {
+            IpNet::V4(_) => "ip",
+            IpNet::V6(_) => "ipv6",
+        };
+        write!(f, "{family} prefix-list {}", self.name)?;
+        if let Some(seq) = self.seq {
+            write!(f, " seq {seq}")?;
+        }
+        write!(f, " {} {}", self.action, self.prefix)?;
+        if let Some(ge) = self.ge {
+            write!(f, " ge {ge}")?;
+        }
+        if let Some(le) = self.le {
+            write!(f, " le {le}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PrefixListEntry {
+    type Err = PrefixListError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_line(s.trim(), 1)
+    }
+}
+
+/// Parse all entries of a prefix-list configuration. Empty lines, comments (starting with `!` or
+/// `#`), and `description` lines are ignored.
+pub fn parse(text: &str) -> Result<Vec<PrefixListEntry>, PrefixListError> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !(line.is_empty() || line.starts_with('!') || line.starts_with('#')))
+        .filter(|(_, line)| line.split_whitespace().nth(3) != Some("description"))
+        .map(|(n, line)| PrefixListEntry::parse_line(line, n))
+        .collect()
+}
+
+/// Sort the entries by their sequence number. Entries without a sequence number obtain the
+/// sequence number of the previous entry plus 5 (starting at 5, and saturating at `u32::MAX`).
+fn sorted(entries: &[PrefixListEntry]) -> Vec<&PrefixListEntry> {
+    let mut last = 0u32;
+    let mut sorted: Vec<(u32, &PrefixListEntry)> = entries
+        .iter()
+        .map(|e| {
+            last = e.seq.unwrap_or(last.saturating_add(5));
+            (last, e)
+        })
+        .collect();
+    sorted.sort_by_key(|(seq, _)| *seq);
+    sorted.into_iter().map(|(_, e)| e).collect()
+}
+
+/// Expand the prefix-list `name` into a map that stores, for each matching prefix, the action of
+/// the first matching entry. Entries of other prefix-lists are ignored. Prefixes that do not match
+/// any entry are not contained in the map (and are implicitly denied). Since the number of
+/// prefixes grows exponentially with the length ranges (see [`PrefixListEntry::num_prefixes`]),
+/// this function fails if the entries match more than [`MAX_EXPANSION`] prefixes, before
+/// expanding any of them.
+///
+/// ```
+/// # use prefix_trie::prefix_list::{self, ExpansionTooLarge};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let entries = prefix_list::parse(
+///     "ip prefix-list SMALL permit 10.0.0.0/8 ge 9 le 10\n\
+///      ip prefix-list ANY permit 0.0.0.0/0 le 32\n",
+/// )?;
+/// assert_eq!(prefix_list::to_map(&entries, "SMALL")?.len(), 6);
+/// assert_eq!(prefix_list::to_map(&entries, "OTHER")?.len(), 0);
+/// assert_eq!(
+///     prefix_list::to_map(&entries, "ANY"),
+///     Err(ExpansionTooLarge((1 << 33) - 1))
+/// );
+/// # Ok(())
+/// # }
+/// ```
+pub fn to_map(
+    entries: &[PrefixListEntry],
+    name: &str,
+) -> Result<IpPrefixMap<Action>, ExpansionTooLarge> {
+    let entries: Vec<&PrefixListEntry> = sorted(entries)
+        .into_iter()
+        .filter(|e| e.name == name)
+        .collect();
+    let count = entries
+        .iter()
+        .map(|e| e.num_prefixes())
+        .fold(0, u128::saturating_add);
+    if count > MAX_EXPANSION {
+        return Err(ExpansionTooLarge(count));
+    }
+    let mut map = IpPrefixMap::new();
+    for entry in entries {
+        for prefix in entry.expand() {
+            if !map.contains_key(&prefix) {
+                map.insert(prefix, entry.action);
+            }
+        }
+    }
+    Ok(map)
+}
+
+/// Expand the prefix-list `name` into the set of all permitted prefixes. See [`to_map`].
+pub fn to_set(entries: &[PrefixListEntry], name: &str) -> Result<IpPrefixSet, ExpansionTooLarge> {
+    Ok(to_map(entries, name)?
+        .iter()
+        .filter(|(_, a)| **a == Action::Permit)
+        .map(|(p, _)| p)
+        .collect())
+}
+
+/// Format a map as a prefix-list named `name`, with one exact-match entry for each element (with
+/// sequence numbers in steps of 5).
+///
+/// ```
+/// # use prefix_trie::prefix_list::{self, Action};
+/// # use prefix_trie::ip::IpPrefixMap;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut map = IpPrefixMap::new();
+/// map.insert("10.0.0.0/8".parse()?, Action::Permit);
+/// map.insert("2001:db8::/32".parse()?, Action::Deny);
+/// assert_eq!(
+///     prefix_list::format_map("EXAMPLE", &map),
+///     "ip prefix-list EXAMPLE seq 5 permit 10.0.0.0/8\n\
+///      ipv6 prefix-list EXAMPLE seq 10 deny 2001:db8::/32\n"
+/// );
+/// let entries = prefix_list::parse(&prefix_list::format_map("EXAMPLE", &map))?;
+/// assert_eq!(prefix_list::to_map(&entries, "EXAMPLE")?, map);
+/// # Ok(())
+/// # }
+/// ```
+pub fn format_map(name: &str, map: &IpPrefixMap<Action>) -> String {
+    format_entries(name, map.iter().map(|(p, a)| (p, *a)))
+}
+
+/// Format a set as a prefix-list named `name` that permits exactly the prefixes in the set. See
+/// [`format_map`].
+pub fn format_set(name: &str, set: &IpPrefixSet) -> String {
+    format_entries(name, set.iter().map(|p| (p, Action::Permit)))
+}
+
+fn format_entries(name: &str, iter: impl Iterator<Item = (IpNet, Action)>) -> String {
+    iter.zip(1..)
+        .map(|((prefix, action), i)| {
+            let entry = PrefixListEntry {
+                name: name.to_string(),
+                seq: Some(i * 5),
+                action,
+                prefix,
+                ge: None,
+                le: None,
+            };
+            format!("{entry}\n")
+        })
+        .collect()
+}
@@ -0,0 +1,334 @@
+//! Arbitrary (inclusive) address ranges, decomposed into prefixes.
+//!
+//! Many data sources (like RIR delegation files or geo-IP feeds) describe address ranges rather
+//! than prefixes. An [`AddrRange`] can be decomposed into the minimal set of prefixes that exactly
+//! covers the range (see [`AddrRange::cidrs`]). A [`RangeMap`] stores each range as its
+//! decomposition, and offers lookups that hide that decomposition, both by address (stabbing
+//! queries) and by range (overlap queries). Conversely, [`crate::PrefixSet::ranges`] merges the
+//! prefixes of a set into the maximal ranges they cover.
+
+use std::collections::BTreeMap;
+
+use num_traits::{One, PrimInt, Zero};
+
+use crate::{prefix::mask_from_prefix_len, set, Prefix, PrefixMap};
+
+/// An inclusive range of addresses `start..=end`.
+///
+/// ```
+/// # use prefix_trie::range::*;
+/// let range = AddrRange::new(0x0a000000u32, 0x0a0002ff).unwrap();
+/// assert_eq!(
+///     range.cidrs::<(u32, u8)>().collect::<Vec<_>>(),
+///     vec![(0x0a000000, 23), (0x0a000200, 24)],
+/// );
+/// assert!(AddrRange::new(2u32, 1).is_none());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AddrRange<R> {
+    start: R,
+    end: R,
+}
+
+impl<R: PrimInt + Zero + One> AddrRange<R> {
+    /// Create a new range from `start` to `end` (inclusive). Returns `None` if `start > end`.
+    pub fn new(start: R, end: R) -> Option<Self> {
+        (start <= end).then_some(Self { start, end })
+    }
+
+    /// Create a range that contains exactly the addresses of `prefix`.
+    ///
+    /// ```
+    /// # use prefix_trie::range::*;
+    /// let range = AddrRange::from_prefix(&(0x0a000000u32, 8));
+    /// assert_eq!((range.start(), range.end()), (0x0a000000, 0x0affffff));
+    /// ```
+    pub fn from_prefix<P: Prefix<R = R>>(prefix: &P) -> Self {
+        let start = prefix.mask();
+        let end = start | !mask_from_prefix_len::<R>(prefix.prefix_len());
+        Self { start, end }
+    }
+
+    /// The first address of the range.
+    pub fn start(&self) -> R {
+        self.start
+    }
+
+    /// The last address of the range (inclusive).
+    pub fn end(&self) -> R {
+        self.end
+    }
+
+    /// Check if `addr` is contained within the range.
+    pub fn contains(&self, addr: R) -> bool {
+        self.start <= addr && addr <= self.end
+    }
+
+    /// Check if `self` and `other` have at least one address in common.
+    ///
+    /// ```
+    /// # use prefix_trie::range::*;
+    /// let a = AddrRange::new(10u8, 20).unwrap();
+    /// assert!(a.overlaps(&AddrRange::new(20, 30).unwrap()));
+    /// assert!(!a.overlaps(&AddrRange::new(21, 30).unwrap()));
+    /// ```
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    /// Decompose the range into the minimal set of prefixes that exactly covers the range. The
+    /// prefixes are yielded in ascending order.
+    pub fn cidrs<P: Prefix<R = R>>(&self) -> Cidrs<P> {
+        Cidrs {
+            next: Some(self.start),
+            end: self.end,
+            _prefix: std::marker::PhantomData,
+        }
+    }
+}
+
+/// Iterator over the minimal decomposition of an [`AddrRange`] into prefixes, created by
+/// [`AddrRange::cidrs`].
+pub struct Cidrs<P: Prefix> {
+    next: Option<P::R>,
+    end: P::R,
+    _prefix: std::marker::PhantomData<P>,
+}
+
+impl<P: Prefix> Iterator for Cidrs<P> {
+    type Item = P;
+
+    fn next(&mut self) -> Option<P> {
+        let cur = self.next?;
+        let width = P::R::zero().count_zeros();
+        // host bits of a prefix with `k` host bits.
+        let host_mask = |k: u32| !mask_from_prefix_len::<P::R>((width - k) as u8);
+        // the largest block that is aligned at `cur`, and that does not exceed `end`.
+        let mut k = cur.trailing_zeros().min(width);
+        while k > 0 && cur | host_mask(k) > self.end {
+            k -= 1;
+        }
+        let last = cur | host_mask(k);
+        self.next = if last >= self.end {
+            None
+        } else {
+            Some(last + P::R::one())
+        };
+        Some(P::from_repr_len(cur, (width - k) as u8))
+    }
+}
+
+/// Iterator over the maximal contiguous ranges of addresses covered by a [`crate::PrefixSet`],
+/// created by [`crate::PrefixSet::ranges`].
+#[derive(Clone)]
+pub struct Ranges<'a, P: Prefix> {
+    iter: set::Iter<'a, P>,
+    /// The range of the first prefix that was not merged into the previously yielded range.
+    next: Option<AddrRange<P::R>>,
+}
+
+impl<'a, P: Prefix> Ranges<'a, P> {
+    pub(crate) fn new(iter: set::Iter<'a, P>) -> Self {
+        Self { iter, next: None }
+    }
+}
+
+impl<P: Prefix> Iterator for Ranges<'_, P> {
+    type Item = AddrRange<P::R>;
+
+    fn next(&mut self) -> Option<AddrRange<P::R>> {
+        let mut cur = match self.next.take() {
+            Some(range) => range,
+            None => AddrRange::from_prefix(self.iter.next()?),
+        };
+        // prefixes are yielded in lexicographic order, i.e., sorted by their first address.
+        for p in self.iter.by_ref() {
+            let range = AddrRange::from_prefix(p);
+            if range.start > cur.end && range.start - cur.end > P::R::one() {
+                self.next = Some(range);
+                break;
+            }
+            cur.end = cur.end.max(range.end);
+        }
+        Some(cur)
+    }
+}
+
+/// A map from address ranges to values. Each range is stored as its decomposition into prefixes,
+/// and lookups return the original range.
+///
+/// Ranges may overlap. In that case, each address is mapped to the range with the most specific
+/// covering prefix, or to the range that was inserted last (if both ranges share the same prefix in
+/// their decomposition).
+///
+/// ```
+/// # use prefix_trie::range::*;
+/// let mut rm: RangeMap<(u32, u8), &str> = RangeMap::new();
+/// let ch = AddrRange::new(0x0a000005, 0x0a0000ff).unwrap();
+/// let de = AddrRange::new(0x0a000100, 0x0a000abc).unwrap();
+/// rm.insert(ch, "CH");
+/// rm.insert(de, "DE");
+/// assert_eq!(rm.get_addr(0x0a000042), Some((&ch, &"CH")));
+/// assert_eq!(rm.get_addr(0x0a000200), Some((&de, &"DE")));
+/// assert_eq!(rm.get_addr(0x0a000004), None);
+/// assert_eq!(rm.get_addr(0x0a000abd), None);
+/// ```
+#[derive(Clone)]
+pub struct RangeMap<P: Prefix, T> {
+    /// All ranges whose decomposition contains the prefix, in insertion order.
+    map: PrefixMap<P, Vec<AddrRange<P::R>>>,
+    ranges: BTreeMap<AddrRange<P::R>, T>,
+}
+
+impl<P: Prefix, T> Default for RangeMap<P, T> {
+    fn default() -> Self {
+        Self {
+            map: PrefixMap::new(),
+            ranges: BTreeMap::new(),
+        }
+    }
+}
+
+impl<P, T> std::fmt::Debug for RangeMap<P, T>
+where
+    P: Prefix,
+    P::R: std::fmt::Debug,
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.ranges.iter()).finish()
+    }
+}
+
+impl<P: Prefix, T> RangeMap<P, T> {
+    /// Create an empty range map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of ranges stored in `self`.
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    /// Returns `true` if the map contains no ranges.
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Insert a range. If the exact same range was already present, then its value is replaced and
+    /// the old value is returned. In both cases, the range counts as inserted last.
+    ///
+    /// ```
+    /// # use prefix_trie::range::*;
+    /// let mut rm: RangeMap<(u8, u8), _> = RangeMap::new();
+    /// let a = AddrRange::new(0, 15).unwrap();
+    /// let b = AddrRange::new(0, 23).unwrap();
+    /// rm.insert(a, 'a');
+    /// rm.insert(b, 'b');
+    /// // both ranges contain the prefix `0/4`, and `b` was inserted last.
+    /// assert_eq!(rm.get_addr(3), Some((&b, &'b')));
+    /// assert_eq!(rm.insert(a, 'c'), Some('a'));
+    /// assert_eq!(rm.get_addr(3), Some((&a, &'c')));
+    /// ```
+    pub fn insert(&mut self, range: AddrRange<P::R>, value: T) -> Option<T> {
+        for p in range.cidrs::<P>() {
+            let ranges = self.map.entry(p).or_default();
+            ranges.retain(|r| *r != range);
+            ranges.push(range);
+        }
+        self.ranges.insert(range, value)
+    }
+
+    /// Get the value of a range by matching exactly.
+    pub fn get(&self, range: &AddrRange<P::R>) -> Option<&T> {
+        self.ranges.get(range)
+    }
+
+    /// Get a mutable reference to the value of a range by matching exactly.
+    pub fn get_mut(&mut self, range: &AddrRange<P::R>) -> Option<&mut T> {
+        self.ranges.get_mut(range)
+    }
+
+    /// Get the range that contains `addr`, along with its value.
+    pub fn get_addr(&self, addr: P::R) -> Option<(&AddrRange<P::R>, &T)> {
+        let width = P::R::zero().count_zeros() as u8;
+        let (_, ranges) = self.map.get_lpm(&P::from_repr_len(addr, width))?;
+        self.ranges.get_key_value(ranges.last()?)
+    }
+
+    /// Iterate over all ranges that have at least one address in common with `range`, in
+    /// ascending order. Only the stored prefixes that overlap with `range` are visited (see
+    /// [`PrefixMap::overlapping`]).
+    ///
+    /// ```
+    /// # use prefix_trie::range::*;
+    /// let mut rm: RangeMap<(u8, u8), _> = RangeMap::new();
+    /// rm.insert(AddrRange::new(0, 9).unwrap(), 'a');
+    /// rm.insert(AddrRange::new(10, 99).unwrap(), 'b');
+    /// rm.insert(AddrRange::new(100, 199).unwrap(), 'c');
+    /// let query = AddrRange::new(50, 120).unwrap();
+    /// assert_eq!(rm.overlapping(&query).map(|(_, t)| *t).collect::<Vec<_>>(), vec!['b', 'c']);
+    /// ```
+    pub fn overlapping(
+        &self,
+        range: &AddrRange<P::R>,
+    ) -> impl Iterator<Item = (&AddrRange<P::R>, &T)> {
+        let mut found: Vec<AddrRange<P::R>> = self
+            .map
+            .overlapping(range)
+            .flat_map(|(_, r)| r)
+            .copied()
+            .collect();
+        found.sort();
+        found.dedup();
+        found
+            .into_iter()
+            .filter_map(move |r| self.ranges.get_key_value(&r))
+    }
+
+    /// Remove a range (matching exactly), and return its value. Addresses of overlapping ranges
+    /// remain mapped to them.
+    ///
+    /// ```
+    /// # use prefix_trie::range::*;
+    /// let mut rm: RangeMap<(u8, u8), _> = RangeMap::new();
+    /// let range = AddrRange::new(3, 17).unwrap();
+    /// let outer = AddrRange::new(0, 23).unwrap();
+    /// rm.insert(range, 1);
+    /// rm.insert(outer, 2);
+    /// assert_eq!(rm.remove(&AddrRange::new(3, 16).unwrap()), None);
+    /// assert_eq!(rm.remove(&range), Some(1));
+    /// assert_eq!(rm.get_addr(5), Some((&outer, &2)));
+    /// assert_eq!(rm.remove(&outer), Some(2));
+    /// assert_eq!(rm.get_addr(5), None);
+    /// assert!(rm.is_empty());
+    /// ```
+    pub fn remove(&mut self, range: &AddrRange<P::R>) -> Option<T> {
+        let value = self.ranges.remove(range)?;
+        for p in range.cidrs::<P>() {
+            if let Some(ranges) = self.map.get_mut(&p) {
+                ranges.retain(|r| r != range);
+                if ranges.is_empty() {
+                    self.map.remove(&p);
+                }
+            }
+        }
+        Some(value)
+    }
+
+    /// Iterate over all ranges in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = (&AddrRange<P::R>, &T)> {
+        self.ranges.iter()
+    }
+}
+
+impl<P: Prefix, T> FromIterator<(AddrRange<P::R>, T)> for RangeMap<P, T> {
+    fn from_iter<I: IntoIterator<Item = (AddrRange<P::R>, T)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        for (r, t) in iter {
+            map.insert(r, t);
+        }
+        map
+    }
+}
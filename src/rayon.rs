@@ -0,0 +1,221 @@
+//! Parallel construction and aggregation of maps and sets using rayon.
+//!
+//! Each rayon job builds its own partial trie, and partial tries are merged pairwise. Merging
+//! always preserves the order of the parallel iterator, i.e., if the same prefix appears multiple
+//! times, the last one wins (exactly like [`FromIterator`] does).
+
+use ::rayon::iter::{FromParallelIterator, IntoParallelIterator, ParallelExtend, ParallelIterator};
+
+use super::*;
+use crate::{inner::Table, map::Iter};
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix + Send + Sync,
+    T: Send + Sync,
+{
+    /// Fold all entries of the map in parallel. The trie is split into disjoint sub-trees, each of
+    /// which is folded on its own, starting with a value created by `identity`. The partial
+    /// results are then combined using `reduce`. Just like [`ParallelIterator::fold`], `identity`
+    /// may be called multiple times, and the result of `identity` must be neutral for `reduce`.
+    ///
+    /// Partial results are always reduced in lexicographic order of the sub-trees. Hence, `reduce`
+    /// only needs to be associative, but not commutative.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # {
+    /// let map: PrefixMap<ipnet::Ipv4Net, u64> = (0..1024u32)
+    ///     .map(|i| (ipnet::Ipv4Net::new((i << 8).into(), 24).unwrap(), i as u64))
+    ///     .collect();
+    /// let (count, sum) = map.par_fold(
+    ///     || (0, 0),
+    ///     |(count, sum), _, x| (count + 1, sum + x),
+    ///     |(c1, s1), (c2, s2)| (c1 + c2, s1 + s2),
+    /// );
+    /// assert_eq!(count, 1024);
+    /// assert_eq!(sum, 1023 * 1024 / 2);
+    /// # }
+    /// ```
+    pub fn par_fold<A, ID, F, R>(&self, identity: ID, fold: F, reduce: R) -> A
+    where
+        A: Send,
+        ID: Fn() -> A + Sync,
+        F: Fn(A, &P, &T) -> A + Sync,
+        R: Fn(A, A) -> A + Sync,
+    {
+        // Split the tree until there are about 16 sub-trees for each thread.
+        let split_depth = ::rayon::current_num_threads()
+            .next_power_of_two()
+            .trailing_zeros()
+            + 4;
+        par_fold_node(&self.table, 0, split_depth, &identity, &fold, &reduce)
+    }
+}
+
+/// Fold the sub-tree rooted at `idx`. The left and the right sub-trees are folded in parallel,
+/// until `split_depth` reaches zero. From there on, the sub-tree is folded sequentially.
+fn par_fold_node<P, T, A, ID, F, R>(
+    table: &Table<P, T>,
+    idx: usize,
+    split_depth: u32,
+    identity: &ID,
+    fold: &F,
+    reduce: &R,
+) -> A
+where
+    P: Send + Sync,
+    T: Send + Sync,
+    A: Send,
+    ID: Fn() -> A + Sync,
+    F: Fn(A, &P, &T) -> A + Sync,
+    R: Fn(A, A) -> A + Sync,
+{
+    if split_depth == 0 {
+        return Iter::new(table, vec![idx]).fold(identity(), |acc, (p, t)| fold(acc, p, t));
+    }
+    let node = &table[idx];
+    let acc = match table.prefix_value(idx) {
+        Some((p, t)) => fold(identity(), p, t),
+        None => identity(),
+    };
+    let rec = |child| par_fold_node(table, child, split_depth - 1, identity, fold, reduce);
+    match (node.left(), node.right()) {
+        (Some(left), Some(right)) => {
+            let (left, right) = ::rayon::join(|| rec(left), || rec(right));
+            reduce(reduce(acc, left), right)
+        }
+        (Some(child), None) | (None, Some(child)) => reduce(acc, rec(child)),
+        (None, None) => acc,
+    }
+}
+
+/// Merge two partial maps, where `b` was built from elements that come after those of `a`. The
+/// smaller map is inserted into the larger one, while making sure that values of `b` take
+/// precedence over values of `a`.
+fn merge<P: Prefix, T>(mut a: PrefixMap<P, T>, mut b: PrefixMap<P, T>) -> PrefixMap<P, T> {
+    if a.len() >= b.len() {
+        for (p, t) in b {
+            a.insert(p, t);
+        }
+        a
+    } else {
+        for (p, t) in a {
+            b.entry(p).or_insert(t);
+        }
+        b
+    }
+}
+
+/// Build a map from a parallel iterator. Each thread builds a partial trie, which are then merged
+/// together. If the same prefix occurs multiple times, then the last one will be stored.
+///
+/// ```
+/// # use prefix_trie::*;
+/// use rayon::prelude::*;
+/// # #[cfg(feature = "ipnet")]
+/// # {
+/// let map: PrefixMap<ipnet::Ipv4Net, u32> = (0..1024u32)
+///     .into_par_iter()
+///     .map(|i| (ipnet::Ipv4Net::new((i << 8).into(), 24).unwrap(), i))
+///     .collect();
+/// assert_eq!(map.len(), 1024);
+/// assert_eq!(map.get(&"0.0.4.0/24".parse().unwrap()), Some(&4));
+/// # }
+/// ```
+impl<P, T> FromParallelIterator<(P, T)> for PrefixMap<P, T>
+where
+    P: Prefix + Send,
+    T: Send,
+{
+    fn from_par_iter<I>(par_iter: I) -> Self
+    where
+        I: IntoParallelIterator<Item = (P, T)>,
+    {
+        par_iter
+            .into_par_iter()
+            .fold(PrefixMap::new, |mut map, (p, t)| {
+                map.insert(p, t);
+                map
+            })
+            .reduce(PrefixMap::new, merge)
+    }
+}
+
+/// Extend the map with the elements of a parallel iterator. The elements are first collected into
+/// a new trie in parallel, which is then merged into `self`.
+///
+/// ```
+/// # use prefix_trie::*;
+/// use rayon::prelude::*;
+/// # #[cfg(feature = "ipnet")]
+/// # {
+/// let mut map: PrefixMap<ipnet::Ipv4Net, u32> = PrefixMap::new();
+/// map.insert("10.0.0.0/8".parse().unwrap(), 0);
+/// map.par_extend(
+///     (0..256u32)
+///         .into_par_iter()
+///         .map(|i| (ipnet::Ipv4Net::new((10 << 24 | i << 16).into(), 16).unwrap(), i)),
+/// );
+/// assert_eq!(map.len(), 257);
+/// assert_eq!(map.get_lpm(&"10.3.1.0/24".parse().unwrap()).unwrap().1, &3);
+/// # }
+/// ```
+impl<P, T> ParallelExtend<(P, T)> for PrefixMap<P, T>
+where
+    P: Prefix + Send,
+    T: Send,
+{
+    fn par_extend<I>(&mut self, par_iter: I)
+    where
+        I: IntoParallelIterator<Item = (P, T)>,
+    {
+        let other: PrefixMap<P, T> = par_iter.into_par_iter().collect();
+        for (p, t) in other {
+            self.insert(p, t);
+        }
+    }
+}
+
+/// Build a set from a parallel iterator. Each thread builds a partial trie, which are then merged
+/// together.
+///
+/// ```
+/// # use prefix_trie::*;
+/// use rayon::prelude::*;
+/// # #[cfg(feature = "ipnet")]
+/// # {
+/// let set: PrefixSet<ipnet::Ipv4Net> = (0..1024u32)
+///     .into_par_iter()
+///     .map(|i| ipnet::Ipv4Net::new((i << 8).into(), 24).unwrap())
+///     .collect();
+/// assert_eq!(set.len(), 1024);
+/// assert!(set.contains(&"0.0.4.0/24".parse().unwrap()));
+/// # }
+/// ```
+impl<P> FromParallelIterator<P> for PrefixSet<P>
+where
+    P: Prefix + Send,
+{
+    fn from_par_iter<I>(par_iter: I) -> Self
+    where
+        I: IntoParallelIterator<Item = P>,
+    {
+        PrefixSet(par_iter.into_par_iter().map(|p| (p, ())).collect())
+    }
+}
+
+/// Extend the set with the elements of a parallel iterator. The elements are first collected into
+/// a new trie in parallel, which is then merged into `self`.
+impl<P> ParallelExtend<P> for PrefixSet<P>
+where
+    P: Prefix + Send,
+{
+    fn par_extend<I>(&mut self, par_iter: I)
+    where
+        I: IntoParallelIterator<Item = P>,
+    {
+        self.0.par_extend(par_iter.into_par_iter().map(|p| (p, ())))
+    }
+}
@@ -0,0 +1,368 @@
+//! Routing tables with multiple paths per prefix, resolved into a forwarding table.
+//!
+//! A [`Rib`] (routing information base) stores, for each prefix, a set of [`Path`]s, each with a
+//! next-hop, a metric, and a weight. Paths are added and removed individually. The best paths of
+//! a prefix are all paths with the lowest metric, which allows equal-cost multi-path (ECMP)
+//! routing. [`Rib::fib`] computes the forwarding table (FIB), mapping each prefix to its set of
+//! weighted next-hops. [`Rib::fib_recursive`] additionally resolves next-hops that are not
+//! directly connected (e.g., BGP next-hops) through the routes of the same table.
+
+use crate::{Prefix, PrefixMap};
+
+/// The maximum number of recursive lookups performed to resolve a single next-hop.
+pub const MAX_RECURSION: usize = 8;
+
+/// A single path towards a prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Path<N> {
+    /// The next-hop of this path.
+    pub next_hop: N,
+    /// The metric of this path. Only the paths with the lowest metric of a prefix are used for
+    /// forwarding.
+    pub metric: u32,
+    /// The weight of this path, used to distribute the traffic among the best paths.
+    pub weight: u32,
+}
+
+impl<N> Path<N> {
+    /// Create a new path with the given next-hop and metric, and a weight of 1.
+    pub fn new(next_hop: N, metric: u32) -> Self {
+        Self {
+            next_hop,
+            metric,
+            weight: 1,
+        }
+    }
+
+    /// Set the weight of the path.
+    pub fn with_weight(self, weight: u32) -> Self {
+        Self { weight, ..self }
+    }
+}
+
+/// A set of weighted next-hops, sorted by next-hop. Each next-hop appears at most once.
+pub type NextHops<N> = Vec<(N, u32)>;
+
+/// A routing table that stores multiple paths for each prefix.
+///
+/// ```
+/// # use prefix_trie::rib::*;
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut rib: Rib<ipnet::Ipv4Net, &str> = Rib::new();
+/// rib.add_path("10.0.0.0/8".parse()?, Path::new("eth0", 10));
+/// rib.add_path("10.0.0.0/8".parse()?, Path::new("eth1", 10).with_weight(3));
+/// rib.add_path("10.0.0.0/8".parse()?, Path::new("eth2", 20));
+///
+/// let fib = rib.fib();
+/// assert_eq!(fib.get(&"10.0.0.0/8".parse()?), Some(&vec![("eth0", 1), ("eth1", 3)]));
+///
+/// // withdrawing one of the best paths keeps the remaining one.
+/// rib.remove_path(&"10.0.0.0/8".parse()?, &"eth1");
+/// assert_eq!(rib.fib().get(&"10.0.0.0/8".parse()?), Some(&vec![("eth0", 1)]));
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+#[derive(Clone)]
+pub struct Rib<P, N> {
+    routes: PrefixMap<P, Vec<Path<N>>>,
+}
+
+impl<P: Prefix, N> Default for Rib<P, N> {
+    fn default() -> Self {
+        Self {
+            routes: PrefixMap::new(),
+        }
+    }
+}
+
+impl<P: std::fmt::Debug, N: std::fmt::Debug> std::fmt::Debug for Rib<P, N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.routes.fmt(f)
+    }
+}
+
+impl<P, N> Rib<P, N>
+where
+    P: Prefix,
+    N: Ord + Clone,
+{
+    /// Create an empty routing table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of prefixes that have at least one path.
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    /// Returns `true` if the table contains no path.
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    /// Get the underlying map from prefixes to their paths, e.g., to create views or to perform
+    /// set operations.
+    pub fn routes(&self) -> &PrefixMap<P, Vec<Path<N>>> {
+        &self.routes
+    }
+
+    /// Add a path to `prefix`. If a path with the same next-hop already exists, then it is
+    /// replaced and returned.
+    pub fn add_path(&mut self, prefix: P, path: Path<N>) -> Option<Path<N>> {
+        let paths = self.routes.entry(prefix).or_default();
+        match paths.iter_mut().find(|p| p.next_hop == path.next_hop) {
+            Some(old) => Some(std::mem::replace(old, path)),
+            None => {
+                paths.push(path);
+                None
+            }
+        }
+    }
+
+    /// Remove the path of `prefix` with the given next-hop, and return it. The prefix is removed
+    /// once its last path is removed.
+    pub fn remove_path(&mut self, prefix: &P, next_hop: &N) -> Option<Path<N>> {
+        let paths = self.routes.get_mut(prefix)?;
+        let idx = paths.iter().position(|p| &p.next_hop == next_hop)?;
+        let path = paths.swap_remove(idx);
+        if paths.is_empty() {
+            self.routes.remove(prefix);
+        }
+        Some(path)
+    }
+
+    /// Remove all paths of `prefix`, and return them.
+    pub fn withdraw(&mut self, prefix: &P) -> Option<Vec<Path<N>>> {
+        self.routes.remove(prefix)
+    }
+
+    /// Remove all paths with the given next-hop from all prefixes (e.g., when a neighbor goes
+    /// down). Returns the number of removed paths.
+    pub fn withdraw_next_hop(&mut self, next_hop: &N) -> usize {
+        let mut num = 0;
+        for (_, paths) in self.routes.iter_mut() {
+            let len = paths.len();
+            paths.retain(|p| &p.next_hop != next_hop);
+            num += len - paths.len();
+        }
+        self.routes.retain(|_, paths| !paths.is_empty());
+        num
+    }
+
+    /// Get all paths of `prefix` (in no particular order), by matching exactly on the prefix.
+    pub fn paths(&self, prefix: &P) -> &[Path<N>] {
+        self.routes
+            .get(prefix)
+            .map(|p| p.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Get the best (lowest-metric) paths of `prefix`, by matching exactly on the prefix.
+    pub fn best_paths(&self, prefix: &P) -> impl Iterator<Item = &Path<N>> {
+        best(self.paths(prefix))
+    }
+
+    /// Get the weighted next-hops of the longest prefix match of `prefix`, without resolving them
+    /// recursively.
+    pub fn get_lpm<'a>(&'a self, prefix: &P) -> Option<(&'a P, NextHops<N>)> {
+        let (p, paths) = self.routes.get_lpm(prefix)?;
+        Some((p, next_hops(paths)))
+    }
+
+    /// Compute the forwarding table, mapping each prefix to the next-hops of its best paths.
+    /// Next-hops are not resolved recursively (see [`Rib::fib_recursive`]).
+    pub fn fib(&self) -> PrefixMap<P, NextHops<N>>
+    where
+        P: Clone,
+    {
+        self.routes
+            .iter()
+            .map(|(p, paths)| (p.clone(), next_hops(paths)))
+            .collect()
+    }
+
+    /// Compute the forwarding table, and resolve next-hops recursively through the routes of this
+    /// table.
+    ///
+    /// The function `recursive` returns the address of a next-hop that must be resolved, or
+    /// `None` if the next-hop is directly connected (e.g., an interface). A recursive next-hop
+    /// is resolved using the longest prefix match of its address, skipping the route that is
+    /// being resolved, and the weights of both paths are multiplied. Next-hops that cannot be
+    /// resolved within [`MAX_RECURSION`] lookups are dropped, and prefixes without any resolved
+    /// next-hop are omitted from the forwarding table.
+    ///
+    /// ```
+    /// # use prefix_trie::rib::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+    /// enum Hop {
+    ///     Interface(&'static str),
+    ///     Address(ipnet::Ipv4Net),
+    /// }
+    ///
+    /// let mut rib: Rib<ipnet::Ipv4Net, Hop> = Rib::new();
+    /// // IGP routes towards the loopback of two border routers
+    /// rib.add_path("192.0.2.1/32".parse()?, Path::new(Hop::Interface("eth0"), 0));
+    /// rib.add_path("192.0.2.0/24".parse()?, Path::new(Hop::Interface("eth1"), 0));
+    /// // BGP route via both border routers
+    /// rib.add_path("10.0.0.0/8".parse()?, Path::new(Hop::Address("192.0.2.1/32".parse()?), 0));
+    /// rib.add_path("10.0.0.0/8".parse()?, Path::new(Hop::Address("192.0.2.2/32".parse()?), 0));
+    /// // BGP route via an unreachable next-hop
+    /// rib.add_path("11.0.0.0/8".parse()?, Path::new(Hop::Address("198.51.100.1/32".parse()?), 0));
+    ///
+    /// let fib = rib.fib_recursive(|hop| match hop {
+    ///     Hop::Interface(_) => None,
+    ///     Hop::Address(addr) => Some(*addr),
+    /// });
+    /// assert_eq!(
+    ///     fib.get(&"10.0.0.0/8".parse()?),
+    ///     Some(&vec![(Hop::Interface("eth0"), 1), (Hop::Interface("eth1"), 1)])
+    /// );
+    /// assert_eq!(fib.get(&"11.0.0.0/8".parse()?), None);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn fib_recursive<F>(&self, recursive: F) -> PrefixMap<P, NextHops<N>>
+    where
+        P: Clone,
+        F: Fn(&N) -> Option<P>,
+    {
+        let mut cache = Resolved::new();
+        self.routes
+            .iter()
+            .map(|(p, paths)| {
+                let hops = self.resolve(p, paths, &recursive, MAX_RECURSION, &mut cache);
+                (p.clone(), hops)
+            })
+            .filter(|(_, hops)| !hops.is_empty())
+            .collect()
+    }
+
+    /// Resolve the longest prefix match of `prefix` into its directly connected next-hops. See
+    /// [`Rib::fib_recursive`] for how next-hops are resolved. Returns `None` if there is no
+    /// matching route, or if none of its next-hops can be resolved.
+    pub fn resolve_lpm<F>(&self, prefix: &P, recursive: F) -> Option<NextHops<N>>
+    where
+        P: Clone,
+        F: Fn(&N) -> Option<P>,
+    {
+        let (p, paths) = self.routes.get_lpm(prefix)?;
+        let mut cache = Resolved::new();
+        let hops = self.resolve(p, paths, &recursive, MAX_RECURSION, &mut cache);
+        (!hops.is_empty()).then_some(hops)
+    }
+
+    /// Resolve the best paths of the route `prefix` into directly connected next-hops, using at
+    /// most `depth` recursive lookups. Intermediate results are memoized in `cache`, such that
+    /// routes whose next-hops resolve through each other do not cause an exponential blowup.
+    fn resolve<F>(
+        &self,
+        prefix: &P,
+        paths: &[Path<N>],
+        recursive: &F,
+        depth: usize,
+        cache: &mut Resolved<P, N>,
+    ) -> NextHops<N>
+    where
+        P: Clone,
+        F: Fn(&N) -> Option<P>,
+    {
+        let mut hops = Vec::new();
+        for path in best(paths) {
+            let Some(addr) = recursive(&path.next_hop) else {
+                hops.push((path.next_hop.clone(), path.weight));
+                continue;
+            };
+            if depth == 0 {
+                continue;
+            }
+            // a route can never be resolved through itself.
+            let via = self
+                .routes
+                .cover(&addr)
+                .filter(|(p, _)| !Prefix::eq(*p, prefix))
+                .last();
+            let Some((via, via_paths)) = via else {
+                continue;
+            };
+            let via_hops = match cache.get(depth - 1, via) {
+                Some(via_hops) => via_hops.clone(),
+                None => {
+                    let via_hops = self.resolve(via, via_paths, recursive, depth - 1, cache);
+                    cache.insert(depth - 1, via.clone(), via_hops.clone());
+                    via_hops
+                }
+            };
+            for (hop, weight) in via_hops {
+                hops.push((hop, weight.saturating_mul(path.weight)));
+            }
+        }
+        normalize(hops)
+    }
+}
+
+/// Memoized resolutions of routes, for each remaining recursion depth.
+struct Resolved<P, N>(Vec<PrefixMap<P, NextHops<N>>>);
+
+impl<P: Prefix, N> Resolved<P, N> {
+    fn new() -> Self {
+        Self((0..MAX_RECURSION).map(|_| PrefixMap::new()).collect())
+    }
+
+    fn get(&self, depth: usize, prefix: &P) -> Option<&NextHops<N>> {
+        self.0[depth].get(prefix)
+    }
+
+    fn insert(&mut self, depth: usize, prefix: P, hops: NextHops<N>) {
+        self.0[depth].insert(prefix, hops);
+    }
+}
+
+/// Iterate over all paths with the lowest metric.
+fn best<N>(paths: &[Path<N>]) -> impl Iterator<Item = &Path<N>> {
+    let min = paths.iter().map(|p| p.metric).min().unwrap_or_default();
+    paths.iter().filter(move |p| p.metric == min)
+}
+
+/// Get the weighted next-hops of the best paths.
+fn next_hops<N: Ord + Clone>(paths: &[Path<N>]) -> NextHops<N> {
+    normalize(
+        best(paths)
+            .map(|p| (p.next_hop.clone(), p.weight))
+            .collect(),
+    )
+}
+
+/// Sort the next-hops, and merge duplicates by adding their weights.
+fn normalize<N: Ord>(mut hops: NextHops<N>) -> NextHops<N> {
+    hops.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut result: NextHops<N> = Vec::with_capacity(hops.len());
+    for (hop, weight) in hops {
+        match result.last_mut() {
+            Some((last, w)) if *last == hop => *w = w.saturating_add(weight),
+            _ => result.push((hop, weight)),
+        }
+    }
+    result
+}
+
+impl<P, N> FromIterator<(P, Path<N>)> for Rib<P, N>
+where
+    P: Prefix,
+    N: Ord + Clone,
+{
+    fn from_iter<I: IntoIterator<Item = (P, Path<N>)>>(iter: I) -> Self {
+        let mut rib = Self::new();
+        for (p, path) in iter {
+            rib.add_path(p, path);
+        }
+        rib
+    }
+}
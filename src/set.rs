@@ -1,19 +1,57 @@
 //! PrefixSet, that is implemened as a simple binary tree, based on the [`PrefixMap`].
 
-use crate::{map::CoverKeys, Prefix, PrefixMap};
+use crate::{
+    map::{CoverKeys, SubnetUsage},
+    range::Ranges,
+    AsView, Prefix, PrefixMap,
+};
 
 /// Set of prefixes, organized in a tree. This strucutre gives efficient access to the longest
 /// prefix in the set that contains another prefix.
 ///
 /// You can perform union, intersection, and (covering) difference operations by first creating a
-/// view over the map using [`crate::AsView`] or [`crate::AsViewMut`].
-#[derive(Clone)]
+/// view over the map using [`crate::AsView`] or [`crate::AsViewMut`]. To build a new set from such
+/// an operation, use [`PrefixSet::union`], [`PrefixSet::intersection`], or
+/// [`PrefixSet::difference`].
 pub struct PrefixSet<P>(pub(crate) PrefixMap<P, ()>);
 
+impl<P: Clone> Clone for PrefixSet<P> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+
+    /// Overwrite `self` with a copy of `source`, reusing the existing allocations of `self`.
+    fn clone_from(&mut self, source: &Self) {
+        self.0.clone_from(&source.0)
+    }
+}
+
 impl<P: Prefix> PrefixSet<P> {
-    /// Create a new, empty prefixset.
-    pub fn new() -> Self {
-        Self(Default::default())
+    /// Create a new, empty prefixset. Like [`PrefixMap::new`], this does not allocate, and can be
+    /// used in a `const` context.
+    pub const fn new() -> Self {
+        Self(PrefixMap::new())
+    }
+
+    /// Create a new, empty prefixset with space for at least `capacity` elements. See
+    /// [`PrefixMap::with_capacity`].
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self(PrefixMap::with_capacity(capacity))
+    }
+
+    /// Create a new, empty prefixset with space for at least `capacity` elements, but return an
+    /// error instead of aborting if the allocation fails. See [`PrefixMap::try_with_capacity`].
+    pub fn try_with_capacity(capacity: usize) -> Result<Self, std::collections::TryReserveError> {
+        PrefixMap::try_with_capacity(capacity).map(Self)
+    }
+
+    /// Try to reserve space for at least `additional` more elements. See
+    /// [`PrefixMap::try_reserve`].
+    pub fn try_reserve(
+        &mut self,
+        additional: usize,
+    ) -> Result<(), std::collections::TryReserveError> {
+        self.0.try_reserve(additional)
     }
 
     /// Returns the number of elements stored in `self`.
@@ -49,6 +87,15 @@ impl<P: Prefix> PrefixSet<P> {
         self.0.contains_key(prefix)
     }
 
+    /// Check wether some prefix is present in the set, where the prefix is of a different type `Q`
+    /// that shares the same representation. See [`PrefixMap::get_by`].
+    pub fn contains_by<Q>(&self, prefix: &Q) -> bool
+    where
+        Q: Prefix<R = P::R>,
+    {
+        self.0.contains_key_by(prefix)
+    }
+
     /// Get a reference to the stored prefix. This function allows you to retrieve the host part of
     /// the prefix. The returned prefix will always have the same network address and prefix length.
     ///
@@ -68,7 +115,9 @@ impl<P: Prefix> PrefixSet<P> {
         self.0.get_key_value(prefix).map(|(p, _)| p)
     }
 
-    /// Get the longest prefix in the set that contains the given preifx.
+    /// Get the longest prefix in the set that contains the given preifx. If the set contains the
+    /// default route (the prefix of length zero), this function falls back to it and never returns
+    /// `None`.
     ///
     /// ```
     /// # use prefix_trie::*;
@@ -90,6 +139,23 @@ impl<P: Prefix> PrefixSet<P> {
         self.0.get_lpm(prefix).map(|(p, _)| p)
     }
 
+    /// Get the length of the shortest prefix in the set, or `None` if the set is empty. See
+    /// [`PrefixMap::min_prefix_len`].
+    pub fn min_prefix_len(&self) -> Option<u8> {
+        self.0.min_prefix_len()
+    }
+
+    /// Get the length of the longest prefix in the set, or `None` if the set is empty. See
+    /// [`PrefixMap::max_prefix_len`].
+    pub fn max_prefix_len(&self) -> Option<u8> {
+        self.0.max_prefix_len()
+    }
+
+    /// Check if the default route (the prefix of length zero) is present in the set.
+    pub fn has_default(&self) -> bool {
+        self.0.has_default()
+    }
+
     /// Get the shortest prefix in the set that contains the given preifx.
     ///
     /// ```
@@ -159,6 +225,15 @@ impl<P: Prefix> PrefixSet<P> {
         self.0.remove(prefix).is_some()
     }
 
+    /// Removes a value from the set, where the prefix is of a different type `Q` that shares the
+    /// same representation. Returns whether the value was present in the set.
+    pub fn remove_by<Q>(&mut self, prefix: &Q) -> bool
+    where
+        Q: Prefix<R = P::R>,
+    {
+        self.0.remove_by(prefix).is_some()
+    }
+
     /// Removes a prefix from the set, returning wether the prefix was present or not. In contrast
     /// to [`Self::remove`], his operation will keep the tree structure as is, but only remove the
     /// element from it. This allows any future `insert` on the same prefix to be faster. However
@@ -213,6 +288,39 @@ impl<P: Prefix> PrefixSet<P> {
         self.0.remove_children(prefix)
     }
 
+    /// Remove all prefixes that contain `prefix` (including `prefix` itself), and return them
+    /// ordered by prefix length, shortest first. See [`PrefixMap::remove_cover`].
+    pub fn remove_cover(&mut self, prefix: &P) -> Vec<P>
+    where
+        P: Clone,
+    {
+        self.0
+            .remove_cover(prefix)
+            .into_iter()
+            .map(|(p, _)| p)
+            .collect()
+    }
+
+    /// Remove the longest prefix match of `prefix`, and return it. See [`PrefixMap::remove_lpm`].
+    pub fn remove_lpm(&mut self, prefix: &P) -> Option<P>
+    where
+        P: Clone,
+    {
+        self.0.remove_lpm(prefix).map(|(p, _)| p)
+    }
+
+    /// Keep only the prefixes that are covered by an element of `other`. See
+    /// [`PrefixMap::retain_covered_by`].
+    pub fn retain_covered_by(&mut self, other: &Self) {
+        self.0.retain_covered_by(other)
+    }
+
+    /// Remove all prefixes that are covered by an element of `other`. See
+    /// [`PrefixMap::remove_covered_by`].
+    pub fn remove_covered_by(&mut self, other: &Self) {
+        self.0.remove_covered_by(other)
+    }
+
     /// Clear the set but keep the allocated memory.
     ///
     /// ```
@@ -234,11 +342,184 @@ impl<P: Prefix> PrefixSet<P> {
         self.0.clear()
     }
 
+    /// Find the first address at or after `addr` that is not covered by any prefix in the set.
+    /// See [`PrefixMap::next_free`].
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// let set: PrefixSet<(u32, u8)> = [(0x0a000000, 24), (0x0a000100, 24)].into_iter().collect();
+    /// let free = set.next_free(0x0a000042).unwrap();
+    /// assert_eq!(free, 0x0a000200);
+    /// assert_eq!(set.free_block(free), Some((0x0a000200, 23)));
+    /// ```
+    pub fn next_free(&self, addr: P::R) -> Option<P::R> {
+        self.0.next_free(addr)
+    }
+
+    /// Find the last address at or before `addr` that is not covered by any prefix in the set.
+    /// See [`PrefixMap::prev_free`].
+    pub fn prev_free(&self, addr: P::R) -> Option<P::R> {
+        self.0.prev_free(addr)
+    }
+
+    /// Get the largest prefix that contains `addr`, and that does not overlap with any prefix in
+    /// the set. See [`PrefixMap::free_block`].
+    pub fn free_block(&self, addr: P::R) -> Option<P> {
+        self.0.free_block(addr)
+    }
+
+    /// Enumerate all subnets of `parent` with prefix length `len`, and classify each one as fully
+    /// covered, partially covered, or free. See [`PrefixMap::utilization`].
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let set: PrefixSet<ipnet::Ipv4Net> = ["10.0.0.0/24".parse()?, "10.0.2.0/26".parse()?]
+    ///     .into_iter()
+    ///     .collect();
+    /// let usage = set.utilization(&"10.0.0.0/22".parse()?, 24);
+    /// assert_eq!((usage.full, usage.partial, usage.free), (1, 1, 2));
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn utilization(&self, parent: &P, len: u8) -> SubnetUsage<P>
+    where
+        P: Clone,
+    {
+        self.0.utilization(parent, len)
+    }
+
+    /// Remove all nodes without an element that are no longer required for the tree structure.
+    /// See [`PrefixMap::cleanup_tree`].
+    pub fn cleanup_tree(&mut self) {
+        self.0.cleanup_tree()
+    }
+
+    /// Rebuild the internal table densely, releasing slots of removed elements. See
+    /// [`PrefixMap::compact`].
+    pub fn compact(&mut self) {
+        self.0.compact()
+    }
+
+    /// Rebuild the internal table densely, storing nodes in a cache-conscious order. See
+    /// [`PrefixMap::optimize`].
+    pub fn optimize(&mut self) {
+        self.0.optimize()
+    }
+
+    /// Create a new set that contains all prefixes of both `a` and `b`. The result is built in a
+    /// single simultaneous traversal over both sets, which is `O(n)`.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let a: PrefixSet<ipnet::Ipv4Net> = ["10.0.0.0/8".parse()?, "10.1.0.0/16".parse()?].into_iter().collect();
+    /// let b: PrefixSet<ipnet::Ipv4Net> = ["10.1.0.0/16".parse()?, "10.2.0.0/16".parse()?].into_iter().collect();
+    /// assert_eq!(
+    ///     PrefixSet::union(&a, &b).into_iter().collect::<Vec<_>>(),
+    ///     vec!["10.0.0.0/8".parse()?, "10.1.0.0/16".parse()?, "10.2.0.0/16".parse()?],
+    /// );
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn union(a: &Self, b: &Self) -> Self
+    where
+        P: Clone,
+    {
+        trace_span!("union", a = a.len(), b = b.len());
+        a.view().union(b).map(|x| x.prefix().clone()).collect()
+    }
+
+    /// Create a new set that contains all prefixes that are present in both `a` and `b`. The result
+    /// is built in a single simultaneous traversal over both sets, which is `O(n)`.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let a: PrefixSet<ipnet::Ipv4Net> = ["10.0.0.0/8".parse()?, "10.1.0.0/16".parse()?].into_iter().collect();
+    /// let b: PrefixSet<ipnet::Ipv4Net> = ["10.1.0.0/16".parse()?, "10.2.0.0/16".parse()?].into_iter().collect();
+    /// assert_eq!(
+    ///     PrefixSet::intersection(&a, &b).into_iter().collect::<Vec<_>>(),
+    ///     vec!["10.1.0.0/16".parse()?],
+    /// );
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn intersection(a: &Self, b: &Self) -> Self
+    where
+        P: Clone,
+    {
+        trace_span!("intersection", a = a.len(), b = b.len());
+        a.view()
+            .intersection(b)
+            .map(|(p, _, _)| p.clone())
+            .collect()
+    }
+
+    /// Create a new set that contains all prefixes of `a` that are not present in `b`. The result
+    /// is built in a single simultaneous traversal over both sets, which is `O(n)`.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let a: PrefixSet<ipnet::Ipv4Net> = ["10.0.0.0/8".parse()?, "10.1.0.0/16".parse()?].into_iter().collect();
+    /// let b: PrefixSet<ipnet::Ipv4Net> = ["10.1.0.0/16".parse()?, "10.2.0.0/16".parse()?].into_iter().collect();
+    /// assert_eq!(
+    ///     PrefixSet::difference(&a, &b).into_iter().collect::<Vec<_>>(),
+    ///     vec!["10.0.0.0/8".parse()?],
+    /// );
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn difference(a: &Self, b: &Self) -> Self
+    where
+        P: Clone,
+    {
+        trace_span!("difference", a = a.len(), b = b.len());
+        a.view().difference(b).map(|x| x.prefix.clone()).collect()
+    }
+
     /// Iterate over all prefixes in the set
     pub fn iter(&self) -> Iter<'_, P> {
         self.into_iter()
     }
 
+    /// Iterate over the maximal contiguous address ranges covered by the set, in ascending order.
+    /// Overlapping and adjacent prefixes are merged into a single range, so no two ranges overlap
+    /// or touch. Use [`crate::range::AddrRange::cidrs`] to turn a range back into prefixes.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use prefix_trie::range::AddrRange;
+    /// let mut set: PrefixSet<(u32, u8)> = PrefixSet::new();
+    /// set.insert((0x0a000000, 24));
+    /// set.insert((0x0a000080, 25));
+    /// set.insert((0x0a000100, 24));
+    /// set.insert((0x0a000300, 32));
+    /// assert_eq!(
+    ///     set.ranges().collect::<Vec<_>>(),
+    ///     vec![
+    ///         AddrRange::new(0x0a000000, 0x0a0001ff).unwrap(),
+    ///         AddrRange::new(0x0a000300, 0x0a000300).unwrap(),
+    ///     ]
+    /// );
+    /// ```
+    pub fn ranges(&self) -> Ranges<'_, P> {
+        Ranges::new(self.iter())
+    }
+
     /// Keep only the elements in the map that satisfy the given condition `f`.
     ///
     /// ```
@@ -341,12 +622,32 @@ where
     P: Prefix + PartialEq,
 {
     fn eq(&self, other: &Self) -> bool {
-        self.iter().zip(other.iter()).all(|(a, b)| a == b)
+        self.len() == other.len() && self.iter().eq(other.iter())
     }
 }
 
 impl<P> Eq for PrefixSet<P> where P: Prefix + Eq {}
 
+/// Sets are compared lexicographically over their prefixes, in the order of [`PrefixSet::iter`]
+/// (like a `BTreeSet`).
+impl<P> PartialOrd for PrefixSet<P>
+where
+    P: Prefix + PartialOrd,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<P> Ord for PrefixSet<P>
+where
+    P: Prefix + Ord,
+{
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
 #[derive(Clone, Default)]
 /// An iterator over all entries of a [`PrefixSet`] in lexicographic order.
 pub struct Iter<'a, P>(crate::map::Iter<'a, P, ()>);
@@ -394,9 +695,7 @@ impl<'a, P: Prefix> IntoIterator for &'a PrefixSet<P> {
 impl<P: Prefix> FromIterator<P> for PrefixSet<P> {
     fn from_iter<I: IntoIterator<Item = P>>(iter: I) -> Self {
         let mut set = Self::new();
-        for p in iter {
-            set.insert(p);
-        }
+        set.0.extend_sorted(iter.into_iter().map(|p| (p, ())));
         set
     }
 }
@@ -0,0 +1,102 @@
+//! Strategies to generate prefixes, maps, and sets using `proptest`.
+//!
+//! The depth of generated tries is controlled by the distribution of the prefix lengths (see
+//! [`prefix_with_len`]) and by restricting prefixes to a common super-prefix (see
+//! [`prefix_within`]). Short prefix lengths and narrow super-prefixes result in many overlapping
+//! prefixes and in deep tries.
+//!
+//! ```
+//! use proptest::prelude::*;
+//! use proptest::test_runner::TestRunner;
+//! use prefix_trie::{strategy::*, Prefix};
+//!
+//! let map = prefix_map(prefix_with_len::<(u32, u8)>(0u8..=16), any::<u8>(), 0..64);
+//! let mut runner = TestRunner::default();
+//! runner
+//!     .run(&(map, prefix::<(u32, u8)>()), |(map, query)| {
+//!         if let Some((p, _)) = map.get_lpm(&query) {
+//!             prop_assert!(p.contains(&query));
+//!         }
+//!         Ok(())
+//!     })
+//!     .unwrap();
+//!
+//! let base = (0x0a00_0000u32, 8);
+//! runner
+//!     .run(&prefix_within(&base, 0u8..=24), |p| {
+//!         prop_assert!(base.contains(&p) && p.prefix_len() <= 24);
+//!         Ok(())
+//!     })
+//!     .unwrap();
+//! ```
+
+use ::proptest::collection::{vec, SizeRange};
+use ::proptest::prelude::*;
+use num_traits::{NumCast, PrimInt, ToPrimitive, Zero};
+
+use crate::prefix::mask_from_prefix_len;
+use crate::*;
+
+/// The number of bits of the prefix type.
+fn num_bits<P: Prefix>() -> u8 {
+    P::R::zero().count_zeros() as u8
+}
+
+/// Create a prefix from the first bits of `x`, masked to the given length.
+fn from_bits<P: Prefix>(x: u128, len: u8) -> P {
+    let bits = num_bits::<P>();
+    let len = len.min(bits);
+    let repr: P::R = <P::R as NumCast>::from(x >> (128 - bits as u32)).unwrap();
+    P::from_repr_len(repr & mask_from_prefix_len(len), len)
+}
+
+/// Generate prefixes of any length (chosen uniformly), with random (masked) bits.
+pub fn prefix<P: Prefix>() -> impl Strategy<Value = P> {
+    prefix_with_len(0..=num_bits::<P>())
+}
+
+/// Generate prefixes with random (masked) bits, where the length is generated by `len`. Lengths
+/// that exceed the number of bits of the prefix type are clamped.
+pub fn prefix_with_len<P: Prefix>(len: impl Strategy<Value = u8>) -> impl Strategy<Value = P> {
+    (any::<u128>(), len).prop_map(|(x, len)| from_bits(x, len))
+}
+
+/// Generate prefixes that are contained within `base`, where the length is generated by `len`.
+/// Lengths shorter than the length of `base` are extended to the length of `base`.
+pub fn prefix_within<P: Prefix>(
+    base: &P,
+    len: impl Strategy<Value = u8>,
+) -> impl Strategy<Value = P> {
+    let base_len = base.prefix_len();
+    let base_bits = base.mask().to_u128().unwrap() << (128 - num_bits::<P>() as u32);
+    let base_mask = if base_len == 0 {
+        0
+    } else {
+        !0u128 << (128 - base_len as u32)
+    };
+    (any::<u128>(), len)
+        .prop_map(move |(x, len)| from_bits((x & !base_mask) | base_bits, len.max(base_len)))
+}
+
+/// Generate maps with keys from `prefix` and values from `value`, with a number of elements in
+/// `size` (before removing duplicate keys).
+pub fn prefix_map<P, T>(
+    prefix: impl Strategy<Value = P>,
+    value: impl Strategy<Value = T>,
+    size: impl Into<SizeRange>,
+) -> impl Strategy<Value = PrefixMap<P, T>>
+where
+    P: Prefix,
+    T: std::fmt::Debug,
+{
+    vec((prefix, value), size).prop_map(PrefixMap::from_iter)
+}
+
+/// Generate sets with elements from `prefix`, with a number of elements in `size` (before
+/// removing duplicates).
+pub fn prefix_set<P: Prefix>(
+    prefix: impl Strategy<Value = P>,
+    size: impl Into<SizeRange>,
+) -> impl Strategy<Value = PrefixSet<P>> {
+    vec(prefix, size).prop_map(PrefixSet::from_iter)
+}
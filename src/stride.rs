@@ -0,0 +1,188 @@
+//! A lookup-optimized, multibit-stride representation of a prefix map.
+//!
+//! A [`StrideMap`] uses a fixed stride of 8 bits: each node of the tree is an array of 256 slots,
+//! indexed by one byte of the address. Each prefix is expanded into all slots that it covers
+//! (controlled prefix expansion), such that a longest prefix match only needs a single memory
+//! access per byte of the address (i.e., at most 4 for IPv4, and 16 for IPv6), independent of the
+//! number of prefixes. This comes at the cost of memory: each node requires 2 KiB.
+//!
+//! A [`StrideMap`] cannot be modified. Create it from a [`PrefixMap`], and convert it back into a
+//! [`PrefixMap`] to apply modifications.
+
+use num_traits::{NumCast, PrimInt, Zero};
+
+use crate::{Prefix, PrefixMap};
+
+/// Number of bits in each stride.
+const STRIDE: u32 = 8;
+/// Number of slots in each node.
+const SLOTS: usize = 1 << STRIDE;
+/// Marker for a slot without a value.
+const NO_VALUE: u32 = u32::MAX;
+
+#[derive(Clone, Copy)]
+struct Slot {
+    /// Index into `entries` of the longest prefix that covers this slot, or `NO_VALUE`.
+    value: u32,
+    /// Index of the child node. The root (at index 0) is never a child, so 0 means no child.
+    child: u32,
+}
+
+const EMPTY: Slot = Slot {
+    value: NO_VALUE,
+    child: 0,
+};
+
+/// An immutable prefix map with 8-bit strides, optimized for fast and deterministic longest prefix
+/// matches on addresses. See the [module documentation](self) for details.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use prefix_trie::stride::StrideMap;
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut pm: PrefixMap<ipnet::Ipv4Net, _> = PrefixMap::new();
+/// pm.insert("10.0.0.0/8".parse()?, 1);
+/// pm.insert("10.1.0.0/20".parse()?, 2);
+/// pm.insert("0.0.0.0/0".parse()?, 0);
+/// let fib = StrideMap::from(pm);
+/// assert_eq!(fib.get_lpm_addr(0x0a010f01).map(|(_, t)| *t), Some(2));
+/// assert_eq!(fib.get_lpm_addr(0x0a011001).map(|(_, t)| *t), Some(1));
+/// assert_eq!(fib.get_lpm_addr(0x0b000000).map(|(_, t)| *t), Some(0));
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+#[derive(Clone)]
+pub struct StrideMap<P, T> {
+    nodes: Vec<[Slot; SLOTS]>,
+    /// The value of the prefix with length 0 (if present).
+    default: u32,
+    /// All entries in lexicographic order.
+    entries: Vec<(P, T)>,
+}
+
+impl<P, T> std::fmt::Debug for StrideMap<P, T>
+where
+    P: std::fmt::Debug,
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map()
+            .entries(self.entries.iter().map(|(p, t)| (p, t)))
+            .finish()
+    }
+}
+
+impl<P: Prefix, T> StrideMap<P, T> {
+    /// Returns the number of elements stored in `self`.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the map contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Get the longest prefix that contains the address `addr`, along with its value. This
+    /// requires at most one memory access for each byte of the address.
+    pub fn get_lpm_addr(&self, addr: P::R) -> Option<(&P, &T)> {
+        let mut best = self.default;
+        let mut node = 0;
+        for depth in 0..levels::<P::R>() {
+            let slot = self.nodes[node][byte(addr, depth)];
+            if slot.value != NO_VALUE {
+                best = slot.value;
+            }
+            if slot.child == 0 {
+                break;
+            }
+            node = slot.child as usize;
+        }
+        self.entry(best)
+    }
+
+    /// Iterate over all elements in lexicographic order.
+    pub fn iter(&self) -> impl Iterator<Item = (&P, &T)> {
+        self.entries.iter().map(|(p, t)| (p, t))
+    }
+
+    fn entry(&self, idx: u32) -> Option<(&P, &T)> {
+        self.entries.get(idx as usize).map(|(p, t)| (p, t))
+    }
+
+    /// Expand the prefix into all slots that it covers, creating nodes on the way.
+    fn expand(&mut self, prefix: &P, value: u32) {
+        let len = prefix.prefix_len() as u32;
+        if len == 0 {
+            self.default = value;
+            return;
+        }
+        let repr = prefix.mask();
+        let depth = (len - 1) / STRIDE;
+        let mut node = 0;
+        for d in 0..depth {
+            let b = byte(repr, d);
+            if self.nodes[node][b].child == 0 {
+                let child = u32::try_from(self.nodes.len()).expect("too many nodes");
+                self.nodes.push([EMPTY; SLOTS]);
+                self.nodes[node][b].child = child;
+            }
+            node = self.nodes[node][b].child as usize;
+        }
+        let bits = len - depth * STRIDE;
+        let start = byte(repr, depth);
+        let num = 1usize << (STRIDE - bits);
+        for slot in &mut self.nodes[node][start..start + num] {
+            slot.value = value;
+        }
+    }
+}
+
+impl<P: Prefix, T> From<PrefixMap<P, T>> for StrideMap<P, T> {
+    /// Build a `StrideMap` from all elements of `map`.
+    fn from(map: PrefixMap<P, T>) -> Self {
+        let mut s = Self {
+            nodes: vec![[EMPTY; SLOTS]],
+            default: NO_VALUE,
+            entries: Vec::with_capacity(map.len()),
+        };
+        // Elements are yielded in lexicographic order. Thus, a prefix is always expanded before any
+        // of the more specific prefixes it contains, which then overwrite its slots.
+        for (p, t) in map {
+            let idx = u32::try_from(s.entries.len()).expect("too many elements");
+            s.expand(&p, idx);
+            s.entries.push((p, t));
+        }
+        s
+    }
+}
+
+impl<P: Prefix + Clone, T: Clone> From<&PrefixMap<P, T>> for StrideMap<P, T> {
+    /// Build a `StrideMap` by cloning all elements of `map`.
+    fn from(map: &PrefixMap<P, T>) -> Self {
+        map.clone().into()
+    }
+}
+
+impl<P: Prefix, T> From<StrideMap<P, T>> for PrefixMap<P, T> {
+    /// Convert the `StrideMap` back into a regular `PrefixMap`.
+    fn from(map: StrideMap<P, T>) -> Self {
+        map.entries.into_iter().collect()
+    }
+}
+
+/// The number of strides of `R`.
+fn levels<R: Zero + PrimInt>() -> u32 {
+    R::zero().count_zeros() / STRIDE
+}
+
+/// Get the `depth`-th byte (counted from the most significant byte) of `addr`.
+#[inline(always)]
+fn byte<R: PrimInt>(addr: R, depth: u32) -> usize {
+    let shift = R::zero().count_zeros() - STRIDE * (depth + 1);
+    let mask: R = <R as NumCast>::from(SLOTS - 1).unwrap();
+    ((addr >> shift as usize) & mask).to_usize().unwrap()
+}
@@ -6,6 +6,12 @@ use super::*;
 
 type Map<P> = PrefixMap<P, u32>;
 
+#[cfg(feature = "derive")]
+#[derive(Debug, Clone, Copy, PartialEq, prefix_trie_derive::Prefix)]
+struct DerivedNet {
+    net: Ipv4Net,
+}
+
 fn ip<P: Prefix>(s: &str) -> P {
     let ip: Ipv4Net = s.parse().unwrap();
     let r = ip.addr().to_bits();
@@ -34,24 +40,16 @@ impl<P: Prefix> TestNode<P> {
     }
 
     pub fn build(mut self, map: &mut PrefixMap<P, u32>) -> usize {
-        let idx = if self.prefix.prefix_len() == 0 {
-            0
-        } else {
-            map.table.as_ref().len()
-        };
-        map.table.as_mut().push(Node {
-            prefix: self.prefix,
-            value: self.value,
-            left: None,
-            right: None,
-        });
+        let root = self.prefix.prefix_len() == 0;
+        let idx = map.table.push(self.prefix, self.value);
+        let idx = if root { 0 } else { idx };
         if let Some(left) = self.left.take() {
             let left = left.build(map);
-            map.table[idx].left = Some(left);
+            map.table[idx].set_left(Some(left));
         }
         if let Some(right) = self.right.take() {
             let right = right.build(map);
-            map.table[idx].right = Some(right);
+            map.table[idx].set_right(Some(right));
         }
         idx
     }
@@ -251,6 +249,23 @@ macro_rules! assert_iter {
     };
 }
 
+#[test]
+fn node_size() {
+    // 8 bytes for the prefix, and 4 bytes for each child index. The value is stored separately.
+    assert!(std::mem::size_of::<Node<(u32, u8)>>() <= 16);
+}
+
+#[test]
+fn send_sync() {
+    fn assert_send_sync<S: Send + Sync>() {}
+    assert_send_sync::<PrefixMap<Ipv4Net, u32>>();
+    assert_send_sync::<PrefixSet<Ipv4Net>>();
+    assert_send_sync::<TrieView<'_, Ipv4Net, u32>>();
+    assert_send_sync::<TrieViewMut<'_, Ipv4Net, u32>>();
+    assert_send_sync::<std::sync::Arc<PrefixMap<Ipv4Net, String>>>();
+    assert_send_sync::<map::PrefixReader<Ipv4Net, String>>();
+}
+
 #[generic_tests::define]
 mod t {
     use super::*;
@@ -714,10 +729,23 @@ mod t {
     #[instantiate_tests(<(u128, u8)>)]
     mod raw128 {}
 
+    #[instantiate_tests(<(std::net::Ipv4Addr, u8)>)]
+    mod std_ipv4 {}
+
+    #[instantiate_tests(<(std::net::Ipv6Addr, u8)>)]
+    mod std_ipv6 {}
+
     #[cfg(feature = "ipnet")]
     #[instantiate_tests(<ipnet::Ipv4Net>)]
     mod ipv4net {}
 
+    #[instantiate_tests(<RdPrefix<Ipv4Net>>)]
+    mod rd_ipv4net {}
+
+    #[cfg(feature = "derive")]
+    #[instantiate_tests(<super::DerivedNet>)]
+    mod derived {}
+
     #[cfg(feature = "ipnet")]
     #[instantiate_tests(<ipnet::Ipv6Net>)]
     mod ipv6net {}
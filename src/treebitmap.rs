@@ -0,0 +1,64 @@
+//! Conversions from `IpLookupTable` of the `treebitmap` crate (as used by `ip_network_table`).
+//!
+//! ```
+//! # use prefix_trie::*;
+//! # use std::net::Ipv4Addr;
+//! use ip_network_table_deps_treebitmap::IpLookupTable;
+//!
+//! let mut table = IpLookupTable::new();
+//! table.insert(Ipv4Addr::new(10, 0, 0, 0), 8, 1);
+//! table.insert(Ipv4Addr::new(10, 1, 0, 0), 16, 2);
+//! let map: PrefixMap<(Ipv4Addr, u8), _> = table.into();
+//! assert_eq!(map.get_lpm(&(Ipv4Addr::new(10, 1, 2, 0), 24)), Some((&(Ipv4Addr::new(10, 1, 0, 0), 16), &2)));
+//! ```
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use ip_network_table_deps_treebitmap::IpLookupTable;
+
+use super::*;
+
+macro_rules! impl_from_lookup_table {
+    ($a:ty, $p:ty, $new:expr) => {
+        impl<T> From<IpLookupTable<$a, T>> for PrefixMap<$p, T> {
+            /// Build a `PrefixMap` from all elements of an `IpLookupTable`.
+            fn from(table: IpLookupTable<$a, T>) -> Self {
+                table
+                    .into_iter()
+                    .map(|(addr, len, t)| ($new(addr, len as u8), t))
+                    .collect()
+            }
+        }
+    };
+}
+
+impl_from_lookup_table!(Ipv4Addr, (Ipv4Addr, u8), |a, l| (a, l));
+impl_from_lookup_table!(Ipv6Addr, (Ipv6Addr, u8), |a, l| (a, l));
+#[cfg(feature = "ipnet")]
+impl_from_lookup_table!(Ipv4Addr, ipnet::Ipv4Net, |a, l| {
+    ipnet::Ipv4Net::new(a, l).unwrap().trunc()
+});
+#[cfg(feature = "ipnet")]
+impl_from_lookup_table!(Ipv6Addr, ipnet::Ipv6Net, |a, l| {
+    ipnet::Ipv6Net::new(a, l).unwrap().trunc()
+});
+
+#[cfg(feature = "ipnet")]
+impl<T> From<IpLookupTable<Ipv4Addr, T>> for crate::ip::IpPrefixMap<T> {
+    /// Build an `IpPrefixMap` from all elements of an IPv4 `IpLookupTable`.
+    fn from(table: IpLookupTable<Ipv4Addr, T>) -> Self {
+        let mut map = Self::new();
+        *map.v4_mut() = table.into();
+        map
+    }
+}
+
+#[cfg(feature = "ipnet")]
+impl<T> From<IpLookupTable<Ipv6Addr, T>> for crate::ip::IpPrefixMap<T> {
+    /// Build an `IpPrefixMap` from all elements of an IPv6 `IpLookupTable`.
+    fn from(table: IpLookupTable<Ipv6Addr, T>) -> Self {
+        let mut map = Self::new();
+        *map.v6_mut() = table.into();
+        map
+    }
+}
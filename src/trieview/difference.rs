@@ -147,7 +147,7 @@ where
             table_r: other.table,
             nodes: extend_lpm(
                 other.table,
-                other.table[other.loc.idx()].prefix_value(),
+                other.table.prefix_value(other.loc.idx()),
                 next_indices(
                     self.table,
                     other.table,
@@ -257,7 +257,7 @@ where
         let other = other.view();
         let nodes = extend_lpm(
             other.table,
-            other.table[other.loc.idx()].prefix_value(),
+            other.table.prefix_value(other.loc.idx()),
             next_indices(
                 self.table,
                 other.table,
@@ -333,15 +333,15 @@ impl<'a, P: Prefix, L, R> Iterator for Difference<'a, P, L, R> {
                     let node_l = &self.table_l[l];
                     let node_r = &self.table_r[r];
                     self.extend(
-                        next_indices(self.table_l, self.table_r, node_l.right, node_r.right),
+                        next_indices(self.table_l, self.table_r, node_l.right(), node_r.right()),
                         lpm_r,
                     );
                     self.extend(
-                        next_indices(self.table_l, self.table_r, node_l.left, node_r.left),
+                        next_indices(self.table_l, self.table_r, node_l.left(), node_r.left()),
                         lpm_r,
                     );
-                    if let Some(value) = node_l.value.as_ref() {
-                        if node_r.value.is_none() {
+                    if let Some(value) = self.table_l.value(l) {
+                        if self.table_r.value(r).is_none() {
                             return Some(DifferenceItem {
                                 prefix: &node_l.prefix,
                                 value,
@@ -357,13 +357,13 @@ impl<'a, P: Prefix, L, R> Iterator for Difference<'a, P, L, R> {
                             self.table_l,
                             self.table_r,
                             l,
-                            node_l.left,
-                            node_l.right,
+                            node_l.left(),
+                            node_l.right(),
                             r,
                         ),
                         lpm_r,
                     );
-                    if let Some(value) = node_l.value.as_ref() {
+                    if let Some(value) = self.table_l.value(l) {
                         return Some(DifferenceItem {
                             prefix: &node_l.prefix,
                             value,
@@ -379,21 +379,21 @@ impl<'a, P: Prefix, L, R> Iterator for Difference<'a, P, L, R> {
                             self.table_r,
                             l,
                             r,
-                            node_r.left,
-                            node_r.right,
+                            node_r.left(),
+                            node_r.right(),
                         ),
                         lpm_r,
                     );
                 }
                 DifferenceIndex::OnlyL(l) => {
                     let node_l = &self.table_l[l];
-                    if let Some(right) = node_l.right {
+                    if let Some(right) = node_l.right() {
                         self.extend([DifferenceIndex::OnlyL(right)], lpm_r);
                     }
-                    if let Some(left) = node_l.left {
+                    if let Some(left) = node_l.left() {
                         self.extend([DifferenceIndex::OnlyL(left)], lpm_r);
                     }
-                    if let Some(value) = node_l.value.as_ref() {
+                    if let Some(value) = self.table_l.value(l) {
                         return Some(DifferenceItem {
                             prefix: &node_l.prefix,
                             value,
@@ -417,22 +417,22 @@ impl<'a, P: Prefix, L, R> Iterator for CoveringDifference<'a, P, L, R> {
                     let node_l = &self.table_l[l];
                     let node_r = &self.table_r[r];
                     // skip if r has a value (this all children must be ignored)
-                    if node_r.value.is_some() {
+                    if self.table_r.value(r).is_some() {
                         continue;
                     }
                     self.nodes.extend(next_indices(
                         self.table_l,
                         self.table_r,
-                        node_l.right,
-                        node_r.right,
+                        node_l.right(),
+                        node_r.right(),
                     ));
                     self.nodes.extend(next_indices(
                         self.table_l,
                         self.table_r,
-                        node_l.left,
-                        node_r.left,
+                        node_l.left(),
+                        node_r.left(),
                     ));
-                    if let Some(value) = node_l.value.as_ref() {
+                    if let Some(value) = self.table_l.value(l) {
                         return Some((&node_l.prefix, value));
                     }
                 }
@@ -442,18 +442,18 @@ impl<'a, P: Prefix, L, R> Iterator for CoveringDifference<'a, P, L, R> {
                         self.table_l,
                         self.table_r,
                         l,
-                        node_l.left,
-                        node_l.right,
+                        node_l.left(),
+                        node_l.right(),
                         r,
                     ));
-                    if let Some(value) = node_l.value.as_ref() {
+                    if let Some(value) = self.table_l.value(l) {
                         return Some((&node_l.prefix, value));
                     }
                 }
                 DifferenceIndex::FirstR(l, r) => {
                     let node_r = &self.table_r[r];
                     // skip if r has a value (this all children must be ignored)
-                    if node_r.value.is_some() {
+                    if self.table_r.value(r).is_some() {
                         continue;
                     }
                     self.nodes.extend(next_indices_first_b(
@@ -461,19 +461,19 @@ impl<'a, P: Prefix, L, R> Iterator for CoveringDifference<'a, P, L, R> {
                         self.table_r,
                         l,
                         r,
-                        node_r.left,
-                        node_r.right,
+                        node_r.left(),
+                        node_r.right(),
                     ));
                 }
                 DifferenceIndex::OnlyL(l) => {
                     let node_l = &self.table_l[l];
-                    if let Some(right) = node_l.right {
+                    if let Some(right) = node_l.right() {
                         self.nodes.extend([DifferenceIndex::OnlyL(right)]);
                     }
-                    if let Some(left) = node_l.left {
+                    if let Some(left) = node_l.left() {
                         self.nodes.extend([DifferenceIndex::OnlyL(left)]);
                     }
-                    if let Some(value) = node_l.value.as_ref() {
+                    if let Some(value) = self.table_l.value(l) {
                         return Some((&node_l.prefix, value));
                     }
                 }
@@ -498,18 +498,18 @@ impl<'a, P: Prefix, L, R> Iterator for DifferenceMut<'a, P, L, R> {
                     let node_l = &self.table_l[l];
                     let node_r = &self.table_r[r];
                     self.extend(
-                        next_indices(self.table_l, self.table_r, node_l.right, node_r.right),
+                        next_indices(self.table_l, self.table_r, node_l.right(), node_r.right()),
                         lpm_r,
                     );
                     self.extend(
-                        next_indices(self.table_l, self.table_r, node_l.left, node_r.left),
+                        next_indices(self.table_l, self.table_r, node_l.left(), node_r.left()),
                         lpm_r,
                     );
-                    let node_l = unsafe { self.table_l.get_mut(l) };
-                    if let Some(value) = node_l.value.as_mut() {
-                        if node_r.value.is_none() {
+                    let (prefix_l, value_l) = unsafe { self.table_l.get_mut(l) };
+                    if let Some(value) = value_l.as_mut() {
+                        if self.table_r.value(r).is_none() {
                             return Some(DifferenceMutItem {
-                                prefix: &node_l.prefix,
+                                prefix: prefix_l,
                                 value,
                                 right: lpm_r,
                             });
@@ -523,16 +523,16 @@ impl<'a, P: Prefix, L, R> Iterator for DifferenceMut<'a, P, L, R> {
                             self.table_l,
                             self.table_r,
                             l,
-                            node_l.left,
-                            node_l.right,
+                            node_l.left(),
+                            node_l.right(),
                             r,
                         ),
                         lpm_r,
                     );
-                    let node_l = unsafe { self.table_l.get_mut(l) };
-                    if let Some(value) = node_l.value.as_mut() {
+                    let (prefix_l, value_l) = unsafe { self.table_l.get_mut(l) };
+                    if let Some(value) = value_l.as_mut() {
                         return Some(DifferenceMutItem {
-                            prefix: &node_l.prefix,
+                            prefix: prefix_l,
                             value,
                             right: lpm_r,
                         });
@@ -546,23 +546,23 @@ impl<'a, P: Prefix, L, R> Iterator for DifferenceMut<'a, P, L, R> {
                             self.table_r,
                             l,
                             r,
-                            node_r.left,
-                            node_r.right,
+                            node_r.left(),
+                            node_r.right(),
                         ),
                         lpm_r,
                     );
                 }
                 DifferenceIndex::OnlyL(l) => {
-                    let node_l = unsafe { self.table_l.get_mut(l) };
-                    if let Some(right) = node_l.right {
+                    let (prefix_l, value_l) = unsafe { self.table_l.get_mut(l) };
+                    if let Some(right) = self.table_l[l].right() {
                         self.extend([DifferenceIndex::OnlyL(right)], lpm_r);
                     }
-                    if let Some(left) = node_l.left {
+                    if let Some(left) = self.table_l[l].left() {
                         self.extend([DifferenceIndex::OnlyL(left)], lpm_r);
                     }
-                    if let Some(value) = node_l.value.as_mut() {
+                    if let Some(value) = value_l.as_mut() {
                         return Some(DifferenceMutItem {
-                            prefix: &node_l.prefix,
+                            prefix: prefix_l,
                             value,
                             right: lpm_r,
                         });
@@ -589,24 +589,24 @@ impl<'a, P: Prefix, L, R> Iterator for CoveringDifferenceMut<'a, P, L, R> {
                     let node_l = &self.table_l[l];
                     let node_r = &self.table_r[r];
                     // skip if r has a value (this all children must be ignored)
-                    if node_r.value.is_some() {
+                    if self.table_r.value(r).is_some() {
                         continue;
                     }
                     self.nodes.extend(next_indices(
                         self.table_l,
                         self.table_r,
-                        node_l.right,
-                        node_r.right,
+                        node_l.right(),
+                        node_r.right(),
                     ));
                     self.nodes.extend(next_indices(
                         self.table_l,
                         self.table_r,
-                        node_l.left,
-                        node_r.left,
+                        node_l.left(),
+                        node_r.left(),
                     ));
-                    let node_l = unsafe { self.table_l.get_mut(l) };
-                    if let Some(value) = node_l.value.as_mut() {
-                        return Some((&node_l.prefix, value));
+                    let (prefix_l, value_l) = unsafe { self.table_l.get_mut(l) };
+                    if let Some(value) = value_l.as_mut() {
+                        return Some((prefix_l, value));
                     }
                 }
                 DifferenceIndex::FirstL(l, r) => {
@@ -615,19 +615,19 @@ impl<'a, P: Prefix, L, R> Iterator for CoveringDifferenceMut<'a, P, L, R> {
                         self.table_l,
                         self.table_r,
                         l,
-                        node_l.left,
-                        node_l.right,
+                        node_l.left(),
+                        node_l.right(),
                         r,
                     ));
-                    let node_l = unsafe { self.table_l.get_mut(l) };
-                    if let Some(value) = node_l.value.as_mut() {
-                        return Some((&node_l.prefix, value));
+                    let (prefix_l, value_l) = unsafe { self.table_l.get_mut(l) };
+                    if let Some(value) = value_l.as_mut() {
+                        return Some((prefix_l, value));
                     }
                 }
                 DifferenceIndex::FirstR(l, r) => {
                     let node_r = &self.table_r[r];
                     // skip if r has a value (this all children must be ignored)
-                    if node_r.value.is_some() {
+                    if self.table_r.value(r).is_some() {
                         continue;
                     }
                     self.nodes.extend(next_indices_first_b(
@@ -635,20 +635,20 @@ impl<'a, P: Prefix, L, R> Iterator for CoveringDifferenceMut<'a, P, L, R> {
                         self.table_r,
                         l,
                         r,
-                        node_r.left,
-                        node_r.right,
+                        node_r.left(),
+                        node_r.right(),
                     ));
                 }
                 DifferenceIndex::OnlyL(l) => {
-                    let node_l = unsafe { self.table_l.get_mut(l) };
-                    if let Some(right) = node_l.right {
+                    let (prefix_l, value_l) = unsafe { self.table_l.get_mut(l) };
+                    if let Some(right) = self.table_l[l].right() {
                         self.nodes.extend([DifferenceIndex::OnlyL(right)]);
                     }
-                    if let Some(left) = node_l.left {
+                    if let Some(left) = self.table_l[l].left() {
                         self.nodes.extend([DifferenceIndex::OnlyL(left)]);
                     }
-                    if let Some(value) = node_l.value.as_mut() {
-                        return Some((&node_l.prefix, value));
+                    if let Some(value) = value_l.as_mut() {
+                        return Some((prefix_l, value));
                     }
                 }
             }
@@ -763,7 +763,7 @@ fn extend_lpm<'a, P: Prefix, R>(
     lpm_r: Option<(&'a P, &'a R)>,
     indices: impl IntoIterator<Item = DifferenceIndex> + 'static,
 ) -> impl Iterator<Item = (DifferenceIndex, Option<(&'a P, &'a R)>)> + 'a {
-    let get_lpm_r = move |r: usize| table_r[r].prefix_value().or(lpm_r);
+    let get_lpm_r = move |r: usize| table_r.prefix_value(r).or(lpm_r);
     indices.into_iter().map(move |x| match x {
         DifferenceIndex::Both(_, r) | DifferenceIndex::FirstR(_, r) => (x, get_lpm_r(r)),
         DifferenceIndex::FirstL(_, _) | DifferenceIndex::OnlyL(_) => (x, lpm_r),
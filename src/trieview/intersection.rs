@@ -176,17 +176,17 @@ impl<'a, P: Prefix, L, R> Iterator for Intersection<'a, P, L, R> {
                     self.nodes.extend(next_indices(
                         self.table_l,
                         self.table_r,
-                        node_l.right,
-                        node_r.right,
+                        node_l.right(),
+                        node_r.right(),
                     ));
                     self.nodes.extend(next_indices(
                         self.table_l,
                         self.table_r,
-                        node_l.left,
-                        node_r.left,
+                        node_l.left(),
+                        node_r.left(),
                     ));
                     if let (Some(left), Some(right)) =
-                        (node_l.value.as_ref(), node_r.value.as_ref())
+                        (self.table_l.value(l), self.table_r.value(r))
                     {
                         return Some((&node_l.prefix, left, right));
                     }
@@ -197,8 +197,8 @@ impl<'a, P: Prefix, L, R> Iterator for Intersection<'a, P, L, R> {
                         self.table_l,
                         self.table_r,
                         l,
-                        node_l.left,
-                        node_l.right,
+                        node_l.left(),
+                        node_l.right(),
                         r,
                     ));
                 }
@@ -209,8 +209,8 @@ impl<'a, P: Prefix, L, R> Iterator for Intersection<'a, P, L, R> {
                         self.table_r,
                         l,
                         r,
-                        node_r.left,
-                        node_r.right,
+                        node_r.left(),
+                        node_r.right(),
                     ));
                 }
             }
@@ -236,21 +236,19 @@ impl<'a, P: Prefix, L, R> Iterator for IntersectionMut<'a, P, L, R> {
                     self.nodes.extend(next_indices(
                         self.table_l,
                         self.table_r,
-                        node_l.right,
-                        node_r.right,
+                        node_l.right(),
+                        node_r.right(),
                     ));
                     self.nodes.extend(next_indices(
                         self.table_l,
                         self.table_r,
-                        node_l.left,
-                        node_r.left,
+                        node_l.left(),
+                        node_r.left(),
                     ));
-                    let node_l = unsafe { self.table_l.get_mut(l) };
-                    let node_r = unsafe { self.table_r.get_mut(r) };
-                    if let (Some(left), Some(right)) =
-                        (node_l.value.as_mut(), node_r.value.as_mut())
-                    {
-                        return Some((&node_l.prefix, left, right));
+                    let (prefix_l, value_l) = unsafe { self.table_l.get_mut(l) };
+                    let (_, value_r) = unsafe { self.table_r.get_mut(r) };
+                    if let (Some(left), Some(right)) = (value_l.as_mut(), value_r.as_mut()) {
+                        return Some((prefix_l, left, right));
                     }
                 }
                 IntersectionIndex::FirstA(l, r) => {
@@ -259,8 +257,8 @@ impl<'a, P: Prefix, L, R> Iterator for IntersectionMut<'a, P, L, R> {
                         self.table_l,
                         self.table_r,
                         l,
-                        node_l.left,
-                        node_l.right,
+                        node_l.left(),
+                        node_l.right(),
                         r,
                     ));
                 }
@@ -271,8 +269,8 @@ impl<'a, P: Prefix, L, R> Iterator for IntersectionMut<'a, P, L, R> {
                         self.table_r,
                         l,
                         r,
-                        node_r.left,
-                        node_r.right,
+                        node_r.left(),
+                        node_r.right(),
                     ));
                 }
             }
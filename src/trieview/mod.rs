@@ -5,8 +5,8 @@
 //! [`PrefixMap`]s and [`PrefixSet`]s, optionally of only a trie-view.
 
 use crate::{
-    inner::{Direction, DirectionForInsert, Node, Table},
-    map::{Iter, IterMut, Keys, Values, ValuesMut},
+    inner::{Direction, DirectionForInsert, Table},
+    map::{Iter, IterMut, Keys, NodeId, Values, ValuesMut},
     to_right, Prefix, PrefixMap, PrefixSet,
 };
 
@@ -206,7 +206,7 @@ where
         loop {
             match self.table.get_direction(idx, prefix) {
                 Direction::Reached => {
-                    return self.table[idx].value.is_some().then_some(Self {
+                    return self.table.value(idx).is_some().then_some(Self {
                         table: self.table,
                         loc: ViewLoc::Node(idx),
                     })
@@ -262,7 +262,7 @@ where
         let mut idx = self.loc.idx();
         let mut best_match = None;
         loop {
-            if self.table[idx].value.is_some() {
+            if self.table.value(idx).is_some() {
                 best_match = Some(idx);
             }
             match self.table.get_direction(idx, prefix) {
@@ -310,7 +310,7 @@ where
         match &self.loc {
             ViewLoc::Node(idx) => Some(Self {
                 table: self.table,
-                loc: ViewLoc::Node(self.table[*idx].left?),
+                loc: ViewLoc::Node(self.table[*idx].left()?),
             }),
             ViewLoc::Virtual(p, idx) => {
                 // first, check if the node is on the left of the virtual one.
@@ -360,7 +360,7 @@ where
         match &self.loc {
             ViewLoc::Node(idx) => Some(Self {
                 table: self.table,
-                loc: ViewLoc::Node(self.table[*idx].right?),
+                loc: ViewLoc::Node(self.table[*idx].right()?),
             }),
             ViewLoc::Virtual(p, idx) => {
                 // first, check if the node is on the right of the virtual one.
@@ -460,6 +460,38 @@ impl<'a, P, T> TrieView<'a, P, T> {
         Values { inner: self.iter() }
     }
 
+    /// Fold over all elements in the given view (including the element itself) in lexicographic
+    /// order, with the ability to skip entire sub-branches. For each element, `f` receives the
+    /// accumulator, and returns the new one wrapped in a [`FoldStep`], which decides if the
+    /// elements contained in the current one are visited. The traversal does not allocate.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use prefix_trie::trieview::FoldStep;
+    /// # #[cfg(feature = "ipnet")]
+    /// macro_rules! net { ($x:literal) => {$x.parse::<ipnet::Ipv4Net>().unwrap()}; }
+    ///
+    /// # #[cfg(feature = "ipnet")]
+    /// # {
+    /// let mut map: PrefixMap<ipnet::Ipv4Net, usize> = PrefixMap::from_iter([
+    ///     (net!("192.168.0.0/20"), 1),
+    ///     (net!("192.168.0.0/22"), 2),
+    ///     (net!("192.168.0.0/24"), 3),
+    ///     (net!("192.168.2.0/23"), 4),
+    /// ]);
+    /// let sub = map.view_at(net!("192.168.0.0/21")).unwrap();
+    /// assert_eq!(sub.fold(0, |acc, _, t| FoldStep::Continue(acc + t)), 9);
+    /// // skip everything below 192.168.0.0/22
+    /// assert_eq!(sub.fold(0, |acc, _, t| FoldStep::Skip(acc + t)), 2);
+    /// # }
+    /// ```
+    pub fn fold<A, F>(&self, init: A, mut f: F) -> A
+    where
+        F: FnMut(A, &'a P, &'a T) -> FoldStep<A>,
+    {
+        fold_node(self.table, self.loc.idx(), init, &mut f)
+    }
+
     /// Get a reference to the prefix that is currently pointed at. This prefix might not exist
     /// explicitly in the map/set, but may be used as a branching node (or when you call
     /// `remove_keep_tree`).
@@ -510,7 +542,7 @@ impl<'a, P, T> TrieView<'a, P, T> {
     /// ```
     pub fn value(&self) -> Option<&'a T> {
         match &self.loc {
-            ViewLoc::Node(idx) => self.table[*idx].value.as_ref(),
+            ViewLoc::Node(idx) => self.table.value(*idx),
             ViewLoc::Virtual(_, _) => None,
         }
     }
@@ -539,10 +571,73 @@ impl<'a, P, T> TrieView<'a, P, T> {
     /// ```
     pub fn prefix_value(&self) -> Option<(&'a P, &'a T)> {
         match &self.loc {
-            ViewLoc::Node(idx) => self.table[*idx].prefix_value(),
+            ViewLoc::Node(idx) => self.table.prefix_value(*idx),
             ViewLoc::Virtual(_, _) => None,
         }
     }
+
+    /// Get the handle of the element at the root of the view (see [`NodeId`]). This function
+    /// returns `None` if `self` is pointing at a branching node.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// macro_rules! net { ($x:literal) => {$x.parse::<ipnet::Ipv4Net>().unwrap()}; }
+    ///
+    /// # #[cfg(feature = "ipnet")]
+    /// # {
+    /// let mut map: PrefixMap<ipnet::Ipv4Net, usize> = PrefixMap::from_iter([
+    ///     (net!("192.168.0.0/20"), 1),
+    ///     (net!("192.168.0.0/22"), 2),
+    /// ]);
+    ///
+    /// let id = map.view().find(net!("192.168.0.0/22")).unwrap().node_id().unwrap();
+    /// assert_eq!(map.get_by_id(id), Some((&net!("192.168.0.0/22"), &2)));
+    /// assert_eq!(map.view_at(net!("192.168.0.0/21")).unwrap().node_id(), None);
+    /// # }
+    /// ```
+    pub fn node_id(&self) -> Option<NodeId> {
+        match &self.loc {
+            ViewLoc::Node(idx) if self.table.value(*idx).is_some() => {
+                Some(NodeId::new(self.table, *idx))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Controls how [`TrieView::fold`] (or [`PrefixMap::fold_under`]) proceeds after visiting an
+/// element. Both variants carry the new accumulator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FoldStep<A> {
+    /// Continue with the elements contained in the current one.
+    Continue(A),
+    /// Skip all elements contained in the current one, and continue with the next element that is
+    /// not contained in it.
+    Skip(A),
+}
+
+/// Recursively fold over the subtree rooted at `idx`.
+fn fold_node<'a, P, T, A, F>(table: &'a Table<P, T>, idx: usize, acc: A, f: &mut F) -> A
+where
+    F: FnMut(A, &'a P, &'a T) -> FoldStep<A>,
+{
+    let node = &table[idx];
+    let acc = match table.value(idx) {
+        Some(v) => match f(acc, &node.prefix, v) {
+            FoldStep::Continue(acc) => acc,
+            FoldStep::Skip(acc) => return acc,
+        },
+        None => acc,
+    };
+    let acc = match node.left() {
+        Some(left) => fold_node(table, left, acc, f),
+        None => acc,
+    };
+    match node.right() {
+        Some(right) => fold_node(table, right, acc, f),
+        None => acc,
+    }
 }
 
 impl<'a, P, T> IntoIterator for TrieView<'a, P, T> {
@@ -576,6 +671,7 @@ impl<'a, P: Prefix, T> AsViewMut<'a, P, T> for &'a mut PrefixMap<P, T> {
     fn view_mut(self) -> TrieViewMut<'a, P, T> {
         // Safety: We borrow the prefixmap mutably here. Thus, this is the only mutable reference,
         // and we can create such a view to the root (referencing the entire tree mutably).
+        self.table.alloc_root();
         unsafe { TrieViewMut::new(&self.table, ViewLoc::Node(0)) }
     }
 }
@@ -644,6 +740,31 @@ impl<'a, P, T> TrieViewMut<'a, P, T> {
     unsafe fn new(table: &'a Table<P, T>, loc: ViewLoc<P>) -> Self {
         Self { table, loc }
     }
+
+    /// Create a mutable view of the entire tree.
+    ///
+    /// # Safety
+    /// The same conditions as for `TrieViewMut::new` apply. If the table is shared (e.g., in
+    /// `SyncPrefixMap`), then the view must only be used to search for a node, and all values of
+    /// the resulting sub-tree must not be accessed by anyone else.
+    pub(crate) unsafe fn root(table: &'a Table<P, T>) -> Self {
+        Self::new(table, ViewLoc::Node(0))
+    }
+}
+
+impl<P: Prefix, T> TrieViewMut<'_, P, T> {
+    /// Create a new mutable view that points to the same node, and that borrows `self` mutably.
+    pub(crate) fn reborrow(&mut self) -> TrieViewMut<'_, P, T> {
+        let loc = match &self.loc {
+            ViewLoc::Node(idx) => ViewLoc::Node(*idx),
+            ViewLoc::Virtual(p, idx) => {
+                ViewLoc::Virtual(P::from_repr_len(p.repr(), p.prefix_len()), *idx)
+            }
+        };
+        // Safety: The new view points to the same sub-tree as `self`, and `self` cannot be used
+        // while the new view exists.
+        unsafe { TrieViewMut::new(self.table, loc) }
+    }
 }
 
 impl<P: std::fmt::Debug, T: std::fmt::Debug> std::fmt::Debug for TrieViewMut<'_, P, T> {
@@ -753,7 +874,7 @@ where
         loop {
             match self.table.get_direction(idx, prefix) {
                 Direction::Reached => {
-                    return if self.table[idx].value.is_some() {
+                    return if self.table.value(idx).is_some() {
                         // Safety: We own the entire sub-tree, including `idx` (which was reached
                         // from `self.idx`). Here, we return a new TrieViewMut pointing to that node
                         // (which is still not covered by any other view), while dropping `self`.
@@ -806,7 +927,7 @@ where
         let mut idx = self.loc.idx();
         let mut best_match = None;
         loop {
-            if self.table[idx].value.is_some() {
+            if self.table.value(idx).is_some() {
                 best_match = Some(idx);
             }
             match self.table.get_direction(idx, prefix) {
@@ -862,7 +983,7 @@ where
         // the safety conditions remain satisfied.
 
         let left_idx = match &self.loc {
-            ViewLoc::Node(idx) => self.table[*idx].left,
+            ViewLoc::Node(idx) => self.table[*idx].left(),
             ViewLoc::Virtual(p, idx) => {
                 // first, check if the node is on the left of the virtual one.
                 if !to_right(p, &self.table[*idx].prefix) {
@@ -920,7 +1041,7 @@ where
         // the safety conditions remain satisfied.
 
         let right_idx = match &self.loc {
-            ViewLoc::Node(idx) => self.table[*idx].right,
+            ViewLoc::Node(idx) => self.table[*idx].right(),
             ViewLoc::Virtual(p, idx) => {
                 // first, check if the node is on the right of the virtual one.
                 if to_right(p, &self.table[*idx].prefix) {
@@ -958,7 +1079,7 @@ where
     /// ```
     pub fn has_left(&self) -> bool {
         match &self.loc {
-            ViewLoc::Node(idx) => self.table[*idx].left.is_some(),
+            ViewLoc::Node(idx) => self.table[*idx].left().is_some(),
             ViewLoc::Virtual(p, idx) => {
                 // first, check if the node is on the right of the virtual one.
                 !to_right(p, &self.table[*idx].prefix)
@@ -986,7 +1107,7 @@ where
     /// ```
     pub fn has_right(&self) -> bool {
         match &self.loc {
-            ViewLoc::Node(idx) => self.table[*idx].right.is_some(),
+            ViewLoc::Node(idx) => self.table[*idx].right().is_some(),
             ViewLoc::Virtual(p, idx) => {
                 // first, check if the node is on the right of the virtual one.
                 to_right(p, &self.table[*idx].prefix)
@@ -1023,7 +1144,7 @@ where
     /// ```
     pub fn split(self) -> (Option<Self>, Option<Self>) {
         let (left, right) = match &self.loc {
-            ViewLoc::Node(idx) => (self.table[*idx].left, self.table[*idx].right),
+            ViewLoc::Node(idx) => (self.table[*idx].left(), self.table[*idx].right()),
             ViewLoc::Virtual(p, idx) => {
                 // check if the node is on the right or the left of the virtual one.
                 if to_right(p, &self.table[*idx].prefix) {
@@ -1171,12 +1292,12 @@ impl<P, T> TrieViewMut<'_, P, T> {
     /// ```
     pub fn value(&self) -> Option<&T> {
         match &self.loc {
-            ViewLoc::Node(idx) => self.table[*idx].value.as_ref(),
+            ViewLoc::Node(idx) => self.table.value(*idx),
             ViewLoc::Virtual(_, _) => None,
         }
     }
 
-    fn node_mut(&mut self) -> Option<&mut Node<P, T>> {
+    fn node_mut(&mut self) -> Option<(&P, &mut Option<T>)> {
         // Safety: In the following, we assume that the safety conditions of `TrieViewMut::new` were
         // satisfied. In that case, we know that we are the only ones owning a mutable reference to
         // a tree that contains that root node. Therefore, it is safe to take a mutable reference of
@@ -1206,7 +1327,7 @@ impl<P, T> TrieViewMut<'_, P, T> {
     /// # }
     /// ```
     pub fn value_mut(&mut self) -> Option<&mut T> {
-        self.node_mut()?.value.as_mut()
+        self.node_mut()?.1.as_mut()
     }
 
     /// Get a reference to both the prefix and the value. This function may return `None` if either
@@ -1233,7 +1354,7 @@ impl<P, T> TrieViewMut<'_, P, T> {
     /// ```
     pub fn prefix_value(&self) -> Option<(&P, &T)> {
         match &self.loc {
-            ViewLoc::Node(idx) => self.table[*idx].prefix_value(),
+            ViewLoc::Node(idx) => self.table.prefix_value(*idx),
             ViewLoc::Virtual(_, _) => None,
         }
     }
@@ -1257,7 +1378,8 @@ impl<P, T> TrieViewMut<'_, P, T> {
     /// # }
     /// ```
     pub fn prefix_value_mut(&mut self) -> Option<(&P, &mut T)> {
-        self.node_mut()?.prefix_value_mut()
+        let (prefix, value) = self.node_mut()?;
+        value.as_mut().map(|v| (prefix, v))
     }
 
     /// Remove the element at the current position of the view. The tree structure is not modified
@@ -1286,6 +1408,7 @@ impl<P, T> TrieViewMut<'_, P, T> {
     ///         (&net!("192.168.2.0/23"), &mut 4),
     ///     ]
     /// );
+    /// assert_eq!(map.len(), 4);
     /// assert_eq!(
     ///     map.into_iter().collect::<Vec<_>>(),
     ///     vec![
@@ -1297,8 +1420,15 @@ impl<P, T> TrieViewMut<'_, P, T> {
     /// );
     /// # }
     /// ```
-    pub fn remove(&mut self) -> Option<T> {
-        self.node_mut()?.value.take()
+    pub fn remove(&mut self) -> Option<T>
+    where
+        P: Prefix,
+    {
+        let (prefix, value) = self.node_mut()?;
+        let (len, value) = (prefix.prefix_len(), value.take());
+        self.table.update_count(len, value.is_some(), false);
+        self.table.debug_check_structure("TrieViewMut::remove");
+        value
     }
 
     /// Set the value of the node currently pointed at. This operation fails if the current view
@@ -1348,9 +1478,22 @@ impl<P, T> TrieViewMut<'_, P, T> {
     /// );
     /// # }
     /// ```
-    pub fn set(&mut self, value: T) -> Result<Option<T>, T> {
+    pub fn set(&mut self, value: T) -> Result<Option<T>, T>
+    where
+        P: Prefix,
+    {
         match self.node_mut() {
-            Some(n) => Ok(n.value.replace(value)),
+            Some((prefix, v)) => {
+                let len = prefix.prefix_len();
+                let old = v.replace(value);
+                self.table.update_count(len, old.is_some(), true);
+                if old.is_none() {
+                    // Safety: see `node_mut`. The view owns the node exclusively.
+                    unsafe { self.table.bump_generation_shared(self.loc.idx()) };
+                }
+                self.table.debug_check_structure("TrieViewMut::set");
+                Ok(old)
+            }
             None => Err(value),
         }
     }
@@ -193,8 +193,8 @@ where
             nodes: extend_lpm(
                 self.table,
                 other.table,
-                self.table[self.loc.idx()].prefix_value(),
-                other.table[other.loc.idx()].prefix_value(),
+                self.table.prefix_value(self.loc.idx()),
+                other.table.prefix_value(other.loc.idx()),
                 next_indices(
                     self.table,
                     other.table,
@@ -333,19 +333,19 @@ impl<'a, P: Prefix, L, R> Iterator for Union<'a, P, L, R> {
                     let node_l = &self.table_l[l];
                     let node_r = &self.table_r[r];
                     self.extend(
-                        next_indices(self.table_l, self.table_r, node_l.right, node_r.right),
+                        next_indices(self.table_l, self.table_r, node_l.right(), node_r.right()),
                         lpm_l,
                         lpm_r,
                     );
                     self.extend(
-                        next_indices(self.table_l, self.table_r, node_l.left, node_r.left),
+                        next_indices(self.table_l, self.table_r, node_l.left(), node_r.left()),
                         lpm_l,
                         lpm_r,
                     );
                     if let Some(x) = self.get_next(
                         &node_l.prefix,
-                        node_l.value.as_ref(),
-                        node_r.value.as_ref(),
+                        self.table_l.value(l),
+                        self.table_r.value(r),
                         lpm_l,
                         lpm_r,
                     ) {
@@ -359,15 +359,15 @@ impl<'a, P: Prefix, L, R> Iterator for Union<'a, P, L, R> {
                             self.table_l,
                             self.table_r,
                             l,
-                            node_l.left,
-                            node_l.right,
+                            node_l.left(),
+                            node_l.right(),
                             r,
                         ),
                         lpm_l,
                         lpm_r,
                     );
                     if let Some(x) =
-                        self.get_next(&node_l.prefix, node_l.value.as_ref(), None, lpm_l, lpm_r)
+                        self.get_next(&node_l.prefix, self.table_l.value(l), None, lpm_l, lpm_r)
                     {
                         return Some(x);
                     }
@@ -380,42 +380,42 @@ impl<'a, P: Prefix, L, R> Iterator for Union<'a, P, L, R> {
                             self.table_r,
                             l,
                             r,
-                            node_r.left,
-                            node_r.right,
+                            node_r.left(),
+                            node_r.right(),
                         ),
                         lpm_l,
                         lpm_r,
                     );
                     if let Some(x) =
-                        self.get_next(&node_r.prefix, None, node_r.value.as_ref(), lpm_l, lpm_r)
+                        self.get_next(&node_r.prefix, None, self.table_r.value(r), lpm_l, lpm_r)
                     {
                         return Some(x);
                     }
                 }
                 UnionIndex::OnlyL(l) => {
                     let node_l = &self.table_l[l];
-                    if let Some(right) = node_l.right {
+                    if let Some(right) = node_l.right() {
                         self.extend([UnionIndex::OnlyL(right)], lpm_l, lpm_r);
                     }
-                    if let Some(left) = node_l.left {
+                    if let Some(left) = node_l.left() {
                         self.extend([UnionIndex::OnlyL(left)], lpm_l, lpm_r);
                     }
                     if let Some(x) =
-                        self.get_next(&node_l.prefix, node_l.value.as_ref(), None, lpm_l, lpm_r)
+                        self.get_next(&node_l.prefix, self.table_l.value(l), None, lpm_l, lpm_r)
                     {
                         return Some(x);
                     }
                 }
                 UnionIndex::OnlyR(r) => {
                     let node_r = &self.table_r[r];
-                    if let Some(right) = node_r.right {
+                    if let Some(right) = node_r.right() {
                         self.extend([UnionIndex::OnlyR(right)], lpm_l, lpm_r);
                     }
-                    if let Some(left) = node_r.left {
+                    if let Some(left) = node_r.left() {
                         self.extend([UnionIndex::OnlyR(left)], lpm_l, lpm_r);
                     }
                     if let Some(x) =
-                        self.get_next(&node_r.prefix, None, node_r.value.as_ref(), lpm_l, lpm_r)
+                        self.get_next(&node_r.prefix, None, self.table_r.value(r), lpm_l, lpm_r)
                     {
                         return Some(x);
                     }
@@ -443,23 +443,19 @@ impl<'a, P: Prefix, L, R> Iterator for UnionMut<'a, P, L, R> {
                     self.nodes.extend(next_indices(
                         self.table_l,
                         self.table_r,
-                        node_l.right,
-                        node_r.right,
+                        node_l.right(),
+                        node_r.right(),
                     ));
                     self.nodes.extend(next_indices(
                         self.table_l,
                         self.table_r,
-                        node_l.left,
-                        node_r.left,
+                        node_l.left(),
+                        node_r.left(),
                     ));
-                    let node_l = unsafe { self.table_l.get_mut(l) };
-                    let node_r = unsafe { self.table_r.get_mut(r) };
-                    if node_l.value.is_some() || node_r.value.is_some() {
-                        return Some((
-                            &node_l.prefix,
-                            node_l.value.as_mut(),
-                            node_r.value.as_mut(),
-                        ));
+                    let (prefix_l, value_l) = unsafe { self.table_l.get_mut(l) };
+                    let (_, value_r) = unsafe { self.table_r.get_mut(r) };
+                    if value_l.is_some() || value_r.is_some() {
+                        return Some((prefix_l, value_l.as_mut(), value_r.as_mut()));
                     }
                 }
                 UnionIndex::FirstL(l, r) => {
@@ -468,13 +464,13 @@ impl<'a, P: Prefix, L, R> Iterator for UnionMut<'a, P, L, R> {
                         self.table_l,
                         self.table_r,
                         l,
-                        node_l.left,
-                        node_l.right,
+                        node_l.left(),
+                        node_l.right(),
                         r,
                     ));
-                    let node_l = unsafe { self.table_l.get_mut(l) };
-                    if node_l.value.is_some() {
-                        return Some((&node_l.prefix, node_l.value.as_mut(), None));
+                    let (prefix_l, value_l) = unsafe { self.table_l.get_mut(l) };
+                    if value_l.is_some() {
+                        return Some((prefix_l, value_l.as_mut(), None));
                     }
                 }
                 UnionIndex::FirstR(l, r) => {
@@ -484,36 +480,36 @@ impl<'a, P: Prefix, L, R> Iterator for UnionMut<'a, P, L, R> {
                         self.table_r,
                         l,
                         r,
-                        node_r.left,
-                        node_r.right,
+                        node_r.left(),
+                        node_r.right(),
                     ));
-                    let node_r = unsafe { self.table_r.get_mut(r) };
-                    if node_r.value.is_some() {
-                        return Some((&node_r.prefix, None, node_r.value.as_mut()));
+                    let (prefix_r, value_r) = unsafe { self.table_r.get_mut(r) };
+                    if value_r.is_some() {
+                        return Some((prefix_r, None, value_r.as_mut()));
                     }
                 }
                 UnionIndex::OnlyL(l) => {
-                    let node_l = unsafe { self.table_l.get_mut(l) };
-                    if let Some(right) = node_l.right {
+                    let (prefix_l, value_l) = unsafe { self.table_l.get_mut(l) };
+                    if let Some(right) = self.table_l[l].right() {
                         self.nodes.push(UnionIndex::OnlyL(right));
                     }
-                    if let Some(left) = node_l.left {
+                    if let Some(left) = self.table_l[l].left() {
                         self.nodes.push(UnionIndex::OnlyL(left));
                     }
-                    if node_l.value.is_some() {
-                        return Some((&node_l.prefix, node_l.value.as_mut(), None));
+                    if value_l.is_some() {
+                        return Some((prefix_l, value_l.as_mut(), None));
                     }
                 }
                 UnionIndex::OnlyR(r) => {
-                    let node_r = unsafe { self.table_r.get_mut(r) };
-                    if let Some(right) = node_r.right {
+                    let (prefix_r, value_r) = unsafe { self.table_r.get_mut(r) };
+                    if let Some(right) = self.table_r[r].right() {
                         self.nodes.push(UnionIndex::OnlyR(right));
                     }
-                    if let Some(left) = node_r.left {
+                    if let Some(left) = self.table_r[r].left() {
                         self.nodes.push(UnionIndex::OnlyR(left));
                     }
-                    if node_r.value.is_some() {
-                        return Some((&node_r.prefix, None, node_r.value.as_mut()));
+                    if value_r.is_some() {
+                        return Some((prefix_r, None, value_r.as_mut()));
                     }
                 }
             }
@@ -621,8 +617,8 @@ fn extend_lpm<'a, P: Prefix, L, R>(
     lpm_r: Lpm<'a, P, R>,
     indices: impl IntoIterator<Item = UnionIndex> + 'static,
 ) -> impl Iterator<Item = Node<'a, P, L, R>> + 'a {
-    let get_lpm_l = move |l: usize| table_l[l].prefix_value().or(lpm_l);
-    let get_lpm_r = move |r: usize| table_r[r].prefix_value().or(lpm_r);
+    let get_lpm_l = move |l: usize| table_l.prefix_value(l).or(lpm_l);
+    let get_lpm_r = move |r: usize| table_r.prefix_value(r).or(lpm_r);
     indices.into_iter().map(move |x| match x {
         UnionIndex::Both(l, r) => (x, get_lpm_l(l), get_lpm_r(r)),
         UnionIndex::FirstL(l, _) | UnionIndex::OnlyL(l) => (x, get_lpm_l(l), lpm_r),
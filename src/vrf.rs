@@ -0,0 +1,253 @@
+//! Tables of prefix maps, keyed by an exact-match VRF (or table) identifier.
+//!
+//! A [`VrfPrefixMap`] holds one [`PrefixMap`] for each VRF. Each VRF can be accessed directly,
+//! such that all trie-level operations (views, set operations across VRFs, etc.) remain available.
+//! In addition, the map provides helpers to leak routes from one VRF into another, and to iterate
+//! over the routes of all VRFs.
+
+use std::collections::BTreeMap;
+
+use crate::{AsView, Prefix, PrefixMap, TrieView};
+
+/// A collection of prefix maps, one for each VRF `K`.
+///
+/// ```
+/// # use prefix_trie::vrf::*;
+/// # use prefix_trie::*;
+/// # #[cfg(feature = "ipnet")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut vrfs: VrfPrefixMap<&str, ipnet::Ipv4Net, _> = VrfPrefixMap::new();
+/// vrfs.insert("red", "10.0.0.0/8".parse()?, 1);
+/// vrfs.insert("blue", "10.1.0.0/16".parse()?, 2);
+/// assert_eq!(vrfs.get_lpm(&"red", &"10.1.1.0/24".parse()?), Some((&"10.0.0.0/8".parse()?, &1)));
+/// assert_eq!(vrfs.get_lpm(&"blue", &"10.2.0.0/16".parse()?), None);
+///
+/// // set operations across VRFs
+/// let red = vrfs.vrf(&"red").unwrap();
+/// let blue = vrfs.vrf(&"blue").unwrap();
+/// assert_eq!(red.view().union(blue).count(), 2);
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "ipnet"))]
+/// # fn main() {}
+/// ```
+#[derive(Clone)]
+pub struct VrfPrefixMap<K, P, T> {
+    vrfs: BTreeMap<K, PrefixMap<P, T>>,
+}
+
+impl<K, P, T> Default for VrfPrefixMap<K, P, T> {
+    fn default() -> Self {
+        Self {
+            vrfs: BTreeMap::new(),
+        }
+    }
+}
+
+impl<K, P, T> std::fmt::Debug for VrfPrefixMap<K, P, T>
+where
+    K: std::fmt::Debug,
+    P: std::fmt::Debug,
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.vrfs.iter()).finish()
+    }
+}
+
+impl<K, P, T> PartialEq for VrfPrefixMap<K, P, T>
+where
+    K: Ord,
+    P: Prefix + PartialEq,
+    T: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.vrfs == other.vrfs
+    }
+}
+
+impl<K, P, T> VrfPrefixMap<K, P, T>
+where
+    K: Ord,
+    P: Prefix,
+{
+    /// Create an empty map without any VRF.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of elements stored in all VRFs.
+    pub fn len(&self) -> usize {
+        self.vrfs.values().map(|m| m.len()).sum()
+    }
+
+    /// Returns `true` if no VRF contains any element.
+    pub fn is_empty(&self) -> bool {
+        self.vrfs.values().all(|m| m.is_empty())
+    }
+
+    /// Get the prefix map of a VRF, or `None` if that VRF does not exist.
+    pub fn vrf(&self, vrf: &K) -> Option<&PrefixMap<P, T>> {
+        self.vrfs.get(vrf)
+    }
+
+    /// Get a mutable reference to the prefix map of a VRF, or `None` if that VRF does not exist.
+    pub fn vrf_mut(&mut self, vrf: &K) -> Option<&mut PrefixMap<P, T>> {
+        self.vrfs.get_mut(vrf)
+    }
+
+    /// Get a mutable reference to the prefix map of a VRF, creating an empty one if that VRF does
+    /// not exist yet.
+    pub fn vrf_or_default(&mut self, vrf: K) -> &mut PrefixMap<P, T> {
+        self.vrfs.entry(vrf).or_default()
+    }
+
+    /// Get a view of the prefix map of a VRF, or `None` if that VRF does not exist.
+    pub fn view(&self, vrf: &K) -> Option<TrieView<'_, P, T>> {
+        self.vrfs.get(vrf).map(|m| m.view())
+    }
+
+    /// Remove an entire VRF, and return its prefix map.
+    pub fn remove_vrf(&mut self, vrf: &K) -> Option<PrefixMap<P, T>> {
+        self.vrfs.remove(vrf)
+    }
+
+    /// Iterate over all VRF identifiers (in ascending order).
+    pub fn vrfs(&self) -> impl Iterator<Item = &K> {
+        self.vrfs.keys()
+    }
+
+    /// Insert a new item into the given VRF (creating it if necessary). If the prefix was already
+    /// present in that VRF, then the old value is replaced and returned.
+    pub fn insert(&mut self, vrf: K, prefix: P, value: T) -> Option<T> {
+        self.vrf_or_default(vrf).insert(prefix, value)
+    }
+
+    /// Get the value of an element in a VRF by matching exactly on the prefix.
+    pub fn get(&self, vrf: &K, prefix: &P) -> Option<&T> {
+        self.vrfs.get(vrf)?.get(prefix)
+    }
+
+    /// Get a mutable reference to the value of an element in a VRF by matching exactly on the
+    /// prefix.
+    pub fn get_mut(&mut self, vrf: &K, prefix: &P) -> Option<&mut T> {
+        self.vrfs.get_mut(vrf)?.get_mut(prefix)
+    }
+
+    /// Get the value of an element in a VRF by using longest prefix matching.
+    pub fn get_lpm<'a>(&'a self, vrf: &K, prefix: &P) -> Option<(&'a P, &'a T)> {
+        self.vrfs.get(vrf)?.get_lpm(prefix)
+    }
+
+    /// Remove a prefix from a VRF, and return its value. The VRF is kept, even if it becomes
+    /// empty.
+    pub fn remove(&mut self, vrf: &K, prefix: &P) -> Option<T> {
+        self.vrfs.get_mut(vrf)?.remove(prefix)
+    }
+
+    /// Iterate over all elements of all VRFs. VRFs are traversed in ascending order, and the
+    /// elements of each VRF are yielded in lexicographic order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &P, &T)> {
+        self.vrfs
+            .iter()
+            .flat_map(|(k, m)| m.iter().map(move |(p, t)| (k, p, t)))
+    }
+
+    /// Iterate over all elements of all VRFs, yielding mutable references to the values.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &P, &mut T)> {
+        self.vrfs
+            .iter_mut()
+            .flat_map(|(k, m)| m.iter_mut().map(move |(p, t)| (k, p, t)))
+    }
+
+    /// Leak all routes of VRF `from` that are contained in `prefix` (including `prefix` itself)
+    /// into VRF `to` (creating it if necessary). Existing routes in `to` are overwritten. Returns
+    /// the number of leaked routes.
+    ///
+    /// ```
+    /// # use prefix_trie::vrf::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut vrfs: VrfPrefixMap<u32, ipnet::Ipv4Net, _> = VrfPrefixMap::new();
+    /// vrfs.insert(1, "10.0.0.0/8".parse()?, 'a');
+    /// vrfs.insert(1, "10.1.0.0/16".parse()?, 'b');
+    /// vrfs.insert(1, "192.168.0.0/16".parse()?, 'c');
+    /// vrfs.insert(2, "10.1.0.0/16".parse()?, 'x');
+    /// assert_eq!(vrfs.leak(&1, 2, "10.0.0.0/8".parse()?), 2);
+    /// assert_eq!(
+    ///     vrfs.vrf(&2).unwrap().iter().collect::<Vec<_>>(),
+    ///     vec![(&"10.0.0.0/8".parse()?, &'a'), (&"10.1.0.0/16".parse()?, &'b')]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn leak(&mut self, from: &K, to: K, prefix: P) -> usize
+    where
+        P: Clone,
+        T: Clone,
+    {
+        self.leak_with(from, to, prefix, |_, new, _| new.clone())
+    }
+
+    /// Leak all routes of VRF `from` that are contained in `prefix` (including `prefix` itself)
+    /// into VRF `to` (creating it if necessary). For each route, the value stored in `to` is
+    /// computed by `merge(prefix, value_in_from, existing_value_in_to)`. Returns the number of
+    /// leaked routes.
+    ///
+    /// ```
+    /// # use prefix_trie::vrf::*;
+    /// # #[cfg(feature = "ipnet")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut vrfs: VrfPrefixMap<u32, ipnet::Ipv4Net, u32> = VrfPrefixMap::new();
+    /// vrfs.insert(1, "10.1.0.0/16".parse()?, 1);
+    /// vrfs.insert(2, "10.1.0.0/16".parse()?, 10);
+    /// vrfs.leak_with(&1, 2, "0.0.0.0/0".parse()?, |_, new, old| new + old.unwrap_or(&0));
+    /// assert_eq!(vrfs.get(&2, &"10.1.0.0/16".parse()?), Some(&11));
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "ipnet"))]
+    /// # fn main() {}
+    /// ```
+    pub fn leak_with<F>(&mut self, from: &K, to: K, prefix: P, mut merge: F) -> usize
+    where
+        P: Clone,
+        F: FnMut(&P, &T, Option<&T>) -> T,
+    {
+        if from == &to {
+            return 0;
+        }
+        let Some(source) = self.vrfs.get(from) else {
+            return 0;
+        };
+        let target = self.vrfs.get(&to);
+        let leaked: Vec<(P, T)> = match source.view_at(prefix) {
+            Some(view) => view
+                .into_iter()
+                .map(|(p, t)| (p.clone(), merge(p, t, target.and_then(|m| m.get(p)))))
+                .collect(),
+            None => Vec::new(),
+        };
+        let num = leaked.len();
+        let target = self.vrf_or_default(to);
+        for (p, t) in leaked {
+            target.insert(p, t);
+        }
+        num
+    }
+}
+
+impl<K, P, T> FromIterator<(K, P, T)> for VrfPrefixMap<K, P, T>
+where
+    K: Ord,
+    P: Prefix,
+{
+    fn from_iter<I: IntoIterator<Item = (K, P, T)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        for (k, p, t) in iter {
+            map.insert(k, p, t);
+        }
+        map
+    }
+}
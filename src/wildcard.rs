@@ -0,0 +1,225 @@
+//! Ternary matching with non-contiguous masks (e.g., ACL wildcards).
+//!
+//! A [`Wildcard`] consists of a value and a mask, where each bit that is set in the mask must
+//! match exactly, and all other bits are ignored. As opposed to a prefix, the mask does not need
+//! to be contiguous. A [`WildcardMap`] stores each wildcard at the node of its longest contiguous
+//! prefix (i.e., all bits up to the first bit that is ignored). A lookup traverses the trie towards
+//! the address, and checks the remaining bits of each wildcard stored along that path. Hence,
+//! wildcards are never expanded into multiple prefixes.
+
+use num_traits::{PrimInt, Zero};
+
+use crate::{inner::Direction, prefix::mask_from_prefix_len, Prefix, PrefixMap};
+
+/// A value together with a (possibly non-contiguous) mask. Bits that are set in the mask must
+/// match exactly, while all other bits are ignored. The value is always masked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Wildcard<R> {
+    value: R,
+    mask: R,
+}
+
+impl<R: PrimInt + Zero> Wildcard<R> {
+    /// Create a new wildcard, where a bit set in `mask` must match `value`.
+    pub fn new(value: R, mask: R) -> Self {
+        Self {
+            value: value & mask,
+            mask,
+        }
+    }
+
+    /// Create a new wildcard from an inverse mask (as used in ACLs), where a bit set in
+    /// `wildcard_bits` is ignored.
+    ///
+    /// ```
+    /// # use prefix_trie::wildcard::*;
+    /// let w = Wildcard::from_inverse(0x0a000001u32, 0x00ff00ff);
+    /// assert_eq!(w, Wildcard::new(0x0a000001, 0xff00ff00));
+    /// ```
+    pub fn from_inverse(value: R, wildcard_bits: R) -> Self {
+        Self::new(value, !wildcard_bits)
+    }
+
+    /// Create a wildcard that matches exactly the addresses of a prefix.
+    pub fn from_prefix<P: Prefix<R = R>>(prefix: &P) -> Self {
+        Self::new(prefix.mask(), mask_from_prefix_len(prefix.prefix_len()))
+    }
+
+    /// Get the (masked) value.
+    pub fn value(&self) -> R {
+        self.value
+    }
+
+    /// Get the mask.
+    pub fn mask(&self) -> R {
+        self.mask
+    }
+
+    /// Check if `addr` matches the wildcard.
+    pub fn matches(&self, addr: R) -> bool {
+        addr & self.mask == self.value
+    }
+
+    /// Get the longest contiguous prefix of the wildcard, i.e., all bits up to the first bit that
+    /// is ignored.
+    fn contiguous_prefix<P: Prefix<R = R>>(&self) -> P {
+        let len = (!self.mask).leading_zeros() as u8;
+        P::from_repr_len(self.value & mask_from_prefix_len(len), len)
+    }
+}
+
+/// All wildcards that share the same contiguous prefix.
+type Entries<R, T> = Vec<(Wildcard<R>, T)>;
+
+/// A map from wildcards to values, supporting lookups that return all matching entries. See the
+/// [module documentation](self) for how wildcards are stored.
+///
+/// ```
+/// # use prefix_trie::wildcard::*;
+/// let mut acl: WildcardMap<(u32, u8), &str> = WildcardMap::new();
+/// // permit 10.0.0.0 0.255.0.255 (i.e., 10.*.0.*)
+/// acl.insert(Wildcard::from_inverse(0x0a000000, 0x00ff00ff), "permit");
+/// // deny 10.1.0.0/16
+/// acl.insert(Wildcard::new(0x0a010000, 0xffff0000), "deny");
+/// assert_eq!(acl.matches(0x0a020005).map(|(_, t)| *t).collect::<Vec<_>>(), vec!["permit"]);
+/// assert_eq!(acl.matches(0x0a010005).map(|(_, t)| *t).collect::<Vec<_>>(), vec!["permit", "deny"]);
+/// assert_eq!(acl.matches(0x0a010105).map(|(_, t)| *t).collect::<Vec<_>>(), vec!["deny"]);
+/// assert_eq!(acl.matches(0x0b000000).count(), 0);
+/// ```
+#[derive(Clone)]
+pub struct WildcardMap<P: Prefix, T> {
+    map: PrefixMap<P, Entries<P::R, T>>,
+    count: usize,
+}
+
+impl<P: Prefix, T> Default for WildcardMap<P, T> {
+    fn default() -> Self {
+        Self {
+            map: PrefixMap::new(),
+            count: 0,
+        }
+    }
+}
+
+impl<P, T> std::fmt::Debug for WildcardMap<P, T>
+where
+    P: Prefix,
+    P::R: std::fmt::Debug,
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<P: Prefix, T> WildcardMap<P, T> {
+    /// Create an empty wildcard map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of elements stored in `self`.
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    /// Returns `true` if the map contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Insert a new wildcard. If the same wildcard was already present, then its value is replaced
+    /// and the old value is returned.
+    pub fn insert(&mut self, wildcard: Wildcard<P::R>, value: T) -> Option<T> {
+        let entries = self.map.entry(wildcard.contiguous_prefix()).or_default();
+        if let Some((_, t)) = entries.iter_mut().find(|(w, _)| *w == wildcard) {
+            return Some(std::mem::replace(t, value));
+        }
+        entries.push((wildcard, value));
+        self.count += 1;
+        None
+    }
+
+    /// Get the value of a wildcard by matching exactly on the value and the mask.
+    ///
+    /// ```
+    /// # use prefix_trie::wildcard::*;
+    /// let mut wm: WildcardMap<(u8, u8), _> = WildcardMap::new();
+    /// wm.insert(Wildcard::new(0b1010_0000, 0b1111_0101), 1);
+    /// assert_eq!(wm.get(&Wildcard::new(0b1010_1010, 0b1111_0101)), Some(&1));
+    /// assert_eq!(wm.get(&Wildcard::new(0b1010_0000, 0b1111_0000)), None);
+    /// ```
+    pub fn get(&self, wildcard: &Wildcard<P::R>) -> Option<&T> {
+        self.map
+            .get(&wildcard.contiguous_prefix())?
+            .iter()
+            .find(|(w, _)| w == wildcard)
+            .map(|(_, t)| t)
+    }
+
+    /// Get a mutable reference to the value of a wildcard by matching exactly on the value and the
+    /// mask.
+    pub fn get_mut(&mut self, wildcard: &Wildcard<P::R>) -> Option<&mut T> {
+        self.map
+            .get_mut(&wildcard.contiguous_prefix())?
+            .iter_mut()
+            .find(|(w, _)| w == wildcard)
+            .map(|(_, t)| t)
+    }
+
+    /// Remove a wildcard (matching exactly on the value and the mask), and return its value.
+    ///
+    /// ```
+    /// # use prefix_trie::wildcard::*;
+    /// let mut wm: WildcardMap<(u8, u8), _> = WildcardMap::new();
+    /// wm.insert(Wildcard::new(0b1010_0000, 0b1111_0101), 1);
+    /// assert_eq!(wm.remove(&Wildcard::new(0b1010_0000, 0b1111_0000)), None);
+    /// assert_eq!(wm.remove(&Wildcard::new(0b1010_0000, 0b1111_0101)), Some(1));
+    /// assert!(wm.is_empty());
+    /// ```
+    pub fn remove(&mut self, wildcard: &Wildcard<P::R>) -> Option<T> {
+        let key = wildcard.contiguous_prefix();
+        let entries = self.map.get_mut(&key)?;
+        let pos = entries.iter().position(|(w, _)| w == wildcard)?;
+        let (_, t) = entries.remove(pos);
+        if entries.is_empty() {
+            self.map.remove(&key);
+        }
+        self.count -= 1;
+        Some(t)
+    }
+
+    /// Iterate over all wildcards that match the address `addr`. Wildcards are yielded in
+    /// ascending order of the length of their contiguous prefix, and in insertion order for
+    /// wildcards with the same contiguous prefix.
+    pub fn matches(&self, addr: P::R) -> impl Iterator<Item = (&Wildcard<P::R>, &T)> {
+        let width = P::R::zero().count_zeros() as u8;
+        let host = P::from_repr_len(addr, width);
+        // Collect all entries along the path towards `host`. We cannot use `PrefixMap::cover`, as
+        // the returned iterator would borrow `host`.
+        let table = &self.map.table;
+        let mut path = Vec::new();
+        let mut idx = 0;
+        loop {
+            if let Some(entries) = table.value(idx) {
+                path.push(entries);
+            }
+            match table.get_direction(idx, &host) {
+                Direction::Enter { next, .. } => idx = next,
+                _ => break,
+            }
+        }
+        path.into_iter()
+            .flatten()
+            .filter(move |(w, _)| w.matches(addr))
+            .map(|(w, t)| (w, t))
+    }
+
+    /// Iterate over all wildcards and their values. Wildcards are yielded in lexicographic order
+    /// of their contiguous prefix.
+    pub fn iter(&self) -> impl Iterator<Item = (&Wildcard<P::R>, &T)> {
+        self.map
+            .iter()
+            .flat_map(|(_, entries)| entries.iter().map(|(w, t)| (w, t)))
+    }
+}